@@ -19,9 +19,11 @@ use crate::{
     Game, GameParam, PLAYER_MOVE_SPEED,
 };
 
-use super::pathfinding::get_next_tile_A_star;
+use super::{pathfinding::get_next_tile_A_star, perception::Perception};
 
-// This trigger checks if the enemy is within the the given range of the target
+// This trigger checks if the enemy is within the the given range of the target.
+// Mobs with a [Perception] also need the target in their view cone, or to have just heard it.
+// At night the range is ignored
 #[derive(Clone, Copy, Reflect)]
 pub struct LineOfSight {
     pub target: Entity,
@@ -33,6 +35,7 @@ pub struct EnemyAttackCooldown(pub Timer);
 impl Trigger for LineOfSight {
     type Param<'w, 's> = (
         Query<'w, 's, &'static Transform>,
+        Query<'w, 's, &'static Perception>,
         Res<'w, Time>,
         Res<'w, NightTracker>,
     );
@@ -43,20 +46,28 @@ impl Trigger for LineOfSight {
     fn trigger(
         &self,
         entity: Entity,
-        (transforms, _time, night_tracker): Self::Param<'_, '_>,
+        (transforms, perceptions, _time, night_tracker): Self::Param<'_, '_>,
     ) -> Result<f32, f32> {
-        if night_tracker.is_night() {
-            return Ok(0.);
-        }
-        if let Ok(tfxm) = transforms.get(entity) {
-            let delta = transforms.get(self.target).unwrap().translation.truncate()
-                - tfxm.translation.truncate();
+        let Ok(tfxm) = transforms.get(entity) else {
+            return Err(0.);
+        };
+        let delta = transforms.get(self.target).unwrap().translation.truncate()
+            - tfxm.translation.truncate();
 
-            let distance = (delta.x * delta.x + delta.y * delta.y).sqrt();
-            (distance <= self.range).then_some(distance).ok_or(distance)
-        } else {
-            Err(0.)
+        let distance = (delta.x * delta.x + delta.y * delta.y).sqrt();
+        if let Ok(perception) = perceptions.get(entity) {
+            if perception.heard_target {
+                return Ok(distance);
+            }
+            // walls and facing still count at night, only the range is lifted
+            if !perception.target_in_view {
+                return Err(distance);
+            }
+        }
+        if night_tracker.is_night() {
+            return Ok(distance);
         }
+        (distance <= self.range).then_some(distance).ok_or(distance)
     }
 }
 
@@ -65,16 +76,23 @@ impl Trigger for LineOfSight {
 pub struct NightTimeAggro;
 
 impl Trigger for NightTimeAggro {
-    type Param<'w, 's> = Res<'w, NightTracker>;
+    type Param<'w, 's> = (Res<'w, NightTracker>, Query<'w, 's, &'static Perception>);
     type Ok = f32;
     type Err = f32;
 
     // Return `Ok` to trigger and `Err` to not trigger
-    fn trigger(&self, _entity: Entity, night_tracker: Self::Param<'_, '_>) -> Result<f32, f32> {
-        if night_tracker.is_night() {
-            Ok(1.)
-        } else {
-            Err(0.)
+    fn trigger(
+        &self,
+        entity: Entity,
+        (night_tracker, perceptions): Self::Param<'_, '_>,
+    ) -> Result<f32, f32> {
+        if !night_tracker.is_night() {
+            return Err(0.);
+        }
+        // mobs that can perceive still have to see or hear the player to come after them
+        match perceptions.get(entity) {
+            Ok(perception) if !perception.target_in_view && !perception.heard_target => Err(0.),
+            _ => Ok(1.),
         }
     }
 }
@@ -94,6 +112,21 @@ impl BoolTrigger for HurtByPlayer {
         false
     }
 }
+// This trigger checks if a searching enemy has given up looking for its target
+#[derive(Clone, Copy, Reflect)]
+pub struct SearchFinished;
+
+impl BoolTrigger for SearchFinished {
+    type Param<'w, 's> = Query<'w, 's, (&'static SearchState, Option<&'static Perception>)>;
+
+    fn trigger(&self, entity: Entity, searches: Self::Param<'_, '_>) -> bool {
+        if let Ok((search, perception_option)) = searches.get(entity) {
+            return search.search_timer.finished()
+                || perception_option.map_or(true, |p| !p.remembers_target());
+        }
+        false
+    }
+}
 // This trigger checks if the enemy is within the the given range of the target
 #[derive(Clone, Copy, Reflect)]
 pub struct AttackDistance {
@@ -151,6 +184,14 @@ pub struct FollowState {
     pub curr_delta: Option<Vec2>,
     pub speed: f32,
 }
+// Entities in the `Search` state should walk to the last place they saw or heard their target,
+// then look around until the timer runs out
+#[derive(Clone, Component, Reflect)]
+#[component(storage = "SparseSet")]
+pub struct SearchState {
+    pub search_timer: Timer,
+    pub speed: f32,
+}
 // Entities in the `Attack` state should move towards the given entity at the given speed
 #[derive(Clone, Component, Reflect)]
 #[component(storage = "SparseSet")]
@@ -264,6 +305,61 @@ pub fn follow(
     }
 }
 
+pub fn search(
+    transforms: Query<&Transform>,
+    mut mover: Query<&mut KinematicCharacterController>,
+    mut searches: Query<(
+        Entity,
+        &mut SearchState,
+        &Perception,
+        &EnemyAnimationState,
+        Option<&Slow>,
+    )>,
+    mut commands: Commands,
+    time: Res<Time>,
+    mut game: GameParam,
+) {
+    for (entity, mut search, perception, anim_state, slowed_option) in searches.iter_mut() {
+        let Some(search_pos) = perception.last_known_target_pos else {
+            continue;
+        };
+        let follow_collider_offset = Vec2::new(0., -3.);
+        let search_translation = AIPos_to_world_pos(world_pos_to_AIPos(
+            transforms.get(entity).unwrap().translation.truncate() + follow_collider_offset,
+        ));
+
+        // once we reach the spot, stand around and look for the target until we give up
+        if search_translation.distance(search_pos) <= TILE_SIZE.x {
+            search.search_timer.tick(time.delta());
+            if anim_state != &EnemyAnimationState::Idle && anim_state != &EnemyAnimationState::Hit {
+                commands.entity(entity).insert(EnemyAnimationState::Idle);
+            }
+            commands
+                .entity(entity)
+                .insert(FacingDirection::from_translation(Vec2::from_angle(
+                    search.search_timer.elapsed_secs() * 2.,
+                )));
+            continue;
+        }
+        let next_tile =
+            get_next_tile_A_star(&search_pos, &search_translation, &mut game).unwrap_or(search_pos);
+        let delta = (next_tile - search_translation).normalize_or_zero();
+        mover.get_mut(entity).unwrap().translation = Some(
+            delta
+                * search.speed
+                * PLAYER_MOVE_SPEED
+                * time.delta_seconds()
+                * (1. - slowed_option.map_or(0., |s| s.num_stacks as f32 * 0.15)),
+        );
+        commands
+            .entity(entity)
+            .insert(FacingDirection::from_translation(delta));
+        if anim_state != &EnemyAnimationState::Walk && anim_state != &EnemyAnimationState::Hit {
+            commands.entity(entity).insert(EnemyAnimationState::Walk);
+        }
+    }
+}
+
 pub fn leap_attack(
     mut transforms: Query<&mut Transform>,
     mut attacks: Query<(
@@ -1,5 +1,6 @@
 mod enemy_hostile_basic;
 pub mod pathfinding;
+pub mod perception;
 
 use crate::{
     client::is_not_paused,
@@ -16,6 +17,7 @@ use crate::{
 use bevy::prelude::*;
 pub use enemy_hostile_basic::*;
 use pathfinding::{cache_ai_path_on_new_obj_spawn, spawn_new_debug_path, DebugPathResetEvent};
+use perception::{emit_player_noises, update_mob_perception, NoiseEvent};
 use seldom_state::StateMachinePlugin;
 
 pub struct AIPlugin;
@@ -23,6 +25,7 @@ pub struct AIPlugin;
 impl Plugin for AIPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<DebugPathResetEvent>()
+            .add_event::<NoiseEvent>()
             .add_plugin(StateMachinePlugin)
             .add_systems((spawn_new_debug_path,).in_set(OnUpdate(GameState::Main)))
             .add_systems(
@@ -44,6 +47,16 @@ impl Plugin for AIPlugin {
                     idle.run_if(is_not_paused),
                 )
                     .in_set(OnUpdate(GameState::Main)),
+            )
+            .add_systems(
+                (
+                    emit_player_noises.run_if(is_not_paused),
+                    update_mob_perception
+                        .after(emit_player_noises)
+                        .run_if(is_not_paused),
+                    search.run_if(is_not_paused),
+                )
+                    .in_set(OnUpdate(GameState::Main)),
             );
     }
 }
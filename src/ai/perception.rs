@@ -0,0 +1,157 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::{QueryFilter, RapierContext};
+
+use crate::{
+    animations::AttackEvent, combat::ObjBreakEvent, inputs::FacingDirection, item::Wall,
+    world::world_helpers::tile_pos_to_world_pos, Game,
+};
+
+/// How far (in pixels) each player action can be heard from.
+pub const ATTACK_NOISE_RADIUS: f32 = 90.;
+pub const DASH_NOISE_RADIUS: f32 = 60.;
+pub const OBJ_BREAK_NOISE_RADIUS: f32 = 120.;
+
+/// Sent whenever the player does something loud. Mobs with a [Perception]
+/// within both the noise radius and their own hearing range will hear it.
+#[derive(Debug, Clone)]
+pub struct NoiseEvent {
+    pub pos: Vec2,
+    pub radius: f32,
+}
+
+/// Gives a mob a view cone, hearing, and a short memory of where the player was last seen.
+/// The [LineOfSight](super::LineOfSight) trigger uses this to decide if a mob can aggro.
+#[derive(Component, Clone, Debug, Reflect, FromReflect)]
+pub struct Perception {
+    /// Full width of the view cone, in degrees, centered on the mob's [FacingDirection]
+    pub view_angle: f32,
+    pub hearing_range: f32,
+    /// Anything within this distance is noticed regardless of facing direction
+    pub awareness_radius: f32,
+    /// How long the mob remembers the target's last known position once it loses track of it
+    pub memory: Timer,
+    pub last_known_target_pos: Option<Vec2>,
+    pub target_in_view: bool,
+    pub heard_target: bool,
+}
+
+impl Perception {
+    pub fn new(view_angle: f32, hearing_range: f32, memory_secs: f32) -> Self {
+        Self {
+            view_angle,
+            hearing_range,
+            awareness_radius: 24.,
+            memory: Timer::from_seconds(memory_secs, TimerMode::Once),
+            last_known_target_pos: None,
+            target_in_view: false,
+            heard_target: false,
+        }
+    }
+    pub fn remembers_target(&self) -> bool {
+        self.last_known_target_pos.is_some()
+    }
+    fn is_in_view_cone(&self, facing: Option<&FacingDirection>, delta: Vec2) -> bool {
+        if delta.length() <= self.awareness_radius {
+            return true;
+        }
+        let Some(facing) = facing else {
+            return true;
+        };
+        let angle = facing
+            .get_dir_vec()
+            .angle_between(delta.normalize_or_zero());
+        angle.abs() <= self.view_angle.to_radians() / 2.
+    }
+    fn remember(&mut self, pos: Vec2) {
+        self.last_known_target_pos = Some(pos);
+        self.memory.reset();
+    }
+}
+
+pub fn emit_player_noises(
+    game: Res<Game>,
+    mut attack_events: EventReader<AttackEvent>,
+    mut obj_break_events: EventReader<ObjBreakEvent>,
+    mut noise_events: EventWriter<NoiseEvent>,
+    mut was_dashing: Local<bool>,
+) {
+    let player = &game.player_state;
+    let player_pos = player.position.truncate();
+    if attack_events.iter().count() > 0 {
+        noise_events.send(NoiseEvent {
+            pos: player_pos,
+            radius: ATTACK_NOISE_RADIUS,
+        });
+    }
+    for broken in obj_break_events.iter() {
+        if !broken.give_drops_and_xp {
+            continue;
+        }
+        noise_events.send(NoiseEvent {
+            pos: tile_pos_to_world_pos(broken.pos, false),
+            radius: OBJ_BREAK_NOISE_RADIUS,
+        });
+    }
+    if player.is_dashing && !*was_dashing {
+        noise_events.send(NoiseEvent {
+            pos: player_pos,
+            radius: DASH_NOISE_RADIUS,
+        });
+    }
+    *was_dashing = player.is_dashing;
+}
+
+pub fn update_mob_perception(
+    game: Res<Game>,
+    context: Res<RapierContext>,
+    mut perceivers: Query<(
+        Entity,
+        &GlobalTransform,
+        Option<&FacingDirection>,
+        &mut Perception,
+    )>,
+    walls: Query<&Wall>,
+    mut noise_events: EventReader<NoiseEvent>,
+    time: Res<Time>,
+) {
+    let player_pos = game.player_state.position.truncate();
+    let noises = noise_events.iter().cloned().collect::<Vec<_>>();
+    // open doors have no solid collider, but skip them anyway in case a mob is standing in one
+    let blocks_sight = |e: Entity| walls.get(e).map_or(false, |w| w != &Wall::WoodDoorOpen);
+
+    for (e, txfm, facing, mut perception) in perceivers.iter_mut() {
+        let mob_pos = txfm.translation().truncate();
+        let delta = player_pos - mob_pos;
+        perception.heard_target = false;
+
+        perception.target_in_view = perception.is_in_view_cone(facing, delta)
+            && context
+                .cast_ray(
+                    mob_pos,
+                    delta.normalize_or_zero(),
+                    delta.length(),
+                    true,
+                    QueryFilter::new()
+                        .exclude_sensors()
+                        .exclude_collider(e)
+                        .predicate(&blocks_sight),
+                )
+                .is_none();
+
+        for noise in noises.iter() {
+            let dist = noise.pos.distance(mob_pos);
+            if dist <= noise.radius && dist <= perception.hearing_range {
+                perception.heard_target = true;
+                perception.remember(noise.pos);
+            }
+        }
+        if perception.target_in_view {
+            perception.remember(player_pos);
+        } else if perception.remembers_target() {
+            perception.memory.tick(time.delta());
+            if perception.memory.finished() {
+                perception.last_known_target_pos = None;
+            }
+        }
+    }
+}
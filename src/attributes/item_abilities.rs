@@ -10,6 +10,7 @@ use crate::{
     inputs::MovementVector,
     inventory::ItemStack,
     item::projectile::{Projectile, RangedAttackEvent},
+    keymap::{ActionInput, InputAction},
     player::{
         skills::{PlayerSkills, Skill},
         MovePlayerEvent, Player,
@@ -60,7 +61,7 @@ pub fn handle_item_abilitiy_on_attack(
     mut ranged_attack_event: EventWriter<RangedAttackEvent>,
     mut move_player: EventWriter<MovePlayerEvent>,
     player: Query<(&GlobalTransform, &PlayerSkills, &MovementVector, &Attack), With<Player>>,
-    key_input: ResMut<Input<KeyCode>>,
+    actions: ActionInput,
     mut game: GameParam,
     proto_param: ProtoParam,
     time: Res<Time>,
@@ -71,7 +72,7 @@ pub fn handle_item_abilitiy_on_attack(
     let player = game.player_mut();
     if skills.get(Skill::Teleport)
         && player.player_dash_cooldown.tick(time.delta()).finished()
-        && key_input.just_pressed(KeyCode::Space)
+        && actions.just_pressed(InputAction::Dash)
     {
         player.player_dash_cooldown.reset();
         if move_direction.0.length() != 0. {
@@ -13,7 +13,7 @@ use crate::{
     assets::Graphics,
     attributes::attribute_helpers::{build_item_stack_with_parsed_attributes, get_rarity_rng},
     client::GameOverEvent,
-    colors::{GREY, LIGHT_BLUE, LIGHT_GREY, LIGHT_RED, ORANGE, UNCOMMON_GREEN},
+    colors::{ColorPalette, GREY, LIGHT_GREY, ORANGE},
     inventory::{Inventory, ItemStack},
     item::{Equipment, EquipmentType, WorldObject},
     player::{
@@ -924,6 +924,8 @@ setup_raw_base_attributes! { struct RawItemBaseAttributes {
     Default,
     Eq,
     PartialEq,
+    Ord,
+    PartialOrd,
     Serialize,
     Deserialize,
 )]
@@ -969,12 +971,22 @@ impl ItemRarity {
             ItemRarity::Legendary => UIElement::LargeTooltipLegendary,
         }
     }
-    pub fn get_color(&self) -> Color {
+    pub fn get_color(&self, palette: ColorPalette) -> Color {
+        let [common, uncommon, rare, legendary] = palette.get_rarity_colors();
+        match self {
+            ItemRarity::Common => common,
+            ItemRarity::Uncommon => uncommon,
+            ItemRarity::Rare => rare,
+            ItemRarity::Legendary => legendary,
+        }
+    }
+    /// Shown on item slots alongside the glow, so rarity doesn't rely on color alone
+    pub fn get_badge(&self) -> Option<&'static str> {
         match self {
-            ItemRarity::Common => LIGHT_GREY,
-            ItemRarity::Uncommon => UNCOMMON_GREEN,
-            ItemRarity::Rare => LIGHT_BLUE,
-            ItemRarity::Legendary => LIGHT_RED,
+            ItemRarity::Common => None,
+            ItemRarity::Uncommon => Some("U"),
+            ItemRarity::Rare => Some("R"),
+            ItemRarity::Legendary => Some("L"),
         }
     }
     pub fn get_next_rarity(&self) -> ItemRarity {
@@ -993,6 +1005,15 @@ impl ItemRarity {
             ItemRarity::Legendary => Some(ItemGlow::Red),
         }
     }
+    /// how many time fragments a merchant pays for one item of this rarity
+    pub fn get_sell_value(&self) -> usize {
+        match self {
+            ItemRarity::Common => 1,
+            ItemRarity::Uncommon => 2,
+            ItemRarity::Rare => 4,
+            ItemRarity::Legendary => 8,
+        }
+    }
     pub fn get_scrap(&self) -> ScrapsInto {
         match self {
             ItemRarity::Common => ScrapsInto(vec![]),
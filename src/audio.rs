@@ -7,7 +7,9 @@ use crate::{
     handle_attack_cooldowns,
     item::WorldObject,
     juice::UseItemEvent,
+    keymap::{ActionInput, InputAction},
     player::Player,
+    settings::Settings,
     ui::UIState,
     GameState,
 };
@@ -34,6 +36,7 @@ impl Plugin for AudioPlugin {
         })
         .add_event::<UpdateBGMTrackEvent>()
         .add_system(bgm_audio)
+        .add_system(update_bgm_volume.after(bgm_audio))
         .add_systems(
             (
                 sword_swing_sound.after(handle_attack_cooldowns),
@@ -49,11 +52,12 @@ impl Plugin for AudioPlugin {
 pub fn sword_swing_sound(
     asset_server: Res<AssetServer>,
     audio: Res<Audio>,
-    mouse_button_input: Res<Input<MouseButton>>,
+    settings: Res<Settings>,
+    actions: ActionInput,
     player_query: Query<Option<&AttackTimer>, With<Player>>,
     curr_ui_state: Res<State<UIState>>,
 ) {
-    if mouse_button_input.pressed(MouseButton::Left) && curr_ui_state.0 == UIState::Closed {
+    if actions.pressed(InputAction::Attack) && curr_ui_state.0 == UIState::Closed {
         let attack_timer_option = player_query.single();
         if attack_timer_option.is_some() {
             return;
@@ -65,13 +69,17 @@ pub fn sword_swing_sound(
         let swing3 = asset_server.load("sounds/swing3.ogg");
         let swings = [swing1, swing2, swing3];
         swings.iter().choose(&mut rand::thread_rng()).map(|sound| {
-            audio.play_with_settings(sound.clone(), PlaybackSettings::ONCE.with_volume(0.5))
+            audio.play_with_settings(
+                sound.clone(),
+                PlaybackSettings::ONCE.with_volume(settings.get_sfx_volume(0.5)),
+            )
         });
     }
 }
 pub fn bgm_audio(
     asset_server: Res<AssetServer>,
     audio: Res<Audio>,
+    settings: Res<Settings>,
     mut bgm_tracker: ResMut<BGMPicker>,
     audio_handles: Res<Assets<AudioSink>>,
     mut bgm_update_events: EventReader<UpdateBGMTrackEvent>,
@@ -87,16 +95,36 @@ pub fn bgm_audio(
         bgm_tracker.current_track = path.clone();
         let bgm1 = asset_server.load(path);
 
-        let new_handle = audio_handles.get_handle(
-            audio.play_with_settings(bgm1.clone(), PlaybackSettings::LOOP.with_volume(1.0)),
-        );
+        let new_handle = audio_handles.get_handle(audio.play_with_settings(
+            bgm1.clone(),
+            PlaybackSettings::LOOP.with_volume(settings.get_music_volume(1.0)),
+        ));
         bgm_tracker.current_handle = Some(new_handle);
     }
 }
 
+/// Keeps the music in step with the volume sliders while it is playing
+pub fn update_bgm_volume(
+    settings: Res<Settings>,
+    bgm_tracker: Res<BGMPicker>,
+    audio_handles: Res<Assets<AudioSink>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    if let Some(bgm) = bgm_tracker
+        .current_handle
+        .as_ref()
+        .and_then(|handle| audio_handles.get(handle))
+    {
+        bgm.set_volume(settings.get_music_volume(1.0));
+    }
+}
+
 pub fn use_item_audio(
     asset_server: Res<AssetServer>,
     audio: Res<Audio>,
+    settings: Res<Settings>,
     mut use_item_event: EventReader<UseItemEvent>,
 ) {
     for item in use_item_event.iter() {
@@ -116,11 +144,17 @@ pub fn use_item_audio(
             let crunch3 = asset_server.load("sounds/crunch3.ogg");
             let crunchs = [crunch1, crunch2, crunch3];
             crunchs.iter().choose(&mut rand::thread_rng()).map(|sound| {
-                audio.play_with_settings(sound.clone(), PlaybackSettings::ONCE.with_volume(0.5))
+                audio.play_with_settings(
+                    sound.clone(),
+                    PlaybackSettings::ONCE.with_volume(settings.get_sfx_volume(0.5)),
+                )
             });
         } else {
             let sound = asset_server.load(format!("sounds/{}.ogg", item.0));
-            audio.play_with_settings(sound.clone(), PlaybackSettings::ONCE.with_volume(0.5));
+            audio.play_with_settings(
+                sound.clone(),
+                PlaybackSettings::ONCE.with_volume(settings.get_sfx_volume(0.5)),
+            );
         }
     }
 }
@@ -128,6 +162,7 @@ pub fn use_item_audio(
 pub fn break_item_audio(
     asset_server: Res<AssetServer>,
     audio: Res<Audio>,
+    settings: Res<Settings>,
     mut obj_break_events: EventReader<ObjBreakEvent>,
 ) {
     for item in obj_break_events.iter() {
@@ -154,11 +189,17 @@ pub fn break_item_audio(
                 rustle1, rustle2, rustle3, rustle4, rustle5, rustle6, rustle7,
             ];
             rustles.iter().choose(&mut rand::thread_rng()).map(|sound| {
-                audio.play_with_settings(sound.clone(), PlaybackSettings::ONCE.with_volume(0.5))
+                audio.play_with_settings(
+                    sound.clone(),
+                    PlaybackSettings::ONCE.with_volume(settings.get_sfx_volume(0.5)),
+                )
             });
         } else {
             let sound = asset_server.load(format!("sounds/{}.ogg", item.obj));
-            audio.play_with_settings(sound.clone(), PlaybackSettings::ONCE.with_volume(0.5));
+            audio.play_with_settings(
+                sound.clone(),
+                PlaybackSettings::ONCE.with_volume(settings.get_sfx_volume(0.5)),
+            );
         }
     }
 }
@@ -166,6 +207,7 @@ pub fn break_item_audio(
 pub fn hit_collision_audio(
     asset_server: Res<AssetServer>,
     audio: Res<Audio>,
+    settings: Res<Settings>,
     mut hit_events: EventReader<HitEvent>,
     world_objects: Query<&WorldObject>,
     mobs: Query<&Mob>,
@@ -173,10 +215,16 @@ pub fn hit_collision_audio(
     for hit in hit_events.iter() {
         if let Ok(obj) = world_objects.get(hit.hit_entity) {
             let sound = asset_server.load(format!("sounds/{}.ogg", obj));
-            audio.play_with_settings(sound.clone(), PlaybackSettings::ONCE.with_volume(0.5));
+            audio.play_with_settings(
+                sound.clone(),
+                PlaybackSettings::ONCE.with_volume(settings.get_sfx_volume(0.5)),
+            );
         } else if let Ok(mob) = mobs.get(hit.hit_entity) {
             let sound = asset_server.load(format!("sounds/{}.ogg", mob));
-            audio.play_with_settings(sound.clone(), PlaybackSettings::ONCE.with_volume(0.5));
+            audio.play_with_settings(
+                sound.clone(),
+                PlaybackSettings::ONCE.with_volume(settings.get_sfx_volume(0.5)),
+            );
         }
     }
 }
@@ -3,7 +3,7 @@ use std::{fs::File, io::BufReader};
 use bevy::{
     math::Vec3Swizzles,
     prelude::*,
-    utils::{HashMap, Uuid},
+    utils::{HashMap, HashSet, Uuid},
 };
 use bevy_ecs_tilemap::{
     prelude::{
@@ -26,10 +26,14 @@ use crate::{
     attributes::{hunger::Hunger, CurrentHealth},
     container::{Container, ContainerRegistry},
     datafiles,
+    enemy::{
+        companion::{Companion, CompanionSaveData, Downed},
+        Mob,
+    },
     inventory::{Inventory, ItemStack},
     item::{
-        projectile::Projectile, CraftingTracker, EquipmentType, Foliage, MainHand, Wall,
-        WorldObject,
+        projectile::Projectile, CraftingQueue, CraftingTracker, EquipmentType, Foliage, MainHand,
+        Wall, WorldObject,
     },
     night::NightTracker,
     player::{
@@ -39,17 +43,24 @@ use crate::{
         Player, TimeFragmentCurrency,
     },
     proto::proto_param::ProtoParam,
-    ui::{ChestContainer, FurnaceContainer},
+    settings::Settings,
+    ui::{
+        guide_hud::CurrentGoal, world_map::WorldMapData, ChestContainer, FurnaceContainer,
+        InventoryState,
+    },
     vectorize::{vectorize, vectorize_inner},
     world::{
+        biome::BiomeNoise,
         chunk::{Chunk, ReflectedPos, TileEntityCollection, TileSpriteData},
         dimension::{
-            ActiveDimension, Dimension, DimensionSpawnEvent, Era, EraManager, GenerationSeed,
+            ActiveDimension, Dimension, DimensionSpawnEvent, Era, EraList, EraManager,
+            GenerationSeed,
         },
         dungeon::Dungeon,
         generation::WorldObjectCache,
+        simulation::{SimulatedObject, SimulatedSpawners},
         world_helpers::world_pos_to_tile_pos,
-        TileMapPosition, WallTextureData, WorldGeneration,
+        TileMapPosition, WallTextureData, WorldGeneration, WorldSize,
     },
     CustomFlush, GameParam, GameState, MainCamera, RawPosition, TextureCamera, UICamera, YSort,
 };
@@ -130,7 +141,12 @@ impl Plugin for ClientPlugin {
             )
             .add_systems(
                 (
-                    save_state.run_if(resource_exists::<AnalyticsData>()),
+                    save_companions.after(tick_save_timer).before(save_state),
+                    save_world_map.after(tick_save_timer).before(save_state),
+                    save_pinned_recipe.after(tick_save_timer).before(save_state),
+                    save_state
+                        .after(tick_save_timer)
+                        .run_if(resource_exists::<AnalyticsData>()),
                     tick_save_timer,
                     handle_append_run_data_after_death.run_if(resource_exists::<AnalyticsData>()),
                 )
@@ -148,10 +164,23 @@ pub struct CurrentRunSaveData {
     placed_objs: Vec<HashMap<TileMapPosition, WorldObject>>,
 
     unique_objs: Vec<HashMap<WorldObject, TileMapPosition>>,
+    #[serde(default, with = "vectorize_inner")]
+    rotated_objs: Vec<HashMap<TileMapPosition, u8>>,
+    #[serde(default)]
+    player_placed_objs: Vec<HashSet<TileMapPosition>>,
+    // what keeps going in unloaded chunks, see the simulation module
+    #[serde(default, with = "vectorize_inner")]
+    simulated_objs: Vec<HashMap<TileMapPosition, SimulatedObject>>,
+    #[serde(default, with = "vectorize_inner")]
+    regrowing_objs: Vec<HashMap<TileMapPosition, SimulatedObject>>,
+    #[serde(default, with = "vectorize_inner")]
+    unloaded_spawners: Vec<HashMap<IVec2, SimulatedSpawners>>,
     #[serde(with = "vectorize")]
     containers: HashMap<TileMapPosition, Container>,
     #[serde(with = "vectorize")]
     container_reg: HashMap<TileMapPosition, Container>,
+    #[serde(default, with = "vectorize")]
+    crafting_queues: HashMap<TileMapPosition, CraftingQueue>,
     craft_tracker: CraftingTracker,
     night_tracker: NightTracker,
 
@@ -170,7 +199,25 @@ pub struct CurrentRunSaveData {
     // Era
     pub current_era: Era,
     pub visited_eras: Vec<Era>,
+    #[serde(default)]
+    pub bosses_killed: Vec<Era>,
     pub analytics_data: AnalyticsData,
+
+    // Companions
+    #[serde(default)]
+    pub companions: Vec<CompanionSaveData>,
+
+    // saves from before world sizes existed were all a single medium island
+    #[serde(default)]
+    pub world_size: WorldSize,
+
+    // explored chunks and pins of every era
+    #[serde(default)]
+    pub world_map: WorldMapData,
+
+    // the recipe pinned to the HUD from the recipe book
+    #[serde(default)]
+    pub pinned_recipe: Option<WorldObject>,
 }
 
 #[derive(Default)]
@@ -200,6 +247,7 @@ pub fn handle_append_run_data_after_death(
     proto_param: ProtoParam,
     mut game_over: EventReader<GameOverEvent>,
     mut analytics_data: ResMut<AnalyticsData>,
+    inv_state: Res<InventoryState>,
 ) {
     for _event in game_over.iter() {
         info!("GAME OVER! Storing run data in game_data.json...");
@@ -220,7 +268,7 @@ pub fn handle_append_run_data_after_death(
         }
         let inv = inv.single();
         for item in inv.items.items.clone().iter().flatten() {
-            if item.slot < 6 {
+            if item.slot < inv_state.hotbar_size {
                 //hotbar item
                 if let Some(eqp_type) = item.get_obj().get_equip_type(&proto_param) {
                     if eqp_type != EquipmentType::Axe && eqp_type != EquipmentType::Pickaxe {
@@ -310,17 +358,11 @@ pub fn save_state(
     save_data.craft_tracker = craft_tracker.clone();
     save_data.current_era = game.era.current_era.clone();
     save_data.visited_eras = game.era.visited_eras.clone();
+    save_data.bosses_killed = game.era.bosses_killed.clone();
     save_data.player_skills = skills.clone();
     save_data.player_skill_queue = skills_queue.clone();
     save_data.currency = currency.time_fragments;
 
-    save_data.placed_objs = game
-        .era
-        .era_generation_cache
-        .iter()
-        .map(|(_, c)| c.objects.clone())
-        .collect();
-
     let curr_era_objs = placed_objs
         .iter()
         .map(|(p, w, _, _)| {
@@ -334,25 +376,48 @@ pub fn save_state(
         })
         .map_into()
         .collect();
-    if (save_data.placed_objs.len() as i32) - 1 < game.era.current_era.index() as i32 {
-        // we are in the newest era and have not saved it in EraManager yet
-        save_data.placed_objs.push(curr_era_objs);
-    } else {
-        save_data.placed_objs[game.era.current_era.index()] = curr_era_objs;
-    }
 
-    save_data.unique_objs = game
+    // one entry per era up to the furthest one visited, eras that were skipped stay empty
+    let curr_era = game.era.current_era.index();
+    let num_eras = game
         .era
         .era_generation_cache
-        .iter()
-        .map(|(_e, c)| c.unique_objs.clone())
-        .collect();
-    let curr_era_unique_objs = game.world_obj_cache.unique_objs.clone();
-    if (save_data.unique_objs.len() as i32) - 1 < game.era.current_era.index() as i32 {
-        save_data.unique_objs.push(curr_era_unique_objs);
-    } else {
-        save_data.unique_objs[game.era.current_era.index()] = curr_era_unique_objs;
+        .keys()
+        .map(|era| era.index())
+        .chain([curr_era])
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let mut era_placed_objs = vec![HashMap::default(); num_eras];
+    let mut era_unique_objs = vec![HashMap::default(); num_eras];
+    let mut era_rotated_objs = vec![HashMap::default(); num_eras];
+    let mut era_player_placed_objs = vec![HashSet::default(); num_eras];
+    let mut era_simulated_objs = vec![HashMap::default(); num_eras];
+    let mut era_regrowing_objs = vec![HashMap::default(); num_eras];
+    let mut era_unloaded_spawners = vec![HashMap::default(); num_eras];
+    for (era, cache) in game.era.era_generation_cache.iter() {
+        era_placed_objs[era.index()] = cache.objects.clone();
+        era_unique_objs[era.index()] = cache.unique_objs.clone();
+        era_rotated_objs[era.index()] = cache.rotated_objs.clone();
+        era_player_placed_objs[era.index()] = cache.player_placed_objs.clone();
+        era_simulated_objs[era.index()] = cache.simulated_objs.clone();
+        era_regrowing_objs[era.index()] = cache.regrowing_objs.clone();
+        era_unloaded_spawners[era.index()] = cache.unloaded_spawners.clone();
     }
+    era_placed_objs[curr_era] = curr_era_objs;
+    era_unique_objs[curr_era] = game.world_obj_cache.unique_objs.clone();
+    era_rotated_objs[curr_era] = game.world_obj_cache.rotated_objs.clone();
+    era_player_placed_objs[curr_era] = game.world_obj_cache.player_placed_objs.clone();
+    era_simulated_objs[curr_era] = game.world_obj_cache.simulated_objs.clone();
+    era_regrowing_objs[curr_era] = game.world_obj_cache.regrowing_objs.clone();
+    era_unloaded_spawners[curr_era] = game.world_obj_cache.unloaded_spawners.clone();
+    save_data.placed_objs = era_placed_objs;
+    save_data.unique_objs = era_unique_objs;
+    save_data.rotated_objs = era_rotated_objs;
+    save_data.player_placed_objs = era_player_placed_objs;
+    save_data.simulated_objs = era_simulated_objs;
+    save_data.regrowing_objs = era_regrowing_objs;
+    save_data.unloaded_spawners = era_unloaded_spawners;
 
     // chain the current chests, and also the ones in registry,
     // since they will be despawned and missed by the query
@@ -400,6 +465,7 @@ pub fn save_state(
             .insert(world_pos_to_tile_pos(pos), furnace.items.clone());
     }
     save_data.container_reg = container_reg.containers.clone();
+    save_data.crafting_queues = container_reg.crafting_queues.clone();
     save_data.night_tracker = night_tracker.clone();
     save_data.seed = seed.seed;
     save_data.analytics_data = analytics_data.clone();
@@ -414,6 +480,49 @@ pub fn save_state(
     }
 }
 
+pub fn save_companions(
+    timer: Res<SaveTimer>,
+    key_input: Res<Input<KeyCode>>,
+    companions: Query<(&Mob, &Companion, &CurrentHealth), Without<Downed>>,
+    mut save_data: ResMut<CurrentRunSaveData>,
+) {
+    if !timer.timer.just_finished() && !key_input.just_pressed(KeyCode::U) {
+        return;
+    }
+    save_data.companions = companions
+        .iter()
+        .map(|(mob, companion, hp)| CompanionSaveData {
+            mob: mob.clone(),
+            command: companion.command,
+            health: hp.0,
+        })
+        .collect();
+}
+
+pub fn save_world_map(
+    timer: Res<SaveTimer>,
+    key_input: Res<Input<KeyCode>>,
+    world_map: Res<WorldMapData>,
+    mut save_data: ResMut<CurrentRunSaveData>,
+) {
+    if !timer.timer.just_finished() && !key_input.just_pressed(KeyCode::U) {
+        return;
+    }
+    save_data.world_map = world_map.clone();
+}
+
+pub fn save_pinned_recipe(
+    timer: Res<SaveTimer>,
+    key_input: Res<Input<KeyCode>>,
+    curr_goal: Option<Res<CurrentGoal>>,
+    mut save_data: ResMut<CurrentRunSaveData>,
+) {
+    if !timer.timer.just_finished() && !key_input.just_pressed(KeyCode::U) {
+        return;
+    }
+    save_data.pinned_recipe = curr_goal.map(|goal| goal.goal);
+}
+
 pub fn load_state(
     mut commands: Commands,
     mut proto_commands: ProtoCommands,
@@ -423,9 +532,13 @@ pub fn load_state(
         (Without<MainCamera>, Without<UICamera>, With<TextureCamera>),
     >,
     mut era: ResMut<EraManager>,
+    eras: Res<EraList>,
+    mut save_data: ResMut<CurrentRunSaveData>,
+    settings: Res<Settings>,
 ) {
     let mut rng = rand::thread_rng();
     let mut seed = rng.gen_range(0..100000);
+    let mut world_size = WorldSize::from_env().unwrap_or(settings.world_size);
 
     // Load data if it exists
     if let Ok(file_file) = File::open(datafiles::save_file()) {
@@ -442,6 +555,31 @@ pub fn load_state(
                 }
                 cache.objects = data.placed_objs[data.current_era.index()].clone();
                 cache.unique_objs = data.unique_objs[data.current_era.index()].clone();
+                cache.rotated_objs = data
+                    .rotated_objs
+                    .get(data.current_era.index())
+                    .cloned()
+                    .unwrap_or_default();
+                cache.player_placed_objs = data
+                    .player_placed_objs
+                    .get(data.current_era.index())
+                    .cloned()
+                    .unwrap_or_default();
+                cache.simulated_objs = data
+                    .simulated_objs
+                    .get(data.current_era.index())
+                    .cloned()
+                    .unwrap_or_default();
+                cache.regrowing_objs = data
+                    .regrowing_objs
+                    .get(data.current_era.index())
+                    .cloned()
+                    .unwrap_or_default();
+                cache.unloaded_spawners = data
+                    .unloaded_spawners
+                    .get(data.current_era.index())
+                    .cloned()
+                    .unwrap_or_default();
 
                 commands.insert_resource(cache);
                 for (i, (objs, unique_objs)) in data
@@ -458,24 +596,41 @@ pub fn load_state(
                         WorldObjectCache {
                             objects: objs.clone(),
                             unique_objs: unique_objs.clone(),
+                            rotated_objs: data.rotated_objs.get(i).cloned().unwrap_or_default(),
+                            player_placed_objs: data
+                                .player_placed_objs
+                                .get(i)
+                                .cloned()
+                                .unwrap_or_default(),
+                            simulated_objs: data.simulated_objs.get(i).cloned().unwrap_or_default(),
+                            regrowing_objs: data.regrowing_objs.get(i).cloned().unwrap_or_default(),
+                            unloaded_spawners: data
+                                .unloaded_spawners
+                                .get(i)
+                                .cloned()
+                                .unwrap_or_default(),
                             ..Default::default()
                         },
                     );
                 }
                 era.current_era = data.current_era;
                 era.visited_eras = data.visited_eras;
+                era.bosses_killed = data.bosses_killed;
                 seed = data.seed;
+                world_size = data.world_size;
                 commands.insert_resource(data.night_tracker);
                 commands.insert_resource(ContainerRegistry {
                     containers: data.containers,
+                    crafting_queues: data.crafting_queues,
                 });
                 commands.insert_resource(data.player_skill_queue);
                 commands.insert_resource(data.analytics_data);
                 commands.insert_resource(data.craft_tracker);
-                proto_commands.apply(format!(
-                    "Era{}WorldGenerationParams",
-                    era.current_era.clone().index() + 1
-                ));
+                commands.insert_resource(data.world_map);
+                if let Some(goal) = data.pinned_recipe {
+                    commands.insert_resource(CurrentGoal { goal });
+                }
+                proto_commands.apply(eras.get_world_generation_params(&era.current_era));
                 // PRE-MOVE CAMERAS TO PLAYER
                 let (mut game_camera_transform, mut raw_camera_pos) = game_camera.single_mut();
 
@@ -486,10 +641,15 @@ pub fn load_state(
             Err(err) => println!("Failed to load data from file {err:?}"),
         }
     } else {
-        proto_commands.apply("Era1WorldGenerationParams");
+        proto_commands.apply(eras.get_world_generation_params(&Era::default()));
         commands.init_resource::<WorldObjectCache>();
+        commands.insert_resource(WorldMapData::default());
     }
     commands.insert_resource(GenerationSeed { seed });
+    commands.insert_resource(BiomeNoise::new(seed));
+    // the world size never changes after the world is made, so it only needs saving once
+    commands.insert_resource(world_size);
+    save_data.world_size = world_size;
 
     dim_event.send(DimensionSpawnEvent {
         swap_to_dim_now: true,
@@ -1,4 +1,5 @@
 use bevy::prelude::Color;
+use serde::{Deserialize, Serialize};
 
 pub const RED: Color = Color::rgba(145. / 255., 54. / 255., 54. / 255., 1.);
 pub const DMG_NUM_RED: Color = Color::rgba(179. / 255., 61. / 255., 61. / 255., 1.);
@@ -27,6 +28,54 @@ pub const YELLOW_2: Color = Color::rgba(223. / 255., 178. / 255., 91. / 255., 1.
 pub const WHITE: Color = Color::rgba(226. / 255., 212. / 255., 177. / 255., 1.);
 pub const PINK: Color = Color::rgba(255. / 255., 136. / 255., 169. / 255., 1.);
 
+// colorblind safe colors, from the Okabe-Ito palette
+pub const CB_ORANGE: Color = Color::rgba(230. / 255., 159. / 255., 0. / 255., 1.);
+pub const CB_SKY_BLUE: Color = Color::rgba(86. / 255., 180. / 255., 233. / 255., 1.);
+pub const CB_BLUISH_GREEN: Color = Color::rgba(0. / 255., 158. / 255., 115. / 255., 1.);
+pub const CB_BLUE: Color = Color::rgba(0. / 255., 114. / 255., 178. / 255., 1.);
+pub const CB_VERMILLION: Color = Color::rgba(213. / 255., 94. / 255., 0. / 255., 1.);
+pub const CB_REDDISH_PURPLE: Color = Color::rgba(204. / 255., 121. / 255., 167. / 255., 1.);
+
+/// Swaps the colors that carry meaning, like item rarity, for ones that stay apart
+/// with color blindness
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ColorPalette {
+    #[default]
+    Default,
+    RedGreen,
+    BlueYellow,
+}
+
+impl ColorPalette {
+    pub fn get_name(&self) -> String {
+        match self {
+            ColorPalette::Default => "Default",
+            ColorPalette::RedGreen => "Red-Green",
+            ColorPalette::BlueYellow => "Blue-Yellow",
+        }
+        .to_string()
+    }
+    /// Common, uncommon, rare and legendary
+    pub fn get_rarity_colors(&self) -> [Color; 4] {
+        match self {
+            ColorPalette::Default => [LIGHT_GREY, UNCOMMON_GREEN, LIGHT_BLUE, LIGHT_RED],
+            ColorPalette::RedGreen => [LIGHT_GREY, CB_BLUE, CB_REDDISH_PURPLE, CB_ORANGE],
+            ColorPalette::BlueYellow => [
+                LIGHT_GREY,
+                CB_BLUISH_GREEN,
+                CB_REDDISH_PURPLE,
+                CB_VERMILLION,
+            ],
+        }
+    }
+    pub fn get_heal_color(&self) -> Color {
+        match self {
+            ColorPalette::RedGreen => CB_SKY_BLUE,
+            _ => DMG_NUM_GREEN,
+        }
+    }
+}
+
 pub fn overwrite_alpha(color: Color, alpha: f32) -> Color {
     Color::rgba(color.r(), color.g(), color.b(), alpha)
 }
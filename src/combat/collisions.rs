@@ -1,18 +1,26 @@
 use crate::{
+    animations::enemy_sprites::EnemyAnimationState,
     animations::DoneAnimation,
+    assets::SpriteAnchor,
     attributes::{
-        modifiers::ModifyHealthEvent, Attack, Defence, Dodge, InvincibilityCooldown, Lifesteal,
-        Thorns,
+        modifiers::ModifyHealthEvent, Attack, CurrentHealth, Defence, Dodge, InvincibilityCooldown,
+        Lifesteal, MaxHealth, Thorns,
     },
     client::analytics::{AnalyticsTrigger, AnalyticsUpdateEvent},
-    enemy::{Mob, MobIsAttacking},
+    enemy::{
+        companion::Companion,
+        raid::{RaidState, Raider, RAIDER_REACH},
+        Mob, MobIsAttacking,
+    },
     inventory::{Inventory, ItemStack},
     item::{
         projectile::{EnemyProjectile, Projectile, ProjectileState},
         Equipment, MainHand, WorldObject,
     },
     player::ModifyTimeFragmentsEvent,
+    proto::proto_param::ProtoParam,
     ui::damage_numbers::DodgeEvent,
+    world::{generation::WorldObjectCache, world_helpers::world_pos_to_tile_pos},
     CustomFlush, GameParam, GameState, Player,
 };
 use bevy::prelude::*;
@@ -28,6 +36,7 @@ impl Plugin for CollisionPlugion {
             (
                 check_melee_hit_collisions,
                 check_boss_to_objects_collisions,
+                check_raider_to_objects_collisions,
                 check_mob_to_player_collisions,
                 check_projectile_hit_mob_collisions,
                 check_projectile_hit_player_collisions,
@@ -53,7 +62,7 @@ fn check_melee_hit_collisions(
     world_obj: Query<Entity, (With<WorldObject>, Without<MainHand>)>,
     lifesteal: Query<&Lifesteal>,
     mut modify_health_events: EventWriter<ModifyHealthEvent>,
-    mobs: Query<(&GlobalTransform, Option<&Frail>), With<Mob>>,
+    mobs: Query<(&GlobalTransform, &Mob, Option<&Frail>, Option<&Thorns>), Without<Companion>>,
     mut hit_tracker: Local<Vec<Entity>>,
 ) {
     if !game.game.player_state.is_attacking {
@@ -74,7 +83,7 @@ fn check_melee_hit_collisions(
             }
 
             hit_tracker.push(hit_entity);
-            let Ok((mob_txfm, frail_option)) = mobs.get(hit_entity) else {
+            let Ok((mob_txfm, mob, frail_option, thorns_option)) = mobs.get(hit_entity) else {
                 continue;
             };
             let (damage, was_crit) = game.calculate_player_damage(
@@ -96,13 +105,34 @@ fn check_melee_hit_collisions(
                 hit_by_mob: None,
                 ignore_tool: false,
             });
+            // reflecting elites send part of the damage back to the player
+            if let Some(thorns) = thorns_option {
+                hit_event.send(HitEvent {
+                    hit_entity: game.game.player,
+                    damage: f32::ceil(damage as f32 * thorns.0 as f32 / 100.) as i32,
+                    dir: delta.normalize_or_zero().truncate(),
+                    hit_with_melee: None,
+                    hit_with_projectile: None,
+                    was_crit: false,
+                    hit_by_mob: Some(mob.clone()),
+                    ignore_tool: false,
+                });
+            }
         }
     }
 }
 fn check_projectile_hit_mob_collisions(
     mut commands: Commands,
     player_attack: Query<(Entity, &Children, Option<&Lifesteal>), With<Player>>,
-    allowed_targets: Query<Entity, (Without<ItemStack>, Without<MainHand>, Without<Projectile>)>,
+    allowed_targets: Query<
+        Entity,
+        (
+            Without<ItemStack>,
+            Without<MainHand>,
+            Without<Projectile>,
+            Without<Companion>,
+        ),
+    >,
     mut hit_event: EventWriter<HitEvent>,
     mut collisions: EventReader<CollisionEvent>,
     mut projectiles: Query<
@@ -116,6 +146,7 @@ fn check_projectile_hit_mob_collisions(
         Without<EnemyProjectile>,
     >,
     is_world_obj: Query<&WorldObject>,
+    reflecting_mobs: Query<(&Mob, &Thorns)>,
     mut children: Query<&Parent>,
     mut modify_health_events: EventWriter<ModifyHealthEvent>,
 ) {
@@ -168,6 +199,18 @@ fn check_projectile_hit_mob_collisions(
                 hit_by_mob: None,
                 was_crit: false,
             });
+            if let Ok((mob, thorns)) = reflecting_mobs.get(*e2) {
+                hit_event.send(HitEvent {
+                    hit_entity: player_e,
+                    damage: f32::ceil(damage as f32 * thorns.0 as f32 / 100.) as i32,
+                    dir: state.direction * -1.,
+                    hit_with_melee: None,
+                    hit_with_projectile: None,
+                    was_crit: false,
+                    hit_by_mob: Some(mob.clone()),
+                    ignore_tool: false,
+                });
+            }
             //non-animating sprites are despawned immediately
             if anim_option.is_none() {
                 commands.entity(proj_entity).despawn_recursive();
@@ -332,11 +375,15 @@ fn check_mob_to_player_collisions(
         ),
         With<Player>,
     >,
-    dmg_source: Query<(&Transform, &Attack, Option<&MobIsAttacking>), Without<Player>>,
+    dmg_source: Query<
+        (&Transform, &Attack, Option<&MobIsAttacking>),
+        (Without<Player>, Without<Companion>),
+    >,
     rapier_context: Res<RapierContext>,
     mut hit_event: EventWriter<HitEvent>,
     mut dodge_event: EventWriter<DodgeEvent>,
     in_i_frame: Query<&InvincibilityTimer>,
+    mut vampiric_mobs: Query<(&mut CurrentHealth, &MaxHealth, &Lifesteal), Without<Player>>,
 ) {
     let (player_e, player_txfm, thorns, defence, dodge, i_frames) = player.single();
     let mut hit_this_frame = false;
@@ -372,9 +419,10 @@ fn check_mob_to_player_collisions(
                     )));
                 continue;
             }
+            let damage = f32::round(attack.0 as f32 * (0.99_f32.powi(defence.0))) as i32;
             hit_event.send(HitEvent {
                 hit_entity: e1,
-                damage: f32::round(attack.0 as f32 * (0.99_f32.powi(defence.0))) as i32,
+                damage,
                 dir: delta.normalize_or_zero().truncate(),
                 hit_with_melee: None,
                 hit_with_projectile: None,
@@ -395,6 +443,14 @@ fn check_mob_to_player_collisions(
                     was_crit: false,
                 });
             }
+            // vampiric elites heal off the damage they deal
+            if let Ok((mut health, max_health, lifesteal)) = vampiric_mobs.get_mut(e2) {
+                if in_i_frame.get(e1).is_err() {
+                    health.0 = (health.0
+                        + f32::floor(damage as f32 * lifesteal.0 as f32 / 100.) as i32)
+                        .min(max_health.0);
+                }
+            }
         }
     }
 }
@@ -444,3 +500,65 @@ fn check_boss_to_objects_collisions(
         }
     }
 }
+
+fn check_raider_to_objects_collisions(
+    objs: Query<(Entity, &GlobalTransform, &WorldObject), (With<WorldObject>, Without<ItemStack>)>,
+    mut raiders: Query<(Entity, &GlobalTransform, &Attack, &Mob, &mut Raider), With<RaidState>>,
+    mut hit_event: EventWriter<HitEvent>,
+    mut commands: Commands,
+    time: Res<Time>,
+    world_obj_cache: Res<WorldObjectCache>,
+    proto_param: ProtoParam,
+) {
+    let is_placed_by_player = |txfm: &GlobalTransform, obj: &WorldObject| {
+        let anchor = proto_param
+            .get_component::<SpriteAnchor, _>(*obj)
+            .unwrap_or(&SpriteAnchor(Vec2::ZERO));
+        let tile_pos = world_pos_to_tile_pos(txfm.translation().truncate() - anchor.0);
+        world_obj_cache.player_placed_objs.contains(&tile_pos)
+    };
+    for (raider_e, raider_txfm, attack, mob, mut raider) in raiders.iter_mut() {
+        raider.attack_cooldown.tick(time.delta());
+        let raider_pos = raider_txfm.translation().truncate();
+
+        // keep hitting the same obj until it breaks, otherwise look for the closest one in reach
+        let target = raider
+            .target_obj
+            .and_then(|e| objs.get(e).ok())
+            .filter(|(_, obj_txfm, _)| {
+                obj_txfm.translation().truncate().distance(raider_pos) <= RAIDER_REACH
+            })
+            .or_else(|| {
+                objs.iter()
+                    .filter(|(_, txfm, obj)| obj.is_raid_target(is_placed_by_player(txfm, obj)))
+                    .map(|o| (o, o.1.translation().truncate().distance(raider_pos)))
+                    .filter(|(_, dist)| *dist <= RAIDER_REACH)
+                    .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                    .map(|(o, _)| o)
+            });
+        let Some((obj_e, obj_txfm, _obj)) = target else {
+            raider.target_obj = None;
+            continue;
+        };
+        raider.target_obj = Some(obj_e);
+        if !raider.attack_cooldown.finished() {
+            continue;
+        }
+        raider.attack_cooldown.reset();
+        commands
+            .entity(raider_e)
+            .insert(EnemyAnimationState::Attack);
+
+        let delta = obj_txfm.translation() - raider_txfm.translation();
+        hit_event.send(HitEvent {
+            hit_entity: obj_e,
+            damage: f32::round(attack.0 as f32) as i32,
+            dir: delta.normalize_or_zero().truncate(),
+            hit_with_melee: Some(WorldObject::WoodAxe),
+            hit_with_projectile: None,
+            ignore_tool: true,
+            hit_by_mob: Some(mob.clone()),
+            was_crit: false,
+        });
+    }
+}
@@ -20,6 +20,8 @@ use crate::{
     },
     custom_commands::CommandsExt,
     enemy::{
+        companion::{Companion, Downed},
+        elite::EliteAffixes,
         red_mushking::{DeathState, ReturnToShrineState, SummonAttackState},
         Mob, MobLevel,
     },
@@ -34,7 +36,7 @@ use crate::{
         skills::{PlayerSkills, Skill},
     },
     proto::proto_param::ProtoParam,
-    world::{world_helpers::world_pos_to_tile_pos, TileMapPosition},
+    world::{dimension::EraManager, world_helpers::world_pos_to_tile_pos, TileMapPosition},
     AppExt, CustomFlush, Game, GameParam, GameState, Player, YSort, DEBUG,
 };
 
@@ -139,25 +141,28 @@ fn handle_enemy_death(
     proto_param: ProtoParam,
     mut death_events: EventReader<EnemyDeathEvent>,
     loot_tables: Query<&LootTable>,
-    mob_data: Query<(&Mob, &ExperienceReward, &MobLevel)>,
+    mob_data: Query<(&Mob, &ExperienceReward, &MobLevel, Option<&EliteAffixes>)>,
     mut player_xp: Query<(&mut PlayerLevel, &PlayerSkills)>,
     mut proto_commands: ProtoCommands,
     loot_bonus: Query<&LootRateBonus>,
 ) {
     for death_event in death_events.iter() {
-        let Ok((mob, mob_xp, mob_lvl)) = mob_data.get(death_event.entity) else {
+        let Ok((mob, mob_xp, mob_lvl, affixes)) = mob_data.get(death_event.entity) else {
             continue;
         };
         let (mut player_level, skills) = player_xp.single_mut();
         let is_crit_bonus = skills.get(Skill::CritLoot) && death_event.killed_by_crit;
-        // drop loot
+        // drop loot, elites roll the table once more for each affix they have
+        let num_rolls = 1 + affixes.map(|a| a.0.len()).unwrap_or(0);
         if let Ok(loot_table) = loot_tables.get(death_event.entity) {
-            for drop in LootTablePlugin::get_drops(
-                loot_table,
-                &proto_param,
-                loot_bonus.single().0 + if is_crit_bonus { 25 } else { 0 },
-                Some(mob_lvl.0),
-            ) {
+            for drop in (0..num_rolls).flat_map(|_| {
+                LootTablePlugin::get_drops(
+                    loot_table,
+                    &proto_param,
+                    loot_bonus.single().0 + if is_crit_bonus { 25 } else { 0 },
+                    Some(mob_lvl.0),
+                )
+            }) {
                 let mut rng = rand::thread_rng();
                 let d = if mob.is_boss() { 30. } else { 10. };
                 let drop_offset = Vec2::new(rng.gen_range(-d..d), rng.gen_range(-d..d));
@@ -245,6 +250,8 @@ pub fn handle_hits(
         Option<&RequiredEquipmentType>,
         Option<&InvincibilityCooldown>,
         Option<&CombatShrineMob>,
+        Option<&Companion>,
+        Option<&Downed>,
     )>,
     mut hit_events: EventReader<HitEvent>,
     mut enemy_death_events: EventWriter<EnemyDeathEvent>,
@@ -269,6 +276,8 @@ pub fn handle_hits(
             hit_req_option,
             i_frame_option,
             shrine_option,
+            companion_option,
+            downed_option,
         )) = health.get_mut(hit.hit_entity)
         {
             // don't shoot a dead horse...
@@ -335,7 +344,13 @@ pub fn handle_hits(
                         Timer::from_seconds(i_frames.0, TimerMode::Once),
                     ));
                 }
-                if hit_health.0 <= 0 && game.player_query.single().0 != e {
+                if hit_health.0 <= 0 && companion_option.is_some() {
+                    // companions get knocked out instead, and can be revived.
+                    // already downed ones keep their timer running
+                    if downed_option.is_none() {
+                        commands.entity(e).insert(Downed::default());
+                    }
+                } else if hit_health.0 <= 0 && game.player_query.single().0 != e {
                     commands.entity(e).insert(MarkedForDeath);
                     enemy_death_events.send(EnemyDeathEvent {
                         entity: e,
@@ -373,9 +388,15 @@ pub fn cleanup_marked_for_death_entities(
     mut commands: Commands,
     dead_query: Query<(Entity, &Mob), With<MarkedForDeath>>,
     mut analytics: EventWriter<AnalyticsUpdateEvent>,
+    mut era: ResMut<EraManager>,
 ) {
     for (e, mob) in dead_query.iter() {
         if mob.is_boss() {
+            // killing an era's boss unlocks travel to the next era
+            let curr_era = era.current_era.clone();
+            if !era.bosses_killed.contains(&curr_era) {
+                era.bosses_killed.push(curr_era);
+            }
             commands
                 .entity(e)
                 .insert(DeathState)
@@ -1,13 +1,18 @@
-use std::cmp::min;
+use std::{cmp::min, ops::Range};
 
 use crate::{
-    inventory::{InventoryError, InventoryItemStack, ItemStack, MAX_STACK_SIZE},
-    item::{CraftedItemEvent, WorldObject},
+    inventory::{
+        InventoryError, InventoryItemStack, InventorySortOrder, ItemStack, MAX_STACK_SIZE,
+    },
+    item::{CraftedItemEvent, CraftingQueue, WorldObject},
     ui::{mark_slot_dirty, InventorySlotState, InventorySlotType, UIContainersParam},
     world::TileMapPosition,
 };
 
-use bevy::{prelude::*, utils::HashMap};
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
 use serde::{Deserialize, Serialize};
 
 pub const CONTAINER_UNIT: Option<InventoryItemStack> = None;
@@ -20,6 +25,8 @@ pub struct Container {
 #[derive(Resource, Default, Debug, Clone)]
 pub struct ContainerRegistry {
     pub containers: HashMap<TileMapPosition, Container>,
+    /// the queues of stations that take time to craft, by the station's position
+    pub crafting_queues: HashMap<TileMapPosition, CraftingQueue>,
 }
 
 impl Container {
@@ -32,11 +39,19 @@ impl Container {
         //TODO: maybe move the actual inv to a type in this file, and move this fn into that struct
         (0..self.items.len()).find(|&i| self.items[i].is_none())
     }
-    pub fn get_first_empty_hotbar_slot(&self) -> Option<usize> {
-        (0..6).find(|&i| self.items[i].is_none())
+    pub fn get_first_empty_hotbar_slot(&self, hotbar_size: usize) -> Option<usize> {
+        (0..hotbar_size).find(|&i| self.items[i].is_none())
+    }
+    pub fn get_first_empty_non_hotbar_slot(&self, hotbar_size: usize) -> Option<usize> {
+        (hotbar_size..self.items.len()).find(|&i| self.items[i].is_none())
     }
-    pub fn get_first_empty_non_hotbar_slot(&self) -> Option<usize> {
-        (6..self.items.len()).find(|&i| self.items[i].is_none())
+    pub fn swap_slots(&mut self, a: usize, b: usize) {
+        self.items.swap(a, b);
+        for slot in [a, b] {
+            if let Some(item) = self.items[slot].as_mut() {
+                item.slot = slot;
+            }
+        }
     }
 
     pub fn get_slot_for_item_in_container(&self, obj: &WorldObject) -> Option<usize> {
@@ -110,11 +125,98 @@ impl Container {
             }
         }
     }
+    /// Moves the item in `slot` into the first slot of `target_container` that accepts it,
+    /// topping up a matching stack before taking an empty slot
+    pub fn move_item_to_allowed_slot(
+        &mut self,
+        target_container: &mut Container,
+        slot: usize,
+        allowed_slots: &[Vec<WorldObject>],
+    ) {
+        let Some(mut item) = self.items[slot].clone() else {
+            return;
+        };
+        for (target_slot, allowed) in allowed_slots.iter().enumerate() {
+            if !allowed.contains(item.get_obj()) {
+                continue;
+            }
+            match target_container.items[target_slot].clone() {
+                Some(mut existing_item) => {
+                    if !existing_item.item_stack.is_stackable(&item.item_stack) {
+                        continue;
+                    }
+                    let moved = min(
+                        MAX_STACK_SIZE - existing_item.item_stack.count,
+                        item.item_stack.count,
+                    );
+                    if moved == 0 {
+                        continue;
+                    }
+                    target_container.items[target_slot] = existing_item.modify_count(moved as i8);
+                    self.items[slot] = item.modify_count(-(moved as i8));
+                }
+                None => {
+                    target_container.items[target_slot] = Some(item.modify_slot(target_slot));
+                    self.items[slot] = None;
+                }
+            }
+            return;
+        }
+    }
+    /// Moves every item in `slots` that `target_container` already holds some of into it
+    pub fn quick_stack_to_target_container(
+        &mut self,
+        target_container: &mut Container,
+        slots: impl Iterator<Item = usize>,
+    ) {
+        for slot in slots {
+            let Some(item) = &self.items[slot] else {
+                continue;
+            };
+            if target_container
+                .get_slot_for_item_in_container(item.get_obj())
+                .is_some()
+            {
+                self.move_item_to_target_container(target_container, slot);
+            }
+        }
+    }
+    /// Merges partial stacks in `slots` and lays them back out in `order`.
+    /// Items in `locked_slots` stay where they are and are skipped over.
+    pub fn sort_items(
+        &mut self,
+        slots: Range<usize>,
+        locked_slots: &HashSet<usize>,
+        order: InventorySortOrder,
+    ) {
+        let free_slots: Vec<usize> = slots.filter(|i| !locked_slots.contains(i)).collect();
+        let mut stacks: Vec<ItemStack> = vec![];
+        for slot in free_slots.iter() {
+            let Some(item) = self.items[*slot].take() else {
+                continue;
+            };
+            let mut stack = item.item_stack;
+            for existing in stacks.iter_mut() {
+                if existing.is_stackable(&stack) {
+                    let moved = min(MAX_STACK_SIZE - existing.count, stack.count);
+                    existing.count += moved;
+                    stack.count -= moved;
+                }
+            }
+            if stack.count > 0 {
+                stacks.push(stack);
+            }
+        }
+        stacks.sort_by(|a, b| order.compare(a, b));
+        for (slot, stack) in free_slots.into_iter().zip(stacks) {
+            self.items[slot] = Some(InventoryItemStack::new(stack, slot));
+        }
+    }
     //TODO: there has to be a nice way to merge the two move_item_between_containers fn
     /// use only on inventory container
-    pub fn move_item_from_hotbar_to_inv_or_vice_versa(&mut self, slot: usize) {
+    pub fn move_item_from_hotbar_to_inv_or_vice_versa(&mut self, slot: usize, hotbar_size: usize) {
         let inv_item = self.items[slot].clone();
-        let is_from_hotbar = slot < 6;
+        let is_from_hotbar = slot < hotbar_size;
         if let Some(mut inv_item_stack) = inv_item {
             let stack_count = inv_item_stack.item_stack.count;
             if let Some(existing_item_slot) = Self::get_slot_for_item_in_container_with_space(
@@ -122,15 +224,19 @@ impl Container {
                 &inv_item_stack.item_stack,
                 Some(slot),
             ) {
-                if is_from_hotbar && existing_item_slot < 6 {
-                    if let Some(next_avail_inv_slot) = Self::get_first_empty_non_hotbar_slot(self) {
+                if is_from_hotbar && existing_item_slot < hotbar_size {
+                    if let Some(next_avail_inv_slot) =
+                        Self::get_first_empty_non_hotbar_slot(self, hotbar_size)
+                    {
                         self.items[next_avail_inv_slot] =
                             Some(inv_item_stack.modify_slot(next_avail_inv_slot));
                         self.items[slot] = None;
                     }
                     return;
-                } else if !is_from_hotbar && existing_item_slot >= 6 {
-                    if let Some(next_avail_hotbar_slot) = Self::get_first_empty_hotbar_slot(self) {
+                } else if !is_from_hotbar && existing_item_slot >= hotbar_size {
+                    if let Some(next_avail_hotbar_slot) =
+                        Self::get_first_empty_hotbar_slot(self, hotbar_size)
+                    {
                         self.items[next_avail_hotbar_slot] =
                             Some(inv_item_stack.modify_slot(next_avail_hotbar_slot));
                         self.items[slot] = None;
@@ -154,12 +260,15 @@ impl Container {
                     self.items[slot] = None;
                 }
             } else if !is_from_hotbar {
-                if let Some(next_avail_slot) = Self::get_first_empty_hotbar_slot(self) {
+                if let Some(next_avail_slot) = Self::get_first_empty_hotbar_slot(self, hotbar_size)
+                {
                     self.items[next_avail_slot] =
                         Some(inv_item_stack.modify_slot(next_avail_slot));
                     self.items[slot] = None;
                 }
-            } else if let Some(next_avail_slot) = Self::get_first_empty_non_hotbar_slot(self) {
+            } else if let Some(next_avail_slot) =
+                Self::get_first_empty_non_hotbar_slot(self, hotbar_size)
+            {
                 self.items[next_avail_slot] =
                     Some(inv_item_stack.modify_slot(next_avail_slot));
                 self.items[slot] = None;
@@ -224,6 +333,8 @@ impl Container {
             let new_item = Some(dragging_item.copy_with_count(min(combined_size, MAX_STACK_SIZE)));
             cont_param.crafted_event.send(CraftedItemEvent {
                 obj: dragging_item_type,
+                count: 1,
+                ingredients_used: false,
             });
             new_item
         } else {
@@ -262,8 +373,8 @@ impl Container {
                 .get_slot_for_item_in_container(&item)
                 .expect("player crafted item but does not have the required ingredients?");
             let stack = self.items[ingredient_slot].as_mut().unwrap();
-            if stack.item_stack.count >= amount {
-                self.items[ingredient_slot] = stack.modify_count(-(amount as i8));
+            if stack.item_stack.count >= remaining_cost {
+                self.items[ingredient_slot] = stack.modify_count(-(remaining_cost as i8));
                 remaining_cost = 0_usize;
             } else {
                 let count = stack.item_stack.count;
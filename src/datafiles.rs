@@ -31,6 +31,12 @@ pub fn analytics_dir() -> PathBuf {
     path
 }
 
+pub fn blueprints_dir() -> PathBuf {
+    let mut path = game_dir();
+    path.push("blueprints");
+    path
+}
+
 pub fn save_file() -> PathBuf {
     let mut path = game_dir();
     path.push("save_state.json");
@@ -42,3 +48,15 @@ pub fn game_data() -> PathBuf {
     path.push("game_data.json");
     path
 }
+
+pub fn keymap_file() -> PathBuf {
+    let mut path = game_dir();
+    path.push("keymap.json");
+    path
+}
+
+pub fn settings_file() -> PathBuf {
+    let mut path = game_dir();
+    path.push("settings.json");
+    path
+}
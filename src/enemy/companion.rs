@@ -0,0 +1,431 @@
+use std::{fs::File, io::BufReader};
+
+use bevy::{ecs::system::EntityCommands, prelude::*};
+use bevy_proto::prelude::{ProtoCommands, Prototypes, ReflectSchematic, Schematic};
+use bevy_rapier2d::prelude::KinematicCharacterController;
+use seldom_state::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ai::{
+        pathfinding::{get_next_tile_A_star, world_pos_to_AIPos, AIPos_to_world_pos},
+        FollowState, IdleState, LeapAttackState, ProjectileAttackState, SearchState,
+    },
+    animations::enemy_sprites::EnemyAnimationState,
+    attributes::{Attack, CurrentHealth, MaxHealth},
+    client::{is_not_paused, CurrentRunSaveData},
+    colors::{GREY, PINK},
+    combat::{HitEvent, MarkedForDeath},
+    custom_commands::CommandsExt,
+    datafiles,
+    inputs::FacingDirection,
+    inventory::Inventory,
+    item::WorldObject,
+    status_effects::Slow,
+    ui::damage_numbers::spawn_floating_text_with_shadow,
+    world::TILE_SIZE,
+    GameParam, GameState, PLAYER_MOVE_SPEED,
+};
+
+use super::{raid::RaidState, CombatAlignment, Mob};
+
+/// Companions will not wander off after a target further than this from the player
+pub const COMPANION_LEASH_RANGE: f32 = TILE_SIZE.x * 10.;
+/// Companions set to attack go after any unfriendly mob within this range of them
+pub const COMPANION_AGGRO_RANGE: f32 = TILE_SIZE.x * 6.;
+pub const COMPANION_REACH: f32 = TILE_SIZE.x * 1.25;
+/// Companions further than this from the player teleport back to them
+const COMPANION_TELEPORT_RANGE: f32 = TILE_SIZE.x * 15.;
+/// How long a companion stays downed before it is lost for good
+const COMPANION_DOWNED_SECS: f32 = 60.;
+
+pub struct CompanionPlugin;
+
+impl Plugin for CompanionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<FeedMobEvent>()
+            .add_system(spawn_saved_companions.in_schedule(OnEnter(GameState::Main)))
+            .add_systems(
+                (
+                    handle_feed_mob,
+                    handle_companion_targets_on_player_hit.run_if(is_not_paused),
+                    companion.run_if(is_not_paused),
+                    handle_companion_downed,
+                    tick_downed_companions.run_if(is_not_paused),
+                )
+                    .in_set(OnUpdate(GameState::Main)),
+            );
+    }
+}
+
+/// Mobs with this can be tamed by feeding them one of `foods` enough times
+#[derive(Component, Reflect, FromReflect, Schematic, Default, Clone, Debug)]
+#[reflect(Component, Schematic)]
+pub struct Tameable {
+    pub foods: Vec<WorldObject>,
+    pub feeds_to_tame: u8,
+}
+#[derive(Component, Default, Debug)]
+pub struct TameProgress(pub u8);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompanionCommand {
+    #[default]
+    Follow,
+    Stay,
+    Attack,
+}
+
+#[derive(Component, Clone, Debug)]
+pub struct Companion {
+    pub command: CompanionCommand,
+    pub target: Option<Entity>,
+    pub attack_cooldown: Timer,
+}
+impl Companion {
+    pub fn new(command: CompanionCommand) -> Self {
+        Self {
+            command,
+            target: None,
+            attack_cooldown: Timer::from_seconds(0.8, TimerMode::Once),
+        }
+    }
+}
+
+/// A companion at 0 HP. It can be revived by feeding it before the timer runs out,
+/// otherwise it is gone for good.
+#[derive(Component, Debug)]
+pub struct Downed(pub Timer);
+impl Default for Downed {
+    fn default() -> Self {
+        Self(Timer::from_seconds(COMPANION_DOWNED_SECS, TimerMode::Once))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CompanionSaveData {
+    pub mob: Mob,
+    pub command: CompanionCommand,
+    pub health: i32,
+}
+
+pub struct FeedMobEvent {
+    pub mob: Entity,
+    pub food: WorldObject,
+    pub slot: usize,
+}
+
+// This trigger hands tamed mobs over to the companion AI
+#[derive(Clone, Copy, Reflect)]
+pub struct IsCompanion;
+
+impl BoolTrigger for IsCompanion {
+    type Param<'w, 's> = Query<'w, 's, &'static Companion>;
+
+    fn trigger(&self, entity: Entity, companions: Self::Param<'_, '_>) -> bool {
+        companions.get(entity).is_ok()
+    }
+}
+
+// Entities in the `Companion` state follow the player and fight whatever the player fights,
+// based on their current [CompanionCommand]
+#[derive(Clone, Component, Reflect)]
+#[component(storage = "SparseSet")]
+pub struct CompanionState {
+    pub speed: f32,
+}
+
+fn handle_feed_mob(
+    mut commands: Commands,
+    mut feed_events: EventReader<FeedMobEvent>,
+    mut inv: Query<&mut Inventory>,
+    mut mobs: Query<(
+        &Tameable,
+        &GlobalTransform,
+        &mut CurrentHealth,
+        &MaxHealth,
+        Option<&mut TameProgress>,
+        Option<&Companion>,
+        Option<&Downed>,
+    )>,
+    asset_server: Res<AssetServer>,
+) {
+    for feed in feed_events.iter() {
+        let Ok((tameable, txfm, mut hp, max_hp, progress_option, companion_option, downed_option)) =
+            mobs.get_mut(feed.mob)
+        else {
+            continue;
+        };
+        if !tameable.foods.contains(&feed.food)
+            || (companion_option.is_some() && downed_option.is_none())
+        {
+            continue;
+        }
+        let mut inv = inv.single_mut();
+        let Some(mut food_item) = inv.items.items[feed.slot].clone() else {
+            continue;
+        };
+        inv.items.items[feed.slot] = food_item.modify_count(-1);
+        let text_pos = txfm.translation() + Vec3::new(0., 12., 1.);
+
+        if downed_option.is_some() {
+            hp.0 = max_hp.0 / 2;
+            reset_to_idle(commands.entity(feed.mob).remove::<Downed>())
+                .insert(CombatAlignment::Friendly);
+            spawn_floating_text_with_shadow(
+                &mut commands,
+                &asset_server,
+                text_pos,
+                PINK,
+                "Revived!".to_string(),
+            );
+            continue;
+        }
+
+        let fed = progress_option.map_or(0, |p| p.0) + 1;
+        if fed >= tameable.feeds_to_tame {
+            reset_to_idle(commands.entity(feed.mob).remove::<TameProgress>()).insert((
+                Companion::new(CompanionCommand::Follow),
+                CombatAlignment::Friendly,
+            ));
+            spawn_floating_text_with_shadow(
+                &mut commands,
+                &asset_server,
+                text_pos,
+                PINK,
+                "Tamed!".to_string(),
+            );
+        } else {
+            commands.entity(feed.mob).insert(TameProgress(fed));
+            spawn_floating_text_with_shadow(
+                &mut commands,
+                &asset_server,
+                text_pos,
+                PINK,
+                format!("{fed}/{}", tameable.feeds_to_tame),
+            );
+        }
+    }
+}
+
+/// Drops whatever the mob was doing so the new [IsCompanion] state machine can take over
+fn reset_to_idle<'w, 's, 'a, 'b>(
+    e_cmds: &'b mut EntityCommands<'w, 's, 'a>,
+) -> &'b mut EntityCommands<'w, 's, 'a> {
+    e_cmds
+        .remove::<FollowState>()
+        .remove::<SearchState>()
+        .remove::<LeapAttackState>()
+        .remove::<ProjectileAttackState>()
+        .remove::<RaidState>()
+        .remove::<CompanionState>()
+        .insert(IdleState {
+            walk_timer: Timer::from_seconds(2., TimerMode::Repeating),
+            direction: FacingDirection::new_rand_dir(rand::thread_rng()),
+            speed: 0.5,
+            is_stopped: true,
+        })
+}
+
+fn handle_companion_targets_on_player_hit(
+    mut hit_events: EventReader<HitEvent>,
+    mut companions: Query<&mut Companion, Without<Downed>>,
+    mobs: Query<Entity, (With<Mob>, Without<Companion>)>,
+) {
+    for hit in hit_events.iter() {
+        // only hits from the player have no mob source
+        if hit.hit_by_mob.is_some() || !mobs.contains(hit.hit_entity) {
+            continue;
+        }
+        for mut companion in companions.iter_mut() {
+            if companion.command != CompanionCommand::Stay {
+                companion.target = Some(hit.hit_entity);
+            }
+        }
+    }
+}
+
+pub fn companion(
+    mut transforms: Query<&mut Transform>,
+    mut mover: Query<&mut KinematicCharacterController>,
+    mut companions: Query<(
+        Entity,
+        &CompanionState,
+        &mut Companion,
+        &Mob,
+        Option<&Attack>,
+        &EnemyAnimationState,
+        Option<&Slow>,
+    )>,
+    enemies: Query<
+        (Entity, &GlobalTransform, &CombatAlignment),
+        (With<Mob>, Without<Companion>, Without<MarkedForDeath>),
+    >,
+    mut hit_event: EventWriter<HitEvent>,
+    mut commands: Commands,
+    time: Res<Time>,
+    mut game: GameParam,
+) {
+    let player_pos = game.player().position.truncate();
+    for (entity, state, mut companion, mob, attack_option, anim_state, slowed_option) in
+        companions.iter_mut()
+    {
+        companion.attack_cooldown.tick(time.delta());
+        let companion_collider_offset = Vec2::new(0., -3.);
+        let companion_pos = transforms.get(entity).unwrap().translation.truncate();
+        let companion_translation = AIPos_to_world_pos(world_pos_to_AIPos(
+            companion_pos + companion_collider_offset,
+        ));
+
+        if companion.command != CompanionCommand::Stay
+            && companion_pos.distance(player_pos) > COMPANION_TELEPORT_RANGE
+        {
+            let mut txfm = transforms.get_mut(entity).unwrap();
+            txfm.translation = player_pos.extend(txfm.translation.z);
+            companion.target = None;
+            continue;
+        }
+
+        // drop targets that died or that we would have to leave the player behind to chase
+        let target_pos = companion
+            .target
+            .and_then(|t| enemies.get(t).ok())
+            .map(|(_, t, _)| t.translation().truncate())
+            .filter(|pos| pos.distance(player_pos) <= COMPANION_LEASH_RANGE);
+        if target_pos.is_none() {
+            companion.target = None;
+        }
+        if companion.command == CompanionCommand::Attack && companion.target.is_none() {
+            companion.target = enemies
+                .iter()
+                .filter(|(_, _, a)| a != &&CombatAlignment::Passive)
+                .map(|(e, t, _)| (e, t.translation().truncate().distance(companion_pos)))
+                .filter(|(_, dist)| *dist <= COMPANION_AGGRO_RANGE)
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(e, _)| e);
+        }
+        let target_pos = companion
+            .target
+            .and_then(|t| enemies.get(t).ok())
+            .map(|(_, t, _)| t.translation().truncate());
+
+        let move_to = if let Some(target_pos) = target_pos {
+            if companion_pos.distance(target_pos) <= COMPANION_REACH {
+                if let Some(attack) = attack_option {
+                    if companion.attack_cooldown.finished() {
+                        companion.attack_cooldown.reset();
+                        hit_event.send(HitEvent {
+                            hit_entity: companion.target.unwrap(),
+                            damage: attack.0,
+                            dir: (target_pos - companion_pos).normalize_or_zero(),
+                            hit_with_melee: None,
+                            hit_with_projectile: None,
+                            ignore_tool: false,
+                            hit_by_mob: Some(mob.clone()),
+                            was_crit: false,
+                        });
+                        commands.entity(entity).insert(EnemyAnimationState::Attack);
+                    }
+                }
+                None
+            } else {
+                Some(target_pos)
+            }
+        } else if companion.command == CompanionCommand::Stay
+            || companion_pos.distance(player_pos) <= TILE_SIZE.x * 2.
+        {
+            None
+        } else {
+            Some(player_pos)
+        };
+
+        let Some(move_to) = move_to else {
+            if anim_state == &EnemyAnimationState::Walk {
+                commands.entity(entity).insert(EnemyAnimationState::Idle);
+            }
+            continue;
+        };
+        let next_tile =
+            get_next_tile_A_star(&move_to, &companion_translation, &mut game).unwrap_or(move_to);
+        let delta = (next_tile - companion_translation).normalize_or_zero();
+        mover.get_mut(entity).unwrap().translation = Some(
+            delta
+                * state.speed
+                * PLAYER_MOVE_SPEED
+                * time.delta_seconds()
+                * (1. - slowed_option.map_or(0., |s| s.num_stacks as f32 * 0.15)),
+        );
+        commands
+            .entity(entity)
+            .insert(FacingDirection::from_translation(delta));
+        if anim_state != &EnemyAnimationState::Walk && anim_state != &EnemyAnimationState::Hit {
+            commands.entity(entity).insert(EnemyAnimationState::Walk);
+        }
+    }
+}
+
+/// Companions that hit 0 HP are knocked out instead of killed, see [Downed]
+fn handle_companion_downed(
+    mut commands: Commands,
+    downed: Query<Entity, Added<Downed>>,
+    mut revived: RemovedComponents<Downed>,
+    mut sprites: Query<&mut TextureAtlasSprite>,
+) {
+    for e in downed.iter() {
+        if let Ok(mut sprite) = sprites.get_mut(e) {
+            sprite.color = GREY;
+        }
+        commands
+            .entity(e)
+            .remove::<StateMachine>()
+            .remove::<CompanionState>()
+            .insert(EnemyAnimationState::Idle);
+    }
+    for e in revived.iter() {
+        if let Ok(mut sprite) = sprites.get_mut(e) {
+            sprite.color = Color::WHITE;
+        }
+    }
+}
+
+fn tick_downed_companions(
+    mut commands: Commands,
+    mut downed: Query<(Entity, &mut Downed)>,
+    time: Res<Time>,
+) {
+    for (e, mut downed) in downed.iter_mut() {
+        downed.0.tick(time.delta());
+        if downed.0.finished() {
+            commands.entity(e).despawn_recursive();
+        }
+    }
+}
+
+fn spawn_saved_companions(
+    mut commands: Commands,
+    mut proto_commands: ProtoCommands,
+    prototypes: Prototypes,
+) {
+    let Ok(save_file) = File::open(datafiles::save_file()) else {
+        return;
+    };
+    let reader = BufReader::new(save_file);
+    match serde_json::from_reader::<_, CurrentRunSaveData>(reader) {
+        Ok(data) => {
+            for saved in data.companions {
+                if let Some(e) = proto_commands.spawn_from_proto(
+                    saved.mob.clone(),
+                    &prototypes,
+                    data.player_transform,
+                ) {
+                    commands.entity(e).insert((
+                        Companion::new(saved.command),
+                        CombatAlignment::Friendly,
+                        CurrentHealth(saved.health),
+                    ));
+                }
+            }
+        }
+        Err(err) => error!("Failed to load companions from file {err:?}"),
+    }
+}
@@ -0,0 +1,311 @@
+use bevy::{prelude::*, sprite::Anchor};
+use bevy_proto::prelude::{ProtoCommands, Prototypes};
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{
+    ai::FollowState,
+    attributes::{Attack, InvincibilityCooldown, Lifesteal, Thorns},
+    client::is_not_paused,
+    colors::RED,
+    combat::{
+        cleanup_marked_for_death_entities, handle_hits,
+        status_effects::{Slow, StatusEffect, StatusEffectEvent},
+        EnemyDeathEvent, HitEvent,
+    },
+    custom_commands::CommandsExt,
+    item::projectile::{EnemyProjectile, Projectile},
+    proto::proto_param::ProtoParam,
+    ui::damage_numbers::spawn_text,
+    GameParam, GameState,
+};
+
+use super::{handle_new_mob_state_machine, CombatAlignment, EliteMob, FollowSpeed, Mob};
+
+/// How many affixes an elite rolls, and how likely each amount is
+const ELITE_AFFIX_COUNT_WEIGHTS: [(usize, f32); 3] = [(1, 0.6), (2, 0.3), (3, 0.1)];
+const ELITE_AFFIX_WEIGHTS: [(EliteAffix, f32); 7] = [
+    (EliteAffix::Fast, 1.),
+    (EliteAffix::Shielded, 1.),
+    (EliteAffix::Vampiric, 0.8),
+    (EliteAffix::Explosive, 0.8),
+    (EliteAffix::Summoner, 0.5),
+    (EliteAffix::FrostAura, 0.7),
+    (EliteAffix::Reflect, 0.6),
+];
+const FAST_SPEED_MULTIPLIER: f32 = 1.35;
+const SHIELDED_I_FRAMES: f32 = 0.5;
+const VAMPIRIC_LIFESTEAL: i32 = 50;
+const REFLECT_THORNS: i32 = 35;
+const FROST_AURA_RADIUS: f32 = 40.;
+const EXPLOSION_RADIUS: f32 = 28.;
+const EXPLOSION_FUSE_SECS: f32 = 0.6;
+const MAX_SUMMONED_MINIONS: usize = 2;
+
+pub struct ElitePlugin;
+
+impl Plugin for ElitePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            (
+                roll_elite_affixes.before(handle_new_mob_state_machine),
+                handle_explosive_elite_death
+                    .after(handle_hits)
+                    .before(cleanup_marked_for_death_entities),
+                tick_death_explosions.run_if(is_not_paused),
+                tick_elite_summoners.run_if(is_not_paused),
+                apply_frost_auras.run_if(is_not_paused),
+            )
+                .in_set(OnUpdate(GameState::Main)),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EliteAffix {
+    Fast,
+    Shielded,
+    Vampiric,
+    Explosive,
+    Summoner,
+    FrostAura,
+    Reflect,
+}
+
+impl EliteAffix {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            EliteAffix::Fast => "Fast",
+            EliteAffix::Shielded => "Shielded",
+            EliteAffix::Vampiric => "Vampiric",
+            EliteAffix::Explosive => "Explosive",
+            EliteAffix::Summoner => "Summoner",
+            EliteAffix::FrostAura => "Frost",
+            EliteAffix::Reflect => "Reflect",
+        }
+    }
+}
+
+/// The affixes an [EliteMob] rolled when it spawned. Each one grants an extra
+/// roll on the mob's loot table when it dies.
+#[derive(Component, Debug, Clone)]
+pub struct EliteAffixes(pub Vec<EliteAffix>);
+
+impl EliteAffixes {
+    pub fn has(&self, affix: EliteAffix) -> bool {
+        self.0.contains(&affix)
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct FrostAura {
+    pub tick_timer: Timer,
+}
+
+#[derive(Component, Debug)]
+pub struct EliteSummoner {
+    pub summon_timer: Timer,
+    pub minions: Vec<Entity>,
+}
+
+/// Left behind by an explosive elite when it dies, blows up once the fuse runs out
+#[derive(Component, Debug)]
+pub struct DeathExplosion {
+    pub fuse: Timer,
+    pub damage: i32,
+    pub mob: Mob,
+}
+
+fn roll_elite_affixes(
+    mut commands: Commands,
+    mut elites: Query<(Entity, &mut FollowSpeed), Added<EliteMob>>,
+) {
+    let mut rng = rand::thread_rng();
+    for (e, mut follow_speed) in elites.iter_mut() {
+        let num_affixes = ELITE_AFFIX_COUNT_WEIGHTS
+            .choose_weighted(&mut rng, |(_, weight)| *weight)
+            .map_or(1, |(count, _)| *count);
+        let affixes: Vec<EliteAffix> = ELITE_AFFIX_WEIGHTS
+            .choose_multiple_weighted(&mut rng, num_affixes, |(_, weight)| *weight)
+            .map_or(vec![], |chosen| {
+                chosen.map(|(affix, _)| *affix).collect::<Vec<_>>()
+            });
+
+        let mut e_cmds = commands.entity(e);
+        for affix in affixes.iter() {
+            match affix {
+                // runs before the state machine is built, so the faster speed is used for chasing
+                EliteAffix::Fast => follow_speed.0 *= FAST_SPEED_MULTIPLIER,
+                EliteAffix::Shielded => {
+                    e_cmds.insert(InvincibilityCooldown(SHIELDED_I_FRAMES));
+                }
+                EliteAffix::Vampiric => {
+                    e_cmds.insert(Lifesteal(VAMPIRIC_LIFESTEAL));
+                }
+                EliteAffix::Reflect => {
+                    e_cmds.insert(Thorns(REFLECT_THORNS));
+                }
+                EliteAffix::FrostAura => {
+                    e_cmds.insert(FrostAura {
+                        tick_timer: Timer::from_seconds(0.5, TimerMode::Repeating),
+                    });
+                }
+                EliteAffix::Summoner => {
+                    e_cmds.insert(EliteSummoner {
+                        summon_timer: Timer::from_seconds(6., TimerMode::Repeating),
+                        minions: vec![],
+                    });
+                }
+                EliteAffix::Explosive => {}
+            }
+        }
+        e_cmds.insert(EliteAffixes(affixes));
+    }
+}
+
+fn handle_explosive_elite_death(
+    mut commands: Commands,
+    mut death_events: EventReader<EnemyDeathEvent>,
+    elites: Query<(&EliteAffixes, &Attack, &Mob)>,
+    asset_server: Res<AssetServer>,
+) {
+    for death_event in death_events.iter() {
+        let Ok((affixes, attack, mob)) = elites.get(death_event.entity) else {
+            continue;
+        };
+        if !affixes.has(EliteAffix::Explosive) {
+            continue;
+        }
+        let warning = spawn_text(
+            &mut commands,
+            &asset_server,
+            Vec3::new(0., 0., 1.),
+            RED,
+            "!".to_string(),
+            Anchor::Center,
+            2.,
+            0,
+        );
+        commands
+            .spawn((
+                SpatialBundle::from_transform(Transform::from_translation(
+                    death_event.enemy_pos.extend(0.),
+                )),
+                DeathExplosion {
+                    fuse: Timer::from_seconds(EXPLOSION_FUSE_SECS, TimerMode::Once),
+                    damage: (attack.0 as f32 * 1.5) as i32,
+                    mob: mob.clone(),
+                },
+                Name::new("Elite Death Explosion"),
+            ))
+            .add_child(warning);
+    }
+}
+
+fn tick_death_explosions(
+    mut commands: Commands,
+    mut explosions: Query<(Entity, &GlobalTransform, &mut DeathExplosion)>,
+    mut hit_event: EventWriter<HitEvent>,
+    mut proto_commands: ProtoCommands,
+    proto: ProtoParam,
+    game: GameParam,
+    time: Res<Time>,
+) {
+    for (e, txfm, mut explosion) in explosions.iter_mut() {
+        if !explosion.fuse.tick(time.delta()).just_finished() {
+            continue;
+        }
+        let pos = txfm.translation().truncate();
+        // the projectile is only for show, its owner is gone so it can't hit anything
+        if let Some(p) = proto_commands.spawn_projectile_from_proto(
+            Projectile::FireExplosionAOE,
+            &proto,
+            pos,
+            Vec2::ZERO,
+        ) {
+            commands.entity(p).insert(EnemyProjectile {
+                entity: e,
+                mob: explosion.mob.clone(),
+            });
+        }
+        let player_pos = game.player().position.truncate();
+        if player_pos.distance(pos) <= EXPLOSION_RADIUS {
+            hit_event.send(HitEvent {
+                hit_entity: game.game.player,
+                damage: explosion.damage,
+                dir: (player_pos - pos).normalize_or_zero(),
+                hit_with_melee: None,
+                hit_with_projectile: Some(Projectile::FireExplosionAOE),
+                hit_by_mob: Some(explosion.mob.clone()),
+                was_crit: false,
+                ignore_tool: false,
+            });
+        }
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+fn tick_elite_summoners(
+    mut commands: Commands,
+    mut summoners: Query<(&GlobalTransform, &mut EliteSummoner), With<FollowState>>,
+    mobs: Query<&Mob>,
+    mut proto_commands: ProtoCommands,
+    prototypes: Prototypes,
+    time: Res<Time>,
+) {
+    let mut rng = rand::thread_rng();
+    for (txfm, mut summoner) in summoners.iter_mut() {
+        summoner.minions.retain(|minion| mobs.contains(*minion));
+        if !summoner.summon_timer.tick(time.delta()).just_finished()
+            || summoner.minions.len() >= MAX_SUMMONED_MINIONS
+        {
+            continue;
+        }
+        let offset = Vec2::new(rng.gen_range(-16. ..16.), rng.gen_range(-16. ..16.));
+        if let Some(minion) = proto_commands.spawn_from_proto(
+            Mob::Slime,
+            &prototypes,
+            txfm.translation().truncate() + offset,
+        ) {
+            commands.entity(minion).insert(CombatAlignment::Hostile);
+            summoner.minions.push(minion);
+        }
+    }
+}
+
+fn apply_frost_auras(
+    mut commands: Commands,
+    mut auras: Query<(&GlobalTransform, &mut FrostAura)>,
+    mut player_slow: Query<Option<&mut Slow>>,
+    mut status_event: EventWriter<StatusEffectEvent>,
+    game: GameParam,
+    time: Res<Time>,
+) {
+    let player_e = game.game.player;
+    let player_pos = game.player().position.truncate();
+    for (txfm, mut aura) in auras.iter_mut() {
+        if !aura.tick_timer.tick(time.delta()).just_finished()
+            || txfm.translation().truncate().distance(player_pos) > FROST_AURA_RADIUS
+        {
+            continue;
+        }
+        let Ok(slow_option) = player_slow.get_mut(player_e) else {
+            continue;
+        };
+        let num_stacks = if let Some(mut slow) = slow_option {
+            slow.num_stacks = (slow.num_stacks + 1).min(2);
+            slow.timer.reset();
+            slow.num_stacks
+        } else {
+            commands.entity(player_e).insert(Slow {
+                num_stacks: 1,
+                timer: Timer::from_seconds(1.7, TimerMode::Repeating),
+            });
+            1
+        };
+        status_event.send(StatusEffectEvent {
+            entity: player_e,
+            effect: StatusEffect::Slow,
+            num_stacks: num_stacks as i32,
+        });
+    }
+}
@@ -5,7 +5,9 @@ use bevy_rapier2d::control::KinematicCharacterController;
 use rand::Rng;
 use seldom_state::{prelude::StateMachine, trigger::BoolTrigger};
 
-use crate::{ai::IdleState, inputs::FacingDirection, ui::SubmitEssenceChoice, PLAYER_MOVE_SPEED};
+use crate::{
+    ai::IdleState, inputs::FacingDirection, ui::shop_ui::MerchantTradeEvent, PLAYER_MOVE_SPEED,
+};
 use bevy_aseprite::{anim::AsepriteAnimation, aseprite, AsepriteBundle};
 
 use super::Mob;
@@ -43,7 +45,16 @@ pub fn handle_new_fairy_state_machine(
                 PlayerFinishedTrade,
                 TradeState {
                     startup_timer: Timer::from_seconds(0.7, TimerMode::Once),
-                    despawn_timer: Timer::from_seconds(2., TimerMode::Once),
+                    finish_timer: Timer::from_seconds(2., TimerMode::Once),
+                },
+            )
+            .trans::<TradeState>(
+                TradeAnimFinished,
+                IdleState {
+                    walk_timer: Timer::from_seconds(2., TimerMode::Repeating),
+                    direction: FacingDirection::Down,
+                    speed: idle_state.speed,
+                    is_stopped: true,
                 },
             );
         e_cmds.insert(state_machine);
@@ -58,7 +69,7 @@ pub struct SproutingState;
 #[component(storage = "SparseSet")]
 pub struct TradeState {
     startup_timer: Timer,
-    despawn_timer: Timer,
+    finish_timer: Timer,
 }
 
 #[derive(Clone, Component, Reflect)]
@@ -126,34 +137,42 @@ pub fn new_idle(
     }
 }
 
-pub fn trade_anim(
-    mut trades: Query<(Entity, &mut TradeState, &mut AsepriteAnimation)>,
-    time: Res<Time>,
-    mut commands: Commands,
-) {
-    for (e, mut trade, mut anim) in trades.iter_mut() {
+pub fn trade_anim(mut trades: Query<(&mut TradeState, &mut AsepriteAnimation)>, time: Res<Time>) {
+    for (mut trade, mut anim) in trades.iter_mut() {
         trade.startup_timer.tick(time.delta());
-        trade.despawn_timer.tick(time.delta());
+        trade.finish_timer.tick(time.delta());
 
         if trade.startup_timer.just_finished() {
             *anim = AsepriteAnimation::from(Fairy::tags::FRONT_TRADE);
         }
-        if trade.despawn_timer.just_finished() {
-            commands.entity(e).despawn_recursive();
+        if trade.finish_timer.just_finished() {
+            *anim = AsepriteAnimation::from(Fairy::tags::IDLE_FRONT);
         }
     }
 }
 
+// The fairy stays around to keep trading, so it only plays the trade animation
+// when the player buys or sells something at its shop
 #[derive(Clone, Copy, Reflect)]
 pub struct PlayerFinishedTrade;
 
 impl BoolTrigger for PlayerFinishedTrade {
-    type Param<'w, 's> = EventReader<'w, 's, SubmitEssenceChoice>;
+    type Param<'w, 's> = EventReader<'w, 's, MerchantTradeEvent>;
 
-    fn trigger(&self, _entity: Entity, trade_event: Self::Param<'_, '_>) -> bool {
-        if !trade_event.is_empty() {
-            return true;
-        }
-        false
+    fn trigger(&self, entity: Entity, mut trade_event: Self::Param<'_, '_>) -> bool {
+        trade_event.iter().any(|trade| trade.merchant == entity)
+    }
+}
+
+#[derive(Clone, Copy, Reflect)]
+pub struct TradeAnimFinished;
+
+impl BoolTrigger for TradeAnimFinished {
+    type Param<'w, 's> = Query<'w, 's, &'static TradeState>;
+
+    fn trigger(&self, entity: Entity, trades: Self::Param<'_, '_>) -> bool {
+        trades
+            .get(entity)
+            .map_or(false, |trade| trade.finish_timer.finished())
     }
 }
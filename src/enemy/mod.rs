@@ -12,8 +12,9 @@ use strum_macros::{Display, EnumIter, IntoStaticStr};
 
 use crate::{
     ai::{
-        AttackDistance, FollowState, HurtByPlayer, IdleState, LeapAttackState, LineOfSight,
-        NightTimeAggro, ProjectileAttackState,
+        perception::Perception, AttackDistance, FollowState, HurtByPlayer, IdleState,
+        LeapAttackState, LineOfSight, NightTimeAggro, ProjectileAttackState, SearchFinished,
+        SearchState,
     },
     attributes::{add_current_health_with_max_health, Attack, MaxHealth},
     colors::{BLACK, DARK_GREEN, LIGHT_BROWN, LIGHT_GREEN, PINK, RED, WHITE},
@@ -22,16 +23,24 @@ use crate::{
     night::NightTracker,
     player::levels::ExperienceReward,
     ui::minimap::UpdateMiniMapEvent,
-    world::{dungeon::Dungeon, TileMapPosition},
+    world::{dungeon::Dungeon, world_helpers::tile_pos_to_world_pos, TileMapPosition},
     AppExt, GameParam, GameState,
 };
 
+pub mod companion;
+pub mod elite;
 pub mod fairy;
+pub mod raid;
 pub mod red_mushking;
 pub mod red_mushling;
 pub mod spawn_helpers;
 pub mod spawner;
-use self::spawner::SpawnerPlugin;
+use self::{
+    companion::{CompanionPlugin, CompanionState, IsCompanion},
+    elite::ElitePlugin,
+    raid::{RaidOrder, RaidPlugin, RaidState, Raider, RaiderSpotsTarget},
+    spawner::SpawnerPlugin,
+};
 use fairy::*;
 use red_mushking::*;
 use red_mushling::*;
@@ -57,7 +66,10 @@ impl Plugin for EnemyPlugin {
                 )
                     .in_set(OnUpdate(GameState::Main)),
             )
-            .add_plugin(SpawnerPlugin);
+            .add_plugin(SpawnerPlugin)
+            .add_plugin(RaidPlugin)
+            .add_plugin(CompanionPlugin)
+            .add_plugin(ElitePlugin);
     }
 }
 
@@ -125,6 +137,8 @@ pub enum CombatAlignment {
     Passive,
     Neutral,
     Hostile,
+    /// Tamed mobs that fight for the player, see [Companion]
+    Friendly,
 }
 
 #[derive(Component, Default, Deserialize, Debug, Clone, FromReflect, Schematic, Reflect)]
@@ -175,16 +189,26 @@ pub fn handle_new_mob_state_machine(
             &FollowSpeed,
             Option<&LeapAttack>,
             Option<&ProjectileAttack>,
+            Option<&Perception>,
+            Option<&Raider>,
         ),
         Or<(Added<Mob>, Added<CombatAlignment>, Changed<CombatAlignment>)>,
     >,
     dungeon_check: Query<&Dungeon>,
 ) {
-    for (e, mob, alignment, follow_speed, leap_attack_option, proj_attack_option) in
-        spawn_events.iter()
+    for (
+        e,
+        mob,
+        alignment,
+        follow_speed,
+        leap_attack_option,
+        proj_attack_option,
+        perception_option,
+        raider_option,
+    ) in spawn_events.iter()
     {
         let mut alignment = alignment.clone();
-        if dungeon_check.get_single().is_ok() {
+        if dungeon_check.get_single().is_ok() && alignment != CombatAlignment::Friendly {
             alignment = CombatAlignment::Hostile;
         }
         if mob == &Mob::RedMushling || mob.is_boss() || mob == &Mob::Fairy {
@@ -192,61 +216,134 @@ pub fn handle_new_mob_state_machine(
         }
         let mut e_cmds = commands.entity(e);
         let mut state_machine = StateMachine::default().set_trans_logging(false);
+        // companions only ever listen to the player, see [CompanionState]
+        if alignment == CombatAlignment::Friendly {
+            e_cmds.insert(state_machine.trans::<IdleState>(
+                IsCompanion,
+                CompanionState {
+                    speed: follow_speed.0,
+                },
+            ));
+            continue;
+        }
         match alignment {
             CombatAlignment::Neutral => {
-                state_machine = state_machine
-                    .trans::<IdleState>(
-                        HurtByPlayer,
-                        FollowState {
-                            target: game.game.player,
-                            curr_delta: None,
-                            curr_path: None,
-                            speed: follow_speed.0,
-                        },
-                    )
-                    .trans::<FollowState>(
-                        Trigger::not(LineOfSight {
-                            target: game.game.player,
-                            range: 130.,
-                        }),
-                        IdleState {
-                            walk_timer: Timer::from_seconds(2., TimerMode::Repeating),
-                            direction: FacingDirection::new_rand_dir(rand::thread_rng()),
-                            speed: 0.5,
-                            is_stopped: false,
-                        },
-                    );
+                state_machine = state_machine.trans::<IdleState>(
+                    HurtByPlayer,
+                    FollowState {
+                        target: game.game.player,
+                        curr_delta: None,
+                        curr_path: None,
+                        speed: follow_speed.0,
+                    },
+                );
             }
             CombatAlignment::Hostile => {
-                state_machine = state_machine
-                    .trans::<IdleState>(
-                        LineOfSight {
-                            target: game.game.player,
-                            range: 130.,
-                        },
-                        FollowState {
-                            target: game.game.player,
-                            curr_delta: None,
-                            curr_path: None,
-                            speed: follow_speed.0,
-                        },
-                    )
-                    .trans::<FollowState>(
-                        Trigger::not(LineOfSight {
-                            target: game.game.player,
-                            range: 160.,
-                        }),
-                        IdleState {
-                            walk_timer: Timer::from_seconds(2., TimerMode::Repeating),
-                            direction: FacingDirection::new_rand_dir(rand::thread_rng()),
-                            speed: 0.5,
-                            is_stopped: false,
-                        },
-                    );
+                state_machine = state_machine.trans::<IdleState>(
+                    LineOfSight {
+                        target: game.game.player,
+                        range: 130.,
+                    },
+                    FollowState {
+                        target: game.game.player,
+                        curr_delta: None,
+                        curr_path: None,
+                        speed: follow_speed.0,
+                    },
+                );
             }
             CombatAlignment::Passive => {
                 //TODO: impl run away
             }
+            CombatAlignment::Friendly => {}
+        }
+        let lose_sight_range = if alignment == CombatAlignment::Neutral {
+            130.
+        } else {
+            160.
+        };
+        // raiders fight the player if they see them, but otherwise keep marching on the base
+        if raider_option.is_some() {
+            let raid_state = RaidState {
+                target: game
+                    .game
+                    .home_pos
+                    .map_or(Vec2::ZERO, |home| tile_pos_to_world_pos(home, true)),
+                speed: follow_speed.0,
+            };
+            state_machine = state_machine
+                .trans::<IdleState>(RaidOrder, raid_state.clone())
+                .trans::<RaidState>(
+                    RaiderSpotsTarget {
+                        target: game.game.player,
+                        range: 130.,
+                    },
+                    FollowState {
+                        target: game.game.player,
+                        curr_delta: None,
+                        curr_path: None,
+                        speed: follow_speed.0,
+                    },
+                )
+                .trans::<FollowState>(
+                    Trigger::not(RaiderSpotsTarget {
+                        target: game.game.player,
+                        range: lose_sight_range,
+                    }),
+                    raid_state,
+                );
+        } else if perception_option.is_some() && alignment != CombatAlignment::Passive {
+            // mobs that can lose sight of the player go check where they last saw it before giving up
+            state_machine = state_machine.trans::<FollowState>(
+                Trigger::not(LineOfSight {
+                    target: game.game.player,
+                    range: lose_sight_range,
+                }),
+                SearchState {
+                    search_timer: Timer::from_seconds(4., TimerMode::Once),
+                    speed: follow_speed.0,
+                },
+            );
+            let follow_state = FollowState {
+                target: game.game.player,
+                curr_delta: None,
+                curr_path: None,
+                speed: follow_speed.0,
+            };
+            // neutral mobs only pick the fight back up if they are hit again
+            state_machine = if alignment == CombatAlignment::Hostile {
+                state_machine.trans::<SearchState>(
+                    LineOfSight {
+                        target: game.game.player,
+                        range: 130.,
+                    },
+                    follow_state,
+                )
+            } else {
+                state_machine.trans::<SearchState>(HurtByPlayer, follow_state)
+            };
+            state_machine = state_machine.trans::<SearchState>(
+                SearchFinished,
+                IdleState {
+                    walk_timer: Timer::from_seconds(2., TimerMode::Repeating),
+                    direction: FacingDirection::new_rand_dir(rand::thread_rng()),
+                    speed: 0.5,
+                    is_stopped: false,
+                },
+            );
+        } else if alignment != CombatAlignment::Passive {
+            state_machine = state_machine.trans::<FollowState>(
+                Trigger::not(LineOfSight {
+                    target: game.game.player,
+                    range: lose_sight_range,
+                }),
+                IdleState {
+                    walk_timer: Timer::from_seconds(2., TimerMode::Repeating),
+                    direction: FacingDirection::new_rand_dir(rand::thread_rng()),
+                    speed: 0.5,
+                    is_stopped: false,
+                },
+            );
         }
         if let Some(leap_attack) = leap_attack_option {
             state_machine = state_machine
@@ -0,0 +1,368 @@
+use bevy::{prelude::*, render::view::RenderLayers};
+use bevy_proto::prelude::{ProtoCommands, Prototypes};
+use bevy_rapier2d::prelude::KinematicCharacterController;
+use rand::{seq::SliceRandom, Rng};
+use seldom_state::prelude::*;
+
+use crate::{
+    ai::{
+        pathfinding::{get_next_tile_A_star, world_pos_to_AIPos, AIPos_to_world_pos},
+        perception::Perception,
+    },
+    animations::enemy_sprites::EnemyAnimationState,
+    client::is_not_paused,
+    colors::RED,
+    custom_commands::CommandsExt,
+    inputs::FacingDirection,
+    night::NightTracker,
+    proto::proto_param::ProtoParam,
+    status_effects::Slow,
+    ui::accessibility::LargeText,
+    world::{
+        dimension::ActiveDimension,
+        dungeon::Dungeon,
+        world_helpers::{camera_pos_to_chunk_pos, tile_pos_to_world_pos},
+        TILE_SIZE,
+    },
+    GameParam, GameState, GAME_HEIGHT, PLAYER_MOVE_SPEED,
+};
+
+use super::{
+    spawn_helpers::{can_spawn_mob_here, is_tile_water},
+    spawner::ELITE_SPAWN_RATE,
+    CombatAlignment, EliteMob, Mob,
+};
+
+/// First day a raid can happen on, so the player has a night to set up a base
+pub const RAID_MIN_DAYS: u8 = 1;
+/// The hour the HUD warns the player that a raid is coming. Raids start when night falls.
+pub const RAID_WARNING_HOUR: f32 = 17.;
+pub const RAID_BASE_WAVE_SIZE: u8 = 2;
+pub const RAID_MAX_WAVE_SIZE: u8 = 10;
+/// How far out from home (in tiles) raiders look for the island edge to spawn on
+const RAID_MIN_SPAWN_TILES: u32 = 6;
+const RAID_MAX_SPAWN_TILES: u32 = 24;
+/// How close a raider has to be to a player-placed object to start hitting it
+pub const RAIDER_REACH: f32 = TILE_SIZE.x * 1.25;
+
+pub struct RaidPlugin;
+
+impl Plugin for RaidPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RaidTracker>().add_systems(
+            (
+                warn_of_incoming_raid,
+                spawn_raid_wave,
+                tick_raid_warning_text,
+                raid.run_if(is_not_paused),
+            )
+                .in_set(OnUpdate(GameState::Main)),
+        );
+    }
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct RaidTracker {
+    pub last_warned_day: Option<u8>,
+    pub last_raid_day: Option<u8>,
+}
+
+/// Marks a mob as part of a night raid. Raiders head for [Game::home_pos](crate::Game)
+/// and break any walls, doors or crafting stations that get in their way.
+#[derive(Component, Debug, Clone)]
+pub struct Raider {
+    pub attack_cooldown: Timer,
+    /// The player-placed object this raider is currently hitting, if any
+    pub target_obj: Option<Entity>,
+}
+impl Default for Raider {
+    fn default() -> Self {
+        Self {
+            attack_cooldown: Timer::from_seconds(1.2, TimerMode::Once),
+            target_obj: None,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct RaidWarningText(pub Timer);
+
+// This trigger sends any idle raider back towards the player's base
+#[derive(Clone, Copy, Reflect)]
+pub struct RaidOrder;
+
+impl BoolTrigger for RaidOrder {
+    type Param<'w, 's> = Query<'w, 's, &'static Raider>;
+
+    fn trigger(&self, entity: Entity, raiders: Self::Param<'_, '_>) -> bool {
+        raiders.get(entity).is_ok()
+    }
+}
+
+// This trigger checks if a raider has noticed the target within the given range. Unlike
+// [LineOfSight](crate::ai::LineOfSight) it holds at night, when every raid happens
+#[derive(Clone, Copy, Reflect)]
+pub struct RaiderSpotsTarget {
+    pub target: Entity,
+    pub range: f32,
+}
+
+impl Trigger for RaiderSpotsTarget {
+    type Param<'w, 's> = (
+        Query<'w, 's, &'static Transform>,
+        Query<'w, 's, &'static Perception>,
+    );
+    type Ok = f32;
+    type Err = f32;
+
+    // Return `Ok` to trigger and `Err` to not trigger
+    fn trigger(
+        &self,
+        entity: Entity,
+        (transforms, perceptions): Self::Param<'_, '_>,
+    ) -> Result<f32, f32> {
+        let (Ok(txfm), Ok(target_txfm)) = (transforms.get(entity), transforms.get(self.target))
+        else {
+            return Err(0.);
+        };
+        let distance = txfm
+            .translation
+            .truncate()
+            .distance(target_txfm.translation.truncate());
+        if let Ok(perception) = perceptions.get(entity) {
+            if !perception.target_in_view && !perception.heard_target {
+                return Err(distance);
+            }
+        }
+        (distance <= self.range).then_some(distance).ok_or(distance)
+    }
+}
+
+// Entities in the `Raid` state should path towards the player's home, stopping to break
+// anything the player built along the way
+#[derive(Clone, Component, Reflect)]
+#[component(storage = "SparseSet")]
+pub struct RaidState {
+    pub target: Vec2,
+    pub speed: f32,
+}
+
+/// Number of mobs in tonight's wave, which grows every couple of days
+pub fn get_raid_wave_size(days: u8) -> u8 {
+    (RAID_BASE_WAVE_SIZE + days / 2).min(RAID_MAX_WAVE_SIZE)
+}
+fn get_raid_mob_pool(days: u8) -> Vec<Mob> {
+    let mut pool = vec![Mob::Slime, Mob::FurDevil];
+    if days >= 2 {
+        pool.push(Mob::StingFly);
+    }
+    if days >= 3 {
+        pool.push(Mob::SpikeSlime);
+    }
+    pool
+}
+
+fn is_raid_possible(
+    game: &GameParam,
+    night_tracker: &NightTracker,
+    maybe_dungeon: &Query<&Dungeon, With<ActiveDimension>>,
+) -> bool {
+    if maybe_dungeon.get_single().is_ok() || night_tracker.days < RAID_MIN_DAYS {
+        return false;
+    }
+    // only raid a home the player can actually see
+    game.game.home_pos.map_or(false, |home| {
+        game.get_chunk_entity(home.chunk_pos).is_some()
+    })
+}
+
+fn warn_of_incoming_raid(
+    mut commands: Commands,
+    game: GameParam,
+    night_tracker: Res<NightTracker>,
+    mut raid_tracker: ResMut<RaidTracker>,
+    maybe_dungeon: Query<&Dungeon, With<ActiveDimension>>,
+    asset_server: Res<AssetServer>,
+) {
+    if night_tracker.time < RAID_WARNING_HOUR
+        || night_tracker.is_night()
+        || raid_tracker.last_warned_day == Some(night_tracker.days)
+        || !is_raid_possible(&game, &night_tracker, &maybe_dungeon)
+    {
+        return;
+    }
+    raid_tracker.last_warned_day = Some(night_tracker.days);
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                format!(
+                    "A raid of {} is coming for your base tonight!",
+                    get_raid_wave_size(night_tracker.days)
+                ),
+                TextStyle {
+                    font: asset_server.load("fonts/4x5.ttf"),
+                    font_size: 5.0,
+                    color: RED,
+                },
+            )
+            .with_alignment(TextAlignment::Center),
+            transform: Transform {
+                translation: Vec3::new(0., GAME_HEIGHT / 2. - 22., 1.),
+                scale: Vec3::new(1., 1., 1.),
+                ..Default::default()
+            },
+            ..default()
+        },
+        Name::new("RAID WARNING TEXT"),
+        RaidWarningText(Timer::from_seconds(6., TimerMode::Once)),
+        LargeText,
+        RenderLayers::from_layers(&[3]),
+    ));
+}
+
+fn tick_raid_warning_text(
+    mut commands: Commands,
+    mut warnings: Query<(Entity, &mut RaidWarningText, &mut Text)>,
+    time: Res<Time>,
+) {
+    for (e, mut warning, mut text) in warnings.iter_mut() {
+        warning.0.tick(time.delta());
+        // blink for the first few seconds so it is hard to miss
+        let alpha = if warning.0.elapsed_secs() < 3. {
+            if (warning.0.elapsed_secs() * 4.) as u32 % 2 == 0 {
+                1.
+            } else {
+                0.3
+            }
+        } else {
+            warning.0.percent_left() * 2.
+        };
+        for section in text.sections.iter_mut() {
+            section.style.color.set_a(alpha);
+        }
+        if warning.0.finished() {
+            commands.entity(e).despawn_recursive();
+        }
+    }
+}
+
+/// Walks outwards from home, away from the island centre, and returns the last
+/// spawnable tile before the water or the edge of the loaded world.
+fn find_raid_spawn_pos(home: Vec2, game: &GameParam, proto_param: &ProtoParam) -> Option<Vec2> {
+    let mut rng = rand::thread_rng();
+    let outward = if home == Vec2::ZERO {
+        Vec2::from_angle(rng.gen_range(0. ..std::f32::consts::TAU))
+    } else {
+        home.normalize()
+    };
+    let dir = Vec2::from_angle(rng.gen_range(-1.0..1.0)).rotate(outward);
+    let mut spawn_pos = None;
+    for i in 1..=RAID_MAX_SPAWN_TILES {
+        let pos = home + dir * i as f32 * TILE_SIZE.x;
+        if game
+            .get_chunk_entity(camera_pos_to_chunk_pos(&pos))
+            .is_none()
+            || is_tile_water(pos, game).unwrap_or(true)
+        {
+            break;
+        }
+        if i >= RAID_MIN_SPAWN_TILES && can_spawn_mob_here(pos, game, proto_param, false) {
+            spawn_pos = Some(pos);
+        }
+    }
+    spawn_pos
+}
+
+fn spawn_raid_wave(
+    mut commands: Commands,
+    mut proto_commands: ProtoCommands,
+    prototypes: Prototypes,
+    proto_param: ProtoParam,
+    game: GameParam,
+    night_tracker: Res<NightTracker>,
+    mut raid_tracker: ResMut<RaidTracker>,
+    maybe_dungeon: Query<&Dungeon, With<ActiveDimension>>,
+) {
+    if !night_tracker.is_night()
+        || raid_tracker.last_raid_day == Some(night_tracker.days)
+        || !is_raid_possible(&game, &night_tracker, &maybe_dungeon)
+    {
+        return;
+    }
+    raid_tracker.last_raid_day = Some(night_tracker.days);
+    let home = tile_pos_to_world_pos(game.game.home_pos.unwrap(), true);
+    let pool = get_raid_mob_pool(night_tracker.days);
+    let elite_rate = ELITE_SPAWN_RATE * (1. + night_tracker.days as f32 * 0.5);
+    let mut rng = rand::thread_rng();
+
+    for _ in 0..get_raid_wave_size(night_tracker.days) {
+        let Some(pos) = find_raid_spawn_pos(home, &game, &proto_param) else {
+            warn!("Could not find a valid spawn location for raider near {home:?}");
+            continue;
+        };
+        let mob = pool.choose(&mut rng).unwrap().clone();
+        if let Some(raider) = proto_commands.spawn_from_proto(mob, &prototypes, pos) {
+            let mut raider_cmds = commands.entity(raider);
+            raider_cmds.insert((Raider::default(), CombatAlignment::Hostile));
+            if rng.gen::<f32>() < elite_rate {
+                raider_cmds.insert(EliteMob);
+            }
+        }
+    }
+}
+
+pub fn raid(
+    transforms: Query<&Transform>,
+    mut mover: Query<&mut KinematicCharacterController>,
+    mut raiders: Query<(
+        Entity,
+        &RaidState,
+        &Raider,
+        &EnemyAnimationState,
+        Option<&Slow>,
+    )>,
+    mut commands: Commands,
+    time: Res<Time>,
+    mut game: GameParam,
+) {
+    for (entity, raid, raider, anim_state, slowed_option) in raiders.iter_mut() {
+        let raid_collider_offset = Vec2::new(0., -3.);
+        let raid_translation = AIPos_to_world_pos(world_pos_to_AIPos(
+            transforms.get(entity).unwrap().translation.truncate() + raid_collider_offset,
+        ));
+
+        // stand still and smash whatever is in the way, see [check_raider_to_objects_collisions]
+        if let Some(target_obj) = raider.target_obj {
+            if let Ok(obj_txfm) = transforms.get(target_obj) {
+                commands
+                    .entity(entity)
+                    .insert(FacingDirection::from_translation(
+                        obj_txfm.translation.truncate() - raid_translation,
+                    ));
+            }
+            continue;
+        }
+        if raid_translation.distance(raid.target) <= TILE_SIZE.x {
+            if anim_state != &EnemyAnimationState::Idle && anim_state != &EnemyAnimationState::Hit {
+                commands.entity(entity).insert(EnemyAnimationState::Idle);
+            }
+            continue;
+        }
+        let next_tile =
+            get_next_tile_A_star(&raid.target, &raid_translation, &mut game).unwrap_or(raid.target);
+        let delta = (next_tile - raid_translation).normalize_or_zero();
+        mover.get_mut(entity).unwrap().translation = Some(
+            delta
+                * raid.speed
+                * PLAYER_MOVE_SPEED
+                * time.delta_seconds()
+                * (1. - slowed_option.map_or(0., |s| s.num_stacks as f32 * 0.15)),
+        );
+        commands
+            .entity(entity)
+            .insert(FacingDirection::from_translation(delta));
+        if anim_state != &EnemyAnimationState::Walk && anim_state != &EnemyAnimationState::Hit {
+            commands.entity(entity).insert(EnemyAnimationState::Walk);
+        }
+    }
+}
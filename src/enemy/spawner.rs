@@ -7,6 +7,7 @@ use rand::{
     seq::{IteratorRandom, SliceRandom},
     Rng,
 };
+use serde::{Deserialize, Serialize};
 
 use crate::{
     combat::EnemyDeathEvent,
@@ -17,6 +18,7 @@ use crate::{
     proto::proto_param::ProtoParam,
     ui::damage_numbers::spawn_screen_locked_icon,
     world::{
+        biome::BiomeNoise,
         chunk::Chunk,
         dimension::ActiveDimension,
         dungeon::Dungeon,
@@ -27,7 +29,9 @@ use crate::{
     GameParam, GameState,
 };
 
-use super::{spawn_helpers::can_spawn_mob_here, CombatAlignment, EliteMob, Mob};
+use super::{
+    companion::Companion, spawn_helpers::can_spawn_mob_here, CombatAlignment, EliteMob, Mob,
+};
 
 pub const MAX_MOB_PER_CHUNK: i32 = 4;
 pub const ELITE_SPAWN_RATE: f32 = 0.07;
@@ -65,12 +69,13 @@ pub struct GlobalSpawnTimer {
     pub timer: Timer,
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 
 pub struct Spawner {
     pub chunk_pos: IVec2,
     // pub radius: u32,
     pub weight: f32,
+    #[serde(with = "spawn_timer_serde")]
     pub spawn_timer: Timer,
     pub min_days_to_spawn: u8,
     pub enemy: Mob,
@@ -85,6 +90,27 @@ impl PartialEq for Spawner {
             && self.enemy == other.enemy
     }
 }
+
+/// bevy only implements serde for [Timer] behind its `serialize` feature, so spawners
+/// save how long their timer is and how far along it was instead
+mod spawn_timer_serde {
+    use std::time::Duration;
+
+    use bevy::prelude::{Timer, TimerMode};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(timer: &Timer, ser: S) -> Result<S::Ok, S::Error> {
+        (timer.duration().as_secs_f32(), timer.elapsed_secs()).serialize(ser)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(des: D) -> Result<Timer, D::Error> {
+        let (duration, elapsed) = <(f32, f32)>::deserialize(des)?;
+        let mut timer = Timer::from_seconds(duration, TimerMode::Once);
+        timer.set_elapsed(Duration::from_secs_f32(elapsed));
+        Ok(timer)
+    }
+}
+
 #[derive(Component, Debug)]
 pub struct ChunkSpawners {
     pub spawners: Vec<Spawner>,
@@ -106,11 +132,28 @@ fn add_global_spawn_timer(mut commands: Commands) {
 fn add_spawners_to_new_chunks(
     mut commands: Commands,
     maybe_dungeon: Query<&Dungeon, With<ActiveDimension>>,
-    game: GameParam,
+    mut game: GameParam,
     proto: ProtoParam,
     mut chunk_spawn_event: EventReader<DoneGeneratingEvent>,
+    biome_noise: Res<BiomeNoise>,
 ) {
     for new_chunk in chunk_spawn_event.iter() {
+        // chunks that were unloaded get back the spawners they had, with their timers caught up
+        if maybe_dungeon.get_single().is_err() {
+            if let Some(unloaded) = game
+                .world_obj_cache
+                .unloaded_spawners
+                .remove(&new_chunk.chunk_pos)
+            {
+                if let Some(chunk_e) = game.get_chunk_entity(new_chunk.chunk_pos) {
+                    commands.entity(chunk_e).insert(ChunkSpawners {
+                        spawners: unloaded.spawners,
+                        spawned_mobs: 0,
+                    });
+                    continue;
+                }
+            }
+        }
         // Don't add spawners to chunks with no spawnable tiles
         let mut at_least_one_spawnable_tile = false;
         'check: for tile_x in 0..=15 {
@@ -218,6 +261,16 @@ fn add_spawners_to_new_chunks(
                 num_spawned: 0,
             });
         }
+        // each biome makes some mobs more or less common, or keeps them out entirely
+        if let Some((_, biome)) = game
+            .world_generation_params
+            .get_biome_for_chunk(new_chunk.chunk_pos, &biome_noise)
+        {
+            for spawner in spawners.iter_mut() {
+                spawner.weight *= biome.mob_weights.get(&spawner.enemy).unwrap_or(&1.);
+            }
+            spawners.retain(|spawner| spawner.weight > 0.);
+        }
         commands
             .entity(
                 game.get_chunk_entity(new_chunk.chunk_pos)
@@ -396,7 +449,7 @@ fn check_mob_count(
 fn despawn_out_of_range_mobs(
     game: GameParam,
     mut commands: Commands,
-    mut query: Query<(Entity, &Transform), With<Mob>>,
+    mut query: Query<(Entity, &Transform), (With<Mob>, Without<Companion>)>,
 ) {
     for (e, t) in query.iter_mut() {
         let chunk_pos = camera_pos_to_chunk_pos(&t.translation.truncate());
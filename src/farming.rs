@@ -0,0 +1,143 @@
+use bevy::{prelude::*, utils::HashMap};
+use bevy_proto::backend::schematics::{ReflectSchematic, Schematic};
+
+use crate::{
+    assets::SpriteAnchor,
+    combat::ObjBreakEvent,
+    item::{PlaceItemEvent, WorldObject},
+    proto::proto_param::ProtoParam,
+    sappling::{tick_sappling_color, Sappling},
+    world::{
+        world_helpers::{tile_pos_to_world_pos, world_pos_to_tile_pos},
+        TileMapPosition,
+    },
+    GameParam, GameState,
+};
+
+/// How long one watering lasts
+pub const WATERED_SECS: f32 = 180.;
+/// Watered crops grow this many times faster
+pub const WATERED_GROWTH_MULTIPLIER: f32 = 2.;
+
+/// Planted on tilled soil, which is left behind when the crop is harvested or cleared.
+/// Growing crops wither after `wither_secs` without water, 0 means they never do.
+#[derive(Component, Reflect, FromReflect, Schematic, Default)]
+#[reflect(Component, Schematic)]
+pub struct Crop {
+    pub wither_secs: f32,
+}
+
+#[derive(Default, Debug, Clone, Copy)]
+pub struct WaterLevel {
+    pub wet_secs: f32,
+    pub dry_secs: f32,
+}
+
+/// Water is tracked per tile so it carries over as a crop swaps to its next stage
+#[derive(Resource, Default, Debug)]
+pub struct WateredTiles(pub HashMap<TileMapPosition, WaterLevel>);
+
+pub struct WaterTileEvent(pub TileMapPosition);
+
+pub struct FarmingPlugin;
+
+impl Plugin for FarmingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WateredTiles>()
+            .add_event::<WaterTileEvent>()
+            .add_systems(
+                (
+                    handle_water_tile_events,
+                    tick_crops.before(tick_sappling_color),
+                    restore_soil_under_crops,
+                )
+                    .in_set(OnUpdate(GameState::Main)),
+            );
+    }
+}
+
+fn handle_water_tile_events(
+    mut events: EventReader<WaterTileEvent>,
+    mut watered: ResMut<WateredTiles>,
+    game: GameParam,
+    proto_param: ProtoParam,
+) {
+    for WaterTileEvent(tile_pos) in events.iter() {
+        let Some((_, obj)) = game.get_obj_entity_at_tile(*tile_pos, &proto_param) else {
+            continue;
+        };
+        if obj != WorldObject::TilledSoil && proto_param.get_component::<Crop, _>(obj).is_none() {
+            continue;
+        }
+        watered.0.insert(
+            *tile_pos,
+            WaterLevel {
+                wet_secs: WATERED_SECS,
+                dry_secs: 0.,
+            },
+        );
+    }
+}
+
+/// Speeds up the sappling timer of watered crops, and withers the ones left dry too long
+fn tick_crops(
+    time: Res<Time>,
+    mut crops: Query<(&WorldObject, &GlobalTransform, &Crop, Option<&mut Sappling>)>,
+    mut watered: ResMut<WateredTiles>,
+    mut events: EventWriter<PlaceItemEvent>,
+    proto_param: ProtoParam,
+) {
+    let dt = time.delta_seconds();
+    for (obj, txfm, crop, sappling) in crops.iter_mut() {
+        let Some(mut sappling) = sappling else {
+            continue;
+        };
+        let anchor = proto_param
+            .get_component::<SpriteAnchor, _>(*obj)
+            .unwrap_or(&SpriteAnchor(Vec2::ZERO));
+        let pos = txfm.translation().truncate() - anchor.0;
+        let water = watered.0.entry(world_pos_to_tile_pos(pos)).or_default();
+        if water.wet_secs > 0. {
+            water.dry_secs = 0.;
+            sappling
+                .0
+                .tick(time.delta().mul_f32(WATERED_GROWTH_MULTIPLIER - 1.));
+            continue;
+        }
+        water.dry_secs += dt;
+        if crop.wither_secs > 0. && water.dry_secs > crop.wither_secs {
+            water.dry_secs = 0.;
+            events.send(PlaceItemEvent {
+                pos,
+                obj: WorldObject::WitheredCrop,
+                placed_by_player: false,
+                override_existing_obj: true,
+            });
+        }
+    }
+    for water in watered.0.values_mut() {
+        water.wet_secs = (water.wet_secs - dt).max(0.);
+    }
+}
+
+fn restore_soil_under_crops(
+    mut obj_break_events: EventReader<ObjBreakEvent>,
+    mut events: EventWriter<PlaceItemEvent>,
+    mut watered: ResMut<WateredTiles>,
+    proto_param: ProtoParam,
+) {
+    for broken in obj_break_events.iter() {
+        if broken.obj == WorldObject::TilledSoil {
+            watered.0.remove(&broken.pos);
+        }
+        if proto_param.get_component::<Crop, _>(broken.obj).is_none() {
+            continue;
+        }
+        events.send(PlaceItemEvent {
+            pos: tile_pos_to_world_pos(broken.pos, false),
+            obj: WorldObject::TilledSoil,
+            placed_by_player: false,
+            override_existing_obj: false,
+        });
+    }
+}
@@ -10,6 +10,7 @@ use crate::client::is_not_paused;
 use crate::enemy::spawn_helpers::can_spawn_mob_here;
 use crate::enemy::spawner::ChunkSpawners;
 use crate::juice::{DustParticles, RunDustTimer};
+use crate::keymap::{ActionInput, InputAction, Keymap, HOTBAR_ACTIONS};
 use crate::player::levels::PlayerLevel;
 use crate::player::skills::{PlayerSkills, Skill};
 use crate::player::MovePlayerEvent;
@@ -31,23 +32,30 @@ use rand::seq::IteratorRandom;
 use rand::Rng;
 
 use crate::attributes::Speed;
-use crate::combat::{AttackTimer, HitEvent};
+use crate::combat::{status_effects::Slow, AttackTimer, HitEvent};
 
+use crate::enemy::companion::{FeedMobEvent, Tameable};
 use crate::enemy::Mob;
 use crate::inventory::Inventory;
-use crate::item::item_actions::{ItemActionParam, ItemActions, ManaCost};
+use crate::item::build_mode::BuildMode;
+use crate::item::item_actions::{
+    handle_item_action_success, ActionSuccessEvent, ConsumableItem, ItemActionParam, ItemActions,
+    ManaCost,
+};
 use crate::item::item_upgrades::{
     ArrowSpeedUpgrade, BowUpgradeSpread, BurnOnHitUpgrade, ClawUpgradeMultiThrow,
     FireStaffAOEUpgrade, LethalHitUpgrade, LightningStaffChainUpgrade, VenomOnHitUpgrade,
 };
 use crate::item::object_actions::ObjectAction;
 use crate::item::projectile::{RangedAttack, RangedAttackEvent};
-use crate::item::{Equipment, WorldObject};
+use crate::item::{Equipment, RequiredEquipmentType, WorldObject};
 use crate::proto::proto_param::ProtoParam;
+use crate::settings::Settings;
 use crate::ui::minimap::UpdateMiniMapEvent;
+use crate::ui::shop_ui::ShopContainer;
 use crate::ui::{
-    change_hotbar_slot, EssenceShopChoices, FlashExpBarEvent, InventorySlotState, InventoryState,
-    UIState,
+    change_hotbar_slot, is_typing, world_map::PinLabelInput, EssenceShopChoices, FlashExpBarEvent,
+    InventorySlotState, InventoryState, UIState,
 };
 use crate::world::chunk::Chunk;
 
@@ -59,14 +67,9 @@ use crate::{
 };
 use crate::{Game, GameUpscale, Player, DEBUG, PLAYER_DASH_SPEED, TIME_STEP};
 
-const HOTBAR_KEYCODES: [KeyCode; 6] = [
-    KeyCode::Key1,
-    KeyCode::Key2,
-    KeyCode::Key3,
-    KeyCode::Key4,
-    KeyCode::Key5,
-    KeyCode::Key6,
-];
+/// How far from the player the right stick places the cursor
+const GAMEPAD_AIM_DISTANCE: f32 = 24.;
+
 pub struct InputsPlugin;
 
 impl Plugin for InputsPlugin {
@@ -85,9 +88,17 @@ impl Plugin for InputsPlugin {
                     handle_hotbar_key_input,
                     tick_dash_timer.run_if(is_not_paused),
                     handle_open_essence_ui,
+                    handle_open_shop_ui,
                     diagnostics,
                     handle_quick_hotbar_consume.before(handle_hotbar_key_input),
+                    handle_swap_hotbar_row,
+                    auto_select_tool.before(mouse_click_system),
+                    auto_select_consumable.after(handle_item_action_success),
                     handle_interact_objects.run_if(is_not_paused),
+                    update_gamepad_aim
+                        .after(update_cursor_pos)
+                        .before(turn_player)
+                        .before(mouse_click_system),
                 )
                     .in_set(OnUpdate(GameState::Main)),
             )
@@ -225,6 +236,7 @@ pub fn move_player(
             &Hunger,
             &mut RunDustTimer,
             &PlayerSkills,
+            Option<&Slow>,
         ),
         (
             With<Player>,
@@ -234,12 +246,15 @@ pub fn move_player(
         ),
     >,
     time: Res<Time>,
-    key_input: ResMut<Input<KeyCode>>,
+    actions: ActionInput,
     mut minimap_event: EventWriter<UpdateMiniMapEvent>,
     mut commands: Commands,
     mut particle: Query<&mut EffectSpawner, With<DustParticles>>,
     asset_server: Res<AssetServer>,
     audio: Res<Audio>,
+    settings: Res<Settings>,
+    ui_state: Res<State<UIState>>,
+    pin_input: Res<PinLabelInput>,
     mut audio_timer: Local<Timer>,
 ) {
     if audio_timer.duration() == Duration::ZERO {
@@ -256,45 +271,63 @@ pub fn move_player(
         hunger,
         mut run_dust_timer,
         skills,
+        slow_option,
     ) = player_query.single_mut();
     let player = game.player_mut();
     if player.is_attacking {
         mv.0 = Vec2::ZERO;
         return;
     }
+    // held movement keys are being typed with, not walked with
+    if is_typing(&ui_state.0, &pin_input) {
+        mv.0 = Vec2::ZERO;
+        player.is_moving = false;
+        return;
+    }
     let mut d = Vec2::ZERO;
     let s = PLAYER_MOVE_SPEED
         * time.delta_seconds()
         * (1. + speed.0 as f32 / 100.)
-        * (if hunger.is_starving() { 0.7 } else { 1. });
+        * (if hunger.is_starving() { 0.7 } else { 1. })
+        * (1. - slow_option.map(|s| s.num_stacks).unwrap_or(0) as f32 * 0.15);
 
-    if key_input.pressed(KeyCode::A) || key_input.pressed(KeyCode::Left) {
+    if actions.pressed(InputAction::MoveLeft) {
         d.x -= 1.;
         player.is_moving = true;
     }
-    if key_input.pressed(KeyCode::D) || key_input.pressed(KeyCode::Right) {
+    if actions.pressed(InputAction::MoveRight) {
         d.x += 1.;
         player.is_moving = true;
     }
-    if key_input.pressed(KeyCode::W) || key_input.pressed(KeyCode::Up) {
+    if actions.pressed(InputAction::MoveUp) {
         d.y += 1.;
         player.is_moving = true;
     }
-    if key_input.pressed(KeyCode::S) || key_input.pressed(KeyCode::Down) {
+    if actions.pressed(InputAction::MoveDown) {
         d.y -= 1.;
         player.is_moving = true;
     }
+    let stick = actions.move_axis();
+    if stick != Vec2::ZERO {
+        d = stick;
+        player.is_moving = true;
+    }
     //TODO: move this tick to animations.rs
     if !skills.get(Skill::Teleport)
         && player.player_dash_cooldown.tick(time.delta()).finished()
-        && key_input.pressed(KeyCode::Space)
+        && actions.pressed(InputAction::Dash)
     {
         player.is_dashing = true;
 
         player.player_dash_cooldown.reset();
     }
-    if (key_input.any_just_released([KeyCode::A, KeyCode::D, KeyCode::S, KeyCode::W])
-        && !key_input.any_pressed([KeyCode::A, KeyCode::D, KeyCode::S, KeyCode::W]))
+    let move_actions = [
+        InputAction::MoveLeft,
+        InputAction::MoveRight,
+        InputAction::MoveDown,
+        InputAction::MoveUp,
+    ];
+    if (actions.any_just_released(move_actions) && !actions.any_pressed(move_actions))
         || (d.x == 0. && d.y == 0.)
     {
         player.is_moving = false;
@@ -367,7 +400,10 @@ pub fn move_player(
             let walk5 = asset_server.load("sounds/walk_grass5.ogg");
             let walks = vec![walk1, walk2, walk3, walk4, walk5];
             walks.iter().choose(&mut rand::thread_rng()).map(|sound| {
-                audio.play_with_settings(sound.clone(), PlaybackSettings::ONCE.with_volume(0.35))
+                audio.play_with_settings(
+                    sound.clone(),
+                    PlaybackSettings::ONCE.with_volume(settings.get_sfx_volume(0.35)),
+                )
             });
         }
     } else if curr_anim != &EnemyAnimationState::Idle
@@ -386,17 +422,24 @@ pub fn tick_dash_timer(mut game: GameParam, time: Res<Time>) {
         }
     }
 }
+/// Closes whatever menu is open, or opens the Options menu if none are
 pub fn close_container(
-    key_input: ResMut<Input<KeyCode>>,
+    actions: ActionInput,
+    curr_ui_state: Res<State<UIState>>,
     mut next_inv_state: ResMut<NextState<UIState>>,
 ) {
-    if key_input.just_pressed(KeyCode::Escape) {
-        next_inv_state.set(UIState::Closed);
+    if actions.just_pressed(InputAction::CloseMenu) {
+        if curr_ui_state.0 == UIState::Closed {
+            next_inv_state.set(UIState::Options);
+        } else {
+            next_inv_state.set(UIState::Closed);
+        }
     }
 }
 pub fn toggle_inventory(
     mut game: GameParam,
-    key_input: ResMut<Input<KeyCode>>,
+    key_input: Res<Input<KeyCode>>,
+    actions: ActionInput,
     mut commands: Commands,
     mut proto_commands: ProtoCommands,
     mut dim_event: EventWriter<DimensionSpawnEvent>,
@@ -408,10 +451,7 @@ pub fn toggle_inventory(
     mut player_xp: Query<&mut PlayerLevel>,
     mut flash_event: EventWriter<FlashExpBarEvent>,
 ) {
-    if key_input.just_pressed(KeyCode::I)
-        || key_input.just_pressed(KeyCode::Tab)
-        || key_input.just_pressed(KeyCode::E)
-    {
+    if actions.just_pressed(InputAction::Inventory) {
         next_ui_state.set(UIState::Inventory);
     }
 
@@ -425,9 +465,15 @@ pub fn toggle_inventory(
             );
         }
         if key_input.just_pressed(KeyCode::O) {
+            // skips the unlock requirement, so eras can be checked without playing to them
+            let next = Era::from_index(game.era.current_era.index() + 1);
             dim_event.send(DimensionSpawnEvent {
                 swap_to_dim_now: true,
-                new_era: Some(Era::Second),
+                new_era: Some(if game.eras.get(&next).is_some() {
+                    next
+                } else {
+                    Era::default()
+                }),
             });
         }
         if key_input.just_pressed(KeyCode::C) {
@@ -437,7 +483,7 @@ pub fn toggle_inventory(
         if key_input.just_pressed(KeyCode::K) {
             dim_event.send(DimensionSpawnEvent {
                 swap_to_dim_now: true,
-                new_era: Some(Era::Main),
+                new_era: Some(Era::default()),
             });
         }
         if key_input.just_pressed(KeyCode::U) {
@@ -482,27 +528,136 @@ pub fn toggle_inventory(
 }
 fn handle_hotbar_key_input(
     mut game: GameParam,
-    key_input: ResMut<Input<KeyCode>>,
+    actions: ActionInput,
     mut mouse_wheel_event: EventReader<MouseWheel>,
     mut inv_state: ResMut<InventoryState>,
 ) {
+    let hotbar_size = inv_state.hotbar_size;
     for e in mouse_wheel_event.iter() {
         if e.y > 0. {
             change_hotbar_slot(
-                (inv_state.active_hotbar_slot + 5) % 6,
+                (inv_state.active_hotbar_slot + hotbar_size - 1) % hotbar_size,
                 &mut inv_state,
                 &mut game.inv_slot_query,
             );
         } else if e.y < 0. {
             change_hotbar_slot(
-                (inv_state.active_hotbar_slot + 1) % 6,
+                (inv_state.active_hotbar_slot + 1) % hotbar_size,
                 &mut inv_state,
                 &mut game.inv_slot_query,
             );
         }
     }
-    for (slot, key) in HOTBAR_KEYCODES.iter().enumerate() {
-        if key_input.just_pressed(*key) {
+    if actions.just_pressed(InputAction::HotbarPrev) {
+        change_hotbar_slot(
+            (inv_state.active_hotbar_slot + hotbar_size - 1) % hotbar_size,
+            &mut inv_state,
+            &mut game.inv_slot_query,
+        );
+    }
+    if actions.just_pressed(InputAction::HotbarNext) {
+        change_hotbar_slot(
+            (inv_state.active_hotbar_slot + 1) % hotbar_size,
+            &mut inv_state,
+            &mut game.inv_slot_query,
+        );
+    }
+    for (slot, action) in HOTBAR_ACTIONS.iter().enumerate().take(hotbar_size) {
+        if actions.just_pressed(*action) {
+            change_hotbar_slot(slot, &mut inv_state, &mut game.inv_slot_query);
+        }
+    }
+}
+/// Trades the hotbar for the row of the inventory behind it
+fn handle_swap_hotbar_row(
+    actions: ActionInput,
+    ui_state: Res<State<UIState>>,
+    inv_state: Res<InventoryState>,
+    mut inv: Query<&mut Inventory>,
+    mut inv_slots: Query<&mut InventorySlotState>,
+) {
+    if ui_state.0 != UIState::Closed || !actions.just_pressed(InputAction::SwapHotbarRow) {
+        return;
+    }
+    let Ok(mut inv) = inv.get_single_mut() else {
+        return;
+    };
+    inv.swap_hotbar_row(inv_state.hotbar_size);
+    // two stacks of the same size trade places without their counts changing
+    for mut state in inv_slots.iter_mut() {
+        if state.r#type.is_hotbar() {
+            state.dirty = true;
+        }
+    }
+}
+/// Attacking an object with the wrong tool switches to one on the hotbar that can break it,
+/// the swing that triggers the switch still uses the old tool
+fn auto_select_tool(
+    actions: ActionInput,
+    settings: Res<Settings>,
+    cursor_pos: Res<CursorPos>,
+    ui_state: Res<State<UIState>>,
+    mut game: GameParam,
+    proto_param: ProtoParam,
+    tool_reqs: Query<&RequiredEquipmentType>,
+    inv: Query<&Inventory>,
+    mut inv_state: ResMut<InventoryState>,
+) {
+    if !settings.auto_select
+        || ui_state.0 != UIState::Closed
+        || !actions.just_pressed(InputAction::Attack)
+    {
+        return;
+    }
+    let cursor_tile_pos = world_pos_to_tile_pos(cursor_pos.world_coords.truncate());
+    let Some((obj_e, _)) = game.get_obj_entity_at_tile(cursor_tile_pos, &proto_param) else {
+        return;
+    };
+    let Ok(req) = tool_reqs.get(obj_e) else {
+        return;
+    };
+    let inv = inv.single();
+    let is_tool_for_req = |slot: usize| {
+        inv.items.items[slot]
+            .as_ref()
+            .and_then(|item| item.get_obj().get_equip_type(&proto_param))
+            == Some(req.0.clone())
+    };
+    if is_tool_for_req(inv_state.active_hotbar_slot) {
+        return;
+    }
+    if let Some(slot) = (0..inv_state.hotbar_size).find(|slot| is_tool_for_req(*slot)) {
+        change_hotbar_slot(slot, &mut inv_state, &mut game.inv_slot_query);
+    }
+}
+/// Using up the held consumable switches to another stack of it on the hotbar
+fn auto_select_consumable(
+    settings: Res<Settings>,
+    mut success_events: EventReader<ActionSuccessEvent>,
+    mut game: GameParam,
+    proto_param: ProtoParam,
+    inv: Query<&Inventory>,
+    mut inv_state: ResMut<InventoryState>,
+) {
+    for e in success_events.iter() {
+        if !settings.auto_select
+            || e.item_slot != inv_state.active_hotbar_slot
+            || proto_param
+                .get_component::<ConsumableItem, _>(e.obj)
+                .is_none()
+        {
+            continue;
+        }
+        let inv = inv.single();
+        if inv.items.items[e.item_slot].is_some() {
+            continue;
+        }
+        let next_stack = (0..inv_state.hotbar_size).find(|slot| {
+            inv.items.items[*slot]
+                .as_ref()
+                .map_or(false, |item| *item.get_obj() == e.obj)
+        });
+        if let Some(slot) = next_stack {
             change_hotbar_slot(slot, &mut inv_state, &mut game.inv_slot_query);
         }
     }
@@ -512,6 +667,7 @@ pub fn update_cursor_pos(
     camera_q: Query<(&Transform, &Camera), With<TextureCamera>>,
     mut cursor_moved_events: EventReader<CursorMoved>,
     mut cursor_pos: ResMut<CursorPos>,
+    settings: Res<Settings>,
 ) {
     for cursor_moved in cursor_moved_events.iter() {
         // To get the mouse's world position, we have to transform its window position by
@@ -520,29 +676,64 @@ pub fn update_cursor_pos(
         for (cam_t, cam) in camera_q.iter() {
             *cursor_pos = CursorPos {
                 world_coords: cursor_pos_in_world(&windows, cursor_moved.position, cam_t, cam),
-                ui_coords: cursor_pos_in_ui(&windows, cursor_moved.position, cam),
+                // the UI camera is zoomed out by the UI scale
+                ui_coords: cursor_pos_in_ui(&windows, cursor_moved.position, cam)
+                    / settings.ui_scale,
                 screen_coords: cursor_moved.position.extend(0.),
             };
         }
     }
 }
+/// The right stick moves the cursor around the player, so facing, attacks and
+/// placing items all aim the same way they do with the mouse
+pub fn update_gamepad_aim(
+    actions: ActionInput,
+    mut cursor_moved_events: EventReader<CursorMoved>,
+    mut cursor_pos: ResMut<CursorPos>,
+    player_query: Query<&GlobalTransform, With<Player>>,
+    ui_state: Res<State<UIState>>,
+    mut aim_dir: Local<Option<Vec2>>,
+) {
+    // the last aim is kept while the stick is let go, until the mouse takes over
+    if cursor_moved_events.iter().count() > 0 {
+        *aim_dir = None;
+    }
+    if ui_state.0 != UIState::Closed {
+        return;
+    }
+    let aim = actions.aim_axis();
+    if aim != Vec2::ZERO {
+        *aim_dir = Some(aim.normalize());
+    }
+    let (Some(dir), Ok(player_t)) = (*aim_dir, player_query.get_single()) else {
+        return;
+    };
+    let offset = dir * GAMEPAD_AIM_DISTANCE;
+    cursor_pos.world_coords = (player_t.translation().truncate() + offset).extend(0.);
+    cursor_pos.ui_coords = offset.extend(0.);
+}
 pub fn handle_quick_hotbar_consume(
+    keymap: Res<Keymap>,
     mut key_input: ResMut<Input<KeyCode>>,
+    mut mouse_input: ResMut<Input<MouseButton>>,
     mut game: GameParam,
     proto_param: ProtoParam,
 
     mut inv: Query<&mut Inventory>,
     mut item_action_param: ItemActionParam,
+    inv_state: Res<InventoryState>,
 ) {
-    let shift_key_pressed =
-        key_input.pressed(KeyCode::LShift) || key_input.pressed(KeyCode::RShift);
-    let hot_bar_key_pressed = HOTBAR_KEYCODES.iter().any(|k| key_input.just_pressed(*k));
-    if shift_key_pressed && hot_bar_key_pressed {
-        let hotbar_slot = HOTBAR_KEYCODES
-            .iter()
-            .position(|k| key_input.just_pressed(*k))
-            .unwrap();
-        key_input.clear_just_pressed(HOTBAR_KEYCODES[hotbar_slot]);
+    let quick_use_pressed = keymap.pressed(InputAction::QuickConsume, &key_input, &mouse_input);
+    let hotbar_slot = HOTBAR_ACTIONS
+        .iter()
+        .take(inv_state.hotbar_size)
+        .position(|a| keymap.just_pressed(*a, &key_input, &mouse_input));
+    if let (true, Some(hotbar_slot)) = (quick_use_pressed, hotbar_slot) {
+        keymap.clear_just_pressed(
+            HOTBAR_ACTIONS[hotbar_slot],
+            &mut key_input,
+            &mut mouse_input,
+        );
         let held_item_option = inv.single().items.items[hotbar_slot].clone();
         if let Some(held_item) = held_item_option {
             let held_obj = *held_item.get_obj();
@@ -606,7 +797,7 @@ pub fn diagnostics(
 }
 pub fn mouse_click_system(
     mut commands: Commands,
-    mouse_button_input: Res<Input<MouseButton>>,
+    actions: ActionInput,
     cursor_pos: Res<CursorPos>,
     mut game: GameParam,
     mut proto_param: ProtoParam,
@@ -621,6 +812,11 @@ pub fn mouse_click_system(
     mut ranged_attack_event: EventWriter<RangedAttackEvent>,
     mut item_action_param: ItemActionParam,
     obj_actions: Query<&ObjectAction>,
+    (tameables, mut feed_mob_event, build_mode): (
+        Query<(Entity, &GlobalTransform, &Tameable)>,
+        EventWriter<FeedMobEvent>,
+        Res<BuildMode>,
+    ),
     // mut meshes: ResMut<Assets<Mesh>>,
     // mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
@@ -632,9 +828,9 @@ pub fn mouse_click_system(
     let player_pos = game.player().position;
     let (player_e, attack_timer_option) = player_query.single();
     // Hit Item, send attack event
-    if mouse_button_input.pressed(MouseButton::Left) {
-        // if *DEBUG && mouse_button_input.just_pressed(MouseButton::Left) {
-        if mouse_button_input.just_pressed(MouseButton::Left) {
+    if actions.pressed(InputAction::Attack) {
+        // if *DEBUG && actions.just_pressed(InputAction::Attack) {
+        if actions.just_pressed(InputAction::Attack) {
             let obj = game.get_object_from_chunk_cache(cursor_tile_pos);
             let ai_pos = world_pos_to_AIPos(cursor_pos.world_coords.truncate());
             let is_valid = game
@@ -696,12 +892,27 @@ pub fn mouse_click_system(
             });
         }
     }
-    // Attempt to place block in hand
-    if mouse_button_input.just_pressed(MouseButton::Right) {
+    // Attempt to place block in hand, build mode handles its own placing
+    if actions.just_pressed(InputAction::UseItem) && !build_mode.enabled {
         let hotbar_slot = inv_state.active_hotbar_slot;
         let held_item_option = inv.single().items.items[hotbar_slot].clone();
         if let Some(held_item) = held_item_option {
             let held_obj = *held_item.get_obj();
+            // feeding a tameable mob takes priority over eating the food yourself
+            let cursor_world_pos = cursor_pos.world_coords.truncate();
+            if let Some((mob_e, _, _)) = tameables.iter().find(|(_, txfm, tameable)| {
+                let mob_pos = txfm.translation().truncate();
+                tameable.foods.contains(&held_obj)
+                    && mob_pos.distance(cursor_world_pos) <= 12.
+                    && mob_pos.distance(player_pos.truncate()) <= game.player().reach_distance * 32.
+            }) {
+                feed_mob_event.send(FeedMobEvent {
+                    mob: mob_e,
+                    food: held_obj,
+                    slot: hotbar_slot,
+                });
+                return;
+            }
             if let Some(item_actions) = proto_param.get_component::<ItemActions, _>(held_obj) {
                 item_actions.run_action(
                     held_obj,
@@ -745,9 +956,9 @@ pub fn handle_interact_objects(
     mut proto_param: ProtoParam,
     mut item_action_param: ItemActionParam,
     mut commands: Commands,
-    key_input: ResMut<Input<KeyCode>>,
+    actions: ActionInput,
 ) {
-    if key_input.just_pressed(KeyCode::F) {
+    if actions.just_pressed(InputAction::Interact) {
         for (obj_e, t, obj_action, obj, anchor) in objs.iter() {
             let obj_t = t.translation().truncate() - anchor.0;
             let (player_t, mut inv) = player_query.single_mut();
@@ -769,12 +980,12 @@ pub fn handle_interact_objects(
 
 pub fn handle_open_essence_ui(
     mut commands: Commands,
-    key_input: ResMut<Input<KeyCode>>,
+    actions: ActionInput,
     player_query: Query<&GlobalTransform, With<Player>>,
     nearby_merchant_query: Query<(&GlobalTransform, &EssenceShopChoices)>,
     mut next_inv_state: ResMut<NextState<UIState>>,
 ) {
-    if key_input.just_pressed(KeyCode::F) {
+    if actions.just_pressed(InputAction::Interact) {
         let player_t = player_query.single().translation().truncate();
         for (transform, choices) in nearby_merchant_query.iter() {
             if player_t.distance(transform.translation().truncate()) < 32. {
@@ -785,6 +996,24 @@ pub fn handle_open_essence_ui(
     }
 }
 
+pub fn handle_open_shop_ui(
+    mut commands: Commands,
+    actions: ActionInput,
+    player_query: Query<&GlobalTransform, With<Player>>,
+    nearby_merchant_query: Query<(&GlobalTransform, &ShopContainer)>,
+    ui_state: Res<State<UIState>>,
+) {
+    if actions.just_pressed(InputAction::Interact) && ui_state.0 == UIState::Closed {
+        let player_t = player_query.single().translation().truncate();
+        for (transform, shop) in nearby_merchant_query.iter() {
+            if player_t.distance(transform.translation().truncate()) < 32. {
+                commands.insert_resource(shop.clone());
+                return;
+            }
+        }
+    }
+}
+
 pub fn move_camera_with_player(
     player_query: Query<
         (&Transform, &RawPosition, &MovementVector),
@@ -1,5 +1,5 @@
 use core::panic;
-use std::cmp::min;
+use std::cmp::{min, Ordering};
 
 use crate::{
     animations::{AnimationPosTracker, AnimationTimer, AttackAnimationTimer},
@@ -18,13 +18,16 @@ use crate::{
 };
 use rand::Rng;
 
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashSet};
 
 use bevy_proto::prelude::*;
 use bevy_rapier2d::prelude::{Collider, RigidBody, Sensor};
 use serde::{Deserialize, Serialize};
 
 pub const INVENTORY_SIZE: usize = 6 * 4;
+/// The hotbar is the first slots of the inventory, it can be stretched into the second row
+pub const MIN_HOTBAR_SIZE: usize = 6;
+pub const MAX_HOTBAR_SIZE: usize = 12;
 pub const MAX_STACK_SIZE: usize = 64;
 
 #[derive(Component, Debug, Default, Clone, Serialize, Deserialize)]
@@ -34,8 +37,33 @@ pub struct Inventory {
     pub accessory_items: Container,
     pub crafting_items: Container,
     // pub crafting_result_item: Container,
+    /// Slots of `items` that sorting and quick stacking leave alone
+    #[serde(default)]
+    pub locked_slots: HashSet<usize>,
 }
 impl Inventory {
+    pub fn toggle_slot_lock(&mut self, slot: usize) {
+        if !self.locked_slots.remove(&slot) {
+            self.locked_slots.insert(slot);
+        }
+    }
+    /// Trades the hotbar with the slots right after it, locks go along with their items
+    pub fn swap_hotbar_row(&mut self, hotbar_size: usize) {
+        for slot in 0..hotbar_size {
+            let other = slot + hotbar_size;
+            if other >= self.items.items.len() {
+                break;
+            }
+            self.items.swap_slots(slot, other);
+            let slot_locked = self.locked_slots.remove(&slot);
+            if self.locked_slots.remove(&other) {
+                self.locked_slots.insert(slot);
+            }
+            if slot_locked {
+                self.locked_slots.insert(other);
+            }
+        }
+    }
     pub fn get_items_from_slot_type(&self, slot_type: InventorySlotType) -> &Container {
         match slot_type {
             InventorySlotType::Equipment => &self.equipment_items,
@@ -75,6 +103,38 @@ pub struct ItemStack {
     pub metadata: ItemDisplayMetaData,
 }
 
+/// How the sort button orders the inventory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum InventorySortOrder {
+    #[default]
+    Type,
+    Rarity,
+    Level,
+}
+
+impl InventorySortOrder {
+    pub fn get_name(&self) -> String {
+        match self {
+            InventorySortOrder::Type => "Type",
+            InventorySortOrder::Rarity => "Rarity",
+            InventorySortOrder::Level => "Level",
+        }
+        .to_string()
+    }
+    /// Rarer and higher level items come first, ties are grouped by type, biggest stack first
+    pub fn compare(&self, a: &ItemStack, b: &ItemStack) -> Ordering {
+        let by_type = a.obj_type.cmp(&b.obj_type);
+        let by_rarity = b.rarity.cmp(&a.rarity);
+        let by_level = b.metadata.level.cmp(&a.metadata.level);
+        match self {
+            InventorySortOrder::Type => by_type.then(by_rarity).then(by_level),
+            InventorySortOrder::Rarity => by_rarity.then(by_type).then(by_level),
+            InventorySortOrder::Level => by_level.then(by_type).then(by_rarity),
+        }
+        .then(b.count.cmp(&a.count))
+    }
+}
+
 #[derive(Debug)]
 pub enum InventoryError {
     FailedToMerge(String),
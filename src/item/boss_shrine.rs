@@ -7,6 +7,7 @@ use crate::{
     enemy::Mob,
     inventory::ItemStack,
     juice::{FlashEffect, ShakeEffect},
+    keymap::{ActionInput, InputAction},
     player::{ModifyTimeFragmentsEvent, TimeFragmentCurrency},
     proto::proto_param::ProtoParam,
     world::{dimension::ActiveDimension, dungeon::Dungeon, world_helpers::tile_pos_to_world_pos},
@@ -74,7 +75,7 @@ pub fn check_for_items_on_shrine(
 }
 pub fn handle_pay_shrine_cost(
     mut commands: Commands,
-    key_input: ResMut<Input<KeyCode>>,
+    actions: ActionInput,
     player_query: Query<(&GlobalTransform, &TimeFragmentCurrency)>,
     game: GameParam,
     mut game_camera: Query<Entity, With<TextureCamera>>,
@@ -84,7 +85,7 @@ pub fn handle_pay_shrine_cost(
     if dungeon_check.get_single().is_ok() {
         return;
     }
-    if key_input.just_pressed(KeyCode::F) {
+    if actions.just_pressed(InputAction::Interact) {
         let (player_t, currency) = player_query.single();
         let Some(shrine) = game
             .world_obj_cache
@@ -0,0 +1,531 @@
+use std::{
+    f32::consts::FRAC_PI_2,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    prelude::*,
+    scene::{serde::SceneDeserializer, DynamicEntity, DynamicScene},
+    utils::HashMap,
+};
+use serde::de::DeserializeSeed;
+use strum::IntoEnumIterator;
+
+use crate::{
+    assets::{Graphics, SpriteAnchor},
+    client::is_not_paused,
+    datafiles,
+    inputs::CursorPos,
+    inventory::Inventory,
+    keymap::{ActionInput, InputAction},
+    proto::proto_param::ProtoParam,
+    schematic::get_schematic_type_registry,
+    ui::{InventoryState, UIElement, UIState},
+    world::{
+        generation::WorldObjectCache,
+        world_helpers::{
+            can_object_be_placed_here, global_tile_to_tile_pos, tile_pos_to_global_tile,
+            tile_pos_to_world_pos, world_pos_to_tile_pos,
+        },
+        y_sort::YSort,
+        TileMapPosition, TILE_SIZE,
+    },
+    GameParam, GameState,
+};
+
+use super::{
+    item_actions::{ItemAction, ItemActions},
+    PlaceItemEvent, WorldObject,
+};
+
+/// Fewer objects than this are not worth a blueprint, and are most likely a misclick
+const MIN_BLUEPRINT_OBJS: usize = 2;
+
+pub struct BuildModePlugin;
+impl Plugin for BuildModePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BuildMode>()
+            .add_system(load_blueprints.in_schedule(OnEnter(GameState::Main)))
+            .add_systems(
+                (
+                    handle_build_mode_keys,
+                    handle_build_mode_clicks
+                        .after(handle_build_mode_keys)
+                        .run_if(is_not_paused),
+                    update_build_ghost.after(handle_build_mode_clicks),
+                )
+                    .in_set(OnUpdate(GameState::Main)),
+            )
+            .add_system(
+                rotate_new_objs
+                    .after(TransformSystem::TransformPropagate)
+                    .in_base_set(CoreSet::PostUpdate)
+                    .run_if(in_state(GameState::Main)),
+            );
+    }
+}
+
+/// Build mode replaces single clicks with drags: holding a placeable item drags out
+/// a line (or a rectangle while holding shift), an empty hand selects an area to save
+/// as a blueprint, and a selected blueprint is stamped where the cursor is.
+#[derive(Resource, Default, Debug)]
+pub struct BuildMode {
+    pub enabled: bool,
+    pub drag_start: Option<TileMapPosition>,
+    /// quarter turns applied to rotatable objects, like doors in vertical walls
+    pub rotation: u8,
+    pub blueprints: Vec<Handle<DynamicScene>>,
+    pub selected_blueprint: Option<usize>,
+}
+
+#[derive(Component)]
+pub struct BuildGhost;
+
+impl WorldObject {
+    pub fn is_rotatable(&self) -> bool {
+        matches!(self, WorldObject::WoodDoor | WorldObject::WoodDoorOpen)
+    }
+}
+
+/// A line snapped to the longest axis of the drag, or the outline of the dragged
+/// rectangle. Selections use the whole rectangle.
+fn get_drag_area(start: IVec2, end: IVec2, rect: bool, filled: bool) -> Vec<IVec2> {
+    let (min, max) = (start.min(end), start.max(end));
+    if !rect && !filled {
+        let delta = end - start;
+        return if delta.x.abs() >= delta.y.abs() {
+            (min.x..=max.x).map(|x| IVec2::new(x, start.y)).collect()
+        } else {
+            (min.y..=max.y).map(|y| IVec2::new(start.x, y)).collect()
+        };
+    }
+    let mut tiles = vec![];
+    for x in min.x..=max.x {
+        for y in min.y..=max.y {
+            if filled || x == min.x || x == max.x || y == min.y || y == max.y {
+                tiles.push(IVec2::new(x, y));
+            }
+        }
+    }
+    tiles
+}
+
+/// The item in the hotbar and the object it places, if it places one
+fn get_held_placeable(
+    inv: &Inventory,
+    inv_state: &InventoryState,
+    proto_param: &ProtoParam,
+) -> Option<(WorldObject, WorldObject)> {
+    let held = *inv.items.items[inv_state.active_hotbar_slot]
+        .as_ref()?
+        .get_obj();
+    proto_param
+        .get_component::<ItemActions, _>(held)?
+        .actions
+        .iter()
+        .find_map(|action| match action {
+            ItemAction::PlacesInto(obj) => Some((held, *obj)),
+            _ => None,
+        })
+}
+
+/// The item that has to be spent to build `obj`
+pub fn get_material_for_obj(obj: WorldObject, proto_param: &ProtoParam) -> Option<WorldObject> {
+    WorldObject::iter().find(|item| {
+        proto_param
+            .get_component::<ItemActions, _>(*item)
+            .map_or(false, |actions| {
+                actions.actions.contains(&ItemAction::PlacesInto(obj))
+            })
+    })
+}
+
+/// Blueprint objects and their tile offset from the blueprint's bottom left corner
+fn get_blueprint_objs(scene: &DynamicScene) -> Vec<(WorldObject, IVec2, u8)> {
+    let mut objs = vec![];
+    for entity in scene.entities.iter() {
+        let mut obj = None;
+        let mut txfm = None;
+        for component in entity.components.iter() {
+            if component.type_name() == std::any::type_name::<WorldObject>() {
+                obj = WorldObject::from_reflect(component.as_ref());
+            } else if component.type_name() == std::any::type_name::<Transform>() {
+                txfm = Transform::from_reflect(component.as_ref());
+            }
+        }
+        if let (Some(obj), Some(txfm)) = (obj, txfm) {
+            let offset = (txfm.translation.truncate() / TILE_SIZE.x)
+                .round()
+                .as_ivec2();
+            let turns = (txfm.rotation.to_euler(EulerRot::XYZ).2 / FRAC_PI_2).round() as i32;
+            objs.push((obj, offset, turns.rem_euclid(4) as u8));
+        }
+    }
+    let min = objs
+        .iter()
+        .map(|(_, offset, _)| *offset)
+        .reduce(|a, b| a.min(b))
+        .unwrap_or_default();
+    objs.iter()
+        .map(|(obj, offset, turns)| (*obj, *offset - min, *turns))
+        .collect()
+}
+
+/// What the current drag or blueprint would place, and the rotation of each object
+fn get_build_plan(
+    build_mode: &BuildMode,
+    cursor_tile: TileMapPosition,
+    rect: bool,
+    held: Option<(WorldObject, WorldObject)>,
+    scenes: &Assets<DynamicScene>,
+    proto_param: &ProtoParam,
+) -> Vec<(TileMapPosition, WorldObject, u8)> {
+    let cursor = tile_pos_to_global_tile(cursor_tile);
+    if let Some(scene) = build_mode
+        .selected_blueprint
+        .and_then(|i| build_mode.blueprints.get(i))
+        .and_then(|handle| scenes.get(handle))
+    {
+        return get_blueprint_objs(scene)
+            .into_iter()
+            .map(|(obj, offset, turns)| (global_tile_to_tile_pos(cursor + offset), obj, turns))
+            .collect();
+    }
+    let start = build_mode
+        .drag_start
+        .map(tile_pos_to_global_tile)
+        .unwrap_or(cursor);
+    let Some((_, obj)) = held else {
+        return get_drag_area(start, cursor, true, true)
+            .into_iter()
+            .map(|tile| (global_tile_to_tile_pos(tile), WorldObject::None, 0))
+            .collect();
+    };
+    let turns = if obj.is_rotatable() {
+        build_mode.rotation
+    } else {
+        0
+    };
+    // medium objects take up 2x2, so a line of them can't use every tile
+    let mut taken: Vec<TileMapPosition> = vec![];
+    let mut plan = vec![];
+    for tile in get_drag_area(start, cursor, rect, false) {
+        let pos = global_tile_to_tile_pos(tile);
+        let footprint = if obj.is_medium_size(proto_param) {
+            [vec![pos], pos.get_neighbour_tiles_for_medium_objects()].concat()
+        } else {
+            vec![pos]
+        };
+        if footprint.iter().any(|p| taken.contains(p)) {
+            continue;
+        }
+        taken.extend(footprint);
+        plan.push((pos, obj, turns));
+    }
+    plan
+}
+
+fn load_blueprints(mut build_mode: ResMut<BuildMode>, mut scenes: ResMut<Assets<DynamicScene>>) {
+    let Ok(dir) = fs::read_dir(datafiles::blueprints_dir()) else {
+        return;
+    };
+    let mut paths: Vec<PathBuf> = dir
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.to_string_lossy().ends_with(".scn.ron"))
+        .collect();
+    // names start with the time they were saved, so this keeps them in the order they were made
+    paths.sort();
+    let type_registry = get_schematic_type_registry();
+    build_mode.blueprints = paths
+        .iter()
+        .filter_map(|path| match read_blueprint(path, &type_registry) {
+            Ok(scene) => Some(scenes.add(scene)),
+            Err(err) => {
+                error!("Error loading blueprint {path:?}: {err}");
+                None
+            }
+        })
+        .collect();
+}
+
+fn read_blueprint(path: &Path, type_registry: &AppTypeRegistry) -> Result<DynamicScene, String> {
+    let bytes = fs::read(path).map_err(|err| err.to_string())?;
+    let mut deserializer =
+        ron::de::Deserializer::from_bytes(&bytes).map_err(|err| err.to_string())?;
+    SceneDeserializer {
+        type_registry: &type_registry.read(),
+    }
+    .deserialize(&mut deserializer)
+    .map_err(|err| err.to_string())
+}
+
+fn handle_build_mode_keys(
+    mut build_mode: ResMut<BuildMode>,
+    actions: ActionInput,
+    ui_state: Res<State<UIState>>,
+) {
+    if ui_state.0 != UIState::Closed {
+        return;
+    }
+    if actions.just_pressed(InputAction::BuildMode) {
+        build_mode.enabled = !build_mode.enabled;
+        build_mode.drag_start = None;
+        build_mode.selected_blueprint = None;
+    }
+    if !build_mode.enabled {
+        return;
+    }
+    if actions.just_pressed(InputAction::BuildRotate) {
+        build_mode.rotation = (build_mode.rotation + 1) % 2;
+    }
+    if actions.just_pressed(InputAction::CycleBlueprint) {
+        let num_blueprints = build_mode.blueprints.len();
+        build_mode.selected_blueprint = match build_mode.selected_blueprint {
+            None if num_blueprints > 0 => Some(0),
+            Some(i) if i + 1 < num_blueprints => Some(i + 1),
+            _ => None,
+        };
+    }
+}
+
+fn handle_build_mode_clicks(
+    mut build_mode: ResMut<BuildMode>,
+    actions: ActionInput,
+    cursor_pos: Res<CursorPos>,
+    inv_state: Res<InventoryState>,
+    mut inv: Query<&mut Inventory>,
+    mut game: GameParam,
+    proto_param: ProtoParam,
+    mut scenes: ResMut<Assets<DynamicScene>>,
+    mut place_item_event: EventWriter<PlaceItemEvent>,
+) {
+    if !build_mode.enabled {
+        return;
+    }
+    let cursor_tile = world_pos_to_tile_pos(cursor_pos.world_coords.truncate());
+    let in_reach = game
+        .player()
+        .position
+        .truncate()
+        .distance(cursor_pos.world_coords.truncate())
+        <= game.player().reach_distance * 32.;
+    if actions.just_pressed(InputAction::UseItem) {
+        if !in_reach {
+            return;
+        }
+        if build_mode.selected_blueprint.is_none() {
+            build_mode.drag_start = Some(cursor_tile);
+            return;
+        }
+    } else if !actions.just_released(InputAction::UseItem) || build_mode.drag_start.is_none() {
+        return;
+    } else if !in_reach {
+        // the drag has to end within reach too, or it is cancelled
+        build_mode.drag_start = None;
+        return;
+    }
+
+    let mut inv = inv.single_mut();
+    let held = get_held_placeable(&inv, &inv_state, &proto_param);
+    let rect = actions.pressed(InputAction::BuildRectangle);
+    let plan = get_build_plan(&build_mode, cursor_tile, rect, held, &scenes, &proto_param);
+    let drag_start = build_mode.drag_start.take();
+
+    if held.is_none() && build_mode.selected_blueprint.is_none() {
+        // a click without dragging is not a selection
+        if drag_start != Some(cursor_tile) {
+            save_blueprint(&mut build_mode, &plan, &game, &proto_param, &mut scenes);
+        }
+        return;
+    }
+
+    // pay for everything up front, anything that can't be afforded is left out
+    let mut placeable = vec![];
+    let mut costs: HashMap<WorldObject, usize> = HashMap::default();
+    for (pos, obj, turns) in plan {
+        if !can_object_be_placed_here(pos, &mut game, obj, &proto_param) {
+            continue;
+        }
+        let Some(material) = get_material_for_obj(obj, &proto_param) else {
+            continue;
+        };
+        let cost = costs.entry(material).or_insert(0);
+        if *cost >= inv.items.get_item_count_in_container(material) {
+            continue;
+        }
+        *cost += 1;
+        placeable.push((pos, obj, turns));
+    }
+    for (material, cost) in costs.iter() {
+        if let Err(err) = inv.items.remove_from_inventory(*cost, *material) {
+            error!(
+                "Error removing building materials from inventory: {:?}",
+                err
+            );
+            return;
+        }
+    }
+    for (pos, obj, turns) in placeable {
+        if turns > 0 {
+            game.world_obj_cache.rotated_objs.insert(pos, turns);
+        }
+        place_item_event.send(PlaceItemEvent {
+            obj,
+            pos: tile_pos_to_world_pos(pos, false),
+            placed_by_player: true,
+            override_existing_obj: false,
+        });
+    }
+}
+
+/// Saves every player-buildable object in the selection as a schematic scene, as long
+/// as there are at least [MIN_BLUEPRINT_OBJS] of them
+fn save_blueprint(
+    build_mode: &mut BuildMode,
+    selection: &[(TileMapPosition, WorldObject, u8)],
+    game: &GameParam,
+    proto_param: &ProtoParam,
+    scenes: &mut Assets<DynamicScene>,
+) {
+    let min = selection
+        .iter()
+        .map(|(pos, _, _)| tile_pos_to_global_tile(*pos))
+        .reduce(|a, b| a.min(b))
+        .unwrap_or_default();
+    let mut entities = vec![];
+    for (pos, _, _) in selection.iter() {
+        let Some(obj) = game.get_object_from_chunk_cache(*pos) else {
+            continue;
+        };
+        if get_material_for_obj(*obj, proto_param).is_none() {
+            continue;
+        }
+        let turns = game
+            .world_obj_cache
+            .rotated_objs
+            .get(pos)
+            .copied()
+            .unwrap_or(0);
+        let offset = (tile_pos_to_global_tile(*pos) - min).as_vec2() * TILE_SIZE.x;
+        let txfm = Transform::from_translation(offset.extend(0.))
+            .with_rotation(Quat::from_rotation_z(turns as f32 * FRAC_PI_2));
+        entities.push(DynamicEntity {
+            entity: entities.len() as u32,
+            components: vec![Box::new(*obj) as Box<dyn Reflect>, Box::new(txfm)],
+        });
+    }
+    if entities.len() < MIN_BLUEPRINT_OBJS {
+        return;
+    }
+    let scene = DynamicScene {
+        resources: vec![],
+        entities,
+    };
+    let type_registry = get_schematic_type_registry();
+    let serialized_scene = match scene.serialize_ron(&type_registry) {
+        Ok(scene) => scene,
+        Err(err) => {
+            error!("Error serializing blueprint: {:?}", err);
+            return;
+        }
+    };
+    let mut path = datafiles::blueprints_dir();
+    path.push(format!(
+        "blueprint_{}.scn.ron",
+        chrono::offset::Local::now().format("%Y%m%d_%H%M%S_%3f")
+    ));
+    if let Err(err) = fs::create_dir_all(datafiles::blueprints_dir())
+        .and_then(|_| fs::write(&path, serialized_scene))
+    {
+        error!("Error while writing blueprint to file: {:?}", err);
+        return;
+    }
+    info!("Saved blueprint {path:?}");
+    build_mode.blueprints.push(scenes.add(scene));
+}
+
+fn update_build_ghost(
+    mut commands: Commands,
+    build_mode: Res<BuildMode>,
+    actions: ActionInput,
+    cursor_pos: Res<CursorPos>,
+    graphics: Res<Graphics>,
+    inv_state: Res<InventoryState>,
+    inv: Query<&Inventory>,
+    mut game: GameParam,
+    proto_param: ProtoParam,
+    scenes: Res<Assets<DynamicScene>>,
+    ghosts: Query<Entity, With<BuildGhost>>,
+    mut last_cursor_tile: Local<Option<TileMapPosition>>,
+) {
+    let cursor_tile = world_pos_to_tile_pos(cursor_pos.world_coords.truncate());
+    let rect = actions.pressed(InputAction::BuildRectangle);
+    if *last_cursor_tile == Some(cursor_tile)
+        && !build_mode.is_changed()
+        && !game.world_obj_cache.is_changed()
+        && !actions.keys.is_changed()
+    {
+        return;
+    }
+    *last_cursor_tile = Some(cursor_tile);
+    for e in ghosts.iter() {
+        commands.entity(e).despawn();
+    }
+    if !build_mode.enabled {
+        return;
+    }
+
+    let held = get_held_placeable(inv.single(), &inv_state, &proto_param);
+    let plan = get_build_plan(&build_mode, cursor_tile, rect, held, &scenes, &proto_param);
+    for (pos, obj, _) in plan {
+        let hover = if obj != WorldObject::None
+            && !can_object_be_placed_here(pos, &mut game, obj, &proto_param)
+        {
+            UIElement::BlockedTileHover
+        } else {
+            UIElement::TileHover
+        };
+        commands
+            .spawn(SpriteBundle {
+                texture: graphics.get_ui_element_texture(hover),
+                transform: Transform::from_translation(
+                    tile_pos_to_world_pos(pos, false).extend(1.),
+                ),
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(16., 16.)),
+                    color: Color::rgba(1., 1., 1., 0.7),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .insert(BuildGhost)
+            .insert(Name::new("Build Ghost"))
+            .insert(YSort(-0.2));
+    }
+}
+
+/// Objects placed rotated keep their rotation when their chunk is reloaded
+fn rotate_new_objs(
+    mut new_objs: Query<(&GlobalTransform, &mut Transform, &WorldObject), Added<WorldObject>>,
+    world_obj_cache: Res<WorldObjectCache>,
+    proto_param: ProtoParam,
+) {
+    if world_obj_cache.rotated_objs.is_empty() {
+        return;
+    }
+    for (g_txfm, mut txfm, obj) in new_objs.iter_mut() {
+        if !obj.is_rotatable() {
+            continue;
+        }
+        let anchor = proto_param
+            .get_component::<SpriteAnchor, _>(*obj)
+            .map(|a| a.0)
+            .unwrap_or_default();
+        let pos = world_pos_to_tile_pos(g_txfm.translation().truncate() - anchor);
+        if let Some(turns) = world_obj_cache.rotated_objs.get(&pos) {
+            txfm.rotation = Quat::from_rotation_z(*turns as f32 * FRAC_PI_2);
+        }
+    }
+}
@@ -3,21 +3,28 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     assets::Graphics,
-    attributes::{attribute_helpers::reroll_item_bonus_attributes, AttributeModifier},
+    attributes::{
+        attribute_helpers::{
+            create_new_random_item_stack_with_attributes, reroll_item_bonus_attributes,
+        },
+        AttributeModifier,
+    },
     client::analytics::{AnalyticsTrigger, AnalyticsUpdateEvent},
     colors::YELLOW,
-    container::Container,
-    inventory::{Inventory, InventoryItemStack, ItemStack},
+    combat::ObjBreakEvent,
+    container::{Container, ContainerRegistry},
+    inventory::{Inventory, InventoryItemStack, ItemStack, MAX_STACK_SIZE},
     item::WorldObject,
     player::Player,
     proto::proto_param::ProtoParam,
     ui::{
-        crafting_ui::CraftingContainerType,
+        crafting_ui::{CraftingContainer, CraftingContainerType},
         damage_numbers::{spawn_floating_text_with_shadow, NewRecipeTextTimer},
         handle_hovering, mark_slot_dirty, spawn_item_stack_icon, FurnaceContainer, FurnaceState,
-        InventorySlotState, InventorySlotType,
+        InventorySlotState, InventorySlotType, FURNACE_SMELT_SECS,
     },
-    GameState,
+    world::world_helpers::tile_pos_to_world_pos,
+    GameParam, GameState,
 };
 
 pub struct CraftingPlugin;
@@ -25,12 +32,19 @@ impl Plugin for CraftingPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(Recipes::default())
             .add_event::<CraftedItemEvent>()
+            .add_event::<BatchCraftEvent>()
             .add_systems(
                 (
                     handle_crafting_update_when_inv_changes,
                     handle_crafted_item,
                     handle_inv_changed_update_crafting_tracker,
                     handle_furnace_slot_update.after(handle_hovering),
+                    handle_batch_craft.before(handle_crafted_item),
+                    tick_crafting_queues,
+                    collect_finished_crafts
+                        .after(tick_crafting_queues)
+                        .run_if(resource_exists::<CraftingContainer>()),
+                    handle_break_crafting_station,
                 )
                     .in_set(OnUpdate(GameState::Main)),
             );
@@ -45,6 +59,22 @@ pub struct Recipes {
     pub upgradeable_items: Vec<WorldObject>,
 }
 
+impl Recipes {
+    /// How many times the recipe can be crafted with the items in the container
+    pub fn get_max_craftable(&self, obj: WorldObject, container: &Container) -> usize {
+        let Some((ingredients, ..)) = self.crafting_list.get(&obj) else {
+            return 0;
+        };
+        ingredients
+            .iter()
+            .map(|ingredient| {
+                container.get_item_count_in_container(ingredient.item) / ingredient.count.max(1)
+            })
+            .min()
+            .unwrap_or_default()
+    }
+}
+
 #[derive(Default, Clone, Debug, Deserialize, PartialEq, Eq, TypeUuid)]
 #[uuid = "413bd529-bfeb-41b3-9db0-4b8b380a2c36"]
 pub struct RecipeItem {
@@ -72,6 +102,92 @@ pub struct CraftingTracker {
 
 pub struct CraftedItemEvent {
     pub obj: WorldObject,
+    /// How many times the recipe was crafted at once
+    pub count: usize,
+    /// Batch and queued crafts take their ingredients when they are started, so
+    /// this is only sent once the items are handed over
+    pub ingredients_used: bool,
+}
+
+/// How many times right clicking a recipe crafts it
+pub const CRAFT_BATCH_SIZE: usize = 5;
+
+/// How many crafts a batch craft asks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CraftAmount {
+    Count(usize),
+    Max,
+}
+
+/// Crafts a recipe several times at once, straight into the inventory, or into the
+/// queue of the open station if it takes time to craft
+pub struct BatchCraftEvent {
+    pub obj: WorldObject,
+    pub amount: CraftAmount,
+}
+
+/// Crafts of one recipe waiting in a station's queue
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueuedCraft {
+    pub obj: WorldObject,
+    pub count: usize,
+}
+
+/// The crafts of a station that takes time to craft, like a cauldron. Kept in the
+/// container registry, so it keeps going while the station's chunk is unloaded.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CraftingQueue {
+    pub crafting_type: CraftingContainerType,
+    pub queued: Vec<QueuedCraft>,
+    /// seconds into the craft at the front of the queue
+    pub progress: f32,
+    /// crafts that are done, waiting for the player to come back to the station
+    pub finished: Vec<QueuedCraft>,
+}
+
+impl CraftingQueue {
+    pub fn new(crafting_type: CraftingContainerType) -> Self {
+        Self {
+            crafting_type,
+            ..Default::default()
+        }
+    }
+    fn push_craft(crafts: &mut Vec<QueuedCraft>, obj: WorldObject, count: usize) {
+        match crafts.iter_mut().find(|craft| craft.obj == obj) {
+            Some(craft) => craft.count += count,
+            None => crafts.push(QueuedCraft { obj, count }),
+        }
+    }
+    pub fn push(&mut self, obj: WorldObject, count: usize) {
+        Self::push_craft(&mut self.queued, obj, count);
+    }
+    pub fn get_num_queued(&self) -> usize {
+        self.queued.iter().map(|craft| craft.count).sum()
+    }
+    /// How far along the craft at the front of the queue is, from 0 to 1
+    pub fn get_progress_percent(&self) -> f32 {
+        let craft_secs = self.crafting_type.get_craft_secs().unwrap_or(1.);
+        (self.progress / craft_secs).min(1.)
+    }
+    pub fn tick(&mut self, secs: f32) {
+        let Some(craft_secs) = self.crafting_type.get_craft_secs() else {
+            return;
+        };
+        if self.queued.is_empty() {
+            self.progress = 0.;
+            return;
+        }
+        self.progress += secs;
+        while self.progress >= craft_secs && !self.queued.is_empty() {
+            self.progress -= craft_secs;
+            let obj = self.queued[0].obj;
+            Self::push_craft(&mut self.finished, obj, 1);
+            self.queued[0].count -= 1;
+            if self.queued[0].count == 0 {
+                self.queued.remove(0);
+            }
+        }
+    }
 }
 
 // there will be N craftable items in a given Crafting UI.
@@ -112,33 +228,8 @@ pub fn handle_crafted_item(
     mut analytics: EventWriter<AnalyticsUpdateEvent>,
 ) {
     for event in events.iter() {
-        let mut inv = inv.single_mut();
-        let mut remaining_cost = recipes
-            .crafting_list
-            .get(&event.obj)
-            .expect("crafted item does not have recipe?")
-            .0
-            .clone();
-        while !remaining_cost.is_empty() {
-            for item in remaining_cost.clone().iter() {
-                let ingredient_slot = inv
-                    .items
-                    .get_slot_for_item_in_container(&item.item)
-                    .expect("player crafted item but does not have the required ingredients?");
-                let stack = inv.items.items[ingredient_slot].as_mut().unwrap();
-                if stack.item_stack.count >= item.count {
-                    inv.items.items[ingredient_slot] = stack.modify_count(-(item.count as i8));
-                    remaining_cost.retain(|x| x != item);
-                } else {
-                    let count = stack.item_stack.count;
-                    inv.items.items[ingredient_slot] = None;
-                    remaining_cost.retain(|x| x != item);
-                    remaining_cost.push(RecipeItem {
-                        item: item.item,
-                        count: (item.count - count),
-                    });
-                }
-            }
+        if !event.ingredients_used {
+            use_ingredients(&mut inv.single_mut(), event.obj, event.count, &recipes);
         }
         analytics.send(AnalyticsUpdateEvent {
             update_type: AnalyticsTrigger::RecipeCrafted(event.obj),
@@ -146,6 +237,42 @@ pub fn handle_crafted_item(
     }
 }
 
+/// Takes the ingredients for `count` crafts of `obj` out of the inventory
+fn use_ingredients(inv: &mut Inventory, obj: WorldObject, count: usize, recipes: &Recipes) {
+    let mut remaining_cost = recipes
+        .crafting_list
+        .get(&obj)
+        .expect("crafted item does not have recipe?")
+        .0
+        .iter()
+        .map(|ingredient| RecipeItem {
+            item: ingredient.item,
+            count: ingredient.count * count,
+        })
+        .collect::<Vec<_>>();
+    while !remaining_cost.is_empty() {
+        for item in remaining_cost.clone().iter() {
+            let ingredient_slot = inv
+                .items
+                .get_slot_for_item_in_container(&item.item)
+                .expect("player crafted item but does not have the required ingredients?");
+            let stack = inv.items.items[ingredient_slot].as_mut().unwrap();
+            if stack.item_stack.count >= item.count {
+                inv.items.items[ingredient_slot] = stack.modify_count(-(item.count as i8));
+                remaining_cost.retain(|x| x != item);
+            } else {
+                let count = stack.item_stack.count;
+                inv.items.items[ingredient_slot] = None;
+                remaining_cost.retain(|x| x != item);
+                remaining_cost.push(RecipeItem {
+                    item: item.item,
+                    count: (item.count - count),
+                });
+            }
+        }
+    }
+}
+
 pub fn get_crafting_inventory_item_stacks(
     objs: Vec<WorldObject>,
     rec: &Recipes,
@@ -301,6 +428,55 @@ pub fn handle_furnace_slot_update(
     }
 }
 
+/// Catches up a furnace whose chunk was unloaded for `elapsed` seconds, smelting
+/// as many items as its fuel and ingredients allow
+pub fn smelt_furnace_offscreen(
+    furnace: &mut Container,
+    elapsed: f32,
+    recipes: &Recipes,
+    proto: &ProtoParam,
+) {
+    // upgrade stations share the furnace container, but only work while on screen
+    if furnace.items.len() != 3 {
+        return;
+    }
+    let (Some(fuel), Some(ingredient)) = (furnace.items[0].clone(), furnace.items[1].clone())
+    else {
+        return;
+    };
+    let Some(expected_result) = recipes.furnace_list.get(ingredient.get_obj()) else {
+        return;
+    };
+    if let Some(curr_result) = &furnace.items[2] {
+        if curr_result.get_obj() != expected_result {
+            return;
+        }
+    }
+    let smelts_per_fuel = ((FurnaceState::from_fuel(*fuel.get_obj())
+        .current_fuel_left
+        .duration()
+        .as_secs_f32()
+        / FURNACE_SMELT_SECS) as usize)
+        .max(1);
+    let num_smelted = ((elapsed / FURNACE_SMELT_SECS) as usize)
+        .min(ingredient.item_stack.count)
+        .min(fuel.item_stack.count * smelts_per_fuel)
+        .min(i8::MAX as usize);
+    if num_smelted == 0 {
+        return;
+    }
+    let fuel_used = (num_smelted + smelts_per_fuel - 1) / smelts_per_fuel;
+    furnace.items[0] = fuel.clone().modify_count(-(fuel_used as i8));
+    furnace.items[1] = ingredient.clone().modify_count(-(num_smelted as i8));
+    furnace.items[2] = if let Some(mut existing_result) = furnace.items[2].clone() {
+        existing_result.modify_count(num_smelted as i8)
+    } else {
+        let mut result =
+            InventoryItemStack::new(proto.get_item_data(*expected_result).unwrap().clone(), 2);
+        result.modify_count(num_smelted as i8 - 1)
+    };
+}
+
 pub fn handle_inv_changed_update_crafting_tracker(
     mut inv: Query<&mut Inventory, Changed<Inventory>>,
     mut craft_tracker: ResMut<CraftingTracker>,
@@ -383,3 +559,194 @@ pub fn handle_inv_changed_update_crafting_tracker(
         };
     }
 }
+
+/// Puts a crafted item in the inventory, or at the player's feet if there is no room
+fn give_crafted_item(
+    commands: &mut Commands,
+    game: &mut GameParam,
+    inv: &mut Inventory,
+    item: ItemStack,
+) {
+    // a partial stack only counts if the whole craft fits on it, the rest would be lost
+    let fits_on_stack = inv
+        .items
+        .get_slot_for_item_in_container_with_space(&item, None)
+        .and_then(|slot| inv.items.items[slot].as_ref())
+        .map_or(false, |stack| {
+            stack.item_stack.count + item.count <= MAX_STACK_SIZE
+        });
+    if fits_on_stack || inv.items.get_first_empty_slot().is_some() {
+        item.add_to_inventory(&mut inv.items, &mut game.inv_slot_query);
+    } else {
+        let pos = game.player().position.truncate();
+        item.spawn_as_drop(commands, game, pos);
+    }
+}
+
+/// Each craft rolls its own attributes, like a single craft does
+fn get_crafted_items(
+    obj: WorldObject,
+    crafts: usize,
+    recipes: &Recipes,
+    proto: &ProtoParam,
+) -> Vec<ItemStack> {
+    let (Some((_, _, stack_count)), Some(stack)) =
+        (recipes.crafting_list.get(&obj), proto.get_item_data(obj))
+    else {
+        return vec![];
+    };
+    (0..crafts)
+        .map(|_| {
+            create_new_random_item_stack_with_attributes(
+                &stack.copy_with_count(*stack_count),
+                proto,
+            )
+        })
+        .collect()
+}
+
+/// Checks the ingredients for every craft before any are used, so a batch either
+/// happens in full or not at all
+pub fn handle_batch_craft(
+    mut commands: Commands,
+    mut events: EventReader<BatchCraftEvent>,
+    mut crafted_events: EventWriter<CraftedItemEvent>,
+    mut inv: Query<&mut Inventory>,
+    mut game: GameParam,
+    mut container_reg: ResMut<ContainerRegistry>,
+    crafting_container: Option<Res<CraftingContainer>>,
+    tracker: Res<CraftingTracker>,
+    recipes: Res<Recipes>,
+    proto: ProtoParam,
+) {
+    for BatchCraftEvent { obj, amount } in events.iter() {
+        let Ok(mut inv) = inv.get_single_mut() else {
+            continue;
+        };
+        if !tracker.discovered_recipes.contains(obj) {
+            continue;
+        }
+        let max = recipes.get_max_craftable(*obj, &inv.items);
+        let crafts = match amount {
+            CraftAmount::Count(count) if *count <= max => *count,
+            CraftAmount::Count(_) => continue,
+            CraftAmount::Max => max,
+        };
+        if crafts == 0 {
+            continue;
+        }
+        // the ingredients are used up before the results are handed over, so they
+        // can go into the slots the ingredients leave behind. Queued crafts are
+        // picked up from the station when they are done
+        use_ingredients(&mut inv, *obj, crafts, &recipes);
+        let queued_station = crafting_container
+            .as_ref()
+            .filter(|container| container.is_queued());
+        if let Some(station) = queued_station {
+            container_reg
+                .crafting_queues
+                .entry(station.station_pos.unwrap())
+                .or_insert_with(|| CraftingQueue::new(station.crafting_type.clone()))
+                .push(*obj, crafts);
+        } else {
+            for item in get_crafted_items(*obj, crafts, &recipes, &proto) {
+                give_crafted_item(&mut commands, &mut game, &mut inv, item);
+            }
+            crafted_events.send(CraftedItemEvent {
+                obj: *obj,
+                count: crafts,
+                ingredients_used: true,
+            });
+        }
+    }
+}
+
+pub fn tick_crafting_queues(time: Res<Time>, mut container_reg: ResMut<ContainerRegistry>) {
+    if container_reg
+        .crafting_queues
+        .values()
+        .all(|queue| queue.queued.is_empty())
+    {
+        return;
+    }
+    for queue in container_reg.crafting_queues.values_mut() {
+        queue.tick(time.delta_seconds());
+    }
+}
+
+/// Hands over everything the open station finished while the player was away
+pub fn collect_finished_crafts(
+    mut commands: Commands,
+    mut inv: Query<&mut Inventory>,
+    mut game: GameParam,
+    mut container_reg: ResMut<ContainerRegistry>,
+    mut crafted_events: EventWriter<CraftedItemEvent>,
+    crafting_container: Res<CraftingContainer>,
+    recipes: Res<Recipes>,
+    proto: ProtoParam,
+) {
+    let Some(pos) = crafting_container.station_pos else {
+        return;
+    };
+    let (Ok(mut inv), Some(queue)) = (
+        inv.get_single_mut(),
+        container_reg.crafting_queues.get_mut(&pos),
+    ) else {
+        return;
+    };
+    if queue.finished.is_empty() {
+        return;
+    }
+    for craft in std::mem::take(&mut queue.finished) {
+        for item in get_crafted_items(craft.obj, craft.count, &recipes, &proto) {
+            give_crafted_item(&mut commands, &mut game, &mut inv, item);
+        }
+        crafted_events.send(CraftedItemEvent {
+            obj: craft.obj,
+            count: craft.count,
+            ingredients_used: true,
+        });
+    }
+}
+
+/// A broken station drops what it finished, and gives back the ingredients of what
+/// it had not gotten to yet
+pub fn handle_break_crafting_station(
+    mut commands: Commands,
+    mut obj_break_events: EventReader<ObjBreakEvent>,
+    mut game: GameParam,
+    mut container_reg: ResMut<ContainerRegistry>,
+    recipes: Res<Recipes>,
+    proto: ProtoParam,
+) {
+    for broken in obj_break_events.iter() {
+        let Some(queue) = container_reg.crafting_queues.remove(&broken.pos) else {
+            continue;
+        };
+        let pos = tile_pos_to_world_pos(broken.pos, false);
+        for craft in queue.finished.iter() {
+            for item in get_crafted_items(craft.obj, craft.count, &recipes, &proto) {
+                item.spawn_as_drop(&mut commands, &mut game, pos);
+            }
+        }
+        for craft in queue.queued.iter() {
+            let Some((ingredients, ..)) = recipes.crafting_list.get(&craft.obj) else {
+                continue;
+            };
+            for ingredient in ingredients.iter() {
+                let Some(stack) = proto.get_item_data(ingredient.item) else {
+                    continue;
+                };
+                // big batches can refund more than fits in one stack
+                let mut remaining = ingredient.count * craft.count;
+                while remaining > 0 {
+                    let count = remaining.min(MAX_STACK_SIZE);
+                    stack
+                        .copy_with_count(count)
+                        .spawn_as_drop(&mut commands, &mut game, pos);
+                    remaining -= count;
+                }
+            }
+        }
+    }
+}
@@ -6,6 +6,7 @@ use crate::{
         modifiers::{ModifyHealthEvent, ModifyManaEvent},
     },
     client::analytics::{AnalyticsTrigger, AnalyticsUpdateEvent},
+    farming::WaterTileEvent,
     inputs::CursorPos,
     inventory::Inventory,
     juice::UseItemEvent,
@@ -18,7 +19,7 @@ use crate::{
     },
     world::{
         dimension::DimensionSpawnEvent,
-        world_helpers::{can_object_be_placed_here, world_pos_to_tile_pos},
+        world_helpers::{can_object_be_placed_here, tile_pos_to_world_pos, world_pos_to_tile_pos},
     },
     GameParam, TextureCamera,
 };
@@ -42,6 +43,9 @@ pub enum ItemAction {
     Essence,
     DungeonKey,
     GrantSkillPoint(u8),
+    Till,
+    Plant(WorldObject),
+    Water,
 }
 impl ItemAction {
     pub fn get_tooltip(&self) -> Option<String> {
@@ -79,7 +83,7 @@ impl ItemActions {
         for action in &self.actions {
             match action {
                 ItemAction::Eat(_) => has_eat = true,
-                ItemAction::PlacesInto(_) => has_places_into = true,
+                ItemAction::PlacesInto(_) | ItemAction::Plant(_) => has_places_into = true,
                 ItemAction::ModifyHealth(_) => has_consumable = true,
                 ItemAction::ModifyMana(_) => has_consumable = true,
                 _ => {}
@@ -121,6 +125,7 @@ pub struct ItemActionParam<'w, 's> {
     pub modify_mana_event: EventWriter<'w, ModifyManaEvent>,
     pub place_item_event: EventWriter<'w, PlaceItemEvent>,
     pub action_success_event: EventWriter<'w, ActionSuccessEvent>,
+    pub water_tile_event: EventWriter<'w, WaterTileEvent>,
     pub cursor_pos: Res<'w, CursorPos>,
     pub hunger_query: Query<'w, 's, &'static mut Hunger>,
     pub chest_query: Query<'w, 's, &'static ChestContainer>,
@@ -217,6 +222,74 @@ impl ItemActions {
 
                     item_action_param.use_item_event.send(UseItemEvent(obj));
                 }
+                ItemAction::Till => {
+                    let pos = item_action_param.cursor_pos.world_coords.truncate();
+                    if game.player().position.truncate().distance(pos)
+                        > game.player().reach_distance * 32.
+                    {
+                        return;
+                    }
+                    let tile_pos = world_pos_to_tile_pos(pos);
+                    let is_grass = game.get_tile_data(tile_pos).map_or(false, |tile_data| {
+                        tile_data
+                            .block_type
+                            .iter()
+                            .all(|block| block == &WorldObject::GrassTile)
+                    });
+                    if !is_grass
+                        || !can_object_be_placed_here(
+                            tile_pos,
+                            game,
+                            WorldObject::TilledSoil,
+                            proto_param,
+                        )
+                    {
+                        return;
+                    }
+                    item_action_param.place_item_event.send(PlaceItemEvent {
+                        obj: WorldObject::TilledSoil,
+                        pos: tile_pos_to_world_pos(tile_pos, false),
+                        placed_by_player: true,
+                        override_existing_obj: false,
+                    });
+                }
+                ItemAction::Plant(crop) => {
+                    let pos = item_action_param.cursor_pos.world_coords.truncate();
+                    if game.player().position.truncate().distance(pos)
+                        > game.player().reach_distance * 32.
+                    {
+                        return;
+                    }
+                    let tile_pos = world_pos_to_tile_pos(pos);
+                    if !matches!(
+                        game.get_obj_entity_at_tile(tile_pos, proto_param),
+                        Some((_, WorldObject::TilledSoil))
+                    ) {
+                        return;
+                    }
+                    item_action_param.place_item_event.send(PlaceItemEvent {
+                        obj: *crop,
+                        pos: tile_pos_to_world_pos(tile_pos, false),
+                        placed_by_player: true,
+                        override_existing_obj: true,
+                    });
+                    item_action_param
+                        .analytics_event
+                        .send(AnalyticsUpdateEvent {
+                            update_type: AnalyticsTrigger::ObjectPlaced(*crop),
+                        });
+                }
+                ItemAction::Water => {
+                    let pos = item_action_param.cursor_pos.world_coords.truncate();
+                    if game.player().position.truncate().distance(pos)
+                        > game.player().reach_distance * 32.
+                    {
+                        return;
+                    }
+                    item_action_param
+                        .water_tile_event
+                        .send(WaterTileEvent(world_pos_to_tile_pos(pos)));
+                }
                 _ => {}
             }
         }
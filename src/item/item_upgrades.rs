@@ -9,6 +9,7 @@ use crate::status_effects::{Burning, Frail, Poisoned, Slow, StatusEffect, Status
 use crate::{
     combat::{AttackTimer, HitEvent},
     inputs::CursorPos,
+    keymap::{ActionInput, InputAction},
     player::Player,
     proto::proto_param::ProtoParam,
     GameParam,
@@ -62,7 +63,7 @@ pub fn handle_delayed_ranged_attack(
     wep_query: Query<&RangedAttack, With<MainHand>>,
     mut ranged_attack_event: EventWriter<RangedAttackEvent>,
     game: GameParam,
-    mouse_button_input: Res<Input<MouseButton>>,
+    actions: ActionInput,
     cursor_pos: Res<CursorPos>,
     time: Res<Time>,
     mut att_cooldown_query: Query<(&mut ClawUpgradeMultiThrow, Option<&AttackTimer>), With<Player>>,
@@ -82,7 +83,7 @@ pub fn handle_delayed_ranged_attack(
         *count = 0;
         return;
     }
-    if mouse_button_input.pressed(MouseButton::Left) || delayed_ranged_attack.0.percent() != 0. {
+    if actions.pressed(InputAction::Attack) || delayed_ranged_attack.0.percent() != 0. {
         delayed_ranged_attack.0.tick(time.delta());
         if delayed_ranged_attack.0.just_finished() {
             *count += 1;
@@ -108,7 +109,7 @@ pub fn handle_spread_arrows_attack(
     wep_query: Query<&RangedAttack, With<MainHand>>,
     mut ranged_attack_event: EventWriter<RangedAttackEvent>,
     game: GameParam,
-    mouse_button_input: Res<Input<MouseButton>>,
+    actions: ActionInput,
     cursor_pos: Res<CursorPos>,
     att_cooldown_query: Query<(&BowUpgradeSpread, Option<&AttackTimer>), With<Player>>,
     mut count: Local<u8>,
@@ -125,7 +126,7 @@ pub fn handle_spread_arrows_attack(
     if cooldown_option.is_none() {
         *count = 0;
     }
-    if mouse_button_input.pressed(MouseButton::Left) && *count < spread_attack.0 {
+    if actions.pressed(InputAction::Attack) && *count < spread_attack.0 {
         *count += 1;
         let raw_dir = (cursor_pos.world_coords.truncate() - game.player().position.truncate())
             .normalize_or_zero();
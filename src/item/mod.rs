@@ -16,6 +16,7 @@ use crate::player::Player;
 use crate::proto::proto_param::ProtoParam;
 
 use crate::schematic::loot_chests::get_random_loot_chest_type;
+use crate::schematic::SchematicLootChests;
 use crate::status_effects::{
     handle_burning_ticks, handle_frail_stack_ticks, handle_slow_stack_ticks,
 };
@@ -40,6 +41,7 @@ use combat_shrine::{
 use gamble_shrine::{add_gamble_visuals_on_spawn, handle_gamble_shrine_rewards, GambleShrineEvent};
 use rand::Rng;
 
+pub mod build_mode;
 mod crafting;
 pub mod item_actions;
 
@@ -61,6 +63,7 @@ use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use strum_macros::{Display, EnumIter, IntoStaticStr};
 
+use self::build_mode::BuildModePlugin;
 use self::crafting::CraftingPlugin;
 use self::item_actions::handle_item_action_success;
 use self::item_upgrades::{
@@ -420,6 +423,24 @@ pub enum WorldObject {
 
     Scrapper,
     ScrapperBlock,
+
+    // Farming
+    WoodHoe,
+    WateringCan,
+    TilledSoil,
+    BerrySeeds,
+    MushroomSpores,
+    MagicSeeds,
+    BerryCropStage1,
+    BerryCropStage2,
+    BerryCrop,
+    MushroomCropStage1,
+    MushroomCropStage2,
+    MushroomCrop,
+    MagicCropStage1,
+    MagicCropStage2,
+    MagicCrop,
+    WitheredCrop,
 }
 
 #[derive(
@@ -603,6 +624,22 @@ impl WorldObject {
             _ => false,
         }
     }
+    /// Player-built objects that night raiders will try to break, never ones that came with the world
+    pub fn is_raid_target(&self, placed_by_player: bool) -> bool {
+        if !placed_by_player {
+            return false;
+        }
+        match self {
+            WorldObject::CraftingTable => true,
+            WorldObject::Anvil => true,
+            WorldObject::Cauldron => true,
+            WorldObject::Furnace => true,
+            WorldObject::AlchemyTable => true,
+            WorldObject::UpgradeStation => true,
+            WorldObject::WoodDoorOpen => true,
+            _ => self.is_wall(),
+        }
+    }
     pub fn is_medium_size(&self, proto_param: &ProtoParam) -> bool {
         proto_param
             .get_component::<SpriteSize, _>(*self)
@@ -722,6 +759,7 @@ impl Plugin for ItemsPlugin {
             .add_plugin(CraftingPlugin)
             .add_plugin(RangedAttackPlugin)
             .add_plugin(LootTablePlugin)
+            .add_plugin(BuildModePlugin)
             .add_system(
                 handle_break_object
                     .before(CustomFlush)
@@ -769,6 +807,7 @@ pub fn handle_placing_world_object(
         (Without<WorldObject>, Without<Mob>, Without<Player>),
     >,
     dungeon_check: Query<&Dungeon, With<ActiveDimension>>,
+    mut schematic_loot_chests: ResMut<SchematicLootChests>,
 ) {
     for place_event in events.iter() {
         let pos = place_event.pos;
@@ -799,9 +838,11 @@ pub fn handle_placing_world_object(
                 //TODO: do what old game data did, add obj to registry
                 commands.entity(item).set_parent(chunk);
                 if !place_event.placed_by_player && place_event.obj == WorldObject::Chest {
-                    commands
-                        .entity(item)
-                        .insert(get_random_loot_chest_type(rand::thread_rng()));
+                    let chest_type = schematic_loot_chests
+                        .chests
+                        .remove(&tile_pos)
+                        .unwrap_or_else(|| get_random_loot_chest_type(rand::thread_rng()));
+                    commands.entity(item).insert(chest_type);
                 }
 
                 minimap_event.send(UpdateMiniMapEvent {
@@ -824,6 +865,9 @@ pub fn handle_placing_world_object(
         }
         if dungeon_check.get_single().is_err() {
             game.add_object_to_chunk_cache(tile_pos, place_event.obj);
+            if place_event.placed_by_player {
+                game.world_obj_cache.player_placed_objs.insert(tile_pos);
+            }
         } else {
             game.add_object_to_dungeon_cache(tile_pos, place_event.obj);
         }
@@ -3,13 +3,16 @@ use super::gamble_shrine::{GambleShrine, GambleShrineAnim};
 use super::item_actions::ItemActionParam;
 use super::{get_crafting_inventory_item_stacks, PlaceItemEvent, WorldObject};
 
+use crate::assets::SpriteAnchor;
 use crate::container::Container;
 use crate::inventory::Inventory;
 use crate::juice::ShakeEffect;
 use crate::player::ModifyTimeFragmentsEvent;
 use crate::proto::proto_param::ProtoParam;
 use crate::ui::crafting_ui::{CraftingContainer, CraftingContainerType};
+use crate::ui::era_select_ui::EraSelectChoices;
 use crate::ui::key_input_guide::InteractionGuideTrigger;
+use crate::ui::UIState;
 use crate::world::dimension::DimensionSpawnEvent;
 use crate::world::dungeon::spawn_new_dungeon_dimension;
 
@@ -41,6 +44,7 @@ pub enum ObjectAction {
     SetHome,
     CombatShrine,
     GambleShrine,
+    TimeGate,
 }
 
 #[derive(Component, Reflect, FromReflect, Schematic, Default)]
@@ -129,12 +133,20 @@ impl ObjectAction {
             ObjectAction::ChangeObject(new_obj) => {
                 commands.entity(e).despawn_recursive();
                 let pos = item_action_param.cursor_pos.world_coords.truncate();
-                game.remove_object_from_chunk_cache(world_pos_to_tile_pos(pos));
+                let tile_pos = world_pos_to_tile_pos(pos);
+                // opening or closing a rotated door keeps it rotated, and a door that
+                // came with the world is still not the player's
+                let rotation = game.world_obj_cache.rotated_objs.get(&tile_pos).copied();
+                let placed_by_player = game.world_obj_cache.player_placed_objs.contains(&tile_pos);
+                game.remove_object_from_chunk_cache(tile_pos);
+                if let Some(rotation) = rotation {
+                    game.world_obj_cache.rotated_objs.insert(tile_pos, rotation);
+                }
 
                 item_action_param.place_item_event.send(PlaceItemEvent {
                     obj: *new_obj,
                     pos,
-                    placed_by_player: true,
+                    placed_by_player,
                     override_existing_obj: false,
                 });
             }
@@ -191,8 +203,17 @@ impl ObjectAction {
                 } else {
                     vec![]
                 };
+                // the same position the station is broken at, wherever it was clicked
+                let station_pos = game.world_object_query.get(e).ok().map(|(_, txfm, ..)| {
+                    let anchor = proto_param
+                        .get_component::<SpriteAnchor, _>(obj)
+                        .unwrap_or(&SpriteAnchor(Vec2::ZERO));
+                    world_pos_to_tile_pos(txfm.translation().truncate() - anchor.0)
+                });
                 let crafting_container_res = CraftingContainer {
                     items: Container { items },
+                    crafting_type: crafting_type.clone(),
+                    station_pos,
                 };
                 commands.insert_resource(crafting_container_res.clone());
 
@@ -201,6 +222,10 @@ impl ObjectAction {
                     .discovered_crafting_types
                     .push(crafting_type.clone());
             }
+            ObjectAction::TimeGate => {
+                commands.insert_resource(EraSelectChoices::new(game));
+                item_action_param.next_inv_state.set(UIState::EraSelect);
+            }
             ObjectAction::Furnace => {
                 let furnace_res = item_action_param.furnace_query.get(e).unwrap();
                 commands.insert_resource(furnace_res.clone());
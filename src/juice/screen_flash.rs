@@ -1,6 +1,6 @@
 use bevy::{prelude::*, render::view::RenderLayers};
 
-use crate::{ScreenResolution, DEBUG, GAME_HEIGHT};
+use crate::{settings::Settings, ScreenResolution, DEBUG, GAME_HEIGHT};
 
 #[derive(Resource)]
 pub struct FlashEffect {
@@ -17,7 +17,15 @@ pub fn screen_flash_effect(
     mut existing_flash: Query<(Entity, &mut Sprite), With<ScreenFlash>>,
     time: Res<Time>,
     resolution: Res<ScreenResolution>,
+    settings: Res<Settings>,
 ) {
+    if !settings.screen_flashes {
+        for (e, _) in existing_flash.iter() {
+            commands.entity(e).despawn_recursive();
+        }
+        commands.remove_resource::<FlashEffect>();
+        return;
+    }
     if let Ok((e, mut flash)) = existing_flash.get_single_mut() {
         if flash_state.timer.finished() {
             commands.entity(e).despawn_recursive();
@@ -2,7 +2,7 @@ use bevy::prelude::*;
 use noise::{NoiseFn, Perlin};
 use rand::Rng;
 
-use crate::{TextureCamera, DEBUG};
+use crate::{settings::Settings, TextureCamera, DEBUG};
 
 #[derive(Component)]
 pub struct ShakeEffect {
@@ -18,6 +18,7 @@ pub fn shake_effect(
     mut commands: Commands,
     mut shakers: Query<(Entity, &mut Transform, &mut ShakeEffect)>,
     time: Res<Time>,
+    settings: Res<Settings>,
 ) {
     for (e, mut t, mut shake) in shakers.iter_mut() {
         shake.timer.tick(time.delta());
@@ -30,7 +31,9 @@ pub fn shake_effect(
                 + perlin.get([time.delta_seconds_f64(), time.delta_seconds_f64()]) as f32
                     * shake.noise;
             let dir = dir.normalize();
-            let extend = (dir * sin * shake.max_mag * shake.timer.percent_left()).extend(0.);
+            let extend =
+                (dir * sin * shake.max_mag * settings.screen_shake * shake.timer.percent_left())
+                    .extend(0.);
             t.translation += extend;
         } else {
             commands.entity(e).remove::<ShakeEffect>();
@@ -0,0 +1,781 @@
+use std::{
+    fmt,
+    fs::{self, File},
+    io::BufReader,
+    marker::PhantomData,
+};
+
+use bevy::{
+    ecs::system::SystemParam,
+    input::InputSystem,
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    datafiles, inventory::MAX_HOTBAR_SIZE, settings::Settings, ui::UIState, vectorize::vectorize,
+};
+
+/// Every gameplay input the player can rebind from the Options menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InputAction {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Dash,
+    Attack,
+    UseItem,
+    Interact,
+    Inventory,
+    CloseMenu,
+    Hotbar(usize),
+    QuickConsume,
+    QuickMove,
+    LockSlot,
+    Skills,
+    CompanionWheel,
+    WorldMap,
+    RecipeBook,
+    BuildMode,
+    BuildRotate,
+    CycleBlueprint,
+    BuildRectangle,
+    HotbarPrev,
+    HotbarNext,
+    HotbarWheel,
+    SwapHotbarRow,
+    MenuUp,
+    MenuDown,
+    MenuLeft,
+    MenuRight,
+    MenuSelect,
+    MenuAlt,
+}
+
+/// Menu navigation actions and the direction they move the gamepad focus in
+pub const MENU_NAV_ACTIONS: [(InputAction, Vec2); 4] = [
+    (InputAction::MenuUp, Vec2::Y),
+    (InputAction::MenuDown, Vec2::NEG_Y),
+    (InputAction::MenuLeft, Vec2::NEG_X),
+    (InputAction::MenuRight, Vec2::X),
+];
+
+pub const STICK_DEAD_ZONE: f32 = 0.25;
+
+pub const HOTBAR_ACTIONS: [InputAction; MAX_HOTBAR_SIZE] = [
+    InputAction::Hotbar(0),
+    InputAction::Hotbar(1),
+    InputAction::Hotbar(2),
+    InputAction::Hotbar(3),
+    InputAction::Hotbar(4),
+    InputAction::Hotbar(5),
+    InputAction::Hotbar(6),
+    InputAction::Hotbar(7),
+    InputAction::Hotbar(8),
+    InputAction::Hotbar(9),
+    InputAction::Hotbar(10),
+    InputAction::Hotbar(11),
+];
+
+impl InputAction {
+    /// In the order they are listed in the Options menu
+    pub fn all() -> Vec<InputAction> {
+        let mut actions = vec![
+            InputAction::MoveUp,
+            InputAction::MoveDown,
+            InputAction::MoveLeft,
+            InputAction::MoveRight,
+            InputAction::Dash,
+            InputAction::Attack,
+            InputAction::UseItem,
+            InputAction::Interact,
+            InputAction::Inventory,
+            InputAction::CloseMenu,
+        ];
+        actions.extend(HOTBAR_ACTIONS);
+        actions.extend([
+            InputAction::QuickConsume,
+            InputAction::QuickMove,
+            InputAction::LockSlot,
+            InputAction::Skills,
+            InputAction::CompanionWheel,
+            InputAction::WorldMap,
+            InputAction::RecipeBook,
+            InputAction::BuildMode,
+            InputAction::BuildRotate,
+            InputAction::CycleBlueprint,
+            InputAction::BuildRectangle,
+            InputAction::HotbarPrev,
+            InputAction::HotbarNext,
+            InputAction::HotbarWheel,
+            InputAction::SwapHotbarRow,
+            InputAction::MenuUp,
+            InputAction::MenuDown,
+            InputAction::MenuLeft,
+            InputAction::MenuRight,
+            InputAction::MenuSelect,
+            InputAction::MenuAlt,
+        ]);
+        actions
+    }
+    pub fn get_name(&self) -> String {
+        match self {
+            InputAction::MoveUp => "Move Up".to_string(),
+            InputAction::MoveDown => "Move Down".to_string(),
+            InputAction::MoveLeft => "Move Left".to_string(),
+            InputAction::MoveRight => "Move Right".to_string(),
+            InputAction::Dash => "Dash".to_string(),
+            InputAction::Attack => "Attack".to_string(),
+            InputAction::UseItem => "Use Item".to_string(),
+            InputAction::Interact => "Interact".to_string(),
+            InputAction::Inventory => "Inventory".to_string(),
+            InputAction::CloseMenu => "Menu".to_string(),
+            InputAction::Hotbar(i) => format!("Hotbar {}", i + 1),
+            InputAction::QuickConsume => "Quick Use".to_string(),
+            InputAction::QuickMove => "Quick Move".to_string(),
+            InputAction::LockSlot => "Lock Slot".to_string(),
+            InputAction::Skills => "Skills".to_string(),
+            InputAction::CompanionWheel => "Companions".to_string(),
+            InputAction::WorldMap => "Map".to_string(),
+            InputAction::RecipeBook => "Recipes".to_string(),
+            InputAction::BuildMode => "Build Mode".to_string(),
+            InputAction::BuildRotate => "Rotate".to_string(),
+            InputAction::CycleBlueprint => "Blueprint".to_string(),
+            InputAction::BuildRectangle => "Build Area".to_string(),
+            InputAction::HotbarPrev => "Prev Slot".to_string(),
+            InputAction::HotbarNext => "Next Slot".to_string(),
+            InputAction::HotbarWheel => "Slot Wheel".to_string(),
+            InputAction::SwapHotbarRow => "Swap Row".to_string(),
+            InputAction::MenuUp => "Menu Up".to_string(),
+            InputAction::MenuDown => "Menu Down".to_string(),
+            InputAction::MenuLeft => "Menu Left".to_string(),
+            InputAction::MenuRight => "Menu Right".to_string(),
+            InputAction::MenuSelect => "Select".to_string(),
+            InputAction::MenuAlt => "Select Alt".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputBinding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    /// Matches the button on any connected gamepad
+    Gamepad(GamepadButtonType),
+}
+
+/// Keys that can be bound to an action. `KeyCode` has no serde support,
+/// so bindings are stored by name and looked up in this list when loaded
+const BINDABLE_KEYS: &[KeyCode] = &[
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Key0,
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::Escape,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::Insert,
+    KeyCode::Home,
+    KeyCode::Delete,
+    KeyCode::End,
+    KeyCode::PageDown,
+    KeyCode::PageUp,
+    KeyCode::Left,
+    KeyCode::Up,
+    KeyCode::Right,
+    KeyCode::Down,
+    KeyCode::Back,
+    KeyCode::Return,
+    KeyCode::Space,
+    KeyCode::Numpad0,
+    KeyCode::Numpad1,
+    KeyCode::Numpad2,
+    KeyCode::Numpad3,
+    KeyCode::Numpad4,
+    KeyCode::Numpad5,
+    KeyCode::Numpad6,
+    KeyCode::Numpad7,
+    KeyCode::Numpad8,
+    KeyCode::Numpad9,
+    KeyCode::NumpadAdd,
+    KeyCode::NumpadSubtract,
+    KeyCode::NumpadMultiply,
+    KeyCode::NumpadDivide,
+    KeyCode::NumpadEnter,
+    KeyCode::Apostrophe,
+    KeyCode::Backslash,
+    KeyCode::Comma,
+    KeyCode::Equals,
+    KeyCode::Grave,
+    KeyCode::LBracket,
+    KeyCode::Minus,
+    KeyCode::Period,
+    KeyCode::RBracket,
+    KeyCode::Semicolon,
+    KeyCode::Slash,
+    KeyCode::Tab,
+    KeyCode::LAlt,
+    KeyCode::LControl,
+    KeyCode::LShift,
+    KeyCode::RAlt,
+    KeyCode::RControl,
+    KeyCode::RShift,
+    KeyCode::Capital,
+    KeyCode::Colon,
+    KeyCode::Asterisk,
+    KeyCode::Plus,
+];
+const BINDABLE_MOUSE_BUTTONS: &[MouseButton] =
+    &[MouseButton::Left, MouseButton::Right, MouseButton::Middle];
+const BINDABLE_GAMEPAD_BUTTONS: &[GamepadButtonType] = &[
+    GamepadButtonType::South,
+    GamepadButtonType::East,
+    GamepadButtonType::North,
+    GamepadButtonType::West,
+    GamepadButtonType::LeftTrigger,
+    GamepadButtonType::LeftTrigger2,
+    GamepadButtonType::RightTrigger,
+    GamepadButtonType::RightTrigger2,
+    GamepadButtonType::Select,
+    GamepadButtonType::Start,
+    GamepadButtonType::LeftThumb,
+    GamepadButtonType::RightThumb,
+    GamepadButtonType::DPadUp,
+    GamepadButtonType::DPadDown,
+    GamepadButtonType::DPadLeft,
+    GamepadButtonType::DPadRight,
+];
+
+impl InputBinding {
+    pub fn is_bindable(&self) -> bool {
+        match self {
+            InputBinding::Key(key) => BINDABLE_KEYS.contains(key),
+            InputBinding::Mouse(button) => BINDABLE_MOUSE_BUTTONS.contains(button),
+            InputBinding::Gamepad(button) => BINDABLE_GAMEPAD_BUTTONS.contains(button),
+        }
+    }
+    pub fn is_gamepad(&self) -> bool {
+        matches!(self, InputBinding::Gamepad(_))
+    }
+    /// The name bindings are saved under, e.g. `LShift`, `MouseLeft` or `PadSouth`
+    pub fn get_name(&self) -> String {
+        match self {
+            InputBinding::Key(key) => format!("{key:?}"),
+            InputBinding::Mouse(button) => format!("Mouse{button:?}"),
+            InputBinding::Gamepad(button) => format!("Pad{button:?}"),
+        }
+    }
+    fn from_name(name: &str) -> Option<Self> {
+        if let Some(button) = name.strip_prefix("Pad") {
+            return BINDABLE_GAMEPAD_BUTTONS
+                .iter()
+                .find(|b| format!("{b:?}") == button)
+                .map(|b| InputBinding::Gamepad(*b));
+        }
+        if let Some(button) = name.strip_prefix("Mouse") {
+            return BINDABLE_MOUSE_BUTTONS
+                .iter()
+                .find(|b| format!("{b:?}") == button)
+                .map(|b| InputBinding::Mouse(*b));
+        }
+        BINDABLE_KEYS
+            .iter()
+            .find(|k| format!("{k:?}") == name)
+            .map(|k| InputBinding::Key(*k))
+    }
+}
+
+/// Short label shown in menus
+impl fmt::Display for InputBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputBinding::Key(KeyCode::Return) => write!(f, "Enter"),
+            InputBinding::Key(KeyCode::Back) => write!(f, "Backspace"),
+            InputBinding::Key(key) => write!(f, "{key:?}"),
+            InputBinding::Mouse(MouseButton::Left) => write!(f, "LMB"),
+            InputBinding::Mouse(MouseButton::Right) => write!(f, "RMB"),
+            InputBinding::Mouse(MouseButton::Middle) => write!(f, "MMB"),
+            InputBinding::Mouse(button) => write!(f, "Mouse{button:?}"),
+            InputBinding::Gamepad(button) => match button {
+                GamepadButtonType::South => write!(f, "A"),
+                GamepadButtonType::East => write!(f, "B"),
+                GamepadButtonType::North => write!(f, "Y"),
+                GamepadButtonType::West => write!(f, "X"),
+                GamepadButtonType::LeftTrigger => write!(f, "LB"),
+                GamepadButtonType::LeftTrigger2 => write!(f, "LT"),
+                GamepadButtonType::RightTrigger => write!(f, "RB"),
+                GamepadButtonType::RightTrigger2 => write!(f, "RT"),
+                GamepadButtonType::Select => write!(f, "Back"),
+                GamepadButtonType::LeftThumb => write!(f, "LS"),
+                GamepadButtonType::RightThumb => write!(f, "RS"),
+                GamepadButtonType::DPadUp => write!(f, "D-Up"),
+                GamepadButtonType::DPadDown => write!(f, "D-Down"),
+                GamepadButtonType::DPadLeft => write!(f, "D-Left"),
+                GamepadButtonType::DPadRight => write!(f, "D-Right"),
+                button => write!(f, "{button:?}"),
+            },
+        }
+    }
+}
+
+impl Serialize for InputBinding {
+    fn serialize<S: Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        ser.serialize_str(&self.get_name())
+    }
+}
+
+impl<'de> Deserialize<'de> for InputBinding {
+    fn deserialize<D: Deserializer<'de>>(des: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(des)?;
+        InputBinding::from_name(&name)
+            .ok_or_else(|| de::Error::custom(format!("unknown input binding {name}")))
+    }
+}
+
+/// Maps each `InputAction` to the keys, mouse and gamepad buttons that trigger it.
+/// Saved to its own file in the data dir so it persists across runs and saves
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+pub struct Keymap {
+    #[serde(with = "vectorize")]
+    pub bindings: HashMap<InputAction, Vec<InputBinding>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        use InputBinding::{Gamepad, Key, Mouse};
+        let mut bindings = HashMap::default();
+        bindings.insert(InputAction::MoveUp, vec![Key(KeyCode::W), Key(KeyCode::Up)]);
+        bindings.insert(
+            InputAction::MoveDown,
+            vec![Key(KeyCode::S), Key(KeyCode::Down)],
+        );
+        bindings.insert(
+            InputAction::MoveLeft,
+            vec![Key(KeyCode::A), Key(KeyCode::Left)],
+        );
+        bindings.insert(
+            InputAction::MoveRight,
+            vec![Key(KeyCode::D), Key(KeyCode::Right)],
+        );
+        bindings.insert(InputAction::Dash, vec![Key(KeyCode::Space)]);
+        bindings.insert(InputAction::Attack, vec![Mouse(MouseButton::Left)]);
+        bindings.insert(InputAction::UseItem, vec![Mouse(MouseButton::Right)]);
+        bindings.insert(InputAction::Interact, vec![Key(KeyCode::F)]);
+        bindings.insert(
+            InputAction::Inventory,
+            vec![Key(KeyCode::E), Key(KeyCode::I), Key(KeyCode::Tab)],
+        );
+        bindings.insert(InputAction::CloseMenu, vec![Key(KeyCode::Escape)]);
+        for (i, key) in [
+            KeyCode::Key1,
+            KeyCode::Key2,
+            KeyCode::Key3,
+            KeyCode::Key4,
+            KeyCode::Key5,
+            KeyCode::Key6,
+            KeyCode::Key7,
+            KeyCode::Key8,
+            KeyCode::Key9,
+            KeyCode::Key0,
+            KeyCode::Minus,
+            KeyCode::Equals,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            bindings.insert(InputAction::Hotbar(i), vec![Key(key)]);
+        }
+        bindings.insert(
+            InputAction::QuickConsume,
+            vec![Key(KeyCode::LShift), Key(KeyCode::RShift)],
+        );
+        bindings.insert(InputAction::QuickMove, vec![Key(KeyCode::LShift)]);
+        bindings.insert(InputAction::LockSlot, vec![Mouse(MouseButton::Middle)]);
+        bindings.insert(InputAction::Skills, vec![Key(KeyCode::B)]);
+        bindings.insert(InputAction::CompanionWheel, vec![Key(KeyCode::R)]);
+        bindings.insert(InputAction::WorldMap, vec![Key(KeyCode::M)]);
+        bindings.insert(InputAction::RecipeBook, vec![Key(KeyCode::C)]);
+        bindings.insert(InputAction::HotbarWheel, vec![Key(KeyCode::Q)]);
+        bindings.insert(InputAction::SwapHotbarRow, vec![Key(KeyCode::T)]);
+        bindings.insert(InputAction::BuildMode, vec![Key(KeyCode::V)]);
+        bindings.insert(InputAction::BuildRotate, vec![Key(KeyCode::X)]);
+        bindings.insert(InputAction::CycleBlueprint, vec![Key(KeyCode::Z)]);
+        bindings.insert(
+            InputAction::BuildRectangle,
+            vec![Key(KeyCode::LShift), Key(KeyCode::RShift)],
+        );
+        for (action, button) in [
+            (InputAction::Dash, GamepadButtonType::LeftTrigger2),
+            (InputAction::Attack, GamepadButtonType::RightTrigger2),
+            (InputAction::UseItem, GamepadButtonType::West),
+            (InputAction::Interact, GamepadButtonType::South),
+            (InputAction::Inventory, GamepadButtonType::North),
+            (InputAction::CloseMenu, GamepadButtonType::Start),
+            (InputAction::Skills, GamepadButtonType::Select),
+            (InputAction::CompanionWheel, GamepadButtonType::East),
+            (InputAction::WorldMap, GamepadButtonType::LeftThumb),
+            (InputAction::BuildMode, GamepadButtonType::RightThumb),
+            (InputAction::HotbarPrev, GamepadButtonType::LeftTrigger),
+            (InputAction::HotbarNext, GamepadButtonType::RightTrigger),
+            (InputAction::HotbarWheel, GamepadButtonType::DPadUp),
+            (InputAction::SwapHotbarRow, GamepadButtonType::DPadDown),
+            (InputAction::MenuUp, GamepadButtonType::DPadUp),
+            (InputAction::MenuDown, GamepadButtonType::DPadDown),
+            (InputAction::MenuLeft, GamepadButtonType::DPadLeft),
+            (InputAction::MenuRight, GamepadButtonType::DPadRight),
+            (InputAction::MenuSelect, GamepadButtonType::South),
+            (InputAction::MenuAlt, GamepadButtonType::West),
+        ] {
+            bindings.entry(action).or_default().push(Gamepad(button));
+        }
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    /// Actions missing from the file, like ones added in a newer version, keep their defaults
+    pub fn load() -> Self {
+        let mut keymap = Keymap::default();
+        let Ok(file) = File::open(datafiles::keymap_file()) else {
+            return keymap;
+        };
+        match serde_json::from_reader::<_, Keymap>(BufReader::new(file)) {
+            Ok(loaded) => {
+                for (action, mut bindings) in loaded.bindings {
+                    // keymaps saved before gamepad support keep the default gamepad buttons
+                    if !bindings.iter().any(|b| b.is_gamepad()) {
+                        bindings.extend(
+                            keymap
+                                .get_bindings(action)
+                                .iter()
+                                .filter(|b| b.is_gamepad()),
+                        );
+                    }
+                    keymap.bindings.insert(action, bindings);
+                }
+            }
+            Err(err) => error!("Failed to load keymap, using the default controls {err:?}"),
+        }
+        keymap
+    }
+    pub fn save(&self) {
+        let path = datafiles::keymap_file();
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let Ok(file) = File::create(path) else {
+            error!("Could not create keymap file");
+            return;
+        };
+        if let Err(err) = serde_json::to_writer(file, self) {
+            error!("Failed to save keymap: {err:?}");
+        }
+    }
+    pub fn get_bindings(&self, action: InputAction) -> &[InputBinding] {
+        self.bindings
+            .get(&action)
+            .map(|b| b.as_slice())
+            .unwrap_or_default()
+    }
+    /// Replaces the action's bindings on the same device as the new one, so rebinding
+    /// a key keeps the gamepad button and the other way around
+    pub fn rebind(&mut self, action: InputAction, binding: InputBinding) {
+        let bindings = self.bindings.entry(action).or_default();
+        bindings.retain(|b| b.is_gamepad() != binding.is_gamepad());
+        bindings.push(binding);
+    }
+    /// Every gamepad or keyboard and mouse binding of the action, or just the first
+    /// if they don't fit in `max_len`
+    pub fn get_binding_label(&self, action: InputAction, gamepad: bool, max_len: usize) -> String {
+        let labels: Vec<String> = self
+            .get_bindings(action)
+            .iter()
+            .filter(|b| b.is_gamepad() == gamepad)
+            .map(|b| b.to_string())
+            .collect();
+        let label = labels.join("/");
+        if labels.is_empty() {
+            "-".to_string()
+        } else if label.len() > max_len {
+            labels[0].clone()
+        } else {
+            label
+        }
+    }
+    pub fn pressed(
+        &self,
+        action: InputAction,
+        keys: &Input<KeyCode>,
+        mouse: &Input<MouseButton>,
+    ) -> bool {
+        self.get_bindings(action)
+            .iter()
+            .any(|binding| match binding {
+                InputBinding::Key(key) => keys.pressed(*key),
+                InputBinding::Mouse(button) => mouse.pressed(*button),
+                InputBinding::Gamepad(_) => false,
+            })
+    }
+    pub fn just_pressed(
+        &self,
+        action: InputAction,
+        keys: &Input<KeyCode>,
+        mouse: &Input<MouseButton>,
+    ) -> bool {
+        self.get_bindings(action)
+            .iter()
+            .any(|binding| match binding {
+                InputBinding::Key(key) => keys.just_pressed(*key),
+                InputBinding::Mouse(button) => mouse.just_pressed(*button),
+                InputBinding::Gamepad(_) => false,
+            })
+    }
+    pub fn just_released(
+        &self,
+        action: InputAction,
+        keys: &Input<KeyCode>,
+        mouse: &Input<MouseButton>,
+    ) -> bool {
+        self.get_bindings(action)
+            .iter()
+            .any(|binding| match binding {
+                InputBinding::Key(key) => keys.just_released(*key),
+                InputBinding::Mouse(button) => mouse.just_released(*button),
+                InputBinding::Gamepad(_) => false,
+            })
+    }
+    /// Stops later systems this frame from also reacting to the action
+    pub fn clear_just_pressed(
+        &self,
+        action: InputAction,
+        keys: &mut Input<KeyCode>,
+        mouse: &mut Input<MouseButton>,
+    ) {
+        for binding in self.get_bindings(action) {
+            match binding {
+                InputBinding::Key(key) => keys.clear_just_pressed(*key),
+                InputBinding::Mouse(button) => mouse.clear_just_pressed(*button),
+                InputBinding::Gamepad(_) => false,
+            };
+        }
+    }
+    pub fn pad_pressed(
+        &self,
+        action: InputAction,
+        gamepads: &Gamepads,
+        buttons: &Input<GamepadButton>,
+    ) -> bool {
+        self.get_pad_buttons(action, gamepads)
+            .any(|button| buttons.pressed(button))
+    }
+    pub fn pad_just_pressed(
+        &self,
+        action: InputAction,
+        gamepads: &Gamepads,
+        buttons: &Input<GamepadButton>,
+    ) -> bool {
+        self.get_pad_buttons(action, gamepads)
+            .any(|button| buttons.just_pressed(button))
+    }
+    pub fn pad_just_released(
+        &self,
+        action: InputAction,
+        gamepads: &Gamepads,
+        buttons: &Input<GamepadButton>,
+    ) -> bool {
+        self.get_pad_buttons(action, gamepads)
+            .any(|button| buttons.just_released(button))
+    }
+    pub fn clear_pad_just_pressed(
+        &self,
+        action: InputAction,
+        gamepads: &Gamepads,
+        buttons: &mut Input<GamepadButton>,
+    ) {
+        for button in self.get_pad_buttons(action, gamepads) {
+            buttons.clear_just_pressed(button);
+        }
+    }
+    /// The action's gamepad buttons on every connected gamepad
+    fn get_pad_buttons<'a>(
+        &'a self,
+        action: InputAction,
+        gamepads: &'a Gamepads,
+    ) -> impl Iterator<Item = GamepadButton> + 'a {
+        self.get_bindings(action)
+            .iter()
+            .filter_map(|binding| match binding {
+                InputBinding::Gamepad(button) => Some(*button),
+                _ => None,
+            })
+            .flat_map(move |button| {
+                gamepads
+                    .iter()
+                    .map(move |gamepad| GamepadButton::new(gamepad, button))
+            })
+    }
+}
+
+/// Read the player's input by action rather than by key, so rebinding applies everywhere
+#[derive(SystemParam)]
+pub struct ActionInput<'w, 's> {
+    pub keymap: Res<'w, Keymap>,
+    pub keys: Res<'w, Input<KeyCode>>,
+    pub mouse: Res<'w, Input<MouseButton>>,
+    pub gamepads: Res<'w, Gamepads>,
+    pub pad_buttons: Res<'w, Input<GamepadButton>>,
+    pub pad_axes: Res<'w, Axis<GamepadAxis>>,
+    pub toggled: Res<'w, ToggledActions>,
+
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+impl<'w, 's> ActionInput<'w, 's> {
+    pub fn pressed(&self, action: InputAction) -> bool {
+        self.toggled.0.contains(&action)
+            || self.keymap.pressed(action, &self.keys, &self.mouse)
+            || self
+                .keymap
+                .pad_pressed(action, &self.gamepads, &self.pad_buttons)
+    }
+    pub fn just_pressed(&self, action: InputAction) -> bool {
+        self.keymap.just_pressed(action, &self.keys, &self.mouse)
+            || self
+                .keymap
+                .pad_just_pressed(action, &self.gamepads, &self.pad_buttons)
+    }
+    pub fn just_released(&self, action: InputAction) -> bool {
+        self.keymap.just_released(action, &self.keys, &self.mouse)
+            || self
+                .keymap
+                .pad_just_released(action, &self.gamepads, &self.pad_buttons)
+    }
+    /// Left stick, zero inside the dead zone
+    pub fn move_axis(&self) -> Vec2 {
+        self.get_stick(GamepadAxisType::LeftStickX, GamepadAxisType::LeftStickY)
+    }
+    /// Right stick, zero inside the dead zone
+    pub fn aim_axis(&self) -> Vec2 {
+        self.get_stick(GamepadAxisType::RightStickX, GamepadAxisType::RightStickY)
+    }
+    pub fn is_gamepad_connected(&self) -> bool {
+        self.gamepads.iter().next().is_some()
+    }
+    fn get_stick(&self, x_axis: GamepadAxisType, y_axis: GamepadAxisType) -> Vec2 {
+        for gamepad in self.gamepads.iter() {
+            let stick = Vec2::new(
+                self.pad_axes
+                    .get(GamepadAxis::new(gamepad, x_axis))
+                    .unwrap_or(0.),
+                self.pad_axes
+                    .get(GamepadAxis::new(gamepad, y_axis))
+                    .unwrap_or(0.),
+            );
+            if stick.length() > STICK_DEAD_ZONE {
+                return stick.clamp_length_max(1.);
+            }
+        }
+        Vec2::ZERO
+    }
+    pub fn any_pressed(&self, actions: impl IntoIterator<Item = InputAction>) -> bool {
+        actions.into_iter().any(|action| self.pressed(action))
+    }
+    pub fn any_just_released(&self, actions: impl IntoIterator<Item = InputAction>) -> bool {
+        actions.into_iter().any(|action| self.just_released(action))
+    }
+}
+
+pub struct KeymapPlugin;
+
+impl Plugin for KeymapPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Keymap::load())
+            .init_resource::<ToggledActions>()
+            .add_system(
+                update_toggled_actions
+                    .in_base_set(CoreSet::PreUpdate)
+                    .after(InputSystem),
+            );
+    }
+}
+
+/// Actions held down by a single press while their hold-to-toggle setting is on
+#[derive(Resource, Default, Debug)]
+pub struct ToggledActions(pub HashSet<InputAction>);
+
+/// Flips a toggled action on every press, and lets go of them all while a menu is open
+fn update_toggled_actions(
+    keymap: Res<Keymap>,
+    keys: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    gamepads: Res<Gamepads>,
+    pad_buttons: Res<Input<GamepadButton>>,
+    settings: Res<Settings>,
+    ui_state: Res<State<UIState>>,
+    mut toggled: ResMut<ToggledActions>,
+) {
+    if ui_state.0 != UIState::Closed {
+        toggled.0.clear();
+        return;
+    }
+    for (action, enabled) in [
+        (InputAction::Attack, settings.toggle_attack),
+        (InputAction::Dash, settings.toggle_dash),
+    ] {
+        if !enabled {
+            toggled.0.remove(&action);
+            continue;
+        }
+        if (keymap.just_pressed(action, &keys, &mouse)
+            || keymap.pad_just_pressed(action, &gamepads, &pad_buttons))
+            && !toggled.0.remove(&action)
+        {
+            toggled.0.insert(action);
+        }
+    }
+}
@@ -39,14 +39,17 @@ use bevy::{
         view::RenderLayers,
     },
     sprite::{Material2d, Material2dPlugin, MaterialMesh2dBundle},
-    window::{PresentMode, PrimaryWindow, WindowMode, WindowResolution},
+    window::{PrimaryWindow, WindowResolution},
 };
 use bevy_common_assets::ron::RonAssetPlugin;
 use bevy_embedded_assets::EmbeddedAssetPlugin;
+use farming::FarmingPlugin;
 use juice::JuicePlugin;
+use keymap::KeymapPlugin;
 use night::NightPlugin;
 use rand::Rng;
 use sappling::SapplingPlugin;
+use settings::{Settings, SettingsPlugin};
 
 mod juice;
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
@@ -60,14 +63,17 @@ mod colors;
 mod combat;
 mod custom_commands;
 mod enemy;
+mod farming;
 mod inputs;
 mod inventory;
 mod item;
+mod keymap;
 mod night;
 mod player;
 mod proto;
 mod sappling;
 mod schematic;
+mod settings;
 mod ui;
 mod world;
 use animations::AnimationsPlugin;
@@ -97,7 +103,10 @@ use world::{
     y_sort::YSort,
     TileMapPosition, WallTextureData, WorldPlugin,
 };
-use world::{dimension::EraManager, WorldGeneration};
+use world::{
+    dimension::{EraList, EraListProto, EraManager},
+    WorldGeneration,
+};
 
 use crate::assets::SpriteAnchor;
 use lazy_static::lazy_static;
@@ -144,6 +153,9 @@ fn main() {
         std::fs::rename(old_save_state, datafiles::save_file()).expect("move save file");
     }
 
+    let settings = Settings::load();
+    let (window_width, window_height) = settings.get_resolution();
+
     // ok now run the game :)
     let mut app = App::new();
 
@@ -169,13 +181,13 @@ fn main() {
                 .set(ImagePlugin::default_nearest())
                 .set(WindowPlugin {
                     primary_window: Some(Window {
-                        resolution: WindowResolution::new(WIDTH, HEIGHT)
+                        resolution: WindowResolution::new(window_width, window_height)
                             .with_scale_factor_override(1.0),
                         title: "Lost in Time".to_string(),
-                        present_mode: PresentMode::Fifo,
+                        present_mode: settings.get_present_mode(),
                         resizable: true,
                         transparent: true,
-                        mode: WindowMode::BorderlessFullscreen,
+                        mode: settings.window_mode.get_window_mode(),
                         ..Default::default()
                     }),
                     ..default()
@@ -185,6 +197,7 @@ fn main() {
         )
         .add_plugin(RonAssetPlugin::<GraphicsDesc>::new(&["desc.ron"]))
         .add_plugin(RonAssetPlugin::<RecipeListProto>::new(&["ron"]))
+        .add_plugin(RonAssetPlugin::<EraListProto>::new(&["era.ron"]))
         .insert_resource(Msaa::Off)
         .insert_resource(FixedTime::new_from_secs(TIME_STEP))
         .add_plugin(panic_handler::PanicHandler::new().build())
@@ -198,10 +211,14 @@ fn main() {
         .add_plugin(AudioPlugin)
         .add_plugin(ItemsPlugin)
         .add_plugin(AnimationsPlugin)
+        .insert_resource(settings)
+        .add_plugin(SettingsPlugin)
+        .add_plugin(KeymapPlugin)
         .add_plugin(InputsPlugin)
         .add_plugin(UIPlugin)
         .add_plugin(NightPlugin)
         .add_plugin(SapplingPlugin)
+        .add_plugin(FarmingPlugin)
         .add_plugin(AIPlugin)
         .add_plugin(AttributesPlugin)
         .add_plugin(CombatPlugin)
@@ -323,6 +340,8 @@ pub struct ImageAssets {
     pub sprite_desc: Handle<GraphicsDesc>,
     #[asset(path = "recipes/recipes.ron")]
     pub recipes: Handle<RecipeListProto>,
+    #[asset(path = "eras/eras.era.ron")]
+    pub eras: Handle<EraListProto>,
 }
 
 #[derive(Component)]
@@ -334,6 +353,7 @@ pub struct GameParam<'w, 's> {
     pub graphics: Res<'w, Graphics>,
     pub resolution: Res<'w, ScreenResolution>,
     pub era: ResMut<'w, EraManager>,
+    pub eras: Res<'w, EraList>,
     pub world_generation_params: ResMut<'w, WorldGeneration>,
     pub pathfinding_cache: ResMut<'w, PathfindingCache>,
     pub world_obj_data: ResMut<'w, WorldObjectResource>,
@@ -416,6 +436,8 @@ impl<'w, 's> GameParam<'w, 's> {
     }
     pub fn remove_object_from_chunk_cache(&mut self, pos: TileMapPosition) {
         self.world_obj_cache.objects.remove(&pos);
+        self.world_obj_cache.rotated_objs.remove(&pos);
+        self.world_obj_cache.player_placed_objs.remove(&pos);
     }
     pub fn add_object_to_dungeon_cache(&mut self, pos: TileMapPosition, obj: WorldObject) {
         self.world_obj_cache.dungeon_objects.insert(pos, obj);
@@ -570,6 +592,9 @@ pub struct MainCamera;
 pub struct TextureCamera;
 #[derive(Component, Default)]
 pub struct UICamera;
+/// Draws the UI layer into the texture shown by the `UICamera`
+#[derive(Component, Default)]
+pub struct UITextureCamera;
 #[derive(Component, Default)]
 pub struct TextureTarget;
 #[derive(Component, Debug, Default)]
@@ -716,6 +741,7 @@ fn setup(
             ..default()
         },
         DoNotDespawnOnGameOver,
+        UITextureCamera,
         RenderLayers::from_layers(&[3]),
     ));
 
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use bevy::{prelude::*, render::view::RenderLayers};
 use serde::{Deserialize, Serialize};
 
@@ -5,9 +7,13 @@ use crate::{
     audio::{BGMPicker, UpdateBGMTrackEvent},
     client::is_not_paused,
     colors::{overwrite_alpha, NIGHT},
+    world::biome::CurrentBiome,
     GameState, ScreenResolution, GAME_HEIGHT,
 };
 
+/// Real seconds that make up one in-game hour
+pub const HOUR_LENGTH_SECS: f32 = 17.;
+
 #[derive(Component)]
 pub struct Night(Timer);
 
@@ -16,6 +22,9 @@ pub struct Night(Timer);
 pub struct NightTracker {
     pub days: u8,
     pub time: f32,
+    /// seconds into the current hour, copied from the [Night] timer
+    #[serde(default)]
+    pub hour_progress: f32,
 }
 impl NightTracker {
     pub fn get_alpha(&self) -> f32 {
@@ -33,6 +42,10 @@ impl NightTracker {
     pub fn is_dawn(&self) -> bool {
         self.time == 0.
     }
+    /// Total game time of this run in seconds
+    pub fn get_game_time_secs(&self) -> f32 {
+        (self.days as f32 * 24. + self.time) * HOUR_LENGTH_SECS + self.hour_progress
+    }
 }
 
 pub struct NightPlugin;
@@ -63,6 +76,9 @@ pub fn spawn_night(
     night_tracker: Res<NightTracker>,
     res: Res<ScreenResolution>,
 ) {
+    // pick up partway through the hour a loaded save left off at
+    let mut night_timer = Timer::from_seconds(HOUR_LENGTH_SECS, TimerMode::Repeating);
+    night_timer.set_elapsed(Duration::from_secs_f32(night_tracker.hour_progress));
     commands
         .spawn(SpriteBundle {
             sprite: Sprite {
@@ -78,7 +94,7 @@ pub fn spawn_night(
             ..default()
         })
         .insert(RenderLayers::from_layers(&[3]))
-        .insert(Night(Timer::from_seconds(17., TimerMode::Repeating)))
+        .insert(Night(night_timer))
         .insert(Name::new("night"));
 }
 
@@ -88,10 +104,12 @@ pub fn tick_night_color(
     mut night_tracker: ResMut<NightTracker>,
     mut bgm_track_event: EventWriter<UpdateBGMTrackEvent>,
     bgm_tracker: Res<BGMPicker>,
+    current_biome: Res<CurrentBiome>,
     mut new_day_event: EventWriter<NewDayEvent>,
 ) {
     for (mut night_state, mut sprite) in query.iter_mut() {
         night_state.0.tick(time.delta());
+        night_tracker.hour_progress = night_state.0.elapsed_secs();
         if night_state.0.finished() {
             night_tracker.time += 1.;
             sprite.color = overwrite_alpha(sprite.color, night_tracker.get_alpha());
@@ -108,10 +126,10 @@ pub fn tick_night_color(
                     asset_path: "sounds/bgm_night.ogg".to_owned(),
                 });
             } else if !night_tracker.is_night()
-                && bgm_tracker.current_track != *"sounds/bgm_day.ogg"
+                && bgm_tracker.current_track != current_biome.get_day_track()
             {
                 bgm_track_event.send(UpdateBGMTrackEvent {
-                    asset_path: "sounds/bgm_day.ogg".to_owned(),
+                    asset_path: current_biome.get_day_track(),
                 });
             }
         }
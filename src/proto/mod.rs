@@ -15,7 +15,7 @@ use bevy_rapier2d::prelude::{Collider, KinematicCharacterController, QueryFilter
 
 pub mod proto_param;
 use crate::{
-    ai::IdleState,
+    ai::{perception::Perception, IdleState},
     animations::{
         enemy_sprites::{
             CharacterAnimationSpriteSheetData, EnemyAnimationState, LeftFacingSideProfile,
@@ -28,8 +28,10 @@ use crate::{
         RawItemBaseAttributes, RawItemBonusAttributes,
     },
     enemy::{
-        CombatAlignment, EnemyMaterial, FollowSpeed, LeapAttack, Mob, MobLevel, ProjectileAttack,
+        companion::Tameable, CombatAlignment, EnemyMaterial, FollowSpeed, LeapAttack, Mob,
+        MobLevel, ProjectileAttack,
     },
+    farming::Crop,
     inputs::FacingDirection,
     inventory::ItemStack,
     item::{
@@ -48,9 +50,13 @@ use crate::{
     ui::{
         crafting_ui::CraftingContainerType,
         scrapper_ui::{Scrap, ScrapsInto},
+        shop_ui::{MerchantTable, ShopOffer, ShopPrice},
         EssenceOption, EssenceShopChoices,
     },
-    world::{ForestGenerationParams, WallTextureData},
+    world::{
+        biome::{Biome, BiomeParams},
+        ForestGenerationParams, WallTextureData,
+    },
     CustomFlush, GameState, YSort,
 };
 pub struct ProtoPlugin;
@@ -86,10 +92,12 @@ impl Plugin for ProtoPlugin {
             .register_type::<RawItemBonusAttributes>()
             .register_type::<ExperienceReward>()
             .register_type::<GrowsInto>()
+            .register_type::<Crop>()
             .register_type::<SapplingProto>()
             .register_type::<ItemDisplayMetaData>()
             .register_type::<YSort>()
             .register_type::<IdleStateProto>()
+            .register_type::<PerceptionProto>()
             .register_type::<EnemyMaterialMesh2DProto>()
             .register_type::<SpriteSheetProto>()
             .register_type::<KCC>()
@@ -119,8 +127,11 @@ impl Plugin for ProtoPlugin {
             .register_type::<ManaCost>()
             .register_type::<FacingDirection>()
             .register_type::<ForestGenerationParams>()
+            .register_type::<Biome>()
+            .register_type::<BiomeParams>()
             .register_type::<CraftingContainerType>()
             .register_type::<LeapAttack>()
+            .register_type::<Tameable>()
             .register_type::<ProjectileAttack>()
             .register_type::<Scrap>()
             .register_type::<ScrapsInto>()
@@ -129,6 +140,10 @@ impl Plugin for ProtoPlugin {
             .register_type::<EssenceOption>()
             .register_type::<Vec<EssenceOption>>()
             .register_type::<EssenceShopChoices>()
+            .register_type::<MerchantTable>()
+            .register_type::<ShopOffer>()
+            .register_type::<Vec<ShopOffer>>()
+            .register_type::<ShopPrice>()
             .register_type::<StatusEffectState>()
             .register_type::<CharacterAnimationSpriteSheetData>()
             .register_type::<AnimationPosTracker>()
@@ -136,6 +151,9 @@ impl Plugin for ProtoPlugin {
             .register_type::<HashMap<WorldObject, f64>>()
             .register_type::<HashMap<SchematicType, f64>>()
             .register_type::<HashMap<WorldObject, f32>>()
+            .register_type::<HashMap<Mob, f32>>()
+            .register_type::<HashMap<Biome, BiomeParams>>()
+            .register_type::<Option<String>>()
             .register_type::<Vec<WorldObject>>()
             .register_type::<Vec<u8>>()
             .register_type::<Vec<StatusEffectState>>()
@@ -230,6 +248,21 @@ impl From<IdleStateProto> for IdleState {
     }
 }
 
+#[derive(Schematic, Reflect, FromReflect)]
+#[reflect(Schematic)]
+#[schematic(into = Perception)]
+struct PerceptionProto {
+    view_angle: f32,
+    hearing_range: f32,
+    memory_secs: f32,
+}
+
+impl From<PerceptionProto> for Perception {
+    fn from(p: PerceptionProto) -> Perception {
+        Perception::new(p.view_angle, p.hearing_range, p.memory_secs)
+    }
+}
+
 #[derive(Schematic, Reflect, FromReflect)]
 #[reflect(Schematic)]
 #[schematic(into = AnimationTimer)]
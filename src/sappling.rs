@@ -24,6 +24,30 @@ impl Plugin for SapplingPlugin {
     }
 }
 
+/// Grows a sappling that has been unloaded for `elapsed` seconds, passing through
+/// as many stages as it would have on screen. Returns the new object and how far
+/// into its current stage it is.
+pub fn grow_sappling_offscreen(
+    mut obj: WorldObject,
+    mut progress: f32,
+    mut elapsed: f32,
+    proto_param: &ProtoParam,
+) -> (WorldObject, f32) {
+    while let (Some(sappling), Some(growth)) = (
+        proto_param.get_component::<Sappling, _>(obj),
+        proto_param.get_component::<GrowsInto, _>(obj),
+    ) {
+        let remaining = sappling.0.duration().as_secs_f32() - progress;
+        if elapsed < remaining {
+            return (obj, progress + elapsed);
+        }
+        elapsed -= remaining;
+        progress = 0.;
+        obj = growth.0;
+    }
+    (obj, 0.)
+}
+
 pub fn tick_sappling_color(
     time: Res<Time>,
     mut query: Query<(&WorldObject, &mut Sappling, &GrowsInto, &GlobalTransform)>,
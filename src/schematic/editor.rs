@@ -0,0 +1,426 @@
+use std::fs;
+
+use bevy::{
+    prelude::*,
+    scene::{DynamicEntity, DynamicScene},
+    sprite::Anchor,
+    utils::HashMap,
+};
+
+use crate::{
+    colors::BLACK,
+    inputs::CursorPos,
+    item::{build_mode::BuildMode, WorldObject},
+    proto::proto_param::ProtoParam,
+    ui::{damage_numbers::spawn_text, UIElement},
+    world::{
+        world_helpers::{
+            global_tile_to_tile_pos, tile_pos_to_global_tile, tile_pos_to_world_pos,
+            world_pos_to_tile_pos,
+        },
+        y_sort::YSort,
+        TileMapPosition, TILE_SIZE,
+    },
+    GameParam, GameState, DEBUG, GAME_HEIGHT,
+};
+
+use super::{
+    get_schematic_type_registry, loot_chests::LootChestType, SchematicOptionalCell,
+    SchematicTransform, SchematicType,
+};
+
+pub const SCHEMATIC_EDITOR_KEY: KeyCode = KeyCode::F3;
+const FREQUENCY_STEP: f64 = 0.005;
+const OMIT_CHANCE_STEP: f32 = 0.25;
+const SCENE_DIR: &str = "scenes";
+
+pub struct SchematicEditorPlugin;
+impl Plugin for SchematicEditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SchematicEditor>().add_systems(
+            (
+                toggle_schematic_editor,
+                handle_schematic_editor_input.after(toggle_schematic_editor),
+                update_schematic_editor_display.after(handle_schematic_editor_input),
+            )
+                .in_set(OnUpdate(GameState::Main)),
+        );
+    }
+}
+
+/// Designer tool for authoring schematics in game, only available with DEBUG set.
+/// Objects are placed as usual, then a middle-click drag selects what gets saved.
+#[derive(Resource, Default, Debug)]
+pub struct SchematicEditor {
+    pub enabled: bool,
+    pub schematic_type: SchematicType,
+    /// chance for each new chunk to get this schematic
+    pub frequency: f64,
+    pub typing_name: bool,
+    pub selection_start: Option<TileMapPosition>,
+    pub selection: Option<(IVec2, IVec2)>,
+    /// rotation and mirroring used when previewing
+    pub transform: SchematicTransform,
+    /// chests that were given a loot type, the rest get a random one when spawned
+    pub chest_types: HashMap<TileMapPosition, LootChestType>,
+    /// objects that are only sometimes spawned, swaps are added by hand in the scene file
+    pub omit_chances: HashMap<TileMapPosition, f32>,
+}
+
+impl SchematicEditor {
+    fn get_scene_path(&self) -> String {
+        format!("{SCENE_DIR}/{}.scn.ron", self.schematic_type)
+    }
+    fn get_selected_tiles(&self) -> Vec<TileMapPosition> {
+        let Some((min, max)) = self.selection else {
+            return vec![];
+        };
+        let mut tiles = vec![];
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                tiles.push(global_tile_to_tile_pos(IVec2::new(x, y)));
+            }
+        }
+        tiles
+    }
+}
+
+#[derive(Component)]
+pub struct SchematicEditorText;
+#[derive(Component)]
+pub struct SchematicSelectionGhost;
+
+fn get_next_chest_type(chest_type: Option<&LootChestType>) -> Option<LootChestType> {
+    match chest_type {
+        None => Some(LootChestType::Common),
+        Some(LootChestType::Common) => Some(LootChestType::Uncommon),
+        Some(LootChestType::Uncommon) => Some(LootChestType::Rare),
+        Some(LootChestType::Rare) => Some(LootChestType::Food),
+        Some(LootChestType::Food) => None,
+    }
+}
+
+fn get_next_schematic_type(schematic_type: &SchematicType) -> SchematicType {
+    match schematic_type {
+        SchematicType::CombatShrine => SchematicType::GambleShrine,
+        SchematicType::GambleShrine => SchematicType::Custom("NewSchematic".to_string()),
+        SchematicType::Custom(_) => SchematicType::CombatShrine,
+    }
+}
+
+fn toggle_schematic_editor(
+    mut editor: ResMut<SchematicEditor>,
+    mut build_mode: ResMut<BuildMode>,
+    key_input: Res<Input<KeyCode>>,
+    game: GameParam,
+) {
+    if !*DEBUG || !key_input.just_pressed(SCHEMATIC_EDITOR_KEY) {
+        return;
+    }
+    editor.enabled = !editor.enabled;
+    editor.typing_name = false;
+    editor.frequency = game
+        .world_generation_params
+        .schematic_frequencies
+        .get(&editor.schematic_type)
+        .copied()
+        .unwrap_or(0.);
+    build_mode.enabled = false;
+}
+
+fn handle_schematic_editor_input(
+    mut commands: Commands,
+    mut editor: ResMut<SchematicEditor>,
+    key_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    mut typed_chars: EventReader<ReceivedCharacter>,
+    cursor_pos: Res<CursorPos>,
+    mut game: GameParam,
+    proto_param: ProtoParam,
+    asset_server: Res<AssetServer>,
+) {
+    if !editor.enabled {
+        typed_chars.clear();
+        return;
+    }
+    let cursor_tile = world_pos_to_tile_pos(cursor_pos.world_coords.truncate());
+
+    if editor.typing_name {
+        if let SchematicType::Custom(name) = &mut editor.schematic_type {
+            for typed in typed_chars.iter() {
+                if typed.char.is_ascii_alphanumeric() || typed.char == '_' || typed.char == '-' {
+                    name.push(typed.char);
+                }
+            }
+            if key_input.just_pressed(KeyCode::Back) {
+                name.pop();
+            }
+        }
+        if key_input.just_pressed(KeyCode::Return) {
+            editor.typing_name = false;
+        }
+        return;
+    }
+    typed_chars.clear();
+
+    if mouse_input.just_pressed(MouseButton::Middle) {
+        editor.selection_start = Some(cursor_tile);
+    }
+    if mouse_input.just_released(MouseButton::Middle) {
+        if let Some(start) = editor.selection_start.take() {
+            let (start, end) = (
+                tile_pos_to_global_tile(start),
+                tile_pos_to_global_tile(cursor_tile),
+            );
+            editor.selection = Some((start.min(end), start.max(end)));
+        }
+    }
+    if key_input.just_pressed(KeyCode::N) {
+        if !matches!(editor.schematic_type, SchematicType::Custom(_)) {
+            editor.schematic_type = SchematicType::Custom(String::new());
+        }
+        editor.typing_name = true;
+    }
+    if key_input.just_pressed(KeyCode::T) {
+        editor.schematic_type = get_next_schematic_type(&editor.schematic_type);
+        editor.frequency = game
+            .world_generation_params
+            .schematic_frequencies
+            .get(&editor.schematic_type)
+            .copied()
+            .unwrap_or(0.);
+    }
+    if key_input.just_pressed(KeyCode::Equals) {
+        editor.frequency = (editor.frequency + FREQUENCY_STEP).min(1.);
+    }
+    if key_input.just_pressed(KeyCode::Minus) {
+        editor.frequency = (editor.frequency - FREQUENCY_STEP).max(0.);
+    }
+    if key_input.just_pressed(KeyCode::Q) {
+        editor.transform.rotation = (editor.transform.rotation + 1) % 4;
+    }
+    if key_input.just_pressed(KeyCode::H) {
+        editor.transform.mirrored = !editor.transform.mirrored;
+    }
+    if key_input.just_pressed(KeyCode::L) {
+        if let Some((_, WorldObject::Chest)) =
+            game.get_obj_entity_at_tile(cursor_tile, &proto_param)
+        {
+            match get_next_chest_type(editor.chest_types.get(&cursor_tile)) {
+                Some(chest_type) => editor.chest_types.insert(cursor_tile, chest_type),
+                None => editor.chest_types.remove(&cursor_tile),
+            };
+        }
+    }
+    if key_input.just_pressed(KeyCode::Y) && game.get_object_from_chunk_cache(cursor_tile).is_some()
+    {
+        let omit_chance =
+            editor.omit_chances.get(&cursor_tile).copied().unwrap_or(0.) + OMIT_CHANCE_STEP;
+        if omit_chance < 1. {
+            editor.omit_chances.insert(cursor_tile, omit_chance);
+        } else {
+            editor.omit_chances.remove(&cursor_tile);
+        }
+    }
+    if key_input.just_pressed(KeyCode::Delete) {
+        let mut cleared = vec![];
+        for tile in editor.get_selected_tiles() {
+            if let Some((e, _)) = game.get_obj_entity_at_tile(tile, &proto_param) {
+                if !cleared.contains(&e) {
+                    commands.entity(e).despawn_recursive();
+                    cleared.push(e);
+                }
+            }
+            game.remove_object_from_chunk_cache(tile);
+            editor.chest_types.remove(&tile);
+            editor.omit_chances.remove(&tile);
+        }
+    }
+    if key_input.just_pressed(KeyCode::Return) {
+        save_schematic(&editor, &mut game, &asset_server);
+    }
+    if key_input.just_pressed(KeyCode::P) {
+        commands
+            .spawn(DynamicSceneBundle {
+                scene: asset_server.load(editor.get_scene_path()),
+                transform: Transform::from_translation(
+                    tile_pos_to_world_pos(cursor_tile, false).extend(0.),
+                ),
+                ..default()
+            })
+            .insert(editor.transform)
+            .insert(Name::new("Schematic"));
+    }
+}
+
+/// Writes everything in the selection to the schematic's scene file, and starts
+/// spawning it at the chosen frequency for the rest of the session
+fn save_schematic(editor: &SchematicEditor, game: &mut GameParam, asset_server: &AssetServer) {
+    let Some((min, _)) = editor.selection else {
+        warn!("Select an area with the middle mouse button before saving a schematic");
+        return;
+    };
+    if editor.schematic_type.to_string().is_empty() {
+        warn!("Name the schematic before saving it");
+        return;
+    }
+    let mut entities = vec![];
+    for tile in editor.get_selected_tiles() {
+        let Some(obj) = game.get_object_from_chunk_cache(tile) else {
+            continue;
+        };
+        let offset = (tile_pos_to_global_tile(tile) - min).as_vec2() * TILE_SIZE.x;
+        let mut components: Vec<Box<dyn Reflect>> = vec![
+            Box::new(*obj),
+            Box::new(Transform::from_translation(offset.extend(0.))),
+        ];
+        if let Some(chest_type) = editor.chest_types.get(&tile) {
+            components.push(Box::new(chest_type.clone()));
+        }
+        if let Some(omit_chance) = editor.omit_chances.get(&tile) {
+            components.push(Box::new(SchematicOptionalCell::with_omit_chance(
+                *omit_chance,
+            )));
+        }
+        entities.push(DynamicEntity {
+            entity: entities.len() as u32,
+            components,
+        });
+    }
+    let scene = DynamicScene {
+        resources: vec![],
+        entities,
+    };
+    let type_registry = get_schematic_type_registry();
+    let serialized_scene = match scene.serialize_ron(&type_registry) {
+        Ok(scene) => scene,
+        Err(err) => {
+            error!("Error serializing schematic: {:?}", err);
+            return;
+        }
+    };
+    let path = editor.get_scene_path();
+    if let Err(err) = fs::write(format!("assets/{path}"), serialized_scene) {
+        error!("Error while writing schematic to file: {:?}", err);
+        return;
+    }
+    asset_server.reload_asset(path.clone());
+
+    game.world_generation_params
+        .schematic_frequencies
+        .insert(editor.schematic_type.clone(), editor.frequency);
+    let type_ron = match &editor.schematic_type {
+        SchematicType::Custom(name) => format!("Custom(\"{name}\")"),
+        schematic_type => schematic_type.to_string(),
+    };
+    info!(
+        "Saved schematic to {path}, add `{type_ron}: {}` to schematic_frequencies in the era protos to keep spawning it",
+        editor.frequency
+    );
+}
+
+fn update_schematic_editor_display(
+    mut commands: Commands,
+    editor: Res<SchematicEditor>,
+    asset_server: Res<AssetServer>,
+    cursor_pos: Res<CursorPos>,
+    game: GameParam,
+    proto_param: ProtoParam,
+    mut editor_text: Query<(Entity, &mut Text), With<SchematicEditorText>>,
+    ghosts: Query<Entity, With<SchematicSelectionGhost>>,
+) {
+    if !editor.enabled {
+        if editor.is_changed() {
+            for (e, _) in editor_text.iter() {
+                commands.entity(e).despawn_recursive();
+            }
+            for e in ghosts.iter() {
+                commands.entity(e).despawn();
+            }
+        }
+        return;
+    }
+
+    let cursor_tile = world_pos_to_tile_pos(cursor_pos.world_coords.truncate());
+    let chest_text = match game.get_obj_entity_at_tile(cursor_tile, &proto_param) {
+        Some((_, WorldObject::Chest)) => match editor.chest_types.get(&cursor_tile) {
+            Some(chest_type) => format!("{chest_type:?}"),
+            None => "Random".to_string(),
+        },
+        _ => "-".to_string(),
+    };
+    let omit_chance = editor.omit_chances.get(&cursor_tile).copied().unwrap_or(0.);
+    let size = editor
+        .selection
+        .map(|(min, max)| max - min + IVec2::ONE)
+        .unwrap_or_default();
+    let text = format!(
+        "SCHEMATIC EDITOR\n\nName: {}{}\nFrequency: {:.3}\nRotation: {}  Mirrored: {}\nSelection: {}x{}\nChest loot: {}\nOmit chance: {}%\n\n[MMB] Select  [N] Name  [T] Type  [+/-] Frequency\n[Q] Rotate  [H] Mirror  [L] Chest loot  [Y] Omit chance\n[P] Preview  [Enter] Save  [Del] Clear selection",
+        editor.schematic_type,
+        if editor.typing_name { "_" } else { "" },
+        editor.frequency,
+        editor.transform.rotation as u32 * 90,
+        editor.transform.mirrored,
+        size.x,
+        size.y,
+        chest_text,
+        (omit_chance * 100.) as u32,
+    );
+    if let Ok((_, mut editor_text)) = editor_text.get_single_mut() {
+        editor_text.sections[0].value = text;
+    } else {
+        let text_e = spawn_text(
+            &mut commands,
+            &asset_server,
+            Vec3::new(
+                -game.resolution.game_width / 2. + 4.,
+                GAME_HEIGHT / 2. - 4.,
+                1.,
+            ),
+            BLACK,
+            text,
+            Anchor::TopLeft,
+            1.,
+            3,
+        );
+        commands
+            .entity(text_e)
+            .insert(SchematicEditorText)
+            .insert(Name::new("Schematic Editor Text"));
+    }
+
+    if !editor.is_changed() {
+        return;
+    }
+    for e in ghosts.iter() {
+        commands.entity(e).despawn();
+    }
+    let Some((min, max)) = editor.selection else {
+        return;
+    };
+    for tile in editor.get_selected_tiles() {
+        let global_tile = tile_pos_to_global_tile(tile);
+        if global_tile.x != min.x
+            && global_tile.x != max.x
+            && global_tile.y != min.y
+            && global_tile.y != max.y
+        {
+            continue;
+        }
+        commands
+            .spawn(SpriteBundle {
+                texture: game.graphics.get_ui_element_texture(UIElement::TileHover),
+                transform: Transform::from_translation(
+                    tile_pos_to_world_pos(tile, false).extend(1.),
+                ),
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(16., 16.)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .insert(SchematicSelectionGhost)
+            .insert(Name::new("Schematic Selection"))
+            .insert(YSort(-0.2));
+    }
+}
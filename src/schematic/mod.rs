@@ -1,77 +1,155 @@
-use std::fs::File;
-use std::io::Write;
+use std::fmt;
 
 use bevy::{
-    ecs::system::SystemState,
     math::{Affine3A, Mat3A, Vec3A},
     prelude::*,
-    tasks::IoTaskPool,
+    utils::HashMap,
 };
 
-use strum_macros::{Display, IntoStaticStr};
+use rand::{seq::SliceRandom, Rng};
+
+pub mod editor;
 pub mod loot_chests;
 mod schematic_spawner;
 use crate::{
-    assets::SpriteSize,
-    inventory::ItemStack,
     item::{handle_placing_world_object, Foliage, PlaceItemEvent, Wall, WorldObject},
     player::Player,
     proto::proto_param::ProtoParam,
     world::{
         generation::{get_radial_tile_positions, GenerationPlugin},
         world_helpers::world_pos_to_tile_pos,
+        TileMapPosition, TILE_SIZE,
     },
-    CustomFlush, GameParam, GameState, DEBUG,
+    CustomFlush, GameParam, GameState,
 };
+use editor::SchematicEditorPlugin;
 use loot_chests::*;
 
 use self::schematic_spawner::{
     attempt_to_spawn_schematic_in_chunk, give_chunks_schematic_spawners,
 };
-#[derive(
-    Component,
-    Debug,
-    Hash,
-    Eq,
-    PartialEq,
-    Clone,
-    Reflect,
-    FromReflect,
-    Default,
-    IntoStaticStr,
-    Display,
-)]
+#[derive(Component, Debug, Hash, Eq, PartialEq, Clone, Reflect, FromReflect, Default)]
 pub enum SchematicType {
     #[default]
     CombatShrine,
     GambleShrine,
+    /// Any other scene in assets/scenes, by file name
+    Custom(String),
+}
+
+impl fmt::Display for SchematicType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchematicType::CombatShrine => write!(f, "CombatShrine"),
+            SchematicType::GambleShrine => write!(f, "GambleShrine"),
+            SchematicType::Custom(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+/// Turns and flips a schematic's layout when its scene is placed
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SchematicTransform {
+    /// quarter turns counter-clockwise
+    pub rotation: u8,
+    pub mirrored: bool,
+}
+
+impl SchematicTransform {
+    /// All four quarter turns, each mirrored and not
+    pub fn all() -> impl Iterator<Item = Self> {
+        (0..4).flat_map(|rotation| {
+            [false, true]
+                .into_iter()
+                .map(move |mirrored| Self { rotation, mirrored })
+        })
+    }
+    pub fn random(rng: &mut impl Rng) -> Self {
+        Self {
+            rotation: rng.gen_range(0..4),
+            mirrored: rng.gen_bool(0.5),
+        }
+    }
+    pub fn apply(&self, offset: Vec2) -> Vec2 {
+        let offset = if self.mirrored {
+            Vec2::new(-offset.x, offset.y)
+        } else {
+            offset
+        };
+        match self.rotation % 4 {
+            1 => Vec2::new(-offset.y, offset.x),
+            2 => -offset,
+            3 => Vec2::new(offset.y, -offset.x),
+            _ => offset,
+        }
+    }
+    /// Where an object's bottom left tile ends up, medium objects cover 2x2 tiles
+    /// so their other corner has to be turned with them
+    pub fn apply_to_obj(&self, offset: Vec2, is_medium: bool) -> Vec2 {
+        let turned = self.apply(offset);
+        if !is_medium {
+            return turned;
+        }
+        turned.min(self.apply(offset + Vec2::new(TILE_SIZE.x, TILE_SIZE.y)))
+    }
+}
+
+/// Put on a schematic object so it varies each time the schematic spawns:
+/// it is left out, kept, or swapped for one of `swaps`, picked by weight
+#[derive(Component, Reflect, FromReflect, Default, Debug, Clone)]
+#[reflect(Component)]
+pub struct SchematicOptionalCell {
+    pub omit_weight: f32,
+    pub keep_weight: f32,
+    pub swaps: Vec<SchematicSwap>,
 }
 
-#[derive(Component)]
-pub struct SchematicBuilderObject;
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub struct SchematicSwap {
+    pub obj: WorldObject,
+    pub weight: f32,
+}
 
-#[derive(Resource, Default, Debug, Reflect, Clone)]
-pub struct SchematicToggle {
-    enabled: bool,
-    chest_type: LootChestType,
+impl SchematicOptionalCell {
+    pub fn with_omit_chance(omit_chance: f32) -> Self {
+        Self {
+            omit_weight: omit_chance,
+            keep_weight: 1. - omit_chance,
+            swaps: vec![],
+        }
+    }
+    /// The object this cell spawns this time, or None if it is left empty
+    pub fn roll(&self, obj: WorldObject, rng: &mut impl Rng) -> Option<WorldObject> {
+        let mut choices = vec![(None, self.omit_weight), (Some(obj), self.keep_weight)];
+        choices.extend(self.swaps.iter().map(|swap| (Some(swap.obj), swap.weight)));
+        choices
+            .choose_weighted(rng, |(_, weight)| *weight)
+            .map(|(choice, _)| *choice)
+            .unwrap_or(Some(obj))
+    }
+}
+
+/// Chests placed by a schematic that were given a loot type in the editor,
+/// any other chest a schematic places gets a random one
+#[derive(Resource, Default, Debug)]
+pub struct SchematicLootChests {
+    pub chests: HashMap<TileMapPosition, LootChestType>,
 }
 
 pub struct SchematicSpawnEvent(pub IVec2);
 pub struct SchematicPlugin;
 impl Plugin for SchematicPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(SchematicToggle::default())
-            .register_type::<SchematicToggle>()
+        app.init_resource::<SchematicLootChests>()
+            .register_type::<SchematicOptionalCell>()
+            .register_type::<SchematicSwap>()
+            .register_type::<Vec<SchematicSwap>>()
             .add_event::<SchematicSpawnEvent>()
-            // .add_plugin(ResourceInspectorPlugin::<SchematicToggle>::default())
+            .add_plugin(SchematicEditorPlugin)
             .add_systems(
                 (
                     handle_new_scene_entities_parent_chunk.before(handle_placing_world_object),
-                    save_schematic_scene,
-                    load_schematic,
                     handle_new_loot_chest_spawn.after(CustomFlush),
-                    clear_schematic_entities,
-                    mark_new_world_obj_as_schematic,
                     attempt_to_spawn_schematic_in_chunk,
                     give_chunks_schematic_spawners
                         .after(GenerationPlugin::generate_and_cache_objects),
@@ -80,219 +158,209 @@ impl Plugin for SchematicPlugin {
             );
     }
 }
-fn mark_new_world_obj_as_schematic(
-    mut commands: Commands,
-    query: Query<
-        (Entity, &WorldObject),
-        (Added<WorldObject>, With<SpriteSize>, Without<ItemStack>),
-    >,
-    toggle: Res<SchematicToggle>,
-    old_txfms: Query<&GlobalTransform>,
-) {
-    if toggle.enabled {
-        for (e, obj) in query.iter() {
-            if let Some(mut entity_cmds) = commands.get_entity(e) {
-                let old_txfm = old_txfms.get(e).unwrap();
-                entity_cmds
-                    .insert(SchematicBuilderObject)
-                    .insert(Transform::from_translation(old_txfm.translation()))
-                    .remove_parent();
-                if obj == &WorldObject::Chest {
-                    entity_cmds.insert(toggle.chest_type.clone());
-                }
-            }
-        }
-    }
-}
-fn clear_schematic_entities(
-    mut commands: Commands,
-    query: Query<Entity, With<SchematicBuilderObject>>,
-    key_input: Res<Input<KeyCode>>,
-) {
-    if key_input.just_pressed(KeyCode::C) {
-        for e in query.iter() {
-            if let Some(entity_cmds) = commands.get_entity(e) {
-                entity_cmds.despawn_recursive();
-            }
-        }
+/// Only the components a schematic needs, so scenes stay small and readable
+pub fn get_schematic_type_registry() -> AppTypeRegistry {
+    let type_registry = AppTypeRegistry::default();
+    {
+        let mut writer = type_registry.write();
+        writer.register::<WorldObject>();
+        writer.register::<LootChestType>();
+        writer.register::<SchematicOptionalCell>();
+        writer.register::<SchematicSwap>();
+        writer.register::<Vec<SchematicSwap>>();
+        writer.register::<f32>();
+        writer.register::<Wall>();
+        writer.register::<Foliage>();
+        writer.register::<Transform>();
+        writer.register::<GlobalTransform>();
+        writer.register::<Vec3>();
+        writer.register::<Quat>();
+        writer.register::<Affine3A>();
+        writer.register::<Mat3A>();
+        writer.register::<Vec3A>();
     }
+    type_registry
 }
-fn save_schematic_scene(world: &mut World) {
-    let mut state: SystemState<(
-        Query<Entity, With<SchematicBuilderObject>>,
-        Res<Input<KeyCode>>,
-    )> = SystemState::new(world);
-    let (query, key_input) = state.get(world);
-    if key_input.just_pressed(KeyCode::J) {
-        info!("Saving schematic scene...");
-        let type_registry = AppTypeRegistry::default();
-        {
-            let mut writer = type_registry.write();
-            writer.register::<WorldObject>();
-            writer.register::<LootChestType>();
-            writer.register::<Wall>();
-            writer.register::<Foliage>();
-            writer.register::<Transform>();
-            writer.register::<GlobalTransform>();
-            writer.register::<Vec3>();
-            writer.register::<Quat>();
-            writer.register::<Affine3A>();
-            writer.register::<Mat3A>();
-            writer.register::<Vec3A>();
-        }
-        let mut builder =
-            DynamicSceneBuilder::from_world_with_type_registry(world, type_registry.clone());
-        for e in query.iter() {
-            builder.extract_entity(e);
-        }
-        let scene = builder.build();
 
-        let serialized_scene = scene.serialize_ron(&type_registry).unwrap();
+type SchematicCell = (WorldObject, Vec2, Option<LootChestType>);
 
-        IoTaskPool::get()
-            .spawn(async move {
-                // Write the scene RON data to file
-                File::create("assets/scenes/test.scn.ron")
-                    .and_then(|mut file| file.write(serialized_scene.as_bytes()))
-                    .expect("Error while writing scene to file");
-            })
-            .detach();
-    }
+/// Where each object of a schematic ends up relative to the scene once it is turned,
+/// keeping the layout's bottom left corner at the scene's position
+fn get_schematic_layout(
+    cells: &[SchematicCell],
+    schematic_txfm: SchematicTransform,
+    proto_param: &ProtoParam,
+) -> Vec<SchematicCell> {
+    let turned_cells: Vec<_> = cells
+        .iter()
+        .map(|(obj, offset, loot_chest_option)| {
+            let offset = schematic_txfm.apply_to_obj(*offset, obj.is_medium_size(proto_param));
+            (*obj, offset, loot_chest_option.clone())
+        })
+        .collect();
+    let min_offset = turned_cells
+        .iter()
+        .map(|(_, offset, _)| *offset)
+        .reduce(|a, b| a.min(b))
+        .unwrap_or_default();
+    turned_cells
+        .into_iter()
+        .map(|(obj, offset, loot_chest_option)| (obj, offset - min_offset, loot_chest_option))
+        .collect()
 }
 
-fn load_schematic(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    key_input: Res<Input<KeyCode>>,
-    game: GameParam,
-) {
-    if key_input.just_pressed(KeyCode::M) && *DEBUG {
-        info!(
-            "Loading schematic scene... {} {:?}",
-            game.player().position,
-            world_pos_to_tile_pos(game.player().position.truncate())
-        );
-        commands
-            .spawn(DynamicSceneBundle {
-                scene: asset_server.load("scenes/CombatShrine.scn.ron"),
-                transform: Transform::from_translation(game.player().position),
-                ..default()
-            })
-            .insert(Name::new("Schematic"));
+/// Every tile the object would cover has to be one it is allowed on, and not water
+/// unless it is allowed there. Tiles that are not generated yet are trusted
+fn can_schematic_obj_spawn(
+    game: &GameParam,
+    proto_param: &ProtoParam,
+    obj: WorldObject,
+    pos: Vec2,
+) -> bool {
+    let tile_pos = world_pos_to_tile_pos(pos);
+    let mut tiles = vec![tile_pos];
+    if obj.is_medium_size(proto_param) {
+        tiles.extend(tile_pos.get_neighbour_tiles_for_medium_objects());
     }
+    let filter = game
+        .world_generation_params
+        .obj_allowed_tiles_map
+        .get(&obj)
+        .cloned()
+        .unwrap_or(vec![WorldObject::GrassTile]);
+    tiles.iter().all(|tile| {
+        let Some(tile_data) = game.get_tile_data(*tile) else {
+            return true;
+        };
+        let tile_type = tile_data.block_type;
+        if tile_type.contains(&WorldObject::WaterTile) && !filter.contains(&WorldObject::WaterTile)
+        {
+            return false;
+        }
+        filter
+            .iter()
+            .any(|allowed_tile| tile_type.contains(allowed_tile))
+    })
 }
 
 pub fn handle_new_scene_entities_parent_chunk(
     mut game: GameParam,
     proto_param: ProtoParam,
     new_scenes: Query<
-        (Entity, &Children, &GlobalTransform),
+        (
+            Entity,
+            &Children,
+            &GlobalTransform,
+            Option<&SchematicTransform>,
+        ),
         (With<Handle<DynamicScene>>, Added<Children>),
     >,
     obj_data: Query<
-        (&WorldObject, &Transform, Option<&LootChestType>),
+        (
+            &WorldObject,
+            &Transform,
+            Option<&LootChestType>,
+            Option<&SchematicOptionalCell>,
+        ),
         (With<WorldObject>, Without<Player>),
     >,
     mut commands: Commands,
     mut place_item_event: EventWriter<PlaceItemEvent>,
+    mut loot_chests: ResMut<SchematicLootChests>,
 ) {
-    for (e, children, scene_g) in new_scenes.iter() {
-        let mut x_offset: f32 = 1_000_000_000.;
-        let mut y_offset: f32 = 1_000_000_000.;
-        for child in children.iter() {
-            if let Ok((_, txfm, _)) = obj_data.get(*child) {
-                if txfm.translation.x < x_offset {
-                    x_offset = txfm.translation.x;
-                }
-                if txfm.translation.y < y_offset {
-                    y_offset = txfm.translation.y;
-                }
-            }
-        }
-        for child in children.iter() {
-            if let Ok((obj, txfm, _loot_chest_option)) = obj_data.get(*child) {
-                let pos = scene_g.translation().truncate()
-                    + (txfm.translation.truncate() - Vec2::new(x_offset, y_offset));
+    let mut rng = rand::thread_rng();
+    for (e, children, scene_g, schematic_txfm) in new_scenes.iter() {
+        let origin = scene_g.translation().truncate();
+        // optional cells are rolled once, so every transform tried places the same objects
+        let cells: Vec<SchematicCell> = children
+            .iter()
+            .filter_map(|child| obj_data.get(*child).ok())
+            .filter_map(|(obj, txfm, loot_chest_option, optional_cell)| {
+                let obj = match optional_cell {
+                    Some(optional_cell) => optional_cell.roll(*obj, &mut rng)?,
+                    None => *obj,
+                };
+                Some((obj, txfm.translation.truncate(), loot_chest_option.cloned()))
+            })
+            .collect();
 
-                let mut is_valid_to_spawn = false;
-                if let Some(tile_data) = game.get_tile_data(world_pos_to_tile_pos(pos)) {
-                    let tile_type = tile_data.block_type;
+        // try the transform the scene was spawned with first, then every other one
+        let first_txfm = schematic_txfm.copied().unwrap_or_default();
+        let mut other_txfms: Vec<_> = SchematicTransform::all()
+            .filter(|txfm| txfm != &first_txfm)
+            .collect();
+        other_txfms.shuffle(&mut rng);
+        let Some(layout) = [first_txfm]
+            .into_iter()
+            .chain(other_txfms)
+            .map(|txfm| get_schematic_layout(&cells, txfm, &proto_param))
+            .find(|layout| {
+                layout.iter().all(|(obj, offset, _)| {
+                    can_schematic_obj_spawn(&game, &proto_param, *obj, origin + *offset)
+                })
+            })
+        else {
+            error!("did not spawn schematic, it does not fit at {:?}", origin);
+            commands.entity(e).despawn_recursive();
+            continue;
+        };
 
-                    let filter = game
-                        .world_generation_params
-                        .obj_allowed_tiles_map
-                        .get(obj)
-                        .unwrap_or(&vec![WorldObject::GrassTile])
-                        .clone();
-                    for allowed_tile in filter.iter() {
-                        if tile_type.contains(allowed_tile) {
-                            is_valid_to_spawn = true;
-                        }
-                    }
-                } else {
-                    is_valid_to_spawn = true;
+        for (obj, offset, loot_chest_option) in layout {
+            let pos = origin + offset;
+            let tile_pos = world_pos_to_tile_pos(pos);
+            if obj.is_medium_size(&proto_param) {
+                if let Some((existing_obj, _)) = game.get_obj_entity_at_tile(tile_pos, &proto_param)
+                {
+                    commands.entity(existing_obj).despawn_recursive();
                 }
-                if is_valid_to_spawn {
-                    let tile_pos = world_pos_to_tile_pos(pos);
-                    if obj.is_medium_size(&proto_param) {
-                        if let Some((existing_obj, _)) =
-                            game.get_obj_entity_at_tile(tile_pos, &proto_param)
-                        {
-                            commands.entity(existing_obj).despawn_recursive();
-                        }
-                        for q in 0..3 {
-                            if let Some((existing_obj, _)) = game.get_obj_entity_at_tile(
-                                tile_pos.get_neighbour_tiles_for_medium_objects()[q],
-                                &proto_param,
-                            ) {
-                                commands.entity(existing_obj).despawn_recursive();
-                            }
-                        }
-                    } else if let Some((existing_obj, _)) =
-                        game.get_obj_entity_at_tile(tile_pos, &proto_param)
-                    {
+                for q in 0..3 {
+                    if let Some((existing_obj, _)) = game.get_obj_entity_at_tile(
+                        tile_pos.get_neighbour_tiles_for_medium_objects()[q],
+                        &proto_param,
+                    ) {
                         commands.entity(existing_obj).despawn_recursive();
                     }
-                    let clear_tiles = get_radial_tile_positions(
-                        world_pos_to_tile_pos(pos + Vec2::new(0., -48.)),
-                        6,
-                    );
-                    for clear_tile_pos in clear_tiles.iter() {
-                        if let Some((existing_obj, _)) =
-                            game.get_obj_entity_at_tile(*clear_tile_pos, &proto_param)
-                        {
-                            let (obj_to_clear, _, _) = obj_data.get(existing_obj).unwrap();
-                            if obj_to_clear.is_tree() {
-                                commands.entity(existing_obj).despawn_recursive();
-                                game.remove_object_from_chunk_cache(*clear_tile_pos);
-                            }
-                        }
+                }
+            } else if let Some((existing_obj, _)) =
+                game.get_obj_entity_at_tile(tile_pos, &proto_param)
+            {
+                commands.entity(existing_obj).despawn_recursive();
+            }
+            let clear_tiles =
+                get_radial_tile_positions(world_pos_to_tile_pos(pos + Vec2::new(0., -48.)), 6);
+            for clear_tile_pos in clear_tiles.iter() {
+                if let Some((existing_obj, _)) =
+                    game.get_obj_entity_at_tile(*clear_tile_pos, &proto_param)
+                {
+                    let (obj_to_clear, _, _, _) = obj_data.get(existing_obj).unwrap();
+                    if obj_to_clear.is_tree() {
+                        commands.entity(existing_obj).despawn_recursive();
+                        game.remove_object_from_chunk_cache(*clear_tile_pos);
                     }
-                    let clear_tiles = get_radial_tile_positions(
-                        world_pos_to_tile_pos(pos + Vec2::new(0., 0.)),
-                        3,
-                    );
-                    for clear_tile_pos in clear_tiles.iter() {
-                        if let Some((existing_obj, _)) =
-                            game.get_obj_entity_at_tile(*clear_tile_pos, &proto_param)
-                        {
-                            let (obj_to_clear, _, _) = obj_data.get(existing_obj).unwrap();
-                            if obj_to_clear.is_tree() {
-                                commands.entity(existing_obj).despawn_recursive();
-                                game.remove_object_from_chunk_cache(*clear_tile_pos);
-                            }
-                        }
+                }
+            }
+            let clear_tiles =
+                get_radial_tile_positions(world_pos_to_tile_pos(pos + Vec2::new(0., 0.)), 3);
+            for clear_tile_pos in clear_tiles.iter() {
+                if let Some((existing_obj, _)) =
+                    game.get_obj_entity_at_tile(*clear_tile_pos, &proto_param)
+                {
+                    let (obj_to_clear, _, _, _) = obj_data.get(existing_obj).unwrap();
+                    if obj_to_clear.is_tree() {
+                        commands.entity(existing_obj).despawn_recursive();
+                        game.remove_object_from_chunk_cache(*clear_tile_pos);
                     }
-                    place_item_event.send(PlaceItemEvent {
-                        obj: *obj,
-                        pos,
-                        placed_by_player: false,
-                        override_existing_obj: false,
-                    });
-                } else {
-                    error!("did not spawn, Invalid tile type for object: {:?}", obj);
                 }
             }
+            if let Some(chest_type) = loot_chest_option {
+                loot_chests.chests.insert(tile_pos, chest_type);
+            }
+            place_item_event.send(PlaceItemEvent {
+                obj,
+                pos,
+                placed_by_player: false,
+                override_existing_obj: false,
+            });
         }
         commands.entity(e).despawn_recursive();
     }
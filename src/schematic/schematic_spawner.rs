@@ -1,4 +1,4 @@
-use super::{SchematicSpawnEvent, SchematicType};
+use super::{SchematicSpawnEvent, SchematicTransform, SchematicType};
 use crate::{
     world::{
         chunk::Chunk,
@@ -36,6 +36,7 @@ pub fn attempt_to_spawn_schematic_in_chunk(
                 transform: Transform::from_translation(target_pos.extend(0.)),
                 ..default()
             })
+            .insert(SchematicTransform::random(&mut rng))
             .insert(Name::new("Schematic"));
         commands.entity(e).remove::<SchematicSpawner>();
     }
@@ -0,0 +1,444 @@
+use std::{
+    fs::{self, File},
+    io::BufReader,
+};
+
+use bevy::{
+    prelude::*,
+    window::{PresentMode, PrimaryWindow, WindowMode},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    colors::ColorPalette,
+    datafiles,
+    inventory::{InventorySortOrder, MAX_HOTBAR_SIZE, MIN_HOTBAR_SIZE},
+    world::WorldSize,
+    UITextureCamera, HEIGHT, WIDTH,
+};
+
+/// The UI is drawn at the game's pixel scale, so it can only shrink without pushing
+/// the HUD off the screen
+pub const MIN_UI_SCALE: f32 = 0.75;
+const UI_SCALE_STEP: f32 = 0.05;
+const VOLUME_STEP: f32 = 0.1;
+const SCREEN_SHAKE_STEP: f32 = 0.25;
+const HOTBAR_SIZE_STEP: usize = 2;
+/// Window sizes offered in windowed mode. They all keep the game's aspect ratio, since
+/// the game is drawn to a texture that is sized once when the game starts.
+const RESOLUTIONS: [(u32, u32); 6] = [
+    (1280, 800),
+    (1440, 900),
+    (1680, 1050),
+    (WIDTH as u32, HEIGHT as u32),
+    (1920, 1200),
+    (2560, 1600),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum WindowModeSetting {
+    Windowed,
+    #[default]
+    Borderless,
+    Fullscreen,
+}
+
+impl WindowModeSetting {
+    pub fn get_name(&self) -> String {
+        match self {
+            WindowModeSetting::Windowed => "Windowed",
+            WindowModeSetting::Borderless => "Borderless",
+            WindowModeSetting::Fullscreen => "Fullscreen",
+        }
+        .to_string()
+    }
+    pub fn get_window_mode(&self) -> WindowMode {
+        match self {
+            WindowModeSetting::Windowed => WindowMode::Windowed,
+            WindowModeSetting::Borderless => WindowMode::BorderlessFullscreen,
+            WindowModeSetting::Fullscreen => WindowMode::Fullscreen,
+        }
+    }
+    fn cycle(&self, forward: bool) -> Self {
+        let modes = [
+            WindowModeSetting::Windowed,
+            WindowModeSetting::Borderless,
+            WindowModeSetting::Fullscreen,
+        ];
+        let i = modes.iter().position(|m| m == self).unwrap_or_default();
+        modes[cycle_index(i, modes.len(), forward)]
+    }
+}
+
+/// Player preferences, saved next to the keymap and applied as soon as they change
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub window_mode: WindowModeSetting,
+    /// Window size in windowed mode, the other modes fill the screen
+    pub resolution: (u32, u32),
+    pub vsync: bool,
+    pub ui_scale: f32,
+    /// Scales the magnitude of every screen shake, 0 turns it off
+    pub screen_shake: f32,
+    pub damage_numbers: bool,
+    pub color_palette: ColorPalette,
+    /// Letter badges on item slots, so rarity isn't shown by color alone
+    pub rarity_badges: bool,
+    pub screen_flashes: bool,
+    /// Bigger tooltips and HUD text
+    pub large_text: bool,
+    /// A press of attack or dash holds it down until the next press
+    pub toggle_attack: bool,
+    pub toggle_dash: bool,
+    pub inventory_sort: InventorySortOrder,
+    /// Size of the world the next new run generates, a run in progress keeps its own
+    pub world_size: WorldSize,
+    /// How many inventory slots the hotbar shows, use `get_hotbar_size`
+    pub hotbar_size: usize,
+    /// Holding the wheel key brings up the hotbar as a radial menu
+    pub hotbar_wheel: bool,
+    /// Attacking an object switches to the hotbar tool that can break it, and running
+    /// out of a consumable switches to another stack of it
+    pub auto_select: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.,
+            music_volume: 1.,
+            sfx_volume: 1.,
+            window_mode: WindowModeSetting::Borderless,
+            resolution: (WIDTH as u32, HEIGHT as u32),
+            vsync: true,
+            ui_scale: 1.,
+            screen_shake: 1.,
+            damage_numbers: true,
+            color_palette: ColorPalette::Default,
+            rarity_badges: false,
+            screen_flashes: true,
+            large_text: false,
+            toggle_attack: false,
+            toggle_dash: false,
+            inventory_sort: InventorySortOrder::Type,
+            world_size: WorldSize::Medium,
+            hotbar_size: MIN_HOTBAR_SIZE,
+            hotbar_wheel: false,
+            auto_select: false,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        let Ok(file) = File::open(datafiles::settings_file()) else {
+            return Settings::default();
+        };
+        match serde_json::from_reader::<_, Settings>(BufReader::new(file)) {
+            Ok(settings) => settings,
+            Err(err) => {
+                error!("Failed to load settings, using the defaults {err:?}");
+                Settings::default()
+            }
+        }
+    }
+    pub fn save(&self) {
+        let path = datafiles::settings_file();
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let Ok(file) = File::create(path) else {
+            error!("Could not create settings file");
+            return;
+        };
+        if let Err(err) = serde_json::to_writer(file, self) {
+            error!("Failed to save settings: {err:?}");
+        }
+    }
+    /// The volume a sound effect mixed at `volume` should play at
+    pub fn get_sfx_volume(&self, volume: f32) -> f32 {
+        volume * self.master_volume * self.sfx_volume
+    }
+    pub fn get_music_volume(&self, volume: f32) -> f32 {
+        volume * self.master_volume * self.music_volume
+    }
+    /// The saved size, kept to one the inventory has room for
+    pub fn get_hotbar_size(&self) -> usize {
+        self.hotbar_size.clamp(MIN_HOTBAR_SIZE, MAX_HOTBAR_SIZE)
+    }
+    pub fn get_resolution(&self) -> (f32, f32) {
+        (self.resolution.0 as f32, self.resolution.1 as f32)
+    }
+    pub fn get_present_mode(&self) -> PresentMode {
+        if self.vsync {
+            PresentMode::Fifo
+        } else {
+            PresentMode::AutoNoVsync
+        }
+    }
+}
+
+/// A row of the settings page in the Options menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    MasterVolume,
+    MusicVolume,
+    SfxVolume,
+    WindowMode,
+    Resolution,
+    Vsync,
+    UIScale,
+    ScreenShake,
+    DamageNumbers,
+    ColorPalette,
+    RarityBadges,
+    ScreenFlashes,
+    LargeText,
+    ToggleAttack,
+    ToggleDash,
+    InventorySort,
+    WorldSize,
+    HotbarSize,
+    HotbarWheel,
+    AutoSelect,
+}
+
+impl Setting {
+    pub fn all() -> Vec<Setting> {
+        vec![
+            Setting::MasterVolume,
+            Setting::MusicVolume,
+            Setting::SfxVolume,
+            Setting::WindowMode,
+            Setting::Resolution,
+            Setting::Vsync,
+            Setting::UIScale,
+            Setting::ScreenShake,
+            Setting::DamageNumbers,
+            Setting::ColorPalette,
+            Setting::RarityBadges,
+            Setting::ScreenFlashes,
+            Setting::LargeText,
+            Setting::ToggleAttack,
+            Setting::ToggleDash,
+            Setting::InventorySort,
+            Setting::WorldSize,
+            Setting::HotbarSize,
+            Setting::HotbarWheel,
+            Setting::AutoSelect,
+        ]
+    }
+    pub fn get_name(&self) -> String {
+        match self {
+            Setting::MasterVolume => "Volume",
+            Setting::MusicVolume => "Music",
+            Setting::SfxVolume => "Sounds",
+            Setting::WindowMode => "Window",
+            Setting::Resolution => "Resolution",
+            Setting::Vsync => "VSync",
+            Setting::UIScale => "UI Scale",
+            Setting::ScreenShake => "Shake",
+            Setting::DamageNumbers => "Dmg Numbers",
+            Setting::ColorPalette => "Colors",
+            Setting::RarityBadges => "Rarity Tags",
+            Setting::ScreenFlashes => "Flashes",
+            Setting::LargeText => "Large Text",
+            Setting::ToggleAttack => "Toggle Atk",
+            Setting::ToggleDash => "Toggle Dash",
+            Setting::InventorySort => "Sort By",
+            Setting::WorldSize => "World Size",
+            Setting::HotbarSize => "Hotbar",
+            Setting::HotbarWheel => "Slot Wheel",
+            Setting::AutoSelect => "Auto Select",
+        }
+        .to_string()
+    }
+    pub fn get_value_label(&self, settings: &Settings) -> String {
+        match self {
+            Setting::MasterVolume => get_percent_label(settings.master_volume),
+            Setting::MusicVolume => get_percent_label(settings.music_volume),
+            Setting::SfxVolume => get_percent_label(settings.sfx_volume),
+            Setting::WindowMode => settings.window_mode.get_name(),
+            Setting::Resolution => format!("{}x{}", settings.resolution.0, settings.resolution.1),
+            Setting::Vsync => get_toggle_label(settings.vsync),
+            Setting::UIScale => get_percent_label(settings.ui_scale),
+            Setting::ScreenShake if settings.screen_shake == 0. => "Off".to_string(),
+            Setting::ScreenShake => get_percent_label(settings.screen_shake),
+            Setting::DamageNumbers => get_toggle_label(settings.damage_numbers),
+            Setting::ColorPalette => settings.color_palette.get_name(),
+            Setting::RarityBadges => get_toggle_label(settings.rarity_badges),
+            Setting::ScreenFlashes => get_toggle_label(settings.screen_flashes),
+            Setting::LargeText => get_toggle_label(settings.large_text),
+            Setting::ToggleAttack => get_toggle_label(settings.toggle_attack),
+            Setting::ToggleDash => get_toggle_label(settings.toggle_dash),
+            Setting::InventorySort => settings.inventory_sort.get_name(),
+            Setting::WorldSize => settings.world_size.get_name(),
+            Setting::HotbarSize => format!("{} Slots", settings.get_hotbar_size()),
+            Setting::HotbarWheel => get_toggle_label(settings.hotbar_wheel),
+            Setting::AutoSelect => get_toggle_label(settings.auto_select),
+        }
+    }
+    /// Moves the setting to its next value, wrapping around at either end
+    pub fn cycle(&self, settings: &mut Settings, forward: bool) {
+        match self {
+            Setting::MasterVolume => {
+                settings.master_volume =
+                    cycle_step(settings.master_volume, 0., VOLUME_STEP, forward)
+            }
+            Setting::MusicVolume => {
+                settings.music_volume = cycle_step(settings.music_volume, 0., VOLUME_STEP, forward)
+            }
+            Setting::SfxVolume => {
+                settings.sfx_volume = cycle_step(settings.sfx_volume, 0., VOLUME_STEP, forward)
+            }
+            Setting::WindowMode => settings.window_mode = settings.window_mode.cycle(forward),
+            Setting::Resolution => {
+                let i = RESOLUTIONS
+                    .iter()
+                    .position(|r| *r == settings.resolution)
+                    .unwrap_or_default();
+                settings.resolution = RESOLUTIONS[cycle_index(i, RESOLUTIONS.len(), forward)];
+            }
+            Setting::Vsync => settings.vsync = !settings.vsync,
+            Setting::UIScale => {
+                settings.ui_scale =
+                    cycle_step(settings.ui_scale, MIN_UI_SCALE, UI_SCALE_STEP, forward)
+            }
+            Setting::ScreenShake => {
+                settings.screen_shake =
+                    cycle_step(settings.screen_shake, 0., SCREEN_SHAKE_STEP, forward)
+            }
+            Setting::DamageNumbers => settings.damage_numbers = !settings.damage_numbers,
+            Setting::ColorPalette => {
+                let palettes = [
+                    ColorPalette::Default,
+                    ColorPalette::RedGreen,
+                    ColorPalette::BlueYellow,
+                ];
+                let i = palettes
+                    .iter()
+                    .position(|p| *p == settings.color_palette)
+                    .unwrap_or_default();
+                settings.color_palette = palettes[cycle_index(i, palettes.len(), forward)];
+            }
+            Setting::RarityBadges => settings.rarity_badges = !settings.rarity_badges,
+            Setting::ScreenFlashes => settings.screen_flashes = !settings.screen_flashes,
+            Setting::LargeText => settings.large_text = !settings.large_text,
+            Setting::ToggleAttack => settings.toggle_attack = !settings.toggle_attack,
+            Setting::ToggleDash => settings.toggle_dash = !settings.toggle_dash,
+            Setting::InventorySort => {
+                let orders = [
+                    InventorySortOrder::Type,
+                    InventorySortOrder::Rarity,
+                    InventorySortOrder::Level,
+                ];
+                let i = orders
+                    .iter()
+                    .position(|o| *o == settings.inventory_sort)
+                    .unwrap_or_default();
+                settings.inventory_sort = orders[cycle_index(i, orders.len(), forward)];
+            }
+            Setting::WorldSize => {
+                let sizes = WorldSize::all();
+                let i = sizes
+                    .iter()
+                    .position(|s| *s == settings.world_size)
+                    .unwrap_or_default();
+                settings.world_size = sizes[cycle_index(i, sizes.len(), forward)];
+            }
+            Setting::HotbarSize => {
+                let sizes = (MIN_HOTBAR_SIZE..=MAX_HOTBAR_SIZE)
+                    .step_by(HOTBAR_SIZE_STEP)
+                    .collect::<Vec<_>>();
+                let i = sizes
+                    .iter()
+                    .position(|s| *s == settings.get_hotbar_size())
+                    .unwrap_or_default();
+                settings.hotbar_size = sizes[cycle_index(i, sizes.len(), forward)];
+            }
+            Setting::HotbarWheel => settings.hotbar_wheel = !settings.hotbar_wheel,
+            Setting::AutoSelect => settings.auto_select = !settings.auto_select,
+        }
+    }
+}
+
+fn get_percent_label(value: f32) -> String {
+    format!("{}%", (value * 100.).round() as i32)
+}
+
+fn get_toggle_label(on: bool) -> String {
+    if on { "On" } else { "Off" }.to_string()
+}
+
+fn cycle_index(i: usize, len: usize, forward: bool) -> usize {
+    if forward {
+        (i + 1) % len
+    } else {
+        (i + len - 1) % len
+    }
+}
+
+/// Steps a value in `min..=1` by `step`, snapping to the nearest step so saved values
+/// don't drift
+fn cycle_step(value: f32, min: f32, step: f32, forward: bool) -> f32 {
+    let num_steps = ((1. - min) / step).round() as usize + 1;
+    let i = (((value - min) / step).round().max(0.) as usize).min(num_steps - 1);
+    min + cycle_index(i, num_steps, forward) as f32 * step
+}
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems((apply_window_settings, apply_ui_scale));
+    }
+}
+
+fn apply_window_settings(
+    settings: Res<Settings>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut applied_resolution: Local<Option<(WindowModeSetting, (u32, u32))>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    let Ok(mut window) = windows.get_single_mut() else {
+        return;
+    };
+    let mode = settings.window_mode.get_window_mode();
+    if window.mode != mode {
+        window.mode = mode;
+    }
+    // only resize when the setting changes, so changing anything else doesn't undo
+    // the player dragging the window to a different size
+    let resolution = (settings.window_mode, settings.resolution);
+    if settings.window_mode == WindowModeSetting::Windowed
+        && *applied_resolution != Some(resolution)
+    {
+        let (width, height) = settings.get_resolution();
+        window.resolution.set(width, height);
+    }
+    *applied_resolution = Some(resolution);
+    let present_mode = settings.get_present_mode();
+    if window.present_mode != present_mode {
+        window.present_mode = present_mode;
+    }
+}
+
+/// Zooms the camera that draws the UI layer, the cursor is scaled to match in
+/// `update_cursor_pos`
+fn apply_ui_scale(
+    settings: Res<Settings>,
+    mut ui_cameras: Query<&mut OrthographicProjection, With<UITextureCamera>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    for mut projection in ui_cameras.iter_mut() {
+        projection.scale = 1. / settings.ui_scale;
+    }
+}
@@ -0,0 +1,78 @@
+use bevy::{prelude::*, render::view::RenderLayers, sprite::Anchor};
+
+use crate::{inventory::ItemStack, settings::Settings};
+
+use super::damage_numbers::spawn_text;
+
+/// HUD text is doubled so the pixel font stays crisp
+pub const LARGE_TEXT_SCALE: f32 = 2.;
+/// Tooltips already sit next to the inventory, so they only have room to grow by half
+pub const LARGE_TOOLTIP_SCALE: f32 = 1.5;
+
+/// HUD text that is drawn bigger in large text mode
+#[derive(Component)]
+pub struct LargeText;
+
+#[derive(Component)]
+pub struct RarityBadge;
+
+pub fn apply_large_text(
+    settings: Res<Settings>,
+    mut texts: Query<(&mut Transform, Ref<LargeText>)>,
+) {
+    let scale = if settings.large_text {
+        LARGE_TEXT_SCALE
+    } else {
+        1.
+    };
+    for (mut txfm, large_text) in texts.iter_mut() {
+        if settings.is_changed() || large_text.is_added() {
+            txfm.scale = Vec3::new(scale, scale, 1.);
+        }
+    }
+}
+
+/// Adds a letter in the corner of every item icon in the UI that has a rarity, and
+/// rebuilds them all when the badges or the color palette change
+pub fn update_rarity_badges(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
+    icons: Query<(Entity, Ref<ItemStack>, &RenderLayers)>,
+    badges: Query<Entity, With<RarityBadge>>,
+) {
+    if settings.is_changed() {
+        for e in badges.iter() {
+            commands.entity(e).despawn_recursive();
+        }
+    }
+    if !settings.rarity_badges {
+        return;
+    }
+    for (e, stack, layers) in icons.iter() {
+        if !settings.is_changed() && !stack.is_added() {
+            continue;
+        }
+        let Some(badge) = stack.rarity.get_badge() else {
+            continue;
+        };
+        if !layers.intersects(&RenderLayers::layer(3)) {
+            continue;
+        }
+        let badge_e = spawn_text(
+            &mut commands,
+            &asset_server,
+            Vec3::new(-5.5, 5., 1.),
+            stack.rarity.get_color(settings.color_palette),
+            badge.to_string(),
+            Anchor::Center,
+            1.,
+            3,
+        );
+        commands
+            .entity(badge_e)
+            .insert(RarityBadge)
+            .insert(Name::new("RARITY BADGE"))
+            .set_parent(e);
+    }
+}
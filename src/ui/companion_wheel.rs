@@ -0,0 +1,140 @@
+use bevy::{prelude::*, sprite::Anchor};
+
+use crate::{
+    colors::{BLACK, WHITE, YELLOW},
+    enemy::companion::{Companion, CompanionCommand, Downed},
+    inputs::CursorPos,
+    keymap::{ActionInput, InputAction},
+};
+
+use super::{damage_numbers::spawn_text, UIState};
+
+const COMPANION_WHEEL_RADIUS: f32 = 28.;
+
+#[derive(Component)]
+pub struct CompanionWheel;
+
+#[derive(Component)]
+pub struct CompanionWheelOption(pub CompanionCommand);
+
+impl CompanionCommand {
+    fn get_wheel_dir(&self) -> Vec2 {
+        match self {
+            CompanionCommand::Follow => Vec2::new(0., 1.),
+            CompanionCommand::Stay => Vec2::new(-0.87, -0.5),
+            CompanionCommand::Attack => Vec2::new(0.87, -0.5),
+        }
+    }
+}
+
+/// Hold the wheel key to bring up the companion command wheel, point the cursor at a
+/// command, and let go to give it to every companion.
+pub fn handle_companion_wheel(
+    mut commands: Commands,
+    actions: ActionInput,
+    cursor_pos: Res<CursorPos>,
+    ui_state: Res<State<UIState>>,
+    asset_server: Res<AssetServer>,
+    wheel: Query<Entity, With<CompanionWheel>>,
+    mut options: Query<(&CompanionWheelOption, &mut Text)>,
+    mut companions: Query<&mut Companion, Without<Downed>>,
+) {
+    let cursor_dir = cursor_pos.ui_coords.truncate();
+    let selected = if cursor_dir.length() < COMPANION_WHEEL_RADIUS / 3. {
+        None
+    } else {
+        [
+            CompanionCommand::Follow,
+            CompanionCommand::Stay,
+            CompanionCommand::Attack,
+        ]
+        .into_iter()
+        .max_by(|a, b| {
+            a.get_wheel_dir()
+                .dot(cursor_dir)
+                .total_cmp(&b.get_wheel_dir().dot(cursor_dir))
+        })
+    };
+
+    if actions.just_pressed(InputAction::CompanionWheel)
+        && ui_state.0 == UIState::Closed
+        && !companions.is_empty()
+        && wheel.is_empty()
+    {
+        let wheel_e = commands
+            .spawn((
+                SpatialBundle::default(),
+                CompanionWheel,
+                Name::new("COMPANION WHEEL"),
+            ))
+            .id();
+        for command in [
+            CompanionCommand::Follow,
+            CompanionCommand::Stay,
+            CompanionCommand::Attack,
+        ] {
+            let option_e = spawn_text(
+                &mut commands,
+                &asset_server,
+                (command.get_wheel_dir() * COMPANION_WHEEL_RADIUS).extend(20.),
+                WHITE,
+                format!("{command:?}"),
+                Anchor::Center,
+                1.,
+                3,
+            );
+            commands
+                .entity(option_e)
+                .insert(CompanionWheelOption(command));
+            commands.entity(wheel_e).add_child(option_e);
+        }
+        let center_e = spawn_text(
+            &mut commands,
+            &asset_server,
+            Vec3::new(0., 0., 20.),
+            BLACK,
+            "+".to_string(),
+            Anchor::Center,
+            1.,
+            3,
+        );
+        commands.entity(wheel_e).add_child(center_e);
+        return;
+    }
+    let Ok(wheel_e) = wheel.get_single() else {
+        return;
+    };
+    if actions.pressed(InputAction::CompanionWheel) {
+        for (option, mut text) in options.iter_mut() {
+            text.sections[0].style.color = if Some(option.0) == selected {
+                YELLOW
+            } else {
+                WHITE
+            };
+        }
+        return;
+    }
+    if let Some(command) = selected {
+        for mut companion in companions.iter_mut() {
+            companion.command = command;
+            if command == CompanionCommand::Stay {
+                companion.target = None;
+            }
+        }
+    }
+    commands.entity(wheel_e).despawn_recursive();
+}
+
+// close the wheel without giving a command if a menu opens while it is up
+pub fn despawn_companion_wheel_on_ui_open(
+    mut commands: Commands,
+    wheel: Query<Entity, With<CompanionWheel>>,
+    ui_state: Res<State<UIState>>,
+) {
+    if ui_state.0 == UIState::Closed {
+        return;
+    }
+    for e in wheel.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+}
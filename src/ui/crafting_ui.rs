@@ -1,14 +1,32 @@
 pub use bevy::prelude::*;
-use bevy::reflect::TypeUuid;
+use bevy::{reflect::TypeUuid, sprite::Anchor};
 use serde::{Deserialize, Serialize};
 
-use crate::{container::Container, item::CraftingTracker};
+use crate::{
+    colors::WHITE,
+    container::{Container, ContainerRegistry},
+    item::CraftingTracker,
+    world::TileMapPosition,
+};
 
-use super::UIState;
+use super::{damage_numbers::spawn_text, InventoryState, InventoryUI, UIState};
+
+#[derive(Component)]
+pub struct CraftingQueueText;
 
 #[derive(Resource, Default, Debug, Clone)]
 pub struct CraftingContainer {
     pub items: Container,
+    pub crafting_type: CraftingContainerType,
+    /// where the station is, so it can be found in the container registry
+    pub station_pos: Option<TileMapPosition>,
+}
+
+impl CraftingContainer {
+    /// Whether crafts go into the station's queue instead of straight to the player
+    pub fn is_queued(&self) -> bool {
+        self.station_pos.is_some() && self.crafting_type.get_craft_secs().is_some()
+    }
 }
 
 #[derive(
@@ -35,6 +53,35 @@ pub enum CraftingContainerType {
     AlchemyTable,
 }
 
+impl CraftingContainerType {
+    /// In the order they are listed in the recipe book
+    pub fn all() -> [CraftingContainerType; 5] {
+        [
+            CraftingContainerType::Inventory,
+            CraftingContainerType::CraftingTable,
+            CraftingContainerType::Anvil,
+            CraftingContainerType::Cauldron,
+            CraftingContainerType::AlchemyTable,
+        ]
+    }
+    pub fn get_name(&self) -> String {
+        match self {
+            CraftingContainerType::Inventory => "Inventory".to_string(),
+            CraftingContainerType::CraftingTable => "Crafting Table".to_string(),
+            CraftingContainerType::Anvil => "Anvil".to_string(),
+            CraftingContainerType::Cauldron => "Cauldron".to_string(),
+            CraftingContainerType::AlchemyTable => "Alchemy Table".to_string(),
+        }
+    }
+    /// Brewing takes time, so these stations queue their crafts instead
+    pub fn get_craft_secs(&self) -> Option<f32> {
+        match self {
+            CraftingContainerType::Cauldron | CraftingContainerType::AlchemyTable => Some(8.),
+            _ => None,
+        }
+    }
+}
+
 pub fn change_ui_state_to_crafting_when_resource_added(
     mut inv_ui_state: ResMut<NextState<UIState>>,
     mut tracker: ResMut<CraftingTracker>,
@@ -49,3 +96,73 @@ pub fn change_ui_state_to_crafting_when_resource_added(
     }
     inv_ui_state.set(UIState::Crafting);
 }
+
+fn get_crafting_queue_label(
+    container_reg: &ContainerRegistry,
+    crafting_container: &CraftingContainer,
+) -> String {
+    let queue = crafting_container
+        .station_pos
+        .and_then(|pos| container_reg.crafting_queues.get(&pos))
+        .filter(|queue| !queue.queued.is_empty());
+    match queue {
+        Some(queue) => format!(
+            "Brewing {} {}%",
+            queue.get_num_queued(),
+            (queue.get_progress_percent() * 100.) as u32
+        ),
+        None => "Nothing brewing".to_string(),
+    }
+}
+
+/// Shows how far along the queue of the open station is, above its crafting grid
+pub fn spawn_crafting_queue_text(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    inv_state: Res<InventoryState>,
+    container_reg: Res<ContainerRegistry>,
+    crafting_container: Option<Res<CraftingContainer>>,
+    new_inv_ui: Query<Entity, Added<InventoryUI>>,
+) {
+    let (Ok(inv_e), Some(crafting_container)) = (new_inv_ui.get_single(), crafting_container)
+    else {
+        return;
+    };
+    if !crafting_container.is_queued() {
+        return;
+    }
+    let text = spawn_text(
+        &mut commands,
+        &asset_server,
+        Vec3::new(
+            -inv_state.inv_size.x / 2. + 2.,
+            inv_state.inv_size.y / 2. + 7.,
+            1.,
+        ),
+        WHITE,
+        get_crafting_queue_label(&container_reg, &crafting_container),
+        Anchor::CenterLeft,
+        1.,
+        3,
+    );
+    commands
+        .entity(text)
+        .insert(CraftingQueueText)
+        .set_parent(inv_e);
+}
+
+pub fn update_crafting_queue_text(
+    container_reg: Res<ContainerRegistry>,
+    crafting_container: Option<Res<CraftingContainer>>,
+    mut texts: Query<&mut Text, With<CraftingQueueText>>,
+) {
+    let Some(crafting_container) = crafting_container else {
+        return;
+    };
+    for mut text in texts.iter_mut() {
+        let label = get_crafting_queue_label(&container_reg, &crafting_container);
+        if text.sections[0].value != label {
+            text.sections[0].value = label;
+        }
+    }
+}
@@ -4,14 +4,15 @@ use rand::Rng;
 use crate::{
     assets::Graphics,
     attributes::{Attack, BonusDamage, CurrentHealth, MaxHealth},
-    colors::{BLACK, DMG_NUM_GREEN, DMG_NUM_PURPLE, DMG_NUM_RED, DMG_NUM_YELLOW},
+    colors::{BLACK, DMG_NUM_PURPLE, DMG_NUM_RED, DMG_NUM_YELLOW},
     inventory::ItemStack,
     item::WorldObject,
+    settings::Settings,
     world::TILE_SIZE,
     Game, TextureCamera,
 };
 
-use super::{spawn_item_stack_icon, UIElement, UI_SLOT_SIZE};
+use super::{accessibility::LargeText, spawn_item_stack_icon, UIElement, UI_SLOT_SIZE};
 
 #[derive(Component)]
 pub struct DamageNumber {
@@ -66,6 +67,7 @@ pub fn handle_add_damage_numbers_after_hit(
     asset_server: Res<AssetServer>,
     raw_dmg: Query<(&Attack, &BonusDamage)>,
     game: Res<Game>,
+    settings: Res<Settings>,
 ) {
     for (e, changed_health, mut prev_health) in changed_health.iter_mut() {
         let delta = changed_health.0 - prev_health.0;
@@ -80,6 +82,9 @@ pub fn handle_add_damage_numbers_after_hit(
             2.,
         );
         prev_health.0 = changed_health.0;
+        if !settings.damage_numbers {
+            continue;
+        }
         let is_player = e == game.player;
         let dmg = raw_dmg.get(game.player).unwrap().0 .0 + raw_dmg.get(game.player).unwrap().1 .0;
         let is_crit = !is_player && delta.abs() > dmg && dmg != 0;
@@ -88,7 +93,7 @@ pub fn handle_add_damage_numbers_after_hit(
             &asset_server,
             txfms.get(e).unwrap().translation() + pos_offset,
             if delta > 0 {
-                DMG_NUM_GREEN
+                settings.color_palette.get_heal_color()
             } else if is_player {
                 DMG_NUM_PURPLE
             } else if is_crit {
@@ -109,8 +114,12 @@ pub fn handle_add_dodge_text(
     mut dodge_events: EventReader<DodgeEvent>,
     txfms: Query<&GlobalTransform>,
     asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
 ) {
     for event in dodge_events.iter() {
+        if !settings.damage_numbers {
+            continue;
+        }
         let mut rng = rand::thread_rng();
         let drop_spread = 16.;
         let pos_offset = Vec3::new(
@@ -257,10 +266,13 @@ pub fn spawn_floating_text_with_shadow(
         if i == 0 {
             shadow_e = entity;
         }
-        commands.entity(entity).insert(DamageNumber {
-            timer: Timer::from_seconds(0.85, TimerMode::Once),
-            velocity: 0.,
-        });
+        commands.entity(entity).insert((
+            DamageNumber {
+                timer: Timer::from_seconds(0.85, TimerMode::Once),
+                velocity: 0.,
+            },
+            LargeText,
+        ));
     }
     shadow_e
 }
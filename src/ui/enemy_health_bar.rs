@@ -1,13 +1,13 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, sprite::Anchor};
 
 use crate::{
     assets::Graphics,
     attributes::{CurrentHealth, MaxHealth},
     colors::{RED, YELLOW},
-    enemy::{EliteMob, Mob},
+    enemy::{elite::EliteAffixes, EliteMob, Mob},
 };
 
-use super::UIElement;
+use super::{accessibility::LargeText, damage_numbers::spawn_text, UIElement};
 #[derive(Component)]
 pub struct EnemyHealthBar;
 
@@ -113,3 +113,29 @@ pub fn add_ui_icon_for_elite_mobs(
             .set_parent(elite);
     }
 }
+
+pub fn add_affix_names_to_elite_mobs(
+    elites: Query<(Entity, &EliteAffixes), Added<EliteAffixes>>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    for (elite, affixes) in elites.iter() {
+        let names = affixes
+            .0
+            .iter()
+            .map(|a| a.get_name())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let text = spawn_text(
+            &mut commands,
+            &asset_server,
+            Vec3::new(0., 10., 1.),
+            YELLOW,
+            names,
+            Anchor::BottomCenter,
+            1.,
+            0,
+        );
+        commands.entity(text).insert(LargeText).set_parent(elite);
+    }
+}
@@ -0,0 +1,253 @@
+use bevy::{prelude::*, render::view::RenderLayers, sprite::Anchor};
+
+use crate::{
+    assets::Graphics,
+    colors::{BLACK, GREY, LIGHT_GREY, RED},
+    player::ModifyTimeFragmentsEvent,
+    world::dimension::{DimensionSpawnEvent, Era, EraList},
+    GameParam, ScreenResolution, GAME_HEIGHT,
+};
+
+use super::{damage_numbers::spawn_text, Interactable, UIElement, UIState, ESSENCE_UI_SIZE};
+
+#[derive(Component)]
+pub struct EraSelectUI;
+
+/// Shown under the era buttons when the player picks an era they can't afford
+#[derive(Component)]
+pub struct EraSelectErrorText;
+
+/// Inserted when the player uses a Time Gate, lists every era it can take them to
+#[derive(Resource, Debug, Clone, Default)]
+pub struct EraSelectChoices {
+    pub choices: Vec<EraOption>,
+}
+
+#[derive(Component, Debug, Clone)]
+pub struct EraOption {
+    pub era: Era,
+    pub cost: i32,
+}
+
+pub struct TravelToEraEvent {
+    pub choice: EraOption,
+}
+
+impl EraSelectChoices {
+    /// Every era visited so far, plus the one after the furthest the player has been
+    /// once its unlock requirement is met
+    pub fn new(game: &GameParam) -> Self {
+        let curr_era = &game.era.current_era;
+        let mut eras: Vec<Era> = game.era.visited_eras.clone();
+        if let Some(next) = eras
+            .iter()
+            .max_by_key(|era| era.index())
+            .and_then(|furthest| game.eras.get_next_era(furthest, &game.era))
+        {
+            eras.push(next);
+        }
+        eras.sort_by_key(|era| era.index());
+        eras.dedup();
+        Self {
+            choices: eras
+                .into_iter()
+                .filter(|era| era != curr_era)
+                .map(|era| EraOption {
+                    cost: get_travel_cost(&game.eras, &era),
+                    era,
+                })
+                .collect(),
+        }
+    }
+}
+
+fn get_travel_cost(eras: &EraList, era: &Era) -> i32 {
+    eras.get(era).map(|desc| desc.travel_cost).unwrap_or(0)
+}
+
+pub fn setup_era_select_ui(
+    mut commands: Commands,
+    graphics: Res<Graphics>,
+    asset_server: Res<AssetServer>,
+    choices: Res<EraSelectChoices>,
+    game: GameParam,
+    resolution: Res<ScreenResolution>,
+) {
+    let (size, texture, t_offset) = (
+        ESSENCE_UI_SIZE,
+        graphics.get_ui_element_texture(UIElement::Essence),
+        Vec2::new(3.5, 3.5),
+    );
+
+    let overlay = commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(146. / 255., 116. / 255., 65. / 255., 0.3),
+                custom_size: Some(Vec2::new(resolution.game_width + 10., GAME_HEIGHT + 10.)),
+                ..default()
+            },
+            transform: Transform {
+                translation: Vec3::new(-t_offset.x, -t_offset.y, -1.),
+                scale: Vec3::new(1., 1., 1.),
+                ..Default::default()
+            },
+            ..default()
+        })
+        .insert(RenderLayers::from_layers(&[3]))
+        .insert(Name::new("overlay"))
+        .id();
+
+    let era_ui_e = commands
+        .spawn(SpriteBundle {
+            texture,
+            sprite: Sprite {
+                custom_size: Some(size),
+                ..Default::default()
+            },
+            transform: Transform {
+                translation: Vec3::new(t_offset.x, t_offset.y, 10.),
+                scale: Vec3::new(1., 1., 1.),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(EraSelectUI)
+        .insert(Name::new("ERA SELECT UI"))
+        .insert(UIState::EraSelect)
+        .insert(RenderLayers::from_layers(&[3]))
+        .id();
+
+    let title = spawn_text(
+        &mut commands,
+        &asset_server,
+        Vec3::new(0., 62., 1.),
+        BLACK,
+        "Travel to...".to_string(),
+        Anchor::Center,
+        1.,
+        3,
+    );
+    commands.entity(title).set_parent(era_ui_e);
+
+    let time_fragments = game.get_time_fragments();
+    for (i, era_option) in choices.choices.iter().enumerate() {
+        let can_afford = time_fragments >= era_option.cost;
+        let translation = Vec3::new(-30., 40.5 - (i as f32 * 29.), 1.);
+        let button_e = commands
+            .spawn((
+                SpriteBundle {
+                    texture: graphics.get_ui_element_texture(UIElement::EssenceButton),
+                    transform: Transform {
+                        translation,
+                        scale: Vec3::new(1., 1., 1.),
+                        ..Default::default()
+                    },
+                    sprite: Sprite {
+                        // grayed out until the player has enough Time Fragments
+                        color: if can_afford { Color::WHITE } else { LIGHT_GREY },
+                        custom_size: Some(Vec2::new(20., 20.)),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                Interactable::default(),
+                UIElement::EssenceButton,
+                era_option.clone(),
+                RenderLayers::from_layers(&[3]),
+                Name::new("Era Button"),
+            ))
+            .set_parent(era_ui_e)
+            .id();
+        let name = game
+            .eras
+            .get(&era_option.era)
+            .map(|desc| desc.name.clone())
+            .unwrap_or(format!("Era {}", era_option.era.index() + 1));
+        let name_text = spawn_text(
+            &mut commands,
+            &asset_server,
+            Vec3::new(14., 3., 1.),
+            if can_afford { BLACK } else { GREY },
+            name,
+            Anchor::CenterLeft,
+            1.,
+            3,
+        );
+        commands.entity(name_text).set_parent(button_e);
+        let cost_text = spawn_text(
+            &mut commands,
+            &asset_server,
+            Vec3::new(14., -5., 1.),
+            if can_afford { BLACK } else { RED },
+            format!("{} Time Fragments", era_option.cost),
+            Anchor::CenterLeft,
+            1.,
+            3,
+        );
+        commands.entity(cost_text).set_parent(button_e);
+    }
+    if choices.choices.is_empty() {
+        let empty_text = spawn_text(
+            &mut commands,
+            &asset_server,
+            Vec3::new(0., 0., 1.),
+            BLACK,
+            "The gate is silent".to_string(),
+            Anchor::Center,
+            1.,
+            3,
+        );
+        commands.entity(empty_text).set_parent(era_ui_e);
+    }
+
+    commands.entity(era_ui_e).push_children(&[overlay]);
+}
+
+pub fn handle_travel_to_era(
+    mut commands: Commands,
+    mut travel_events: EventReader<TravelToEraEvent>,
+    mut next_ui_state: ResMut<NextState<UIState>>,
+    mut dim_event: EventWriter<DimensionSpawnEvent>,
+    mut currency_event: EventWriter<ModifyTimeFragmentsEvent>,
+    game: GameParam,
+    asset_server: Res<AssetServer>,
+    era_ui: Query<Entity, With<EraSelectUI>>,
+    error_text: Query<Entity, With<EraSelectErrorText>>,
+) {
+    for travel in travel_events.iter() {
+        if game.get_time_fragments() < travel.choice.cost {
+            for e in error_text.iter() {
+                commands.entity(e).despawn_recursive();
+            }
+            let Ok(era_ui_e) = era_ui.get_single() else {
+                continue;
+            };
+            let text = spawn_text(
+                &mut commands,
+                &asset_server,
+                Vec3::new(0., -62., 1.),
+                RED,
+                "Not enough Time Fragments".to_string(),
+                Anchor::Center,
+                1.,
+                3,
+            );
+            commands
+                .entity(text)
+                .insert(EraSelectErrorText)
+                .set_parent(era_ui_e);
+            continue;
+        }
+        currency_event.send(ModifyTimeFragmentsEvent {
+            delta: -travel.choice.cost,
+        });
+        // the player entity is kept across eras, so their inventory comes with them
+        dim_event.send(DimensionSpawnEvent {
+            swap_to_dim_now: true,
+            new_era: Some(travel.choice.era.clone()),
+        });
+        next_ui_state.set(UIState::Closed);
+        commands.remove_resource::<EraSelectChoices>();
+        break;
+    }
+}
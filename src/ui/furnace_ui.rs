@@ -14,6 +14,9 @@ use super::{
     UIState,
 };
 
+/// How long a furnace takes to smelt one item
+pub const FURNACE_SMELT_SECS: f32 = 3.;
+
 #[derive(Component, Resource, Debug, Clone)]
 pub struct FurnaceContainer {
     pub items: Container,
@@ -141,7 +144,7 @@ pub fn add_container_to_new_furnace_objs(
                         .clone(),
                     parent: e,
                     slot_map: vec![vec![WorldObject::Coal], ing.clone(), results.clone()],
-                    timer: Timer::from_seconds(3., TimerMode::Once),
+                    timer: Timer::from_seconds(FURNACE_SMELT_SECS, TimerMode::Once),
                     state: None,
                 });
             }
@@ -155,7 +158,7 @@ pub fn add_container_to_new_furnace_objs(
                         vec![WorldObject::UpgradeTome, WorldObject::OrbOfTransformation],
                         recipes.upgradeable_items.clone(),
                     ],
-                    timer: Timer::from_seconds(3., TimerMode::Once),
+                    timer: Timer::from_seconds(FURNACE_SMELT_SECS, TimerMode::Once),
                     state: None,
                 });
             }
@@ -0,0 +1,164 @@
+use bevy::{prelude::*, render::view::RenderLayers};
+
+use crate::{
+    colors::{overwrite_alpha, YELLOW},
+    inputs::CursorPos,
+    keymap::{InputAction, Keymap, MENU_NAV_ACTIONS},
+};
+
+use super::{Interactable, UIState};
+
+/// The `Interactable` the gamepad is pointing at while a menu is open
+#[derive(Resource, Default, Debug)]
+pub struct GamepadFocus {
+    pub focused: Option<Entity>,
+    /// Mouse buttons held down on behalf of the gamepad
+    pub held_buttons: Vec<MouseButton>,
+}
+
+#[derive(Component)]
+pub struct GamepadFocusCursor;
+
+/// Moves the focus between `Interactable`s with the D-pad, and parks the cursor on it so
+/// hovering, tooltips and clicks all work the same as with the mouse. The select buttons
+/// press the mouse buttons for as long as they are held.
+pub fn handle_gamepad_ui_navigation(
+    keymap: Res<Keymap>,
+    gamepads: Res<Gamepads>,
+    mut pad_input: ResMut<Input<GamepadButton>>,
+    mut mouse_input: ResMut<Input<MouseButton>>,
+    mut cursor_pos: ResMut<CursorPos>,
+    mut focus: ResMut<GamepadFocus>,
+    mut cursor_moved_events: EventReader<CursorMoved>,
+    interactables: Query<
+        (Entity, &GlobalTransform, &Sprite, &ComputedVisibility),
+        With<Interactable>,
+    >,
+) {
+    if cursor_moved_events.iter().count() > 0 {
+        focus.focused = None;
+    }
+    let slots: Vec<(Entity, Vec2)> = interactables
+        .iter()
+        .filter(|(_, _, sprite, vis)| sprite.custom_size.is_some() && vis.is_visible())
+        .map(|(e, txfm, _, _)| (e, txfm.translation().truncate()))
+        .collect();
+    let focused = focus
+        .focused
+        .and_then(|focused| slots.iter().find(|(e, _)| *e == focused))
+        .copied();
+    if focused.is_none() {
+        focus.focused = None;
+    }
+
+    for (action, dir) in MENU_NAV_ACTIONS {
+        if !keymap.pad_just_pressed(action, &gamepads, &pad_input) {
+            continue;
+        }
+        let next = match focused {
+            // start from whatever is closest to the middle of the menu
+            None => slots
+                .iter()
+                .min_by(|(_, a), (_, b)| a.length().total_cmp(&b.length())),
+            Some((focused_e, from)) => slots
+                .iter()
+                .filter(|(e, pos)| *e != focused_e && (*pos - from).dot(dir) > 1.)
+                .min_by(|(_, a), (_, b)| {
+                    get_nav_distance(from, *a, dir).total_cmp(&get_nav_distance(from, *b, dir))
+                }),
+        };
+        if let Some((next_e, pos)) = next {
+            focus.focused = Some(*next_e);
+            cursor_pos.ui_coords = pos.extend(0.);
+        }
+    }
+
+    for (action, button) in [
+        (InputAction::MenuSelect, MouseButton::Left),
+        (InputAction::MenuAlt, MouseButton::Right),
+    ] {
+        if keymap.pad_just_released(action, &gamepads, &pad_input) {
+            release_held_button(&mut focus, &mut mouse_input, button);
+        } else if focus.focused.is_some() && keymap.pad_just_pressed(action, &gamepads, &pad_input)
+        {
+            // the button is used up as a click, so it doesn't also interact or attack
+            keymap.clear_pad_just_pressed(action, &gamepads, &mut pad_input);
+            mouse_input.press(button);
+            focus.held_buttons.push(button);
+        }
+    }
+}
+
+fn release_held_button(
+    focus: &mut GamepadFocus,
+    mouse_input: &mut Input<MouseButton>,
+    button: MouseButton,
+) {
+    if focus.held_buttons.contains(&button) {
+        focus.held_buttons.retain(|b| *b != button);
+        mouse_input.release(button);
+    }
+}
+
+/// Favours slots straight ahead over closer ones off to the side
+fn get_nav_distance(from: Vec2, to: Vec2, dir: Vec2) -> f32 {
+    let delta = to - from;
+    let along = delta.dot(dir);
+    along + (delta - dir * along).length() * 2.
+}
+
+pub fn update_gamepad_focus_cursor(
+    mut commands: Commands,
+    focus: Res<GamepadFocus>,
+    ui_state: Res<State<UIState>>,
+    slots: Query<(&GlobalTransform, &Sprite), (With<Interactable>, Without<GamepadFocusCursor>)>,
+    mut cursor: Query<(Entity, &mut Transform, &mut Sprite), With<GamepadFocusCursor>>,
+) {
+    let focused_slot = focus
+        .focused
+        .filter(|_| ui_state.0 != UIState::Closed)
+        .and_then(|e| slots.get(e).ok());
+    let Some((slot_txfm, slot_sprite)) = focused_slot else {
+        for (e, ..) in cursor.iter() {
+            commands.entity(e).despawn();
+        }
+        return;
+    };
+    let size = slot_sprite.custom_size.unwrap_or_default() + Vec2::splat(2.);
+    let translation = slot_txfm.translation().truncate().extend(50.);
+    if let Ok((_, mut txfm, mut sprite)) = cursor.get_single_mut() {
+        txfm.translation = translation;
+        sprite.custom_size = Some(size);
+        return;
+    }
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: overwrite_alpha(YELLOW, 0.35),
+                custom_size: Some(size),
+                ..Default::default()
+            },
+            transform: Transform::from_translation(translation),
+            ..Default::default()
+        },
+        GamepadFocusCursor,
+        RenderLayers::from_layers(&[3]),
+        Name::new("Gamepad Focus Cursor"),
+    ));
+}
+
+/// Also lets go of any held clicks, so a menu closed by a click doesn't leave the
+/// player attacking
+pub fn clear_gamepad_focus_on_ui_close(
+    mut focus: ResMut<GamepadFocus>,
+    mut mouse_input: ResMut<Input<MouseButton>>,
+    ui_state: Res<State<UIState>>,
+) {
+    if !ui_state.is_changed() || ui_state.0 != UIState::Closed {
+        return;
+    }
+    focus.focused = None;
+    for button in focus.held_buttons.clone() {
+        release_held_button(&mut focus, &mut mouse_input, button);
+    }
+}
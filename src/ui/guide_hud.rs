@@ -1,12 +1,16 @@
-use bevy::{prelude::*, render::view::RenderLayers};
+use bevy::{prelude::*, render::view::RenderLayers, sprite::Anchor};
 
 use crate::{
     assets::Graphics,
+    colors::{LIGHT_GREEN, LIGHT_RED},
     inventory::{Inventory, ItemStack},
-    item::{Recipes, WorldObject},
+    item::{CraftedItemEvent, Recipes, WorldObject},
 };
 
-use super::{spawn_item_stack_icon, UIElement, UI_SLOT_SIZE};
+use super::{
+    accessibility::LargeText, damage_numbers::spawn_text, spawn_item_stack_icon, UIElement,
+    UI_SLOT_SIZE,
+};
 
 // pub enum ProgressionGoal {
 //     Axe(WorldObject::WoodAxe),
@@ -17,6 +21,7 @@ use super::{spawn_item_stack_icon, UIElement, UI_SLOT_SIZE};
 //     Anvil(WorldObject::AnvilBlock),
 // }
 
+/// The recipe pinned from the recipe book, shown on the HUD with its ingredients
 #[derive(Resource, Debug)]
 pub struct CurrentGoal {
     pub goal: WorldObject,
@@ -31,23 +36,37 @@ pub fn init_starting_goal(commands: Commands) {
 #[derive(Component)]
 pub struct GoalIcons;
 
-/// runs on new CurrentGoal resource added
+/// How many of an ingredient of the goal the player has, out of how many it needs
+#[derive(Component)]
+pub struct GoalIngredientText {
+    pub item: WorldObject,
+    pub count: usize,
+}
+
+fn get_goal_ingredient_label(have: usize, need: usize) -> (String, Color) {
+    (
+        format!("{}/{need}", have.min(need)),
+        if have >= need { LIGHT_GREEN } else { LIGHT_RED },
+    )
+}
+
+/// runs when the CurrentGoal resource is added or changed
 pub fn handle_display_new_goal(
     curr_goal: Res<CurrentGoal>,
     recipes: Res<Recipes>,
     mut commands: Commands,
     graphics: Res<Graphics>,
     asset_server: Res<AssetServer>,
+    inv: Query<&Inventory>,
+    old_icons: Query<Entity, With<GoalIcons>>,
 ) {
+    for e in old_icons.iter() {
+        commands.entity(e).despawn_recursive();
+    }
     let curr_goal_obj = curr_goal.goal;
-    let curr_goal_ingredients = recipes
-        .crafting_list
-        .get(&curr_goal_obj)
-        .unwrap()
-        .0
-        .iter()
-        .map(|r| r.item)
-        .collect::<Vec<WorldObject>>();
+    let Some((curr_goal_ingredients, ..)) = recipes.crafting_list.get(&curr_goal_obj) else {
+        return;
+    };
 
     let goal_icon = spawn_item_stack_icon(
         &mut commands,
@@ -59,7 +78,7 @@ pub fn handle_display_new_goal(
         3,
     );
 
-    let slot_entity = commands
+    commands
         .spawn(SpriteBundle {
             texture: graphics.get_ui_element_texture(UIElement::ScreenIconSlot),
             transform: Transform::from_translation(Vec3::new(-170., -100., 0.)),
@@ -78,13 +97,35 @@ pub fn handle_display_new_goal(
         let ingredient_icon = spawn_item_stack_icon(
             &mut commands,
             &graphics,
-            &ItemStack::crate_icon_stack(*ingredient),
+            &ItemStack::crate_icon_stack(ingredient.item),
             &asset_server,
             Vec2::ZERO,
             Vec2::new(0., 0.),
             3,
         );
-        let slot_entity = commands
+        let have = inv
+            .get_single()
+            .map(|inv| inv.items.get_item_count_in_container(ingredient.item))
+            .unwrap_or_default();
+        let (label, color) = get_goal_ingredient_label(have, ingredient.count);
+        let count_text = spawn_text(
+            &mut commands,
+            &asset_server,
+            Vec3::new(0., 13., 1.),
+            color,
+            label,
+            Anchor::Center,
+            1.,
+            3,
+        );
+        commands.entity(count_text).insert((
+            GoalIngredientText {
+                item: ingredient.item,
+                count: ingredient.count,
+            },
+            LargeText,
+        ));
+        commands
             .spawn(SpriteBundle {
                 texture: graphics.get_ui_element_texture(UIElement::ScreenIconSlot),
                 transform: Transform::from_translation(Vec3::new(
@@ -101,41 +142,33 @@ pub fn handle_display_new_goal(
             .insert(GoalIcons)
             .insert(RenderLayers::from_layers(&[3]))
             .insert(Name::new("SCREEN ICON"))
-            .push_children(&[ingredient_icon]);
+            .push_children(&[ingredient_icon, count_text]);
     }
 }
 
+/// Keeps the ingredient counts up to date, and clears the goal once it is crafted
 pub fn handle_update_goal_progress(
     inv: Query<&Inventory, Changed<Inventory>>,
     mut commands: Commands,
-    recipes: Res<Recipes>,
+    mut crafted_events: EventReader<CraftedItemEvent>,
     curr_goal: Res<CurrentGoal>,
     icons: Query<Entity, With<GoalIcons>>,
+    mut ingredient_texts: Query<(&GoalIngredientText, &mut Text)>,
 ) {
-    let curr_goal_obj = curr_goal.goal;
-    // let curr_goal_ingredients = recipes
-    //     .crafting_list
-    //     .get(&curr_goal_obj)
-    //     .unwrap()
-    //     .0
-    //     .iter()
-    //     .map(|r| r.item.clone())
-    //     .collect::<Vec<WorldObject>>();
-    // println!("Inv changed! {:?}", inv.single().items.items);
+    if crafted_events.iter().any(|e| e.obj == curr_goal.goal) {
+        debug!("crafted goal item");
+        commands.remove_resource::<CurrentGoal>();
+        for e in icons.iter() {
+            commands.entity(e).despawn_recursive();
+        }
+        return;
+    }
     if let Ok(inv) = inv.get_single() {
-        if inv
-            .items
-            .items
-            .iter()
-            .flatten()
-            .any(|i| i.get_obj() == &curr_goal_obj)
-        {
-            debug!("got goal item");
-            commands.remove_resource::<CurrentGoal>();
-            for e in icons.iter() {
-                debug!("DELETE!!");
-                commands.entity(e).despawn_recursive();
-            }
+        for (ingredient, mut text) in ingredient_texts.iter_mut() {
+            let have = inv.items.get_item_count_in_container(ingredient.item);
+            let (label, color) = get_goal_ingredient_label(have, ingredient.count);
+            text.sections[0].value = label;
+            text.sections[0].style.color = color;
         }
     }
 }
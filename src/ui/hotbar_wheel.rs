@@ -0,0 +1,137 @@
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use bevy::{prelude::*, render::view::RenderLayers};
+
+use crate::{
+    assets::Graphics,
+    inputs::CursorPos,
+    inventory::Inventory,
+    keymap::{ActionInput, InputAction},
+    settings::Settings,
+};
+
+use super::{
+    change_hotbar_slot, spawn_item_stack_icon, InventorySlotState, InventoryState, UIElement,
+    UIState, UI_SLOT_SIZE,
+};
+
+const HOTBAR_WHEEL_RADIUS: f32 = 40.;
+
+#[derive(Component)]
+pub struct HotbarWheel;
+
+#[derive(Component)]
+pub struct HotbarWheelSlot(pub usize);
+
+/// Hotbar slots go clockwise around the wheel, starting at the top
+fn get_wheel_dir(slot: usize, hotbar_size: usize) -> Vec2 {
+    Vec2::from_angle(FRAC_PI_2 - slot as f32 * TAU / hotbar_size as f32)
+}
+
+/// With the slot wheel turned on, hold the wheel key to bring up the hotbar as a ring,
+/// point the cursor or right stick at a slot, and let go to select it.
+pub fn handle_hotbar_wheel(
+    mut commands: Commands,
+    actions: ActionInput,
+    settings: Res<Settings>,
+    cursor_pos: Res<CursorPos>,
+    ui_state: Res<State<UIState>>,
+    graphics: Res<Graphics>,
+    asset_server: Res<AssetServer>,
+    inv: Query<&Inventory>,
+    wheel: Query<Entity, With<HotbarWheel>>,
+    mut wheel_slots: Query<(&HotbarWheelSlot, &mut Handle<Image>)>,
+    mut inv_state: ResMut<InventoryState>,
+    mut inv_slots: Query<&mut InventorySlotState>,
+) {
+    let hotbar_size = inv_state.hotbar_size;
+    let cursor_dir = cursor_pos.ui_coords.truncate();
+    let selected = if cursor_dir.length() < HOTBAR_WHEEL_RADIUS / 3. {
+        None
+    } else {
+        (0..hotbar_size).max_by(|a, b| {
+            get_wheel_dir(*a, hotbar_size)
+                .dot(cursor_dir)
+                .total_cmp(&get_wheel_dir(*b, hotbar_size).dot(cursor_dir))
+        })
+    };
+
+    if actions.just_pressed(InputAction::HotbarWheel)
+        && settings.hotbar_wheel
+        && ui_state.0 == UIState::Closed
+        && wheel.is_empty()
+    {
+        let wheel_e = commands
+            .spawn((
+                SpatialBundle::default(),
+                HotbarWheel,
+                Name::new("HOTBAR WHEEL"),
+            ))
+            .id();
+        let items = &inv.single().items.items;
+        for (slot, item) in items.iter().enumerate().take(hotbar_size) {
+            let slot_e = commands
+                .spawn((
+                    SpriteBundle {
+                        texture: graphics.get_ui_element_texture(UIElement::InventorySlot),
+                        transform: Transform::from_translation(
+                            (get_wheel_dir(slot, hotbar_size) * HOTBAR_WHEEL_RADIUS).extend(20.),
+                        ),
+                        sprite: Sprite {
+                            custom_size: Some(Vec2::new(UI_SLOT_SIZE, UI_SLOT_SIZE)),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    HotbarWheelSlot(slot),
+                    RenderLayers::from_layers(&[3]),
+                ))
+                .id();
+            if let Some(item) = item {
+                let icon_e = spawn_item_stack_icon(
+                    &mut commands,
+                    &graphics,
+                    &item.item_stack,
+                    &asset_server,
+                    Vec2::ZERO,
+                    Vec2::ZERO,
+                    3,
+                );
+                commands.entity(slot_e).add_child(icon_e);
+            }
+            commands.entity(wheel_e).add_child(slot_e);
+        }
+        return;
+    }
+    let Ok(wheel_e) = wheel.get_single() else {
+        return;
+    };
+    if actions.pressed(InputAction::HotbarWheel) {
+        for (wheel_slot, mut texture) in wheel_slots.iter_mut() {
+            *texture = graphics.get_ui_element_texture(if Some(wheel_slot.0) == selected {
+                UIElement::InventorySlotHover
+            } else {
+                UIElement::InventorySlot
+            });
+        }
+        return;
+    }
+    if let Some(slot) = selected {
+        change_hotbar_slot(slot, &mut inv_state, &mut inv_slots);
+    }
+    commands.entity(wheel_e).despawn_recursive();
+}
+
+// close the wheel without changing slots if a menu opens while it is up
+pub fn despawn_hotbar_wheel_on_ui_open(
+    mut commands: Commands,
+    wheel: Query<Entity, With<HotbarWheel>>,
+    ui_state: Res<State<UIState>>,
+) {
+    if ui_state.0 == UIState::Closed {
+        return;
+    }
+    for e in wheel.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+}
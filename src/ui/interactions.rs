@@ -8,10 +8,11 @@ use crate::{
     attributes::{
         attribute_helpers::create_new_random_item_stack_with_attributes, AttributeChangeEvent,
     },
-    colors::{DARK_GREEN, RED, WHITE, YELLOW_2},
+    colors::{overwrite_alpha, BLACK, DARK_GREEN, RED, WHITE, YELLOW_2},
     inputs::CursorPos,
     inventory::{Inventory, InventoryItemStack, ItemStack},
-    item::{CraftedItemEvent, EquipmentType},
+    item::{BatchCraftEvent, CraftAmount, CraftedItemEvent, EquipmentType, CRAFT_BATCH_SIZE},
+    keymap::{InputAction, Keymap},
     player::{
         levels::PlayerLevel,
         skills::{PlayerSkills, SkillChoiceQueue},
@@ -22,9 +23,18 @@ use crate::{
 };
 
 use super::{
-    crafting_ui::CraftingContainer, scrapper_ui::ScrapperContainer, spawn_item_stack_icon,
-    stats_ui::StatsButtonState, ui_helpers, ChestContainer, EssenceOption, FurnaceContainer,
-    InventorySlotState, MenuButton, MenuButtonClickEvent, ShowInvPlayerStatsEvent, SkillChoiceUI,
+    crafting_ui::CraftingContainer,
+    era_select_ui::{EraOption, TravelToEraEvent},
+    inventory_actions::{InventoryButton, InventoryButtonClickEvent},
+    item_finder_ui::{ItemFinderClickEvent, ItemFinderResultButton},
+    options_ui::{OptionsButton, OptionsButtonClickEvent},
+    recipe_book_ui::{RecipeBookButton, RecipeBookButtonClickEvent},
+    scrapper_ui::ScrapperContainer,
+    shop_ui::{BuyShopItemEvent, SellShopItemEvent, ShopContainer},
+    spawn_item_stack_icon,
+    stats_ui::StatsButtonState,
+    ui_helpers, ChestContainer, EssenceOption, FurnaceContainer, InventorySlotState,
+    InventoryState, MenuButton, MenuButtonClickEvent, ShowInvPlayerStatsEvent, SkillChoiceUI,
     SubmitEssenceChoice, ToolTipUpdateEvent, TooltipTeardownEvent, UIContainersParam, UIState,
 };
 
@@ -204,6 +214,7 @@ pub fn handle_drop_on_slot_events(
     asset_server: Res<AssetServer>,
     mut inv: Query<&mut Inventory>,
     mut cont_param: UIContainersParam,
+    mut sell_event: EventWriter<SellShopItemEvent>,
 ) {
     for drop_event in events.iter() {
         // all we need to do here is swap spots in the inventory
@@ -213,9 +224,14 @@ pub fn handle_drop_on_slot_events(
         if slot_type.is_crafting() && !cont_param.crafting_tracker.craftable.contains(&obj) {
             continue;
         }
+        let is_crafting_queued = cont_param
+            .crafting_option
+            .as_ref()
+            .map_or(false, |crafting| crafting.is_queued());
         let return_item = if slot_type.is_crafting()
             && cont_param.crafting_tracker.craftable.contains(&obj)
             && proto_param.get_component::<EquipmentType, _>(obj).is_none()
+            && !is_crafting_queued
         {
             inv.single()
                 .crafting_items
@@ -224,6 +240,23 @@ pub fn handle_drop_on_slot_events(
                     drop_event.drop_target_slot_state.slot_index,
                     &mut cont_param,
                 )
+        } else if slot_type.is_shop() {
+            // dropping gear on the shop sells it, anything else goes back on the cursor
+            if ShopContainer::get_sell_price(&drop_event.dropped_item_stack, &proto_param).is_some()
+            {
+                sell_event.send(SellShopItemEvent {
+                    item: drop_event.dropped_item_stack.clone(),
+                });
+                None
+            } else if drop_event.stack_empty {
+                Some(drop_event.dropped_item_stack.clone())
+            } else {
+                let held_stack = item_stacks.get(drop_event.dropped_entity).unwrap();
+                Some(
+                    held_stack
+                        .copy_with_count(held_stack.count + drop_event.dropped_item_stack.count),
+                )
+            }
         } else {
             if slot_type.is_crafting() {
                 continue;
@@ -329,6 +362,7 @@ pub fn handle_hovering(
     inv: Query<&Inventory>,
     chest_option: Option<Res<ChestContainer>>,
     scrapper_option: Option<Res<ScrapperContainer>>,
+    shop_option: Option<Res<ShopContainer>>,
     crafting_option: Option<Res<CraftingContainer>>,
     furnace_option: Option<Res<FurnaceContainer>>,
     mut tooltip_update_events: EventWriter<ToolTipUpdateEvent>,
@@ -358,6 +392,8 @@ pub fn handle_hovering(
                         chest_option.as_ref().unwrap().items.items[state.slot_index].clone()
                     } else if state.r#type.is_scrapper() {
                         scrapper_option.as_ref().unwrap().items.items[state.slot_index].clone()
+                    } else if state.r#type.is_shop() {
+                        shop_option.as_ref().unwrap().items.items[state.slot_index].clone()
                     } else if state.r#type.is_furnace() {
                         furnace_option.as_ref().unwrap().items.items[state.slot_index].clone()
                     } else if state.r#type.is_crafting() && crafting_option.is_some() {
@@ -386,6 +422,8 @@ pub fn handle_hovering(
                         chest_option.as_ref().unwrap().items.items[state.slot_index].clone()
                     } else if state.r#type.is_scrapper() {
                         scrapper_option.as_ref().unwrap().items.items[state.slot_index].clone()
+                    } else if state.r#type.is_shop() {
+                        shop_option.as_ref().unwrap().items.items[state.slot_index].clone()
                     } else if state.r#type.is_furnace() {
                         furnace_option.as_ref().unwrap().items.items[state.slot_index].clone()
                     } else if state.r#type.is_crafting() && crafting_option.is_some() {
@@ -588,6 +626,13 @@ pub fn handle_interaction_clicks(
     mut container_param: UIContainersParam,
     proto: ProtoParam,
     ui_state: Res<State<UIState>>,
+    (mut buy_event, mut sell_event, keymap, mut batch_craft_event, inv_state): (
+        EventWriter<BuyShopItemEvent>,
+        EventWriter<SellShopItemEvent>,
+        Res<Keymap>,
+        EventWriter<BatchCraftEvent>,
+        Res<InventoryState>,
+    ),
 ) {
     // get cursor resource from inputs
     // do a ray cast and get results
@@ -599,8 +644,12 @@ pub fn handle_interaction_clicks(
     let left_mouse_pressed = mouse_input.just_pressed(MouseButton::Left);
     let left_mouse_pressing = mouse_input.pressed(MouseButton::Left);
     let right_mouse_pressed = mouse_input.just_pressed(MouseButton::Right);
-    let shift_key_pressed = key_input.pressed(KeyCode::LShift);
+    let shift_key_pressed = keymap.pressed(InputAction::QuickMove, &key_input, &mouse_input);
     let currently_dragging = dragging_query.iter().len() > 0;
+    let is_crafting_queued = container_param
+        .crafting_option
+        .as_ref()
+        .map_or(false, |crafting| crafting.is_queued());
     for (e, mut interactable, mut state) in inv_slots.iter_mut() {
         match hit_test {
             Some(hit_ent) if hit_ent.0 == e => match interactable.current() {
@@ -608,7 +657,14 @@ pub fn handle_interaction_clicks(
                     interactable.change(Interaction::Hovering);
                 }
                 Interaction::Hovering => {
-                    if left_mouse_pressed && !currently_dragging && !shift_key_pressed {
+                    if state.r#type.is_shop() {
+                        // shop items are bought with a click instead of being picked up
+                        if left_mouse_pressed && !currently_dragging {
+                            buy_event.send(BuyShopItemEvent {
+                                slot: state.slot_index,
+                            });
+                        }
+                    } else if left_mouse_pressed && !currently_dragging && !shift_key_pressed {
                         //send drag event
                         if let Some(item) = state.item {
                             if let Ok(item_icon) = inv_item_icons.get_mut(item) {
@@ -620,6 +676,15 @@ pub fn handle_interaction_clicks(
                                 {
                                     continue;
                                 }
+                                // stations that take time queue the craft instead of handing it over
+                                if state.r#type.is_crafting() && is_crafting_queued {
+                                    batch_craft_event.send(BatchCraftEvent {
+                                        obj: item_icon.2.obj_type,
+                                        amount: CraftAmount::Count(1),
+                                    });
+                                    mouse_input.clear();
+                                    continue;
+                                }
                                 commands
                                     .entity(item_icon.0)
                                     .remove_parent()
@@ -654,6 +719,8 @@ pub fn handle_interaction_clicks(
                                     );
                                     container_param.crafted_event.send(CraftedItemEvent {
                                         obj: state.obj_type.unwrap(),
+                                        count: 1,
+                                        ingredients_used: false,
                                     });
                                 } else {
                                     container_items.items[state.slot_index] = None;
@@ -665,6 +732,13 @@ pub fn handle_interaction_clicks(
                         }
                     } else if right_mouse_pressed && !currently_dragging && !shift_key_pressed {
                         if state.r#type.is_crafting() {
+                            if let Some(obj) = state.obj_type {
+                                batch_craft_event.send(BatchCraftEvent {
+                                    obj,
+                                    amount: CraftAmount::Count(CRAFT_BATCH_SIZE),
+                                });
+                            }
+                            mouse_input.clear();
                             continue;
                         }
                         if let Some(item) = state.item {
@@ -704,14 +778,48 @@ pub fn handle_interaction_clicks(
                         }
                     } else if shift_key_pressed && left_mouse_pressing {
                         if state.r#type.is_crafting() {
+                            if left_mouse_pressed && !currently_dragging {
+                                if let Some(obj) = state.obj_type {
+                                    batch_craft_event.send(BatchCraftEvent {
+                                        obj,
+                                        amount: CraftAmount::Max,
+                                    });
+                                }
+                            }
+                            continue;
+                        }
+                        if container_param.shop_option.is_some() {
+                            // shift clicking gear while a shop is open sells it
+                            let mut inv = inv.single_mut();
+                            let sold_item = inv.items.items[state.slot_index].clone();
+                            if let Some(sold_item) = sold_item {
+                                if left_mouse_pressed
+                                    && state.r#type.is_inventory()
+                                    && ShopContainer::get_sell_price(&sold_item.item_stack, &proto)
+                                        .is_some()
+                                {
+                                    inv.items.items[state.slot_index] = None;
+                                    state.dirty = true;
+                                    sell_event.send(SellShopItemEvent {
+                                        item: sold_item.item_stack,
+                                    });
+                                }
+                            }
                             continue;
                         }
-                        let is_furnace = container_param.furnace_option.is_some();
                         let mut inv = inv.single_mut();
-                        if let Some(active_container) =
+                        if state.r#type.is_inventory() && container_param.furnace_option.is_some() {
+                            // furnace slots only take the fuel, ingredients or results they are for
+                            let furnace = &mut **container_param.furnace_option.as_mut().unwrap();
+                            inv.items.move_item_to_allowed_slot(
+                                &mut furnace.items,
+                                state.slot_index,
+                                &furnace.slot_map,
+                            );
+                        } else if let Some(active_container) =
                             container_param.get_active_ui_container_mut()
                         {
-                            if state.r#type.is_inventory() && !is_furnace {
+                            if state.r#type.is_inventory() {
                                 inv.items.move_item_to_target_container(
                                     active_container,
                                     state.slot_index,
@@ -721,8 +829,10 @@ pub fn handle_interaction_clicks(
                                     .move_item_to_target_container(&mut inv.items, state.slot_index)
                             }
                         } else {
-                            inv.items
-                                .move_item_from_hotbar_to_inv_or_vice_versa(state.slot_index)
+                            inv.items.move_item_from_hotbar_to_inv_or_vice_versa(
+                                state.slot_index,
+                                inv_state.hotbar_size,
+                            )
                         }
                     }
                 }
@@ -898,3 +1008,208 @@ pub fn handle_cursor_essence_buttons(
         }
     }
 }
+
+pub fn handle_cursor_era_buttons(
+    cursor_pos: Res<CursorPos>,
+    mouse_input: Res<Input<MouseButton>>,
+    ui_sprites: Query<(Entity, &Sprite, &GlobalTransform), With<Interactable>>,
+    mut era_buttons: Query<(Entity, &mut Interactable, &EraOption)>,
+    mut travel_event: EventWriter<TravelToEraEvent>,
+) {
+    let hit_test = ui_helpers::pointcast_2d(&cursor_pos, &ui_sprites, None);
+    let left_mouse_pressed = mouse_input.just_pressed(MouseButton::Left);
+
+    for (e, mut interactable, era_option) in era_buttons.iter_mut() {
+        match hit_test {
+            Some(hit_ent) if hit_ent.0 == e => match interactable.current() {
+                Interaction::None => {
+                    interactable.change(Interaction::Hovering);
+                }
+                Interaction::Hovering => {
+                    if left_mouse_pressed {
+                        travel_event.send(TravelToEraEvent {
+                            choice: era_option.clone(),
+                        });
+                    }
+                }
+                _ => (),
+            },
+            _ => {
+                let Interaction::Hovering = interactable.current() else {
+                    continue;
+                };
+
+                interactable.change(Interaction::None);
+            }
+        }
+    }
+}
+
+pub fn handle_cursor_options_buttons(
+    cursor_pos: Res<CursorPos>,
+    mouse_input: Res<Input<MouseButton>>,
+    mut queries: ParamSet<(
+        Query<(Entity, &Sprite, &GlobalTransform), With<Interactable>>,
+        Query<(Entity, &mut Interactable, &mut Sprite, &OptionsButton)>,
+    )>,
+    mut click_event: EventWriter<OptionsButtonClickEvent>,
+) {
+    let hit_test = ui_helpers::pointcast_2d(&cursor_pos, &queries.p0(), None).map(|hit| hit.0);
+    let left_mouse_pressed = mouse_input.just_pressed(MouseButton::Left);
+    let right_mouse_pressed = mouse_input.just_pressed(MouseButton::Right);
+
+    for (e, mut interactable, mut sprite, button) in queries.p1().iter_mut() {
+        match hit_test {
+            Some(hit_ent) if hit_ent == e => match interactable.current() {
+                Interaction::None => {
+                    interactable.change(Interaction::Hovering);
+                    sprite.color = overwrite_alpha(BLACK, 0.15);
+                }
+                Interaction::Hovering => {
+                    if left_mouse_pressed || right_mouse_pressed {
+                        click_event.send(OptionsButtonClickEvent {
+                            button: *button,
+                            right_click: right_mouse_pressed,
+                        });
+                    }
+                }
+                _ => (),
+            },
+            _ => {
+                let Interaction::Hovering = interactable.current() else {
+                    continue;
+                };
+
+                interactable.change(Interaction::None);
+                sprite.color = Color::NONE;
+            }
+        }
+    }
+}
+
+pub fn handle_cursor_inventory_buttons(
+    cursor_pos: Res<CursorPos>,
+    mouse_input: Res<Input<MouseButton>>,
+    mut queries: ParamSet<(
+        Query<(Entity, &Sprite, &GlobalTransform), With<Interactable>>,
+        Query<(Entity, &mut Interactable, &mut Sprite, &InventoryButton)>,
+    )>,
+    mut click_event: EventWriter<InventoryButtonClickEvent>,
+) {
+    let hit_test = ui_helpers::pointcast_2d(&cursor_pos, &queries.p0(), None).map(|hit| hit.0);
+    let left_mouse_pressed = mouse_input.just_pressed(MouseButton::Left);
+    let right_mouse_pressed = mouse_input.just_pressed(MouseButton::Right);
+
+    for (e, mut interactable, mut sprite, button) in queries.p1().iter_mut() {
+        match hit_test {
+            Some(hit_ent) if hit_ent == e => match interactable.current() {
+                Interaction::None => {
+                    interactable.change(Interaction::Hovering);
+                    sprite.color = overwrite_alpha(BLACK, 0.8);
+                }
+                Interaction::Hovering => {
+                    if left_mouse_pressed || right_mouse_pressed {
+                        click_event.send(InventoryButtonClickEvent {
+                            button: *button,
+                            right_click: right_mouse_pressed,
+                        });
+                    }
+                }
+                _ => (),
+            },
+            _ => {
+                let Interaction::Hovering = interactable.current() else {
+                    continue;
+                };
+
+                interactable.change(Interaction::None);
+                sprite.color = overwrite_alpha(BLACK, 0.5);
+            }
+        }
+    }
+}
+
+pub fn handle_cursor_item_finder_buttons(
+    cursor_pos: Res<CursorPos>,
+    mouse_input: Res<Input<MouseButton>>,
+    mut queries: ParamSet<(
+        Query<(Entity, &Sprite, &GlobalTransform), With<Interactable>>,
+        Query<(
+            Entity,
+            &mut Interactable,
+            &mut Sprite,
+            &ItemFinderResultButton,
+        )>,
+    )>,
+    mut click_event: EventWriter<ItemFinderClickEvent>,
+) {
+    let hit_test = ui_helpers::pointcast_2d(&cursor_pos, &queries.p0(), None).map(|hit| hit.0);
+    let left_mouse_pressed = mouse_input.just_pressed(MouseButton::Left);
+
+    for (e, mut interactable, mut sprite, button) in queries.p1().iter_mut() {
+        match hit_test {
+            Some(hit_ent) if hit_ent == e => match interactable.current() {
+                Interaction::None => {
+                    interactable.change(Interaction::Hovering);
+                    sprite.color = overwrite_alpha(BLACK, 0.15);
+                }
+                Interaction::Hovering => {
+                    if left_mouse_pressed {
+                        click_event.send(ItemFinderClickEvent { obj: button.0 });
+                    }
+                }
+                _ => (),
+            },
+            _ => {
+                let Interaction::Hovering = interactable.current() else {
+                    continue;
+                };
+
+                interactable.change(Interaction::None);
+                sprite.color = Color::NONE;
+            }
+        }
+    }
+}
+
+pub fn handle_cursor_recipe_book_buttons(
+    cursor_pos: Res<CursorPos>,
+    mouse_input: Res<Input<MouseButton>>,
+    mut queries: ParamSet<(
+        Query<(Entity, &Sprite, &GlobalTransform), With<Interactable>>,
+        Query<(Entity, &mut Interactable, &mut Sprite, &RecipeBookButton)>,
+    )>,
+    mut click_event: EventWriter<RecipeBookButtonClickEvent>,
+) {
+    let hit_test = ui_helpers::pointcast_2d(&cursor_pos, &queries.p0(), None).map(|hit| hit.0);
+    let left_mouse_pressed = mouse_input.just_pressed(MouseButton::Left);
+    let right_mouse_pressed = mouse_input.just_pressed(MouseButton::Right);
+
+    for (e, mut interactable, mut sprite, button) in queries.p1().iter_mut() {
+        match hit_test {
+            Some(hit_ent) if hit_ent == e => match interactable.current() {
+                Interaction::None => {
+                    interactable.change(Interaction::Hovering);
+                    sprite.color = overwrite_alpha(BLACK, 0.15);
+                }
+                Interaction::Hovering => {
+                    if left_mouse_pressed || right_mouse_pressed {
+                        click_event.send(RecipeBookButtonClickEvent {
+                            button: *button,
+                            right_click: right_mouse_pressed,
+                        });
+                    }
+                }
+                _ => (),
+            },
+            _ => {
+                let Interaction::Hovering = interactable.current() else {
+                    continue;
+                };
+
+                interactable.change(Interaction::None);
+                sprite.color = Color::NONE;
+            }
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use bevy::{prelude::*, render::view::RenderLayers, sprite::Anchor};
+
+use crate::{
+    colors::{overwrite_alpha, BLACK, WHITE, YELLOW},
+    inventory::Inventory,
+    keymap::{ActionInput, InputAction},
+    player::Player,
+    settings::{Setting, Settings},
+    world::TILE_SIZE,
+};
+
+use super::{
+    damage_numbers::spawn_text, interactions::Interaction, ChestContainer, DraggedItem,
+    Interactable, InventorySlotState, InventoryState, InventoryUI, UIState,
+};
+
+/// Chests this close to the player are filled by quick stack
+const QUICK_STACK_RADIUS: f32 = 6. * TILE_SIZE.x;
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InventoryButton {
+    Sort,
+    QuickStack,
+    ItemFinder,
+}
+
+impl InventoryButton {
+    fn get_label(&self, settings: &Settings) -> String {
+        match self {
+            InventoryButton::Sort => get_sort_label(settings),
+            InventoryButton::QuickStack => "Stack".to_string(),
+            InventoryButton::ItemFinder => "Find".to_string(),
+        }
+    }
+    fn get_width(&self, label: &str) -> f32 {
+        match self {
+            // wide enough for every sort order, so the row doesn't shift when it changes
+            InventoryButton::Sort => 60.,
+            _ => label.len() as f32 * 5. + 4.,
+        }
+    }
+}
+
+pub struct InventoryButtonClickEvent {
+    pub button: InventoryButton,
+    /// Right clicking the sort button changes what it sorts by
+    pub right_click: bool,
+}
+
+#[derive(Component)]
+pub struct SortButtonText;
+
+fn get_sort_label(settings: &Settings) -> String {
+    format!("Sort {}", settings.inventory_sort.get_name())
+}
+
+/// Adds the inventory buttons above the player's inventory, right aligned
+pub fn spawn_inventory_buttons(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
+    inv_state: Res<InventoryState>,
+    ui_state: Res<State<UIState>>,
+    new_inv_ui: Query<Entity, Added<InventoryUI>>,
+) {
+    let Ok(inv_e) = new_inv_ui.get_single() else {
+        return;
+    };
+    let buttons = match ui_state.0 {
+        UIState::Inventory | UIState::Chest => [InventoryButton::QuickStack, InventoryButton::Sort],
+        UIState::Crafting => [InventoryButton::ItemFinder, InventoryButton::Sort],
+        _ => return,
+    };
+    let y = inv_state.inv_size.y / 2. + 7.;
+    let mut right = inv_state.inv_size.x / 2.;
+    for button in buttons {
+        let label = button.get_label(&settings);
+        let width = button.get_width(&label);
+        let x = right - width / 2.;
+        right -= width + 2.;
+        let button_e = commands
+            .spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: overwrite_alpha(BLACK, 0.5),
+                        custom_size: Some(Vec2::new(width, 10.)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(Vec3::new(x, y, 1.)),
+                    ..default()
+                },
+                Interactable::default(),
+                button,
+                RenderLayers::from_layers(&[3]),
+                Name::new("Inventory Button"),
+            ))
+            .set_parent(inv_e)
+            .id();
+        let text_e = spawn_text(
+            &mut commands,
+            &asset_server,
+            Vec3::new(0., 0., 1.),
+            WHITE,
+            label,
+            Anchor::Center,
+            1.,
+            3,
+        );
+        if button == InventoryButton::Sort {
+            commands.entity(text_e).insert(SortButtonText);
+        }
+        commands.entity(button_e).add_child(text_e);
+    }
+}
+
+pub fn handle_inventory_button_clicks(
+    mut events: EventReader<InventoryButtonClickEvent>,
+    mut settings: ResMut<Settings>,
+    mut inv: Query<&mut Inventory>,
+    player: Query<&GlobalTransform, With<Player>>,
+    mut open_chest: Option<ResMut<ChestContainer>>,
+    mut chests: Query<(Entity, &GlobalTransform, &mut ChestContainer)>,
+    mut inv_slots: Query<&mut InventorySlotState>,
+    dragging: Query<&DraggedItem>,
+    mut next_ui_state: ResMut<NextState<UIState>>,
+    inv_state: Res<InventoryState>,
+) {
+    for InventoryButtonClickEvent {
+        button,
+        right_click,
+    } in events.iter()
+    {
+        // the dragged item is out of its slot, so it would be lost in the shuffle
+        if !dragging.is_empty() {
+            continue;
+        }
+        let Ok(mut inv) = inv.get_single_mut() else {
+            continue;
+        };
+        let inv = &mut *inv;
+        // the hotbar stays the way the player set it up
+        let first_non_hotbar_slot = inv_state.hotbar_size;
+        match button {
+            InventoryButton::Sort if *right_click => {
+                Setting::InventorySort.cycle(&mut settings, true);
+                settings.save();
+            }
+            InventoryButton::Sort => {
+                inv.items.sort_items(
+                    first_non_hotbar_slot..inv.items.items.len(),
+                    &inv.locked_slots,
+                    settings.inventory_sort,
+                );
+                // the counts may not change, so the slots would not notice on their own
+                for mut state in inv_slots.iter_mut() {
+                    if state.r#type.is_inventory() {
+                        state.dirty = true;
+                    }
+                }
+            }
+            InventoryButton::QuickStack => {
+                let Ok(player_txfm) = player.get_single() else {
+                    continue;
+                };
+                let player_pos = player_txfm.translation().truncate();
+                let slots = (first_non_hotbar_slot..inv.items.items.len())
+                    .filter(|i| !inv.locked_slots.contains(i))
+                    .collect::<Vec<_>>();
+                // the open chest is a copy of its entity's until the chest closes
+                let open_chest_e = open_chest.as_ref().map(|chest| chest.parent);
+                if let Some(chest) = open_chest.as_mut() {
+                    inv.items
+                        .quick_stack_to_target_container(&mut chest.items, slots.iter().copied());
+                }
+                for (e, txfm, mut chest) in chests.iter_mut() {
+                    if Some(e) == open_chest_e
+                        || txfm.translation().truncate().distance(player_pos) > QUICK_STACK_RADIUS
+                    {
+                        continue;
+                    }
+                    inv.items
+                        .quick_stack_to_target_container(&mut chest.items, slots.iter().copied());
+                }
+            }
+            InventoryButton::ItemFinder => next_ui_state.set(UIState::ItemFinder),
+        }
+    }
+}
+
+pub fn update_sort_button_text(
+    settings: Res<Settings>,
+    mut texts: Query<&mut Text, With<SortButtonText>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    for mut text in texts.iter_mut() {
+        text.sections[0].value = get_sort_label(&settings);
+    }
+}
+
+/// Locks or unlocks the inventory slot under the cursor
+pub fn handle_lock_slot_input(
+    actions: ActionInput,
+    ui_state: Res<State<UIState>>,
+    mut inv: Query<&mut Inventory>,
+    slots: Query<(&Interactable, &InventorySlotState)>,
+) {
+    if !ui_state.0.is_inv_open() || !actions.just_pressed(InputAction::LockSlot) {
+        return;
+    }
+    let hovered_slot = slots.iter().find(|(interactable, state)| {
+        state.r#type.is_inventory() && matches!(interactable.current(), Interaction::Hovering)
+    });
+    if let (Some((_, state)), Ok(mut inv)) = (hovered_slot, inv.get_single_mut()) {
+        inv.toggle_slot_lock(state.slot_index);
+    }
+}
+
+/// Tints locked slots, in the inventory and on the hotbar
+pub fn update_locked_slot_tint(
+    inv: Query<&Inventory>,
+    mut slots: Query<(&InventorySlotState, &mut Sprite)>,
+) {
+    let Ok(inv) = inv.get_single() else {
+        return;
+    };
+    for (state, mut sprite) in slots.iter_mut() {
+        if !state.r#type.is_inventory() && !state.r#type.is_hotbar() {
+            continue;
+        }
+        let color = if inv.locked_slots.contains(&state.slot_index) {
+            YELLOW
+        } else {
+            Color::WHITE
+        };
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
@@ -10,9 +10,9 @@ use crate::{
 };
 
 use super::{
-    crafting_ui::CraftingContainer, interactions::Interaction, Interactable,
-    ShowInvPlayerStatsEvent, UIContainersParam, UIElement, CRAFTING_INVENTORY_UI_SIZE,
-    FURNACE_INVENTORY_UI_SIZE, UI_SLOT_SIZE,
+    accessibility::LargeText, crafting_ui::CraftingContainer, interactions::Interaction,
+    Interactable, ShowInvPlayerStatsEvent, UIContainersParam, UIElement,
+    CRAFTING_INVENTORY_UI_SIZE, FURNACE_INVENTORY_UI_SIZE, UI_SLOT_SIZE,
 };
 
 #[derive(Clone, Eq, PartialEq, Debug, Hash, Default, States, Component)]
@@ -28,12 +28,18 @@ pub enum UIState {
     Essence,
     Options,
     Scrapper,
+    Shop,
+    EraSelect,
+    WorldMap,
+    ItemFinder,
+    RecipeBook,
 }
 impl UIState {
     pub fn is_inv_open(&self) -> bool {
         self == &UIState::Inventory
             || self == &UIState::Chest
             || self == &UIState::Scrapper
+            || self == &UIState::Shop
             || self == &UIState::Crafting
             || self == &UIState::Furnace
     }
@@ -55,6 +61,8 @@ pub struct InventoryState {
     pub active_hotbar_slot: usize,
     pub inv_size: Vec2,
     pub hotbar_dirty: bool,
+    /// How many slots the hotbar HUD was last spawned with
+    pub hotbar_size: usize,
 }
 #[derive(FromReflect, PartialEq, Reflect, Debug, Clone, Copy)]
 pub enum InventorySlotType {
@@ -66,6 +74,7 @@ pub enum InventorySlotType {
     Chest,
     Furnace,
     Scrapper,
+    Shop,
 }
 impl InventorySlotType {
     pub fn is_crafting(self) -> bool {
@@ -92,6 +101,9 @@ impl InventorySlotType {
     pub fn is_scrapper(self) -> bool {
         self == InventorySlotType::Scrapper
     }
+    pub fn is_shop(self) -> bool {
+        self == InventorySlotType::Shop
+    }
 }
 pub fn setup_inv_ui(
     mut commands: Commands,
@@ -117,6 +129,11 @@ pub fn setup_inv_ui(
             graphics.get_ui_element_texture(UIElement::ChestInventory),
             Vec2::new(22.5, 0.),
         ),
+        UIState::Shop => (
+            CHEST_INVENTORY_UI_SIZE,
+            graphics.get_ui_element_texture(UIElement::ChestInventory),
+            Vec2::new(22.5, 0.),
+        ),
         UIState::Crafting => (
             CRAFTING_INVENTORY_UI_SIZE,
             graphics.get_ui_element_texture(UIElement::CraftingInventory),
@@ -196,6 +213,7 @@ pub fn setup_inv_slots_ui(
         UIState::Crafting => (true, Some(crafting_container.unwrap().items.clone())),
         UIState::Chest => (false, None),
         UIState::Scrapper => (false, None),
+        UIState::Shop => (false, None),
         UIState::Furnace => (true, None),
         _ => return,
     };
@@ -290,8 +308,9 @@ pub fn spawn_inv_slot(
         + UI_SLOT_SIZE / 2.;
 
     if slot_type.is_hotbar() {
+        // centered on the xp bar, however long the hotbar is
         y = -GAME_HEIGHT / 2. + 14.;
-        x = ((slot_index % 6) as f32 * UI_SLOT_SIZE) - 2. * UI_SLOT_SIZE;
+        x = (slot_index as f32 - (inv_state.hotbar_size as f32 - 1.) / 2.) * UI_SLOT_SIZE + 10.;
     } else if slot_type.is_crafting() {
         x = ((slot_index % 8) as f32 * UI_SLOT_SIZE) - (inv_state.inv_size.x) / 2.
             + UI_SLOT_SIZE / 2.
@@ -316,7 +335,7 @@ pub fn spawn_inv_slot(
             + UI_SLOT_SIZE
             + 1. * slot_index as f32
             + 4.;
-    } else if slot_type.is_chest() || slot_type.is_scrapper() {
+    } else if slot_type.is_chest() || slot_type.is_scrapper() || slot_type.is_shop() {
         y += 4. * UI_SLOT_SIZE + 11.;
     } else if slot_type.is_furnace() {
         if slot_index == 0 {
@@ -509,6 +528,7 @@ pub fn spawn_item_stack_icon(
                     ..default()
                 },
                 Name::new("ITEM STACK TEXT"),
+                LargeText,
                 RenderLayers::from_layers(&[render_layer]),
             ))
             .id();
@@ -557,6 +577,8 @@ pub fn update_inventory_ui(
             cont_param.chest_option.as_ref().unwrap().items.items[slot_state.slot_index].clone()
         } else if slot_state.r#type.is_scrapper() {
             cont_param.scrapper_option.as_ref().unwrap().items.items[slot_state.slot_index].clone()
+        } else if slot_state.r#type.is_shop() {
+            cont_param.shop_option.as_ref().unwrap().items.items[slot_state.slot_index].clone()
         } else if slot_state.r#type.is_furnace() {
             cont_param.furnace_option.as_ref().unwrap().items.items[slot_state.slot_index].clone()
         } else if slot_state.r#type.is_crafting() && cont_param.crafting_option.is_some() {
@@ -0,0 +1,400 @@
+use bevy::{
+    input::InputSystem, prelude::*, render::view::RenderLayers, sprite::Anchor, utils::HashMap,
+    window::ReceivedCharacter,
+};
+
+use crate::{
+    assets::Graphics,
+    colors::BLACK,
+    container::{Container, ContainerRegistry},
+    item::{EquipmentType, WorldObject},
+    keymap::{InputAction, InputBinding, Keymap},
+    proto::proto_param::ProtoParam,
+    world::{generation::WorldObjectCache, world_helpers::world_pos_to_tile_pos, TileMapPosition},
+    CustomFlush, GameState, ScreenResolution, GAME_HEIGHT,
+};
+
+use super::{
+    damage_numbers::spawn_text, interactions::handle_cursor_item_finder_buttons,
+    world_map::WorldMapHighlights, ChestContainer, FurnaceContainer, Interactable, UIElement,
+    UIState, ESSENCE_UI_SIZE,
+};
+
+const MAX_QUERY_LEN: usize = 14;
+const MAX_RESULT_ROWS: usize = 10;
+const RESULT_ROW_HEIGHT: f32 = 9.;
+const MAX_NAME_LEN: usize = 12;
+
+#[derive(Component)]
+pub struct ItemFinderUI;
+
+#[derive(Component)]
+pub struct ItemFinderQueryText;
+
+/// A row of the results, clicking it shows where the item is stored on the map
+#[derive(Component, Debug, Clone, Copy)]
+pub struct ItemFinderResultButton(pub WorldObject);
+
+#[derive(Component)]
+pub struct ItemFinderResultRow;
+
+pub struct ItemFinderClickEvent {
+    pub obj: WorldObject,
+}
+
+/// What the player typed, kept so reopening the finder repeats the last search
+#[derive(Resource, Default, Debug)]
+pub struct ItemFinderQuery(pub String);
+
+/// One kind of item found in storage, with how much of it each container holds
+#[derive(Debug, Clone)]
+pub struct ItemFinderResult {
+    pub obj: WorldObject,
+    pub name: String,
+    pub count: usize,
+    pub containers: Vec<(TileMapPosition, usize)>,
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct ItemFinderResults(pub Vec<ItemFinderResult>);
+
+pub struct ItemFinderPlugin;
+
+impl Plugin for ItemFinderPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ItemFinderQuery>()
+            .init_resource::<ItemFinderResults>()
+            .add_event::<ItemFinderClickEvent>()
+            .add_systems(
+                (
+                    handle_enter_item_finder_ui
+                        .before(CustomFlush)
+                        .run_if(state_changed::<UIState>().and_then(in_state(UIState::ItemFinder))),
+                    update_item_finder_results
+                        .after(CustomFlush)
+                        .run_if(in_state(UIState::ItemFinder)),
+                    handle_cursor_item_finder_buttons.run_if(in_state(UIState::ItemFinder)),
+                    handle_item_finder_clicks.after(handle_cursor_item_finder_buttons),
+                )
+                    .in_set(OnUpdate(GameState::Main)),
+            )
+            // takes the typed search before any hotkey can react to it
+            .add_system(
+                handle_item_finder_text_input
+                    .in_base_set(CoreSet::PreUpdate)
+                    .after(InputSystem)
+                    .run_if(in_state(UIState::ItemFinder)),
+            );
+    }
+}
+
+fn handle_enter_item_finder_ui(
+    mut commands: Commands,
+    graphics: Res<Graphics>,
+    asset_server: Res<AssetServer>,
+    res: Res<ScreenResolution>,
+    query: Res<ItemFinderQuery>,
+) {
+    let (size, texture, t_offset) = (
+        ESSENCE_UI_SIZE,
+        graphics.get_ui_element_texture(UIElement::Essence),
+        Vec2::new(3.5, 3.5),
+    );
+
+    let overlay = commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(146. / 255., 116. / 255., 65. / 255., 0.3),
+                custom_size: Some(Vec2::new(res.game_width + 10., GAME_HEIGHT + 10.)),
+                ..default()
+            },
+            transform: Transform {
+                translation: Vec3::new(-t_offset.x, -t_offset.y, -1.),
+                scale: Vec3::new(1., 1., 1.),
+                ..Default::default()
+            },
+            ..default()
+        })
+        .insert(RenderLayers::from_layers(&[3]))
+        .insert(Name::new("overlay"))
+        .id();
+    let finder_e = commands
+        .spawn(SpriteBundle {
+            texture,
+            sprite: Sprite {
+                custom_size: Some(size),
+                ..Default::default()
+            },
+            transform: Transform {
+                translation: Vec3::new(t_offset.x, t_offset.y, 10.),
+                scale: Vec3::new(1., 1., 1.),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(ItemFinderUI)
+        .insert(Name::new("ITEM FINDER UI"))
+        .insert(UIState::ItemFinder)
+        .insert(RenderLayers::from_layers(&[3]))
+        .id();
+
+    let title = spawn_text(
+        &mut commands,
+        &asset_server,
+        Vec3::new(0., 62., 1.),
+        BLACK,
+        "Item Finder".to_string(),
+        Anchor::Center,
+        1.,
+        3,
+    );
+    let query_text = spawn_text(
+        &mut commands,
+        &asset_server,
+        Vec3::new(-48., 50., 1.),
+        BLACK,
+        get_query_label(&query.0),
+        Anchor::CenterLeft,
+        1.,
+        3,
+    );
+    commands.entity(query_text).insert(ItemFinderQueryText);
+    commands
+        .entity(finder_e)
+        .push_children(&[overlay, title, query_text]);
+}
+
+fn get_query_label(query: &str) -> String {
+    format!("Find: {query}_")
+}
+
+/// Whether the item's name or equipment type contains what the player typed
+fn matches_query(obj: WorldObject, name: &str, query: &str, proto: &ProtoParam) -> bool {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return true;
+    }
+    let equipment_type = proto
+        .get_component::<EquipmentType, _>(obj)
+        .filter(|eqp_type| **eqp_type != EquipmentType::None)
+        .map(|eqp_type| format!("{eqp_type:?}"))
+        .unwrap_or_default();
+    [name.to_string(), obj.to_string(), equipment_type]
+        .iter()
+        .any(|label| label.to_lowercase().contains(&query))
+}
+
+/// Every chest and furnace of the current era. The registry keeps a copy of the
+/// containers in unloaded chunks, the loaded ones are read from their objects.
+fn get_era_containers(
+    container_reg: &ContainerRegistry,
+    world_obj_cache: &WorldObjectCache,
+    loaded_containers: &Query<(
+        &GlobalTransform,
+        Option<&ChestContainer>,
+        Option<&FurnaceContainer>,
+    )>,
+) -> HashMap<TileMapPosition, Container> {
+    // the registry is shared by every era, so only keep the spots that hold a
+    // container in this one
+    let mut containers: HashMap<TileMapPosition, Container> = container_reg
+        .containers
+        .iter()
+        .filter(|(pos, _)| {
+            matches!(
+                world_obj_cache.objects.get(*pos),
+                Some(WorldObject::Chest | WorldObject::Furnace | WorldObject::UpgradeStation)
+            )
+        })
+        .map(|(pos, container)| (*pos, container.clone()))
+        .collect();
+    for (txfm, chest, furnace) in loaded_containers.iter() {
+        let items = match (chest, furnace) {
+            (Some(chest), _) => &chest.items,
+            (_, Some(furnace)) => &furnace.items,
+            _ => continue,
+        };
+        containers.insert(
+            world_pos_to_tile_pos(txfm.translation().truncate()),
+            items.clone(),
+        );
+    }
+    containers
+}
+
+/// Searches storage again whenever the search changes, and redraws the result rows
+fn update_item_finder_results(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    query: Res<ItemFinderQuery>,
+    mut results: ResMut<ItemFinderResults>,
+    container_reg: Res<ContainerRegistry>,
+    world_obj_cache: Res<WorldObjectCache>,
+    loaded_containers: Query<(
+        &GlobalTransform,
+        Option<&ChestContainer>,
+        Option<&FurnaceContainer>,
+    )>,
+    proto: ProtoParam,
+    finder_ui: Query<Entity, Added<ItemFinderUI>>,
+    finder: Query<Entity, With<ItemFinderUI>>,
+    mut query_text: Query<&mut Text, With<ItemFinderQueryText>>,
+    old_rows: Query<Entity, With<ItemFinderResultRow>>,
+) {
+    if !query.is_changed() && finder_ui.is_empty() {
+        return;
+    }
+    let Ok(finder_e) = finder.get_single() else {
+        return;
+    };
+    for mut text in query_text.iter_mut() {
+        text.sections[0].value = get_query_label(&query.0);
+    }
+    for e in old_rows.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+
+    let mut found: HashMap<WorldObject, ItemFinderResult> = HashMap::default();
+    for (pos, container) in get_era_containers(&container_reg, &world_obj_cache, &loaded_containers)
+    {
+        for item in container.items.iter().flatten() {
+            let stack = &item.item_stack;
+            let name = if stack.metadata.name.is_empty() {
+                stack.obj_type.to_string()
+            } else {
+                stack.metadata.name.clone()
+            };
+            if !matches_query(stack.obj_type, &name, &query.0, &proto) {
+                continue;
+            }
+            let result = found.entry(stack.obj_type).or_insert(ItemFinderResult {
+                obj: stack.obj_type,
+                name,
+                count: 0,
+                containers: vec![],
+            });
+            result.count += stack.count;
+            match result.containers.iter_mut().find(|(p, _)| *p == pos) {
+                Some((_, count)) => *count += stack.count,
+                None => result.containers.push((pos, stack.count)),
+            }
+        }
+    }
+    let mut found: Vec<ItemFinderResult> = found.into_values().collect();
+    found.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut rows = vec![];
+    for (i, result) in found.iter().take(MAX_RESULT_ROWS).enumerate() {
+        let row_e = commands
+            .spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::NONE,
+                        custom_size: Some(Vec2::new(100., RESULT_ROW_HEIGHT - 1.)),
+                        ..Default::default()
+                    },
+                    transform: Transform::from_translation(Vec3::new(
+                        0.,
+                        38. - i as f32 * RESULT_ROW_HEIGHT,
+                        1.,
+                    )),
+                    ..Default::default()
+                },
+                Interactable::default(),
+                ItemFinderResultButton(result.obj),
+                ItemFinderResultRow,
+                RenderLayers::from_layers(&[3]),
+                Name::new("Item Finder Row"),
+            ))
+            .id();
+        let name_text = spawn_text(
+            &mut commands,
+            &asset_server,
+            Vec3::new(-48., 0., 1.),
+            BLACK,
+            result.name.chars().take(MAX_NAME_LEN).collect(),
+            Anchor::CenterLeft,
+            1.,
+            3,
+        );
+        let count_text = spawn_text(
+            &mut commands,
+            &asset_server,
+            Vec3::new(48., 0., 1.),
+            BLACK,
+            format!("{} in {}", result.count, result.containers.len()),
+            Anchor::CenterRight,
+            1.,
+            3,
+        );
+        commands
+            .entity(row_e)
+            .push_children(&[name_text, count_text]);
+        rows.push(row_e);
+    }
+    let footer = spawn_text(
+        &mut commands,
+        &asset_server,
+        Vec3::new(0., -62., 1.),
+        BLACK,
+        if found.is_empty() {
+            "Nothing stored".to_string()
+        } else if found.len() > MAX_RESULT_ROWS {
+            format!("{} more, type to narrow", found.len() - MAX_RESULT_ROWS)
+        } else {
+            "Click to show on map".to_string()
+        },
+        Anchor::Center,
+        1.,
+        3,
+    );
+    commands.entity(footer).insert(ItemFinderResultRow);
+    rows.push(footer);
+    commands.entity(finder_e).push_children(&rows);
+    results.0 = found;
+}
+
+/// Points out every container holding the clicked item on the world map
+fn handle_item_finder_clicks(
+    mut events: EventReader<ItemFinderClickEvent>,
+    results: Res<ItemFinderResults>,
+    mut highlights: ResMut<WorldMapHighlights>,
+    mut next_ui_state: ResMut<NextState<UIState>>,
+) {
+    for ItemFinderClickEvent { obj } in events.iter() {
+        let Some(result) = results.0.iter().find(|result| result.obj == *obj) else {
+            continue;
+        };
+        highlights.0 = result
+            .containers
+            .iter()
+            .map(|(pos, count)| (*pos, format!("{} x{count}", result.name)))
+            .collect();
+        next_ui_state.set(UIState::WorldMap);
+    }
+}
+
+/// Runs before the rest of the game sees the keys, so typing a search doesn't also
+/// press hotkeys. Only the keys that close menus still go through.
+fn handle_item_finder_text_input(
+    mut key_input: ResMut<Input<KeyCode>>,
+    mut chars: EventReader<ReceivedCharacter>,
+    keymap: Res<Keymap>,
+    mut query: ResMut<ItemFinderQuery>,
+) {
+    let close_keys = keymap.get_bindings(InputAction::CloseMenu);
+    if key_input.just_pressed(KeyCode::Back) {
+        query.0.pop();
+    }
+    let pressed: Vec<KeyCode> = key_input.get_just_pressed().copied().collect();
+    for key in pressed {
+        if !close_keys.contains(&InputBinding::Key(key)) {
+            key_input.clear_just_pressed(key);
+        }
+    }
+    for ReceivedCharacter { char, .. } in chars.iter() {
+        if (char.is_ascii_alphanumeric() || *char == ' ') && query.0.len() < MAX_QUERY_LEN {
+            query.0.push(*char);
+        }
+    }
+}
@@ -4,7 +4,9 @@ use crate::{
     assets::SpriteAnchor, inventory::ItemStack, item::WorldObject, player::Player, GameParam,
 };
 
-use super::{damage_numbers::spawn_text, spawn_item_stack_icon, UIElement};
+use super::{
+    accessibility::LargeText, damage_numbers::spawn_text, spawn_item_stack_icon, UIElement,
+};
 
 #[derive(Component)]
 pub struct InteractGuide;
@@ -57,6 +59,14 @@ pub fn add_guide_to_unique_objs(
                     icon_stack: Some(ItemStack::crate_icon_stack(WorldObject::TimeFragment)),
                 });
             }
+            WorldObject::TimeGate => {
+                commands.entity(e).insert(InteractionGuideTrigger {
+                    key: Some("F".to_string()),
+                    text: Some("Travel".to_string()),
+                    activation_distance: 32.,
+                    icon_stack: Some(ItemStack::crate_icon_stack(WorldObject::TimeFragment)),
+                });
+            }
             _ => {}
         }
     }
@@ -128,7 +138,10 @@ pub fn spawn_shrine_interact_key_guide(
                     if let Some(key_e) = key_entity {
                         commands.entity(key_e).set_parent(text_e);
                     }
-                    commands.entity(text_e).set_parent(parent_entity);
+                    commands
+                        .entity(text_e)
+                        .insert(LargeText)
+                        .set_parent(parent_entity);
                 };
                 if let Some(icon_stack) = guide.icon_stack.clone() {
                     let icon = spawn_item_stack_icon(
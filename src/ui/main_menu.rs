@@ -3,7 +3,7 @@ use std::{
     process::exit,
 };
 
-use bevy::{prelude::*, render::view::RenderLayers, sprite::Anchor};
+use bevy::{prelude::*, render::view::RenderLayers};
 
 use crate::{
     ai::pathfinding::PathfindingCache,
@@ -16,6 +16,7 @@ use crate::{
     item::CraftingTracker,
     night::NightTracker,
     player::skills::{PlayerSkills, SkillChoiceQueue},
+    settings::{Setting, Settings},
     ui::{ChestContainer, FurnaceContainer},
     world::{
         dimension::{ActiveDimension, EraManager, GenerationSeed},
@@ -24,13 +25,14 @@ use crate::{
     DoNotDespawnOnGameOver, Game, GameState, ScreenResolution, DEBUG, GAME_HEIGHT, ZOOM_SCALE,
 };
 
-use super::{scrapper_ui::ScrapperEvent, Interactable, UIElement, UIState, OPTIONS_UI_SIZE};
+use super::{guide_hud::CurrentGoal, scrapper_ui::ScrapperEvent, Interactable, UIElement, UIState};
 
 #[derive(Component, Clone, Eq, PartialEq)]
 pub enum MenuButton {
     Start,
     Options,
     Quit,
+    WorldSize,
     InfoOK,
     GameOverOK,
     Scrapper,
@@ -114,6 +116,8 @@ pub fn handle_menu_button_click_events(
     night_tracker: Option<Res<NightTracker>>,
     seed: Option<Res<GenerationSeed>>,
     mut scrapper_event: EventWriter<ScrapperEvent>,
+    mut settings: ResMut<Settings>,
+    mut menu_texts: Query<(&MenuButton, &mut Text)>,
 ) {
     for event in event_reader.iter() {
         match event.button {
@@ -164,6 +168,18 @@ pub fn handle_menu_button_click_events(
                 info!("Quit button pressed, quitting!");
                 exit(0);
             }
+            MenuButton::WorldSize => {
+                if info_modal.iter().count() != 0 {
+                    continue;
+                }
+                Setting::WorldSize.cycle(&mut settings, true);
+                settings.save();
+                for (button, mut text) in menu_texts.iter_mut() {
+                    if button == &MenuButton::WorldSize {
+                        text.sections[0].value = get_world_size_label(&settings);
+                    }
+                }
+            }
             MenuButton::InfoOK => {
                 for e in info_modal.iter() {
                     commands.entity(e).despawn_recursive();
@@ -220,13 +236,22 @@ pub fn handle_menu_button_click_events(
                 commands.remove_resource::<NightTracker>();
                 commands.remove_resource::<ContainerRegistry>();
                 commands.remove_resource::<CraftingTracker>();
+                commands.remove_resource::<CurrentGoal>();
                 commands.remove_resource::<EraManager>();
                 commands.remove_resource::<WorldObjectCache>();
             }
         }
     }
 }
-pub fn spawn_menu_text_buttons(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn get_world_size_label(settings: &Settings) -> String {
+    format!("World: {}", settings.world_size.get_name())
+}
+
+pub fn spawn_menu_text_buttons(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
+) {
     // MENU TEXT BUTTONS
     commands.spawn((
         Text2dBundle {
@@ -322,6 +347,35 @@ pub fn spawn_menu_text_buttons(mut commands: Commands, asset_server: Res<AssetSe
             ..default()
         },
     ));
+
+    // picks the size of the world the next run generates
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                get_world_size_label(&settings),
+                TextStyle {
+                    font: asset_server.load("fonts/alagard.ttf"),
+                    font_size: 10.0,
+                    color: YELLOW_2,
+                },
+            ),
+            transform: Transform {
+                translation: Vec3::new(-39.5, -92., 1.),
+                scale: Vec3::new(1., 1., 1.),
+                ..Default::default()
+            },
+            ..default()
+        },
+        Name::new("MENU TEXT"),
+        RenderLayers::from_layers(&[3]),
+        Interactable::default(),
+        UIElement::MenuButton,
+        MenuButton::WorldSize,
+        Sprite {
+            custom_size: Some(Vec2::new(70., 9.)),
+            ..default()
+        },
+    ));
 }
 
 pub fn spawn_info_modal(
@@ -412,134 +466,6 @@ pub fn spawn_info_modal(
         .add_child(info_texts);
 }
 
-#[derive(Component)]
-pub struct OptionsUI;
-
-pub fn handle_enter_options_ui(
-    mut commands: Commands,
-    graphics: Res<Graphics>,
-    asset_server: Res<AssetServer>,
-    res: Res<ScreenResolution>,
-) {
-    let (size, texture, t_offset) = (
-        OPTIONS_UI_SIZE,
-        graphics.get_ui_element_texture(UIElement::Options),
-        Vec2::new(0., 0.),
-    );
-
-    let overlay = commands
-        .spawn(SpriteBundle {
-            sprite: Sprite {
-                color: Color::rgba(146. / 255., 116. / 255., 65. / 255., 0.3),
-                custom_size: Some(Vec2::new(res.game_width + 10., GAME_HEIGHT + 10.)),
-                ..default()
-            },
-            transform: Transform {
-                translation: Vec3::new(-t_offset.x, -t_offset.y, -1.),
-                scale: Vec3::new(1., 1., 1.),
-                ..Default::default()
-            },
-            ..default()
-        })
-        .insert(RenderLayers::from_layers(&[3]))
-        .insert(Name::new("overlay"))
-        .id();
-    let stats_e = commands
-        .spawn(SpriteBundle {
-            texture,
-            sprite: Sprite {
-                custom_size: Some(size),
-                ..Default::default()
-            },
-            transform: Transform {
-                translation: Vec3::new(t_offset.x, t_offset.y, 10.),
-                scale: Vec3::new(1., 1., 1.),
-                ..Default::default()
-            },
-            ..Default::default()
-        })
-        .insert(OptionsUI)
-        .insert(Name::new("STATS UI"))
-        .insert(UIState::Options)
-        .insert(RenderLayers::from_layers(&[3]))
-        .id();
-
-    for i in 0..4 {
-        let translation = Vec3::new(6., (-i as f32 * 21.) + 10., 1.);
-        let mut slot_entity = commands.spawn(SpriteBundle {
-            texture: graphics.get_ui_element_texture(UIElement::StatsButton),
-            transform: Transform {
-                translation,
-                scale: Vec3::new(1., 1., 1.),
-                ..Default::default()
-            },
-            sprite: Sprite {
-                custom_size: Some(Vec2::new(16., 16.)),
-                ..Default::default()
-            },
-            ..Default::default()
-        });
-        slot_entity
-            .set_parent(stats_e)
-            .insert(Interactable::default())
-            .insert(UIElement::StatsButton)
-            .insert(RenderLayers::from_layers(&[3]))
-            .insert(Name::new("STATS BUTTON"));
-
-        let mut text = commands.spawn((
-            Text2dBundle {
-                text: Text::from_section(
-                    "Update",
-                    TextStyle {
-                        font: asset_server.load("fonts/Kitchen Sink.ttf"),
-                        font_size: 8.0,
-                        color: BLACK,
-                    },
-                ),
-                text_anchor: Anchor::CenterLeft,
-                transform: Transform {
-                    translation: Vec3::new(23., (-i as f32 * 21.) + 10., 1.),
-                    scale: Vec3::new(1., 1., 1.),
-                    ..Default::default()
-                },
-                ..default()
-            },
-            Name::new("STATS TEXT"),
-            RenderLayers::from_layers(&[3]),
-        ));
-        text.set_parent(stats_e)
-            .insert(RenderLayers::from_layers(&[3]));
-    }
-
-    // sp remaining text
-    let mut sp_text = commands.spawn((
-        Text2dBundle {
-            text: Text::from_section(
-                "Update",
-                TextStyle {
-                    font: asset_server.load("fonts/Kitchen Sink.ttf"),
-                    font_size: 8.0,
-                    color: BLACK,
-                },
-            ),
-            text_anchor: Anchor::CenterLeft,
-            transform: Transform {
-                translation: Vec3::new(29., 43., 1.),
-                scale: Vec3::new(1., 1., 1.),
-                ..Default::default()
-            },
-            ..default()
-        },
-        Name::new("SP TEXT"),
-        RenderLayers::from_layers(&[3]),
-    ));
-    sp_text
-        .set_parent(stats_e)
-        .insert(RenderLayers::from_layers(&[3]));
-
-    commands.entity(stats_e).push_children(&[overlay]);
-}
-
 pub fn tick_game_start_overlay(
     mut commands: Commands,
     time: Res<Time>,
@@ -1,11 +1,23 @@
+pub mod accessibility;
 pub mod chest_ui;
+pub mod companion_wheel;
 pub mod crafting_ui;
 pub mod damage_numbers;
+pub mod era_select_ui;
+pub mod gamepad_focus;
 pub mod guide_hud;
+pub mod hotbar_wheel;
+pub mod inventory_actions;
+pub mod item_finder_ui;
+pub mod options_ui;
+pub mod recipe_book_ui;
 pub mod scrapper_ui;
 pub mod screen_effects;
+pub mod shop_ui;
 use guide_hud::*;
 pub mod ui_container_param;
+use accessibility::{apply_large_text, update_rarity_badges};
+use bevy::input::InputSystem;
 use bevy::sprite::Material2dPlugin;
 use damage_numbers::{handle_clamp_screen_locked_icons, NewRecipeTextTimer};
 use scrapper_ui::{
@@ -13,6 +25,12 @@ use scrapper_ui::{
     handle_scrap_items_in_scrapper, setup_scrapper_slots_ui, ScrapperContainer, ScrapperEvent,
 };
 use screen_effects::ScreenEffectMaterial;
+use shop_ui::{
+    add_price_tags_to_shop_slots, add_shop_to_new_merchants,
+    change_ui_state_to_shop_when_resource_added, handle_buy_shop_item, handle_sell_shop_item,
+    restock_merchants_on_new_day, setup_shop_slots_ui, BuyShopItemEvent, MerchantTradeEvent,
+    SellShopItemEvent, ShopContainer,
+};
 pub use ui_container_param::*;
 mod enemy_health_bar;
 mod fps_text;
@@ -29,6 +47,7 @@ pub mod stats_ui;
 mod tile_hover;
 mod tooltips;
 mod ui_helpers;
+pub mod world_map;
 pub use chest_ui::*;
 pub use enemy_health_bar::*;
 use fps_text::*;
@@ -50,19 +69,42 @@ use crate::{
 };
 
 use self::{
-    crafting_ui::{change_ui_state_to_crafting_when_resource_added, CraftingContainer},
+    companion_wheel::{despawn_companion_wheel_on_ui_open, handle_companion_wheel},
+    crafting_ui::{
+        change_ui_state_to_crafting_when_resource_added, spawn_crafting_queue_text,
+        update_crafting_queue_text, CraftingContainer,
+    },
     damage_numbers::{
         add_previous_health, handle_add_damage_numbers_after_hit, handle_add_dodge_text,
         tick_damage_numbers, DodgeEvent,
     },
+    era_select_ui::{
+        handle_travel_to_era, setup_era_select_ui, EraSelectChoices, TravelToEraEvent,
+    },
+    gamepad_focus::{
+        clear_gamepad_focus_on_ui_close, handle_gamepad_ui_navigation, update_gamepad_focus_cursor,
+        GamepadFocus,
+    },
+    hotbar_wheel::{despawn_hotbar_wheel_on_ui_open, handle_hotbar_wheel},
+    inventory_actions::{
+        handle_inventory_button_clicks, handle_lock_slot_input, spawn_inventory_buttons,
+        update_locked_slot_tint, update_sort_button_text, InventoryButtonClickEvent,
+    },
+    item_finder_ui::ItemFinderPlugin,
     minimap::MinimapPlugin,
+    options_ui::{
+        handle_enter_options_ui, handle_options_button_clicks, handle_rebind_input,
+        update_keybind_text, update_setting_text, OptionsButtonClickEvent, OptionsPage,
+        RebindingAction,
+    },
+    recipe_book_ui::RecipeBookPlugin,
     tile_hover::spawn_tile_hover_on_cursor_move,
+    world_map::{PinLabelInput, WorldMapPlugin},
 };
 
 pub const INVENTORY_UI_SIZE: Vec2 = Vec2::new(172., 145.);
 pub const STATS_UI_SIZE: Vec2 = Vec2::new(79., 104.);
 pub const SKILLS_CHOICE_UI_SIZE: Vec2 = Vec2::new(96., 120.);
-pub const OPTIONS_UI_SIZE: Vec2 = Vec2::new(79., 104.);
 pub const ESSENCE_UI_SIZE: Vec2 = Vec2::new(109., 151.);
 pub const TOOLTIP_UI_SIZE: Vec2 = Vec2::new(93., 120.5);
 pub const CHEST_INVENTORY_UI_SIZE: Vec2 = Vec2::new(127., 142.);
@@ -77,11 +119,17 @@ impl Plugin for UIPlugin {
         app.add_state::<UIState>()
             .insert_resource(InventoryState::default())
             .insert_resource(NewRecipeTextTimer::new(0.8))
+            .init_resource::<OptionsPage>()
+            .init_resource::<RebindingAction>()
+            .init_resource::<GamepadFocus>()
             .insert_resource(TooltipsManager {
                 timer: Timer::from_seconds(0.7, TimerMode::Once),
             })
             .add_event::<ActionSuccessEvent>()
             .add_event::<ScrapperEvent>()
+            .add_event::<BuyShopItemEvent>()
+            .add_event::<SellShopItemEvent>()
+            .add_event::<MerchantTradeEvent>()
             .add_event::<FlashExpBarEvent>()
             .add_event::<DropOnSlotEvent>()
             .add_event::<DodgeEvent>()
@@ -90,11 +138,17 @@ impl Plugin for UIPlugin {
             .add_event::<TooltipTeardownEvent>()
             .add_event::<ShowInvPlayerStatsEvent>()
             .add_event::<SubmitEssenceChoice>()
+            .add_event::<TravelToEraEvent>()
             .add_event::<DropInWorldEvent>()
             .add_event::<MenuButtonClickEvent>()
+            .add_event::<OptionsButtonClickEvent>()
+            .add_event::<InventoryButtonClickEvent>()
             .add_plugin(Material2dPlugin::<ScreenEffectMaterial>::default())
             .register_type::<InventorySlotState>()
             .add_plugin(MinimapPlugin)
+            .add_plugin(WorldMapPlugin)
+            .add_plugin(ItemFinderPlugin)
+            .add_plugin(RecipeBookPlugin)
             .add_system(spawn_fps_text.in_schedule(OnEnter(GameState::Main)))
             .add_systems((
                 setup_inv_ui
@@ -106,6 +160,9 @@ impl Plugin for UIPlugin {
                 setup_inv_ui
                     .before(CustomFlush)
                     .run_if(state_changed::<UIState>().and_then(in_state(UIState::Scrapper))),
+                setup_inv_ui
+                    .before(CustomFlush)
+                    .run_if(state_changed::<UIState>().and_then(in_state(UIState::Shop))),
                 setup_inv_ui
                     .before(CustomFlush)
                     .run_if(state_changed::<UIState>().and_then(in_state(UIState::Crafting))),
@@ -131,9 +188,32 @@ impl Plugin for UIPlugin {
                     handle_enemy_health_bar_change,
                     handle_enemy_health_visibility,
                     add_ui_icon_for_elite_mobs,
+                    add_affix_names_to_elite_mobs,
                     handle_add_damage_numbers_after_hit.after(handle_hits),
                     handle_add_dodge_text,
                     tick_damage_numbers,
+                    handle_companion_wheel,
+                    despawn_companion_wheel_on_ui_open,
+                    handle_hotbar_wheel,
+                    despawn_hotbar_wheel_on_ui_open,
+                )
+                    .in_set(OnUpdate(GameState::Main)),
+            )
+            .add_systems(
+                (apply_large_text, update_rarity_badges.after(CustomFlush))
+                    .in_set(OnUpdate(GameState::Main)),
+            )
+            .add_systems(
+                (
+                    spawn_inventory_buttons,
+                    handle_cursor_inventory_buttons.run_if(ui_inv_open_condition),
+                    handle_inventory_button_clicks.after(handle_cursor_inventory_buttons),
+                    update_sort_button_text,
+                    handle_lock_slot_input,
+                    update_locked_slot_tint.after(CustomFlush),
+                    spawn_crafting_queue_text,
+                    update_crafting_queue_text,
+                    resize_hotbar_hud.before(CustomFlush),
                 )
                     .in_set(OnUpdate(GameState::Main)),
             )
@@ -178,11 +258,37 @@ impl Plugin for UIPlugin {
                     .in_set(OnUpdate(GameState::Main)),
             )
             .add_system(handle_spawn_inv_player_stats.in_base_set(CoreSet::PostUpdate))
+            .add_systems(
+                (
+                    handle_enter_options_ui
+                        .before(CustomFlush)
+                        .run_if(state_changed::<UIState>().and_then(in_state(UIState::Options))),
+                    handle_cursor_options_buttons.run_if(in_state(UIState::Options)),
+                    handle_options_button_clicks.after(handle_cursor_options_buttons),
+                    update_keybind_text.after(handle_options_button_clicks),
+                    update_setting_text.after(handle_options_button_clicks),
+                )
+                    .in_set(OnUpdate(GameState::Main)),
+            )
+            // picks up the new key before any gameplay system can react to it
             .add_system(
-                handle_enter_options_ui
-                    .before(CustomFlush)
-                    .run_if(state_changed::<UIState>().and_then(in_state(UIState::Options))),
+                handle_rebind_input
+                    .in_base_set(CoreSet::PreUpdate)
+                    .after(InputSystem)
+                    .run_if(in_state(UIState::Options)),
+            )
+            // turns gamepad presses into mouse clicks before the UI reads them
+            .add_system(
+                handle_gamepad_ui_navigation
+                    .in_base_set(CoreSet::PreUpdate)
+                    .after(InputSystem)
+                    .after(handle_rebind_input)
+                    .run_if(not(in_state(UIState::Closed))),
             )
+            .add_systems((
+                clear_gamepad_focus_on_ui_close,
+                update_gamepad_focus_cursor.after(clear_gamepad_focus_on_ui_close),
+            ))
             .add_systems(
                 (
                     setup_inv_slots_ui,
@@ -219,11 +325,32 @@ impl Plugin for UIPlugin {
                 )
                     .in_set(OnUpdate(GameState::Main)),
             )
+            .add_systems(
+                (
+                    change_ui_state_to_shop_when_resource_added
+                        .before(CustomFlush)
+                        .run_if(resource_added::<ShopContainer>()),
+                    setup_shop_slots_ui.run_if(in_state(UIState::Shop)),
+                    add_price_tags_to_shop_slots,
+                    add_shop_to_new_merchants,
+                    restock_merchants_on_new_day,
+                    handle_buy_shop_item.run_if(in_state(UIState::Shop)),
+                    handle_sell_shop_item.run_if(in_state(UIState::Shop)),
+                    setup_era_select_ui
+                        .before(CustomFlush)
+                        .run_if(resource_added::<EraSelectChoices>()),
+                    handle_cursor_era_buttons.run_if(in_state(UIState::EraSelect)),
+                    handle_travel_to_era,
+                )
+                    .in_set(OnUpdate(GameState::Main)),
+            )
             .add_system(
                 handle_new_ui_state.in_base_set(CoreSet::PostUpdate), // .run_if(in_state(GameState::Main)),
             )
             .add_system(init_starting_goal.in_schedule(OnEnter(GameState::Main)))
-            .add_system(handle_display_new_goal.run_if(resource_added::<CurrentGoal>()))
+            .add_system(
+                handle_display_new_goal.run_if(resource_exists_and_changed::<CurrentGoal>()),
+            )
             .add_system(handle_update_goal_progress.run_if(resource_exists::<CurrentGoal>()))
             .add_system(handle_hovering.run_if(ui_hover_interactions_condition))
             .add_system(handle_cursor_main_menu_buttons)
@@ -231,10 +358,19 @@ impl Plugin for UIPlugin {
     }
 }
 
+fn ui_inv_open_condition(ui_state: Res<State<UIState>>) -> bool {
+    ui_state.0.is_inv_open()
+}
+
 fn ui_hover_interactions_condition(state: Res<State<GameState>>) -> bool {
     state.0 == GameState::Main || state.0 == GameState::MainMenu
 }
 
+/// Typing a map pin label or an item search holds down keys that would otherwise move the player
+pub fn is_typing(ui_state: &UIState, pin_input: &PinLabelInput) -> bool {
+    *ui_state == UIState::ItemFinder || pin_input.0.is_some()
+}
+
 pub fn handle_new_ui_state(
     mut next_ui_state: ResMut<NextState<UIState>>,
     mut next_client_state: ResMut<NextState<ClientState>>,
@@ -243,6 +379,7 @@ pub fn handle_new_ui_state(
     mut commands: Commands,
     chest_option: Option<Res<ChestContainer>>,
     scrapper_option: Option<Res<ScrapperContainer>>,
+    shop_option: Option<Res<ShopContainer>>,
     furnace_option: Option<Res<FurnaceContainer>>,
     mut hotbar_slots: Query<(&mut Visibility, &mut InventorySlotState), Without<Interactable>>,
 ) {
@@ -285,6 +422,14 @@ pub fn handle_new_ui_state(
             commands.remove_resource::<ScrapperContainer>();
         }
     }
+    if let Some(shop) = shop_option {
+        if let Some(mut shop_parent) = commands.get_entity(shop.parent) {
+            shop_parent.insert(shop.to_owned());
+        }
+        if next_ui != UIState::Shop {
+            commands.remove_resource::<ShopContainer>();
+        }
+    }
     if let Some(furnace) = furnace_option {
         if let Some(mut furnace_parent) = commands.get_entity(furnace.parent) {
             furnace_parent.insert(furnace.to_owned());
@@ -299,6 +444,9 @@ pub fn handle_new_ui_state(
     if next_ui != UIState::Essence {
         commands.remove_resource::<EssenceShopChoices>();
     }
+    if next_ui != UIState::EraSelect {
+        commands.remove_resource::<EraSelectChoices>();
+    }
     if let Some(next_ui) = &next_ui_state.0 {
         for (mut hbv, mut state) in hotbar_slots.iter_mut() {
             if !next_ui.is_inv_open() {
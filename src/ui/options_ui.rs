@@ -0,0 +1,462 @@
+use bevy::{prelude::*, render::view::RenderLayers, sprite::Anchor};
+
+use crate::{
+    assets::Graphics,
+    colors::{BLACK, RED},
+    keymap::{InputAction, InputBinding, Keymap},
+    settings::{Setting, Settings},
+    ScreenResolution, GAME_HEIGHT,
+};
+
+use super::{damage_numbers::spawn_text, Interactable, UIElement, UIState, ESSENCE_UI_SIZE};
+
+const ROWS_PER_PAGE: usize = 11;
+const CONTROL_ROW_HEIGHT: f32 = 9.;
+const MAX_BINDING_LABEL_LEN: usize = 9;
+
+#[derive(Component)]
+pub struct OptionsUI;
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionsButton {
+    Setting(Setting),
+    Keybind(InputAction),
+    PrevPage,
+    NextPage,
+    /// Resets whatever the current page shows, the settings or the controls
+    Reset,
+}
+
+pub struct OptionsButtonClickEvent {
+    pub button: OptionsButton,
+    /// Right clicking a setting steps it back instead of forward
+    pub right_click: bool,
+}
+
+#[derive(Component)]
+pub struct KeybindText(pub InputAction);
+
+#[derive(Component)]
+pub struct SettingText(pub Setting);
+
+/// The settings come first, followed by the controls
+#[derive(Resource, Default, Debug)]
+pub struct OptionsPage(pub usize);
+
+/// Set while the Options menu is waiting for the new key of an action
+#[derive(Resource, Default, Debug)]
+pub struct RebindingAction(pub Option<InputAction>);
+
+pub fn handle_enter_options_ui(
+    mut commands: Commands,
+    graphics: Res<Graphics>,
+    asset_server: Res<AssetServer>,
+    res: Res<ScreenResolution>,
+    keymap: Res<Keymap>,
+    settings: Res<Settings>,
+    gamepads: Res<Gamepads>,
+    mut page: ResMut<OptionsPage>,
+    mut rebinding: ResMut<RebindingAction>,
+) {
+    page.0 = 0;
+    rebinding.0 = None;
+    spawn_options_ui(
+        &mut commands,
+        &graphics,
+        &asset_server,
+        &res,
+        &keymap,
+        &settings,
+        is_gamepad_connected(&gamepads),
+        0,
+    );
+}
+
+fn get_num_pages_for(num_rows: usize) -> usize {
+    (num_rows + ROWS_PER_PAGE - 1) / ROWS_PER_PAGE
+}
+
+fn get_num_settings_pages() -> usize {
+    get_num_pages_for(Setting::all().len())
+}
+
+fn get_num_pages() -> usize {
+    get_num_settings_pages() + get_num_pages_for(InputAction::all().len())
+}
+
+fn spawn_options_ui(
+    commands: &mut Commands,
+    graphics: &Graphics,
+    asset_server: &AssetServer,
+    res: &ScreenResolution,
+    keymap: &Keymap,
+    settings: &Settings,
+    gamepad: bool,
+    page: usize,
+) {
+    let (size, texture, t_offset) = (
+        ESSENCE_UI_SIZE,
+        graphics.get_ui_element_texture(UIElement::Essence),
+        Vec2::new(3.5, 3.5),
+    );
+
+    let overlay = commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(146. / 255., 116. / 255., 65. / 255., 0.3),
+                custom_size: Some(Vec2::new(res.game_width + 10., GAME_HEIGHT + 10.)),
+                ..default()
+            },
+            transform: Transform {
+                translation: Vec3::new(-t_offset.x, -t_offset.y, -1.),
+                scale: Vec3::new(1., 1., 1.),
+                ..Default::default()
+            },
+            ..default()
+        })
+        .insert(RenderLayers::from_layers(&[3]))
+        .insert(Name::new("overlay"))
+        .id();
+    let options_e = commands
+        .spawn(SpriteBundle {
+            texture,
+            sprite: Sprite {
+                custom_size: Some(size),
+                ..Default::default()
+            },
+            transform: Transform {
+                translation: Vec3::new(t_offset.x, t_offset.y, 10.),
+                scale: Vec3::new(1., 1., 1.),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(OptionsUI)
+        .insert(Name::new("OPTIONS UI"))
+        .insert(UIState::Options)
+        .insert(RenderLayers::from_layers(&[3]))
+        .id();
+
+    let settings_pages = get_num_settings_pages();
+    let title = spawn_text(
+        commands,
+        asset_server,
+        Vec3::new(0., 62., 1.),
+        BLACK,
+        if page < settings_pages {
+            "Settings"
+        } else if gamepad {
+            "Gamepad Controls"
+        } else {
+            "Controls"
+        }
+        .to_string(),
+        Anchor::Center,
+        1.,
+        3,
+    );
+    commands.entity(title).set_parent(options_e);
+
+    if page < settings_pages {
+        for (i, setting) in Setting::all()
+            .into_iter()
+            .skip(page * ROWS_PER_PAGE)
+            .take(ROWS_PER_PAGE)
+            .enumerate()
+        {
+            let value_text = spawn_options_row(
+                commands,
+                asset_server,
+                options_e,
+                i,
+                OptionsButton::Setting(setting),
+                setting.get_name(),
+                setting.get_value_label(settings),
+            );
+            commands.entity(value_text).insert(SettingText(setting));
+        }
+    } else {
+        for (i, action) in InputAction::all()
+            .into_iter()
+            .skip((page - settings_pages) * ROWS_PER_PAGE)
+            .take(ROWS_PER_PAGE)
+            .enumerate()
+        {
+            let binding_text = spawn_options_row(
+                commands,
+                asset_server,
+                options_e,
+                i,
+                OptionsButton::Keybind(action),
+                action.get_name(),
+                keymap.get_binding_label(action, gamepad, MAX_BINDING_LABEL_LEN),
+            );
+            commands.entity(binding_text).insert(KeybindText(action));
+        }
+    }
+
+    let footer_y = -62.;
+    for (button, label, x) in [
+        (OptionsButton::PrevPage, "<", -44.),
+        (OptionsButton::NextPage, ">", -20.),
+        (OptionsButton::Reset, "Reset", 32.),
+    ] {
+        let width = label.len() as f32 * 5. + 4.;
+        let button_e = spawn_options_button(
+            commands,
+            Vec3::new(x, footer_y, 1.),
+            Vec2::new(width, CONTROL_ROW_HEIGHT),
+            button,
+        );
+        let text = spawn_text(
+            commands,
+            asset_server,
+            Vec3::new(0., 0., 1.),
+            BLACK,
+            label.to_string(),
+            Anchor::Center,
+            1.,
+            3,
+        );
+        commands
+            .entity(button_e)
+            .set_parent(options_e)
+            .push_children(&[text]);
+    }
+    let page_text = spawn_text(
+        commands,
+        asset_server,
+        Vec3::new(-32., footer_y, 1.),
+        BLACK,
+        format!("{}/{}", page + 1, get_num_pages()),
+        Anchor::Center,
+        1.,
+        3,
+    );
+    commands.entity(page_text).set_parent(options_e);
+
+    commands.entity(options_e).push_children(&[overlay]);
+}
+
+/// Spawns a clickable row with a name on the left and a value on the right, returning
+/// the value text so it can be kept up to date
+fn spawn_options_row(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    options_e: Entity,
+    i: usize,
+    button: OptionsButton,
+    name: String,
+    value: String,
+) -> Entity {
+    let row_e = spawn_options_button(
+        commands,
+        Vec3::new(0., 48. - i as f32 * CONTROL_ROW_HEIGHT, 1.),
+        Vec2::new(100., CONTROL_ROW_HEIGHT - 1.),
+        button,
+    );
+    commands.entity(row_e).set_parent(options_e);
+    let name_text = spawn_text(
+        commands,
+        asset_server,
+        Vec3::new(-48., 0., 1.),
+        BLACK,
+        name,
+        Anchor::CenterLeft,
+        1.,
+        3,
+    );
+    let value_text = spawn_text(
+        commands,
+        asset_server,
+        Vec3::new(48., 0., 1.),
+        BLACK,
+        value,
+        Anchor::CenterRight,
+        1.,
+        3,
+    );
+    commands
+        .entity(row_e)
+        .push_children(&[name_text, value_text]);
+    value_text
+}
+
+fn spawn_options_button(
+    commands: &mut Commands,
+    translation: Vec3,
+    size: Vec2,
+    button: OptionsButton,
+) -> Entity {
+    commands
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::NONE,
+                    custom_size: Some(size),
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(translation),
+                ..Default::default()
+            },
+            Interactable::default(),
+            button,
+            RenderLayers::from_layers(&[3]),
+            Name::new("Options Button"),
+        ))
+        .id()
+}
+
+pub fn handle_options_button_clicks(
+    mut commands: Commands,
+    mut events: EventReader<OptionsButtonClickEvent>,
+    graphics: Res<Graphics>,
+    asset_server: Res<AssetServer>,
+    res: Res<ScreenResolution>,
+    mut keymap: ResMut<Keymap>,
+    mut settings: ResMut<Settings>,
+    gamepads: Res<Gamepads>,
+    mut page: ResMut<OptionsPage>,
+    mut rebinding: ResMut<RebindingAction>,
+    options_ui: Query<Entity, With<OptionsUI>>,
+) {
+    for OptionsButtonClickEvent {
+        button,
+        right_click,
+    } in events.iter()
+    {
+        let num_pages = get_num_pages();
+        match button {
+            OptionsButton::Setting(setting) => {
+                setting.cycle(&mut settings, !right_click);
+                settings.save();
+                continue;
+            }
+            OptionsButton::Keybind(_) if *right_click => continue,
+            OptionsButton::Keybind(action) => {
+                rebinding.0 = Some(*action);
+                continue;
+            }
+            OptionsButton::PrevPage => page.0 = (page.0 + num_pages - 1) % num_pages,
+            OptionsButton::NextPage => page.0 = (page.0 + 1) % num_pages,
+            OptionsButton::Reset if page.0 < get_num_settings_pages() => {
+                *settings = Settings::default();
+                settings.save();
+            }
+            OptionsButton::Reset => {
+                *keymap = Keymap::default();
+                keymap.save();
+            }
+        }
+        rebinding.0 = None;
+        for e in options_ui.iter() {
+            commands.entity(e).despawn_recursive();
+        }
+        spawn_options_ui(
+            &mut commands,
+            &graphics,
+            &asset_server,
+            &res,
+            &keymap,
+            &settings,
+            is_gamepad_connected(&gamepads),
+            page.0,
+        );
+    }
+}
+
+fn is_gamepad_connected(gamepads: &Gamepads) -> bool {
+    gamepads.iter().next().is_some()
+}
+
+/// Runs before the rest of the game sees the input, so the key pressed to rebind an
+/// action doesn't also trigger it. Escape or Start cancels the rebind.
+pub fn handle_rebind_input(
+    mut key_input: ResMut<Input<KeyCode>>,
+    mut mouse_input: ResMut<Input<MouseButton>>,
+    mut pad_input: ResMut<Input<GamepadButton>>,
+    mut keymap: ResMut<Keymap>,
+    mut rebinding: ResMut<RebindingAction>,
+) {
+    let Some(action) = rebinding.0 else {
+        return;
+    };
+    let pad_cancel = pad_input
+        .get_just_pressed()
+        .find(|b| b.button_type == GamepadButtonType::Start)
+        .copied();
+    if key_input.just_pressed(KeyCode::Escape) || pad_cancel.is_some() {
+        key_input.clear_just_pressed(KeyCode::Escape);
+        if let Some(button) = pad_cancel {
+            pad_input.clear_just_pressed(button);
+        }
+        rebinding.0 = None;
+        return;
+    }
+    let pressed = key_input
+        .get_just_pressed()
+        .map(|key| InputBinding::Key(*key))
+        .chain(
+            mouse_input
+                .get_just_pressed()
+                .map(|button| InputBinding::Mouse(*button)),
+        )
+        .chain(
+            pad_input
+                .get_just_pressed()
+                .map(|button| InputBinding::Gamepad(button.button_type)),
+        )
+        .find(|binding| binding.is_bindable());
+    let Some(binding) = pressed else {
+        return;
+    };
+    match binding {
+        InputBinding::Key(key) => key_input.clear_just_pressed(key),
+        InputBinding::Mouse(button) => mouse_input.clear_just_pressed(button),
+        InputBinding::Gamepad(button_type) => {
+            let pressed: Vec<GamepadButton> = pad_input
+                .get_just_pressed()
+                .filter(|b| b.button_type == button_type)
+                .copied()
+                .collect();
+            for button in pressed {
+                pad_input.clear_just_pressed(button);
+            }
+            true
+        }
+    };
+    keymap.rebind(action, binding);
+    keymap.save();
+    rebinding.0 = None;
+}
+
+pub fn update_keybind_text(
+    keymap: Res<Keymap>,
+    gamepads: Res<Gamepads>,
+    rebinding: Res<RebindingAction>,
+    mut texts: Query<(&KeybindText, &mut Text)>,
+) {
+    if !keymap.is_changed() && !rebinding.is_changed() && !gamepads.is_changed() {
+        return;
+    }
+    let gamepad = is_gamepad_connected(&gamepads);
+    for (KeybindText(action), mut text) in texts.iter_mut() {
+        if rebinding.0 == Some(*action) {
+            text.sections[0].value = if gamepad { "Press btn" } else { "Press key" }.to_string();
+            text.sections[0].style.color = RED;
+        } else {
+            text.sections[0].value =
+                keymap.get_binding_label(*action, gamepad, MAX_BINDING_LABEL_LEN);
+            text.sections[0].style.color = BLACK;
+        }
+    }
+}
+
+pub fn update_setting_text(settings: Res<Settings>, mut texts: Query<(&SettingText, &mut Text)>) {
+    if !settings.is_changed() {
+        return;
+    }
+    for (SettingText(setting), mut text) in texts.iter_mut() {
+        text.sections[0].value = setting.get_value_label(&settings);
+    }
+}
@@ -1,8 +1,8 @@
 use bevy::{prelude::*, render::view::RenderLayers, sprite::Anchor};
 
 use super::{
-    interactions::Interaction, spawn_inv_slot, spawn_item_stack_icon, InventorySlotType,
-    InventoryState, InventoryUI, UIElement, UIState,
+    accessibility::LargeText, interactions::Interaction, spawn_inv_slot, spawn_item_stack_icon,
+    InventorySlotState, InventorySlotType, InventoryState, InventoryUI, UIElement, UIState,
 };
 use crate::{
     assets::Graphics,
@@ -16,6 +16,7 @@ use crate::{
         skills::{PlayerSkills, Skill},
         Player, TimeFragmentCurrency,
     },
+    settings::Settings,
     ScreenResolution, GAME_HEIGHT,
 };
 use bevy::utils::Duration;
@@ -215,6 +216,7 @@ pub fn setup_xp_bar_ui(
             },
             Name::new("XP TEXT"),
             XPBarText,
+            LargeText,
             RenderLayers::from_layers(&[3]),
         ))
         .id();
@@ -251,6 +253,7 @@ pub fn setup_currency_ui(
             },
             Name::new("TIME FRAGMENTS TEXT"),
             CurrencyText,
+            LargeText,
             RenderLayers::from_layers(&[3]),
         ))
         .id();
@@ -458,28 +461,89 @@ pub fn setup_hotbar_hud(
     mut commands: Commands,
     graphics: Res<Graphics>,
     inv_query: Query<Entity, With<InventoryUI>>,
-    inv_state: Res<InventoryState>,
+    mut inv_state: ResMut<InventoryState>,
     asset_server: Res<AssetServer>,
-    mut inv: Query<&mut Inventory>,
+    inv: Query<&Inventory>,
     inv_ui_state: Res<State<UIState>>,
+    settings: Res<Settings>,
 ) {
-    for (slot_index, item) in inv.single_mut().items.items.iter().enumerate() {
-        // hotbar slots
-        if slot_index <= 5 {
-            spawn_inv_slot(
-                &mut commands,
-                &inv_ui_state,
-                &graphics,
-                slot_index,
-                Interaction::None,
-                &inv_state,
-                &inv_query,
-                &asset_server,
-                InventorySlotType::Hotbar,
-                item.clone(),
-            );
+    inv_state.hotbar_size = settings.get_hotbar_size();
+    spawn_hotbar_slots(
+        &mut commands,
+        &graphics,
+        &inv_query,
+        &inv_state,
+        &asset_server,
+        inv.single(),
+        &inv_ui_state,
+    );
+}
+
+/// Respawns the hotbar when its size is changed in the Options menu
+pub fn resize_hotbar_hud(
+    mut commands: Commands,
+    graphics: Res<Graphics>,
+    inv_query: Query<Entity, With<InventoryUI>>,
+    mut inv_state: ResMut<InventoryState>,
+    asset_server: Res<AssetServer>,
+    inv: Query<&Inventory>,
+    inv_ui_state: Res<State<UIState>>,
+    settings: Res<Settings>,
+    slots: Query<(Entity, &InventorySlotState)>,
+) {
+    let hotbar_size = settings.get_hotbar_size();
+    if inv_state.hotbar_size == hotbar_size {
+        return;
+    }
+    for (e, state) in slots.iter() {
+        if state.r#type.is_hotbar() {
+            commands.entity(e).despawn_recursive();
         }
     }
+    inv_state.hotbar_size = hotbar_size;
+    if inv_state.active_hotbar_slot >= hotbar_size {
+        inv_state.active_hotbar_slot = 0;
+    }
+    spawn_hotbar_slots(
+        &mut commands,
+        &graphics,
+        &inv_query,
+        &inv_state,
+        &asset_server,
+        inv.single(),
+        &inv_ui_state,
+    );
+}
+
+fn spawn_hotbar_slots(
+    commands: &mut Commands,
+    graphics: &Graphics,
+    inv_query: &Query<Entity, With<InventoryUI>>,
+    inv_state: &InventoryState,
+    asset_server: &AssetServer,
+    inv: &Inventory,
+    inv_ui_state: &Res<State<UIState>>,
+) {
+    for (slot_index, item) in inv
+        .items
+        .items
+        .iter()
+        .enumerate()
+        .take(inv_state.hotbar_size)
+    {
+        spawn_inv_slot(
+            commands,
+            inv_ui_state,
+            graphics,
+            slot_index,
+            Interaction::None,
+            inv_state,
+            inv_query,
+            asset_server,
+            InventorySlotType::Hotbar,
+            item.clone(),
+        );
+    }
 }
 
 pub fn update_mana_bar(
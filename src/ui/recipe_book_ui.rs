@@ -0,0 +1,455 @@
+use bevy::{ecs::system::SystemParam, prelude::*, render::view::RenderLayers, sprite::Anchor};
+
+use crate::{
+    assets::Graphics,
+    colors::{BLACK, DARK_GREEN, RED},
+    inventory::Inventory,
+    item::{CraftingTracker, Recipes, WorldObject},
+    keymap::{ActionInput, InputAction},
+    proto::proto_param::ProtoParam,
+    CustomFlush, GameState, ScreenResolution, GAME_HEIGHT,
+};
+
+use super::{
+    crafting_ui::CraftingContainerType,
+    damage_numbers::spawn_text,
+    guide_hud::{CurrentGoal, GoalIcons},
+    interactions::handle_cursor_recipe_book_buttons,
+    Interactable, UIElement, UIState, ESSENCE_UI_SIZE,
+};
+
+const RECIPES_PER_PAGE: usize = 6;
+const RECIPE_ROW_HEIGHT: f32 = 9.;
+const MAX_RECIPE_NAME_LEN: usize = 12;
+
+#[derive(Component)]
+pub struct RecipeBookUI;
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecipeBookButton {
+    Recipe(WorldObject),
+    PrevPage,
+    NextPage,
+    Pin,
+}
+
+pub struct RecipeBookButtonClickEvent {
+    pub button: RecipeBookButton,
+    /// Right clicking a recipe pins it straight away
+    pub right_click: bool,
+}
+
+/// The page the book is open on and the recipe whose ingredients are shown
+#[derive(Resource, Default, Debug)]
+pub struct RecipeBookState {
+    pub page: usize,
+    pub selected: Option<WorldObject>,
+}
+
+#[derive(SystemParam)]
+pub struct RecipeBookParam<'w, 's> {
+    pub state: ResMut<'w, RecipeBookState>,
+    pub tracker: Res<'w, CraftingTracker>,
+    pub recipes: Res<'w, Recipes>,
+    pub proto: ProtoParam<'w, 's>,
+    pub inv: Query<'w, 's, &'static Inventory>,
+}
+
+impl<'w, 's> RecipeBookParam<'w, 's> {
+    fn get_item_name(&self, obj: WorldObject) -> String {
+        self.proto
+            .get_item_data(obj)
+            .map(|stack| stack.metadata.name.clone())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| obj.to_string())
+    }
+    fn get_item_count(&self, obj: WorldObject) -> usize {
+        self.inv
+            .get_single()
+            .map(|inv| inv.items.get_item_count_in_container(obj))
+            .unwrap_or_default()
+    }
+    /// Every discovered recipe, split by where it is crafted and then into pages
+    fn get_pages(&self) -> Vec<(CraftingContainerType, Vec<WorldObject>)> {
+        let mut pages = vec![];
+        for crafting_type in CraftingContainerType::all() {
+            let Some(recipes) = self.tracker.crafting_type_map.get(&crafting_type) else {
+                continue;
+            };
+            let mut recipes = recipes.clone();
+            recipes.sort_by_key(|obj| self.get_item_name(*obj));
+            for page in recipes.chunks(RECIPES_PER_PAGE) {
+                pages.push((crafting_type.clone(), page.to_vec()));
+            }
+        }
+        pages
+    }
+}
+
+pub struct RecipeBookPlugin;
+
+impl Plugin for RecipeBookPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RecipeBookState>()
+            .add_event::<RecipeBookButtonClickEvent>()
+            .add_systems(
+                (
+                    toggle_recipe_book,
+                    handle_enter_recipe_book_ui
+                        .before(CustomFlush)
+                        .run_if(state_changed::<UIState>().and_then(in_state(UIState::RecipeBook))),
+                    handle_cursor_recipe_book_buttons.run_if(in_state(UIState::RecipeBook)),
+                    handle_recipe_book_button_clicks.after(handle_cursor_recipe_book_buttons),
+                )
+                    .in_set(OnUpdate(GameState::Main)),
+            );
+    }
+}
+
+fn toggle_recipe_book(
+    actions: ActionInput,
+    curr_ui_state: Res<State<UIState>>,
+    mut next_ui_state: ResMut<NextState<UIState>>,
+) {
+    if !actions.just_pressed(InputAction::RecipeBook) {
+        return;
+    }
+    // setting the same state again closes the book
+    if curr_ui_state.0 == UIState::Closed || curr_ui_state.0 == UIState::RecipeBook {
+        next_ui_state.set(UIState::RecipeBook);
+    }
+}
+
+/// Opens on the page of the pinned recipe, if there is one
+fn handle_enter_recipe_book_ui(
+    mut commands: Commands,
+    graphics: Res<Graphics>,
+    asset_server: Res<AssetServer>,
+    res: Res<ScreenResolution>,
+    curr_goal: Option<Res<CurrentGoal>>,
+    mut book: RecipeBookParam,
+) {
+    let pinned = curr_goal.map(|goal| goal.goal);
+    let page = pinned.and_then(|pinned| {
+        book.get_pages()
+            .iter()
+            .position(|(_, recipes)| recipes.contains(&pinned))
+    });
+    book.state.page = page.unwrap_or_default();
+    book.state.selected = pinned;
+    spawn_recipe_book_ui(&mut commands, &graphics, &asset_server, &res, &book, pinned);
+}
+
+fn spawn_recipe_book_ui(
+    commands: &mut Commands,
+    graphics: &Graphics,
+    asset_server: &AssetServer,
+    res: &ScreenResolution,
+    book: &RecipeBookParam,
+    pinned: Option<WorldObject>,
+) {
+    let (size, texture, t_offset) = (
+        ESSENCE_UI_SIZE,
+        graphics.get_ui_element_texture(UIElement::Essence),
+        Vec2::new(3.5, 3.5),
+    );
+
+    let overlay = commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(146. / 255., 116. / 255., 65. / 255., 0.3),
+                custom_size: Some(Vec2::new(res.game_width + 10., GAME_HEIGHT + 10.)),
+                ..default()
+            },
+            transform: Transform {
+                translation: Vec3::new(-t_offset.x, -t_offset.y, -1.),
+                scale: Vec3::new(1., 1., 1.),
+                ..Default::default()
+            },
+            ..default()
+        })
+        .insert(RenderLayers::from_layers(&[3]))
+        .insert(Name::new("overlay"))
+        .id();
+    let book_e = commands
+        .spawn(SpriteBundle {
+            texture,
+            sprite: Sprite {
+                custom_size: Some(size),
+                ..Default::default()
+            },
+            transform: Transform {
+                translation: Vec3::new(t_offset.x, t_offset.y, 10.),
+                scale: Vec3::new(1., 1., 1.),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(RecipeBookUI)
+        .insert(Name::new("RECIPE BOOK UI"))
+        .insert(UIState::RecipeBook)
+        .insert(RenderLayers::from_layers(&[3]))
+        .id();
+
+    let pages = book.get_pages();
+    let page = pages.get(book.state.page);
+    let title = spawn_text(
+        commands,
+        asset_server,
+        Vec3::new(0., 62., 1.),
+        BLACK,
+        page.map(|(crafting_type, _)| crafting_type.get_name())
+            .unwrap_or_else(|| "Recipes".to_string()),
+        Anchor::Center,
+        1.,
+        3,
+    );
+    commands.entity(title).set_parent(book_e);
+
+    if let Some((_, recipes)) = page {
+        for (i, obj) in recipes.iter().enumerate() {
+            let marker = if book.state.selected == Some(*obj) {
+                "> "
+            } else {
+                ""
+            };
+            let (status, status_color) = if pinned == Some(*obj) {
+                ("Pinned", BLACK)
+            } else if book.tracker.craftable.contains(obj) {
+                ("Ready", DARK_GREEN)
+            } else {
+                ("", BLACK)
+            };
+            spawn_recipe_book_row(
+                commands,
+                asset_server,
+                book_e,
+                48. - i as f32 * RECIPE_ROW_HEIGHT,
+                Some(RecipeBookButton::Recipe(*obj)),
+                (format!("{marker}{}", book.get_item_name(*obj)), BLACK),
+                (status.to_string(), status_color),
+            );
+        }
+    } else {
+        let empty_text = spawn_text(
+            commands,
+            asset_server,
+            Vec3::new(0., 30., 1.),
+            BLACK,
+            "No recipes yet".to_string(),
+            Anchor::Center,
+            1.,
+            3,
+        );
+        commands.entity(empty_text).set_parent(book_e);
+    }
+
+    // what the selected recipe needs, and how much of it the player is carrying
+    if let Some((ingredients, _, _)) = book
+        .state
+        .selected
+        .and_then(|selected| book.recipes.crafting_list.get(&selected))
+    {
+        for (i, ingredient) in ingredients.iter().enumerate() {
+            let have = book.get_item_count(ingredient.item);
+            spawn_recipe_book_row(
+                commands,
+                asset_server,
+                book_e,
+                -10. - i as f32 * RECIPE_ROW_HEIGHT,
+                None,
+                (book.get_item_name(ingredient.item), BLACK),
+                (
+                    format!("{have}/{}", ingredient.count),
+                    if have >= ingredient.count {
+                        DARK_GREEN
+                    } else {
+                        RED
+                    },
+                ),
+            );
+        }
+    }
+
+    let footer_y = -62.;
+    let mut footer_buttons = vec![
+        (RecipeBookButton::PrevPage, "<", -44.),
+        (RecipeBookButton::NextPage, ">", -20.),
+    ];
+    if let Some(selected) = book.state.selected {
+        footer_buttons.push((
+            RecipeBookButton::Pin,
+            if pinned == Some(selected) {
+                "Unpin"
+            } else {
+                "Pin"
+            },
+            32.,
+        ));
+    }
+    for (button, label, x) in footer_buttons {
+        let width = label.len() as f32 * 5. + 4.;
+        let button_e = spawn_recipe_book_button(
+            commands,
+            Vec3::new(x, footer_y, 1.),
+            Vec2::new(width, RECIPE_ROW_HEIGHT),
+            button,
+        );
+        let text = spawn_text(
+            commands,
+            asset_server,
+            Vec3::new(0., 0., 1.),
+            BLACK,
+            label.to_string(),
+            Anchor::Center,
+            1.,
+            3,
+        );
+        commands
+            .entity(button_e)
+            .set_parent(book_e)
+            .push_children(&[text]);
+    }
+    let page_text = spawn_text(
+        commands,
+        asset_server,
+        Vec3::new(-32., footer_y, 1.),
+        BLACK,
+        format!("{}/{}", book.state.page + 1, pages.len().max(1)),
+        Anchor::Center,
+        1.,
+        3,
+    );
+    commands.entity(page_text).set_parent(book_e);
+
+    commands.entity(book_e).push_children(&[overlay]);
+}
+
+/// Spawns a row with a name on the left and a value on the right, clickable if it
+/// has a button
+fn spawn_recipe_book_row(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    book_e: Entity,
+    y: f32,
+    button: Option<RecipeBookButton>,
+    (name, name_color): (String, Color),
+    (value, value_color): (String, Color),
+) {
+    let translation = Vec3::new(0., y, 1.);
+    let size = Vec2::new(100., RECIPE_ROW_HEIGHT - 1.);
+    let row_e = match button {
+        Some(button) => spawn_recipe_book_button(commands, translation, size, button),
+        None => commands
+            .spawn(SpatialBundle::from_transform(Transform::from_translation(
+                translation,
+            )))
+            .insert(RenderLayers::from_layers(&[3]))
+            .id(),
+    };
+    commands.entity(row_e).set_parent(book_e);
+    let name_text = spawn_text(
+        commands,
+        asset_server,
+        Vec3::new(-48., 0., 1.),
+        name_color,
+        name.chars().take(MAX_RECIPE_NAME_LEN).collect(),
+        Anchor::CenterLeft,
+        1.,
+        3,
+    );
+    let value_text = spawn_text(
+        commands,
+        asset_server,
+        Vec3::new(48., 0., 1.),
+        value_color,
+        value,
+        Anchor::CenterRight,
+        1.,
+        3,
+    );
+    commands
+        .entity(row_e)
+        .push_children(&[name_text, value_text]);
+}
+
+fn spawn_recipe_book_button(
+    commands: &mut Commands,
+    translation: Vec3,
+    size: Vec2,
+    button: RecipeBookButton,
+) -> Entity {
+    commands
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::NONE,
+                    custom_size: Some(size),
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(translation),
+                ..Default::default()
+            },
+            Interactable::default(),
+            button,
+            RenderLayers::from_layers(&[3]),
+            Name::new("Recipe Book Button"),
+        ))
+        .id()
+}
+
+pub fn handle_recipe_book_button_clicks(
+    mut commands: Commands,
+    mut events: EventReader<RecipeBookButtonClickEvent>,
+    graphics: Res<Graphics>,
+    asset_server: Res<AssetServer>,
+    res: Res<ScreenResolution>,
+    mut curr_goal: Option<ResMut<CurrentGoal>>,
+    mut book: RecipeBookParam,
+    book_ui: Query<Entity, With<RecipeBookUI>>,
+    goal_icons: Query<Entity, With<GoalIcons>>,
+) {
+    for RecipeBookButtonClickEvent {
+        button,
+        right_click,
+    } in events.iter()
+    {
+        let num_pages = book.get_pages().len().max(1);
+        let mut pinned = curr_goal.as_ref().map(|goal| goal.goal);
+        let to_pin = match button {
+            RecipeBookButton::Recipe(obj) => {
+                book.state.selected = Some(*obj);
+                right_click.then_some(*obj)
+            }
+            RecipeBookButton::PrevPage => {
+                book.state.page = (book.state.page + num_pages - 1) % num_pages;
+                None
+            }
+            RecipeBookButton::NextPage => {
+                book.state.page = (book.state.page + 1) % num_pages;
+                None
+            }
+            RecipeBookButton::Pin => book.state.selected,
+        };
+        if let Some(to_pin) = to_pin {
+            if pinned == Some(to_pin) {
+                commands.remove_resource::<CurrentGoal>();
+                for e in goal_icons.iter() {
+                    commands.entity(e).despawn_recursive();
+                }
+                pinned = None;
+            } else {
+                // the HUD redraws when the goal changes, so an existing one is swapped in place
+                match curr_goal.as_mut() {
+                    Some(goal) => goal.goal = to_pin,
+                    None => commands.insert_resource(CurrentGoal { goal: to_pin }),
+                }
+                pinned = Some(to_pin);
+            }
+        }
+        for e in book_ui.iter() {
+            commands.entity(e).despawn_recursive();
+        }
+        spawn_recipe_book_ui(&mut commands, &graphics, &asset_server, &res, &book, pinned);
+    }
+}
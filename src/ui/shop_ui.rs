@@ -0,0 +1,382 @@
+use bevy::{prelude::*, render::view::RenderLayers, sprite::Anchor};
+use bevy_proto::prelude::{ReflectSchematic, Schematic};
+use rand::{seq::SliceRandom, Rng};
+use serde::Deserialize;
+
+use crate::{
+    assets::Graphics,
+    attributes::attribute_helpers::create_new_random_item_stack_with_attributes,
+    colors::{WHITE, YELLOW},
+    container::Container,
+    inventory::{Inventory, InventoryItemStack, ItemStack},
+    item::{EquipmentType, WorldObject},
+    night::NewDayEvent,
+    player::ModifyTimeFragmentsEvent,
+    proto::proto_param::ProtoParam,
+    GameParam,
+};
+
+use super::{
+    interactions::Interaction, spawn_inv_slot, InventorySlotState, InventorySlotType,
+    InventoryState, InventoryUI, UIState,
+};
+
+pub const SHOP_SIZE: usize = 6 * 2;
+/// The bottom row of the shop holds the last few items the player sold,
+/// the top row holds the merchant's stock for the day
+pub const SHOP_BUYBACK_SIZE: usize = 6;
+pub const SHOP_STOCK_SIZE: usize = SHOP_SIZE - SHOP_BUYBACK_SIZE;
+
+/// What a shop slot costs. A price in [WorldObject::TimeFragment] is paid from the
+/// player's [TimeFragmentCurrency](crate::player::TimeFragmentCurrency), anything else
+/// is taken out of the player's inventory.
+#[derive(Default, Reflect, FromReflect, Clone, Debug, PartialEq, Deserialize)]
+pub struct ShopPrice {
+    pub obj: WorldObject,
+    pub count: usize,
+}
+impl ShopPrice {
+    pub fn time_fragments(count: usize) -> Self {
+        Self {
+            obj: WorldObject::TimeFragment,
+            count,
+        }
+    }
+    pub fn is_time_fragments(&self) -> bool {
+        self.obj == WorldObject::TimeFragment
+    }
+}
+
+#[derive(Default, Reflect, FromReflect, Clone, Debug, Deserialize)]
+pub struct ShopOffer {
+    pub item: WorldObject,
+    pub min: usize,
+    pub max: usize,
+    pub weight: f32,
+    pub price: ShopPrice,
+}
+
+/// Any entity with a [MerchantTable] can be traded with. Every day the merchant rolls
+/// `num_offers` of its offers into the stock row of its [ShopContainer].
+#[derive(Default, Schematic, Reflect, FromReflect, Clone, Debug, Component, Deserialize)]
+#[reflect(Schematic)]
+pub struct MerchantTable {
+    pub num_offers: usize,
+    pub offers: Vec<ShopOffer>,
+}
+
+impl MerchantTable {
+    pub fn roll_stock(&self, proto_param: &ProtoParam) -> Vec<(ItemStack, ShopPrice)> {
+        let mut rng = rand::thread_rng();
+        let Ok(offers) = self.offers.choose_multiple_weighted(
+            &mut rng,
+            self.num_offers.min(SHOP_STOCK_SIZE),
+            |offer| offer.weight,
+        ) else {
+            warn!("Merchant table has invalid offer weights");
+            return vec![];
+        };
+        offers
+            .map(|offer| {
+                let count = if offer.min >= offer.max {
+                    offer.min
+                } else {
+                    rng.gen_range(offer.min..=offer.max)
+                };
+                (
+                    create_new_random_item_stack_with_attributes(
+                        &ItemStack::crate_icon_stack(offer.item).copy_with_count(count.max(1)),
+                        proto_param,
+                    ),
+                    offer.price.clone(),
+                )
+            })
+            .collect()
+    }
+}
+
+#[derive(Component, Resource, Debug, Clone)]
+pub struct ShopContainer {
+    pub items: Container,
+    pub prices: Vec<Option<ShopPrice>>,
+    pub parent: Entity,
+}
+
+impl ShopContainer {
+    pub fn new(parent: Entity) -> Self {
+        Self {
+            items: Container::with_size(SHOP_SIZE),
+            prices: vec![None; SHOP_SIZE],
+            parent,
+        }
+    }
+    /// replaces today's stock with a fresh roll, the buyback row is kept
+    pub fn restock(&mut self, table: &MerchantTable, proto_param: &ProtoParam) {
+        for slot in SHOP_BUYBACK_SIZE..SHOP_SIZE {
+            self.items.items[slot] = None;
+            self.prices[slot] = None;
+        }
+        for (i, (item, price)) in table.roll_stock(proto_param).into_iter().enumerate() {
+            let slot = SHOP_BUYBACK_SIZE + i;
+            self.items.items[slot] = Some(InventoryItemStack::new(item, slot));
+            self.prices[slot] = Some(price);
+        }
+    }
+    /// pushes a sold item to the front of the buyback row, the oldest sold item falls off the end
+    pub fn add_to_buyback(&mut self, item: ItemStack, price: ShopPrice) {
+        for slot in (1..SHOP_BUYBACK_SIZE).rev() {
+            self.items.items[slot] = self.items.items[slot - 1]
+                .as_ref()
+                .map(|i| i.modify_slot(slot));
+            self.prices[slot] = self.prices[slot - 1].clone();
+        }
+        self.items.items[0] = Some(InventoryItemStack::new(item, 0));
+        self.prices[0] = Some(price);
+    }
+    /// Merchants only buy gear. Returns [None] if the item can not be sold.
+    pub fn get_sell_price(item: &ItemStack, proto_param: &ProtoParam) -> Option<ShopPrice> {
+        proto_param.get_component::<EquipmentType, _>(item.obj_type)?;
+        Some(ShopPrice::time_fragments(
+            item.rarity.get_sell_value() * item.count,
+        ))
+    }
+}
+
+pub struct BuyShopItemEvent {
+    pub slot: usize,
+}
+pub struct SellShopItemEvent {
+    pub item: ItemStack,
+}
+/// Sent whenever the player buys from or sells to a merchant
+pub struct MerchantTradeEvent {
+    pub merchant: Entity,
+}
+
+#[derive(Component)]
+pub struct ShopPriceTag;
+
+pub fn add_shop_to_new_merchants(
+    mut commands: Commands,
+    new_merchants: Query<(Entity, &MerchantTable), Without<ShopContainer>>,
+    proto_param: ProtoParam,
+) {
+    for (e, table) in new_merchants.iter() {
+        let mut shop = ShopContainer::new(e);
+        shop.restock(table, &proto_param);
+        commands.entity(e).insert(shop);
+    }
+}
+
+pub fn restock_merchants_on_new_day(
+    mut new_day_event: EventReader<NewDayEvent>,
+    mut merchants: Query<(&MerchantTable, &mut ShopContainer)>,
+    open_shop_option: Option<ResMut<ShopContainer>>,
+    mut inv_slots: Query<&mut InventorySlotState>,
+    proto_param: ProtoParam,
+) {
+    if new_day_event.iter().count() == 0 {
+        return;
+    }
+    for (table, mut shop) in merchants.iter_mut() {
+        shop.restock(table, &proto_param);
+    }
+    // the open shop is written back to its merchant when the UI closes, so restock it too
+    if let Some(mut open_shop) = open_shop_option {
+        if let Ok((table, _)) = merchants.get(open_shop.parent) {
+            open_shop.restock(table, &proto_param);
+            mark_shop_slots_dirty(&mut inv_slots);
+        }
+    }
+}
+
+pub fn change_ui_state_to_shop_when_resource_added(mut inv_ui_state: ResMut<NextState<UIState>>) {
+    inv_ui_state.set(UIState::Shop);
+}
+
+pub fn setup_shop_slots_ui(
+    mut commands: Commands,
+    graphics: Res<Graphics>,
+    inv_query: Query<Entity, With<InventoryUI>>,
+    inv_state_res: Res<InventoryState>,
+    inv_state: Res<State<UIState>>,
+    inv_spawn_check: Query<Entity, Added<InventoryUI>>,
+
+    asset_server: Res<AssetServer>,
+    shop: Res<ShopContainer>,
+) {
+    if inv_spawn_check.get_single().is_err() {
+        return;
+    }
+    if inv_state.0 != UIState::Shop {
+        return;
+    };
+    for (slot_index, item) in shop.items.items.iter().enumerate() {
+        spawn_inv_slot(
+            &mut commands,
+            &inv_state,
+            &graphics,
+            slot_index,
+            Interaction::None,
+            &inv_state_res,
+            &inv_query,
+            &asset_server,
+            InventorySlotType::Shop,
+            item.clone(),
+        );
+    }
+}
+
+/// Shop slots get respawned whenever their item changes, so tag any new ones with their price
+pub fn add_price_tags_to_shop_slots(
+    mut commands: Commands,
+    new_slots: Query<(Entity, &InventorySlotState), Added<InventorySlotState>>,
+    shop_option: Option<Res<ShopContainer>>,
+    graphics: Res<Graphics>,
+    asset_server: Res<AssetServer>,
+) {
+    let Some(shop) = shop_option else {
+        return;
+    };
+    for (e, state) in new_slots.iter() {
+        if !state.r#type.is_shop() || state.item.is_none() {
+            continue;
+        }
+        let Some(price) = &shop.prices[state.slot_index] else {
+            continue;
+        };
+        let icon_sprite = graphics
+            .icons
+            .as_ref()
+            .unwrap()
+            .get(&price.obj)
+            .or(graphics.spritesheet_map.as_ref().unwrap().get(&price.obj))
+            .cloned();
+        let price_tag = commands
+            .spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        price.count.to_string(),
+                        TextStyle {
+                            font: asset_server.load("fonts/4x5.ttf"),
+                            font_size: 5.0,
+                            color: if price.is_time_fragments() {
+                                YELLOW
+                            } else {
+                                WHITE
+                            },
+                        },
+                    ),
+                    text_anchor: Anchor::CenterLeft,
+                    transform: Transform::from_translation(Vec3::new(-3., 6.5, 4.)),
+                    ..default()
+                },
+                ShopPriceTag,
+                Name::new("SHOP PRICE TAG"),
+                RenderLayers::from_layers(&[3]),
+            ))
+            .id();
+        commands.entity(e).add_child(price_tag);
+        if let Some(mut sprite) = icon_sprite {
+            sprite.custom_size = Some(Vec2::new(8., 8.));
+            let icon = commands
+                .spawn((
+                    SpriteSheetBundle {
+                        sprite,
+                        texture_atlas: graphics.texture_atlas.as_ref().unwrap().clone(),
+                        transform: Transform::from_translation(Vec3::new(-6., 6.5, 4.)),
+                        ..default()
+                    },
+                    ShopPriceTag,
+                    RenderLayers::from_layers(&[3]),
+                ))
+                .id();
+            commands.entity(e).add_child(icon);
+        }
+    }
+}
+
+pub fn handle_buy_shop_item(
+    mut commands: Commands,
+    mut events: EventReader<BuyShopItemEvent>,
+    shop_option: Option<ResMut<ShopContainer>>,
+    mut inv: Query<&mut Inventory>,
+    mut game: GameParam,
+    mut currency_event: EventWriter<ModifyTimeFragmentsEvent>,
+    mut trade_event: EventWriter<MerchantTradeEvent>,
+) {
+    let Some(mut shop) = shop_option else {
+        return;
+    };
+    for event in events.iter() {
+        let (Some(item), Some(price)) = (
+            shop.items.items[event.slot].clone(),
+            shop.prices[event.slot].clone(),
+        ) else {
+            continue;
+        };
+        let mut inv = inv.single_mut();
+        if price.is_time_fragments() {
+            if game.get_time_fragments() < price.count as i32 {
+                continue;
+            }
+            currency_event.send(ModifyTimeFragmentsEvent {
+                delta: -(price.count as i32),
+            });
+        } else if inv.items.get_item_count_in_container(price.obj) < price.count
+            || inv
+                .items
+                .remove_from_inventory(price.count, price.obj)
+                .is_err()
+        {
+            continue;
+        }
+
+        shop.items.items[event.slot] = None;
+        shop.prices[event.slot] = None;
+        if inv.items.get_first_empty_slot().is_some() {
+            item.item_stack
+                .add_to_inventory(&mut inv.items, &mut game.inv_slot_query);
+        } else {
+            let pos = game.player().position.truncate();
+            item.item_stack.spawn_as_drop(&mut commands, &mut game, pos);
+        }
+        trade_event.send(MerchantTradeEvent {
+            merchant: shop.parent,
+        });
+    }
+}
+
+pub fn handle_sell_shop_item(
+    mut events: EventReader<SellShopItemEvent>,
+    shop_option: Option<ResMut<ShopContainer>>,
+    mut inv_slots: Query<&mut InventorySlotState>,
+    proto_param: ProtoParam,
+    mut currency_event: EventWriter<ModifyTimeFragmentsEvent>,
+    mut trade_event: EventWriter<MerchantTradeEvent>,
+) {
+    let Some(mut shop) = shop_option else {
+        return;
+    };
+    for event in events.iter() {
+        let Some(price) = ShopContainer::get_sell_price(&event.item, &proto_param) else {
+            continue;
+        };
+        currency_event.send(ModifyTimeFragmentsEvent {
+            delta: price.count as i32,
+        });
+        shop.add_to_buyback(event.item.clone(), price);
+        mark_shop_slots_dirty(&mut inv_slots);
+        trade_event.send(MerchantTradeEvent {
+            merchant: shop.parent,
+        });
+    }
+}
+
+fn mark_shop_slots_dirty(inv_slots: &mut Query<&mut InventorySlotState>) {
+    for mut state in inv_slots.iter_mut() {
+        if state.r#type.is_shop() {
+            state.dirty = true;
+        }
+    }
+}
@@ -3,6 +3,7 @@ use bevy::{prelude::*, render::view::RenderLayers, sprite::Anchor};
 use crate::{
     assets::Graphics,
     colors::{BLACK, WHITE},
+    keymap::{ActionInput, InputAction},
     player::skills::{SkillChoiceQueue, SkillChoiceState},
     ScreenResolution, GAME_HEIGHT,
 };
@@ -176,9 +177,9 @@ pub fn setup_skill_choice_ui(
 
 pub fn toggle_skills_visibility(
     mut next_inv_state: ResMut<NextState<UIState>>,
-    key_input: ResMut<Input<KeyCode>>,
+    actions: ActionInput,
 ) {
-    if key_input.just_pressed(KeyCode::B) {
+    if actions.just_pressed(InputAction::Skills) {
         next_inv_state.set(UIState::Skills);
     }
 }
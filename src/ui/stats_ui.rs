@@ -2,6 +2,7 @@ use bevy::prelude::*;
 
 use crate::{
     assets::Graphics,
+    keymap::{ActionInput, InputAction},
     player::stats::{PlayerStats, SkillPoints},
 };
 
@@ -164,9 +165,9 @@ pub fn setup_stats_ui(
 
 pub fn toggle_stats_visibility(
     mut next_inv_state: ResMut<NextState<UIState>>,
-    key_input: ResMut<Input<KeyCode>>,
+    actions: ActionInput,
 ) {
-    if key_input.just_pressed(KeyCode::B) {
+    if actions.just_pressed(InputAction::Skills) {
         next_inv_state.set(UIState::Skills);
     }
 }
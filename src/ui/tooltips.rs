@@ -13,12 +13,15 @@ use crate::{
     juice::bounce::BounceOnHit,
     player::{stats::StatType, Player},
     proto::proto_param::ProtoParam,
+    settings::Settings,
     ui::{spawn_item_stack_icon, TOOLTIP_UI_SIZE},
+    ScreenResolution,
 };
 
 use super::{
-    EssenceUI, InventoryUI, UIElement, UIState, CHEST_INVENTORY_UI_SIZE,
-    CRAFTING_INVENTORY_UI_SIZE, ESSENCE_UI_SIZE, FURNACE_INVENTORY_UI_SIZE, INVENTORY_UI_SIZE,
+    accessibility::LARGE_TOOLTIP_SCALE, EssenceUI, InventoryUI, UIElement, UIState,
+    CHEST_INVENTORY_UI_SIZE, CRAFTING_INVENTORY_UI_SIZE, ESSENCE_UI_SIZE,
+    FURNACE_INVENTORY_UI_SIZE, INVENTORY_UI_SIZE,
 };
 #[derive(Component)]
 pub struct PlayerStatsTooltip;
@@ -104,11 +107,14 @@ pub fn handle_spawn_inv_item_tooltip(
     recipes: Res<Recipes>,
     item_stacks: Query<(Entity, &ItemStack)>,
     proto: ProtoParam,
+    settings: Res<Settings>,
+    res: Res<ScreenResolution>,
 ) {
     for item in updates.iter() {
         let parent_inv_size = match cur_inv_state.0 {
             UIState::Inventory => INVENTORY_UI_SIZE,
             UIState::Chest => CHEST_INVENTORY_UI_SIZE,
+            UIState::Shop => CHEST_INVENTORY_UI_SIZE,
             UIState::Crafting => CRAFTING_INVENTORY_UI_SIZE,
             UIState::Furnace => FURNACE_INVENTORY_UI_SIZE,
             UIState::Essence => ESSENCE_UI_SIZE,
@@ -133,14 +139,24 @@ pub fn handle_spawn_inv_item_tooltip(
         let item_actions = proto.get_component::<ItemActions, _>(item.item_stack.obj_type);
         let should_show_attributes = !attributes.is_empty() && !item.is_recipe;
         let size = Vec2::new(93., 120.5);
+        let scale = if settings.large_text {
+            LARGE_TOOLTIP_SCALE
+        } else {
+            1.
+        };
+        // a bigger tooltip grows away from the inventory, but stops at the screen edge
+        let mut x = -(parent_inv_size.x / 2. + 1.) - size.x * scale / 2.;
+        if settings.large_text {
+            x = x.max((size.x * scale - res.game_width) / 2.);
+        }
         let tooltip = commands
             .spawn((
                 SpriteBundle {
                     texture: graphics
                         .get_ui_element_texture(item_rarity.clone().get_tooltip_ui_element()),
                     transform: Transform {
-                        translation: Vec3::new(-(parent_inv_size.x + size.x + 2.) / 2., 0., 4.),
-                        scale: Vec3::new(1., 1., 1.),
+                        translation: Vec3::new(x, 0., 4.),
+                        scale: Vec3::new(scale, scale, 1.),
                         ..Default::default()
                     },
                     sprite: Sprite {
@@ -305,7 +321,7 @@ pub fn handle_spawn_inv_item_tooltip(
                                     font: asset_server.load("fonts/4x5.ttf"),
                                     font_size: 5.0,
                                     color: if i == 0 {
-                                        item.item_stack.rarity.get_color()
+                                        item.item_stack.rarity.get_color(settings.color_palette)
                                     } else if j == 1 {
                                         LIGHT_GREY
                                     } else {
@@ -11,14 +11,15 @@ use crate::{
 };
 
 use super::{
-    crafting_ui::CraftingContainer, scrapper_ui::ScrapperContainer, ChestContainer,
-    FurnaceContainer, UIState,
+    crafting_ui::CraftingContainer, scrapper_ui::ScrapperContainer, shop_ui::ShopContainer,
+    ChestContainer, FurnaceContainer, UIState,
 };
 
 #[derive(SystemParam)]
 pub struct UIContainersParam<'w, 's> {
     pub chest_option: Option<ResMut<'w, ChestContainer>>,
     pub scrapper_option: Option<ResMut<'w, ScrapperContainer>>,
+    pub shop_option: Option<ResMut<'w, ShopContainer>>,
     pub furnace_option: Option<ResMut<'w, FurnaceContainer>>,
     pub crafting_option: Option<ResMut<'w, CraftingContainer>>,
 
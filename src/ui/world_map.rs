@@ -0,0 +1,578 @@
+use bevy::{
+    input::InputSystem,
+    prelude::*,
+    render::{
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        view::RenderLayers,
+    },
+    sprite::Anchor,
+    utils::{HashMap, HashSet},
+    window::ReceivedCharacter,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    colors::{overwrite_alpha, BLACK, LIGHT_BLUE, LIGHT_RED, PINK, WHITE, YELLOW},
+    inputs::CursorPos,
+    keymap::{ActionInput, InputAction},
+    player::Player,
+    world::{
+        chunk::generate_tile_data_for_chunk,
+        dimension::{EraList, EraManager, GenerationSeed, SpawnDimension},
+        dungeon::Dungeon,
+        generation::WorldObjectCache,
+        world_helpers::{camera_pos_to_chunk_pos, tile_pos_to_global_tile, world_pos_to_tile_pos},
+        TileMapPosition, WorldGeneration, WorldSize, CHUNK_SIZE,
+    },
+    CustomFlush, Game, GameState, ScreenResolution, GAME_HEIGHT,
+};
+
+use super::{damage_numbers::spawn_text, UIState};
+
+/// Chunks this far from the player's chunk are revealed as they walk around
+const EXPLORE_RADIUS: i32 = 1;
+const MAX_PIN_LABEL_LEN: usize = 12;
+/// How close to a pin, in pixels, a click has to be to pick it
+const PIN_CLICK_RADIUS: f32 = 4.;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MapPin {
+    pub tile: IVec2,
+    pub label: String,
+}
+
+/// The fog of war and pins of one era
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct EraMap {
+    pub explored_chunks: HashSet<IVec2>,
+    pub pins: Vec<MapPin>,
+}
+
+/// Everything the player has uncovered on the world map, one entry per era index,
+/// saved with the run
+#[derive(Resource, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct WorldMapData {
+    pub eras: Vec<EraMap>,
+}
+
+impl WorldMapData {
+    pub fn get_era(&self, era: usize) -> Option<&EraMap> {
+        self.eras.get(era)
+    }
+    pub fn get_era_mut(&mut self, era: usize) -> &mut EraMap {
+        if self.eras.len() <= era {
+            self.eras.resize(era + 1, EraMap::default());
+        }
+        &mut self.eras[era]
+    }
+}
+
+/// Terrain colors of explored chunks, so reopening the map doesn't regenerate them
+#[derive(Resource, Default)]
+pub struct WorldMapTileCache(pub HashMap<IVec2, Vec<(IVec2, Color)>>);
+
+/// The pin whose label is being typed
+#[derive(Resource, Default, Debug)]
+pub struct PinLabelInput(pub Option<usize>);
+
+/// Labelled spots another UI wants to point out on the map, like the chests the item
+/// finder found. Opening the map with its key clears them.
+#[derive(Resource, Default, Debug)]
+pub struct WorldMapHighlights(pub Vec<(TileMapPosition, String)>);
+
+#[derive(Component)]
+pub struct WorldMapUI;
+
+/// The map texture, which covers the explored chunks from `min_tile` up
+#[derive(Component)]
+pub struct WorldMapImage {
+    pub min_tile: IVec2,
+    pub size: IVec2,
+    pub scale: f32,
+}
+
+impl WorldMapImage {
+    /// Where the center of a tile is on the map, relative to its center
+    pub fn tile_to_map_pos(&self, tile: IVec2) -> Vec2 {
+        ((tile - self.min_tile).as_vec2() + 0.5 - self.size.as_vec2() / 2.) * self.scale
+    }
+    pub fn map_pos_to_tile(&self, pos: Vec2) -> IVec2 {
+        (pos / self.scale + self.size.as_vec2() / 2. - 0.5)
+            .round()
+            .as_ivec2()
+            + self.min_tile
+    }
+}
+
+#[derive(Component)]
+pub struct WorldMapMarker;
+
+#[derive(Component)]
+pub struct PinLabelText(pub usize);
+
+pub struct WorldMapPlugin;
+
+impl Plugin for WorldMapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WorldMapData>()
+            .init_resource::<WorldMapTileCache>()
+            .init_resource::<PinLabelInput>()
+            .init_resource::<WorldMapHighlights>()
+            .add_systems(
+                (
+                    explore_chunks_around_player,
+                    clear_tile_cache_for_new_dimensions,
+                    toggle_world_map,
+                    handle_enter_world_map
+                        .before(CustomFlush)
+                        .run_if(state_changed::<UIState>().and_then(in_state(UIState::WorldMap))),
+                    handle_world_map_clicks.run_if(in_state(UIState::WorldMap)),
+                    update_world_map_markers
+                        .after(CustomFlush)
+                        .after(handle_world_map_clicks),
+                    update_pin_label_text.after(update_world_map_markers),
+                )
+                    .in_set(OnUpdate(GameState::Main)),
+            )
+            // takes the typed label before any hotkey can react to it
+            .add_system(
+                handle_pin_label_input
+                    .in_base_set(CoreSet::PreUpdate)
+                    .after(InputSystem)
+                    .run_if(in_state(UIState::WorldMap)),
+            );
+    }
+}
+
+fn explore_chunks_around_player(
+    player: Query<&GlobalTransform, With<Player>>,
+    era: Res<EraManager>,
+    dungeon_check: Query<&Dungeon>,
+    mut map_data: ResMut<WorldMapData>,
+) {
+    if dungeon_check.get_single().is_ok() {
+        return;
+    }
+    let Ok(player_txfm) = player.get_single() else {
+        return;
+    };
+    let player_chunk = camera_pos_to_chunk_pos(&player_txfm.translation().truncate());
+    let era_index = era.current_era.index();
+    let mut new_chunks = vec![];
+    for y in -EXPLORE_RADIUS..=EXPLORE_RADIUS {
+        for x in -EXPLORE_RADIUS..=EXPLORE_RADIUS {
+            let chunk = player_chunk + IVec2::new(x, y);
+            let explored = map_data
+                .get_era(era_index)
+                .map_or(false, |era_map| era_map.explored_chunks.contains(&chunk));
+            if !explored {
+                new_chunks.push(chunk);
+            }
+        }
+    }
+    // only touch the resource when something new is found, the map redraws on change
+    if !new_chunks.is_empty() {
+        map_data
+            .get_era_mut(era_index)
+            .explored_chunks
+            .extend(new_chunks);
+    }
+}
+
+fn clear_tile_cache_for_new_dimensions(
+    new_dim: Query<Entity, Added<SpawnDimension>>,
+    mut cache: ResMut<WorldMapTileCache>,
+) {
+    if !new_dim.is_empty() {
+        cache.0.clear();
+    }
+}
+
+fn toggle_world_map(
+    actions: ActionInput,
+    curr_ui_state: Res<State<UIState>>,
+    mut next_ui_state: ResMut<NextState<UIState>>,
+    mut highlights: ResMut<WorldMapHighlights>,
+    dungeon_check: Query<&Dungeon>,
+) {
+    if !actions.just_pressed(InputAction::WorldMap) || dungeon_check.get_single().is_ok() {
+        return;
+    }
+    // setting the same state again closes the map
+    if curr_ui_state.0 == UIState::Closed || curr_ui_state.0 == UIState::WorldMap {
+        next_ui_state.set(UIState::WorldMap);
+        highlights.0.clear();
+    }
+}
+
+fn handle_enter_world_map(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    asset_server: Res<AssetServer>,
+    res: Res<ScreenResolution>,
+    map_data: Res<WorldMapData>,
+    era: Res<EraManager>,
+    eras: Res<EraList>,
+    world_generation_params: Res<WorldGeneration>,
+    seed: Res<GenerationSeed>,
+    world_size: Res<WorldSize>,
+    mut tile_cache: ResMut<WorldMapTileCache>,
+    mut pin_input: ResMut<PinLabelInput>,
+) {
+    pin_input.0 = None;
+    let era_index = era.current_era.index();
+    let explored = map_data
+        .get_era(era_index)
+        .map(|era_map| era_map.explored_chunks.clone())
+        .unwrap_or_default();
+    let texture_offset = eras.get_texture_index(&era.current_era) as u8;
+
+    let min_chunk = explored
+        .iter()
+        .copied()
+        .reduce(IVec2::min)
+        .unwrap_or_default();
+    let max_chunk = explored
+        .iter()
+        .copied()
+        .reduce(IVec2::max)
+        .unwrap_or_default();
+    let min_tile = min_chunk * CHUNK_SIZE as i32;
+    let size = (max_chunk - min_chunk + IVec2::ONE) * CHUNK_SIZE as i32;
+
+    // unexplored land stays dark
+    let fog = BLACK;
+    let mut data = [fog.r(), fog.g(), fog.b(), 1.]
+        .map(|c| (c * 255.) as u8)
+        .repeat((size.x * size.y) as usize);
+    for chunk in explored.iter() {
+        let tiles = tile_cache.0.entry(*chunk).or_insert_with(|| {
+            generate_tile_data_for_chunk(
+                &world_generation_params,
+                *chunk,
+                seed.seed,
+                *world_size,
+                texture_offset,
+            )
+            .into_iter()
+            .map(|(pos, tile)| {
+                (
+                    tile_pos_to_global_tile(pos),
+                    tile.block_type[0].get_obj_color(),
+                )
+            })
+            .collect()
+        });
+        for (tile, color) in tiles.iter() {
+            let pixel = *tile - min_tile;
+            // the first row of the image is the top of the map
+            let i = (((size.y - 1 - pixel.y) * size.x + pixel.x) * 4) as usize;
+            data[i] = (color.r() * 255.) as u8;
+            data[i + 1] = (color.g() * 255.) as u8;
+            data[i + 2] = (color.b() * 255.) as u8;
+        }
+    }
+    let image = Image::new(
+        Extent3d {
+            width: size.x as u32,
+            height: size.y as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    );
+
+    let map_area = Vec2::new(res.game_width - 40., GAME_HEIGHT - 40.);
+    let mut scale = (map_area / size.as_vec2()).min_element();
+    // whole pixels keep the map crisp when there is room
+    if scale >= 1. {
+        scale = scale.floor();
+    }
+
+    let map_ui = commands
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: overwrite_alpha(BLACK, 0.9),
+                    custom_size: Some(Vec2::new(res.game_width + 10., GAME_HEIGHT + 10.)),
+                    ..default()
+                },
+                transform: Transform::from_translation(Vec3::new(0., 0., 10.)),
+                ..default()
+            },
+            WorldMapUI,
+            UIState::WorldMap,
+            RenderLayers::from_layers(&[3]),
+            Name::new("WORLD MAP UI"),
+        ))
+        .id();
+    commands
+        .spawn((
+            SpriteBundle {
+                texture: images.add(image),
+                sprite: Sprite {
+                    custom_size: Some(size.as_vec2() * scale),
+                    ..default()
+                },
+                transform: Transform::from_translation(Vec3::new(0., -2., 1.)),
+                ..default()
+            },
+            WorldMapImage {
+                min_tile,
+                size,
+                scale,
+            },
+            RenderLayers::from_layers(&[3]),
+            Name::new("WORLD MAP"),
+        ))
+        .set_parent(map_ui);
+
+    let era_name = eras
+        .get(&era.current_era)
+        .map(|desc| desc.name.clone())
+        .unwrap_or_default();
+    let title = spawn_text(
+        &mut commands,
+        &asset_server,
+        Vec3::new(0., GAME_HEIGHT / 2. - 10., 1.),
+        WHITE,
+        format!("Map - {era_name}"),
+        Anchor::Center,
+        1.,
+        3,
+    );
+    let hint = spawn_text(
+        &mut commands,
+        &asset_server,
+        Vec3::new(0., -GAME_HEIGHT / 2. + 10., 1.),
+        WHITE,
+        "Left click: add or rename pin   Right click: remove pin".to_string(),
+        Anchor::Center,
+        1.,
+        3,
+    );
+    commands.entity(map_ui).push_children(&[title, hint]);
+}
+
+fn get_cursor_map_pos(
+    cursor_pos: &CursorPos,
+    map_txfm: &GlobalTransform,
+    map_image: &WorldMapImage,
+) -> Option<Vec2> {
+    let pos = cursor_pos.ui_coords.truncate() - map_txfm.translation().truncate();
+    let half_size = map_image.size.as_vec2() * map_image.scale / 2.;
+    (pos.abs().cmple(half_size).all()).then_some(pos)
+}
+
+/// Left click drops a new pin or renames the one under the cursor, right click removes it
+fn handle_world_map_clicks(
+    mouse_input: Res<Input<MouseButton>>,
+    cursor_pos: Res<CursorPos>,
+    era: Res<EraManager>,
+    map: Query<(&GlobalTransform, &WorldMapImage)>,
+    mut map_data: ResMut<WorldMapData>,
+    mut pin_input: ResMut<PinLabelInput>,
+) {
+    let left_click = mouse_input.just_pressed(MouseButton::Left);
+    let right_click = mouse_input.just_pressed(MouseButton::Right);
+    if !left_click && !right_click {
+        return;
+    }
+    let Ok((map_txfm, map_image)) = map.get_single() else {
+        return;
+    };
+    let Some(pos) = get_cursor_map_pos(&cursor_pos, map_txfm, map_image) else {
+        return;
+    };
+    let era_map = map_data.get_era_mut(era.current_era.index());
+    let clicked_pin = era_map
+        .pins
+        .iter()
+        .position(|pin| map_image.tile_to_map_pos(pin.tile).distance(pos) <= PIN_CLICK_RADIUS);
+    match clicked_pin {
+        Some(i) if right_click => {
+            era_map.pins.remove(i);
+            pin_input.0 = None;
+        }
+        Some(i) => pin_input.0 = Some(i),
+        None if left_click => {
+            era_map.pins.push(MapPin {
+                tile: map_image.map_pos_to_tile(pos),
+                label: format!("Pin {}", era_map.pins.len() + 1),
+            });
+            pin_input.0 = Some(era_map.pins.len() - 1);
+        }
+        None => {}
+    }
+}
+
+/// Runs before the rest of the game sees the keys, so typing a label doesn't also press
+/// hotkeys. Enter or Escape finishes the label.
+fn handle_pin_label_input(
+    mut key_input: ResMut<Input<KeyCode>>,
+    mut chars: EventReader<ReceivedCharacter>,
+    era: Res<EraManager>,
+    mut map_data: ResMut<WorldMapData>,
+    mut pin_input: ResMut<PinLabelInput>,
+) {
+    let Some(i) = pin_input.0 else {
+        chars.clear();
+        return;
+    };
+    let finished =
+        key_input.just_pressed(KeyCode::Return) || key_input.just_pressed(KeyCode::Escape);
+    let backspace = key_input.just_pressed(KeyCode::Back);
+    let pressed: Vec<KeyCode> = key_input.get_just_pressed().copied().collect();
+    for key in pressed {
+        key_input.clear_just_pressed(key);
+    }
+    let Some(pin) = map_data
+        .get_era_mut(era.current_era.index())
+        .pins
+        .get_mut(i)
+    else {
+        pin_input.0 = None;
+        return;
+    };
+    if backspace {
+        pin.label.pop();
+    }
+    for ReceivedCharacter { char, .. } in chars.iter() {
+        if (char.is_ascii_alphanumeric() || *char == ' ') && pin.label.len() < MAX_PIN_LABEL_LEN {
+            pin.label.push(*char);
+        }
+    }
+    if finished {
+        pin_input.0 = None;
+    }
+}
+
+/// Redraws the player, home, unique objects and pins over the map whenever they change
+fn update_world_map_markers(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    map_data: Res<WorldMapData>,
+    era: Res<EraManager>,
+    game: Res<Game>,
+    world_obj_cache: Res<WorldObjectCache>,
+    highlights: Res<WorldMapHighlights>,
+    player: Query<&GlobalTransform, With<Player>>,
+    map: Query<(Entity, Ref<WorldMapImage>)>,
+    old_markers: Query<Entity, With<WorldMapMarker>>,
+) {
+    let Ok((map_e, map_image)) = map.get_single() else {
+        return;
+    };
+    if !map_data.is_changed() && !highlights.is_changed() && !map_image.is_added() {
+        return;
+    }
+    for e in old_markers.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+    let Some(era_map) = map_data.get_era(era.current_era.index()) else {
+        return;
+    };
+    let is_explored = |pos: &TileMapPosition| era_map.explored_chunks.contains(&pos.chunk_pos);
+
+    let mut markers = vec![];
+    for (obj, pos) in world_obj_cache.unique_objs.iter() {
+        if is_explored(pos) {
+            markers.push((
+                tile_pos_to_global_tile(*pos),
+                LIGHT_RED,
+                Some(obj.to_string()),
+                None,
+            ));
+        }
+    }
+    if let Some(home) = game.home_pos {
+        if is_explored(&home) {
+            markers.push((
+                tile_pos_to_global_tile(home),
+                WHITE,
+                Some("Home".to_string()),
+                None,
+            ));
+        }
+    }
+    for (i, pin) in era_map.pins.iter().enumerate() {
+        markers.push((pin.tile, PINK, Some(pin.label.clone()), Some(i)));
+    }
+    for (pos, label) in highlights.0.iter() {
+        markers.push((
+            tile_pos_to_global_tile(*pos),
+            LIGHT_BLUE,
+            Some(label.clone()),
+            None,
+        ));
+    }
+    if let Ok(player_txfm) = player.get_single() {
+        let tile =
+            tile_pos_to_global_tile(world_pos_to_tile_pos(player_txfm.translation().truncate()));
+        markers.push((tile, YELLOW, None, None));
+    }
+
+    for (tile, color, label, pin) in markers {
+        let pos = map_image.tile_to_map_pos(tile);
+        let marker = commands
+            .spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color,
+                        custom_size: Some(Vec2::new(3., 3.)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(pos.extend(2.)),
+                    ..default()
+                },
+                WorldMapMarker,
+                RenderLayers::from_layers(&[3]),
+                Name::new("MAP MARKER"),
+            ))
+            .set_parent(map_e)
+            .id();
+        let Some(label) = label else {
+            continue;
+        };
+        let text = spawn_text(
+            &mut commands,
+            &asset_server,
+            Vec3::new(3., 0., 1.),
+            color,
+            label,
+            Anchor::CenterLeft,
+            1.,
+            3,
+        );
+        if let Some(i) = pin {
+            commands.entity(text).insert(PinLabelText(i));
+        }
+        commands.entity(marker).add_child(text);
+    }
+}
+
+/// Shows a cursor after the label that is being typed
+fn update_pin_label_text(
+    map_data: Res<WorldMapData>,
+    era: Res<EraManager>,
+    pin_input: Res<PinLabelInput>,
+    mut texts: Query<(&PinLabelText, &mut Text)>,
+) {
+    if !map_data.is_changed() && !pin_input.is_changed() {
+        return;
+    }
+    let Some(era_map) = map_data.get_era(era.current_era.index()) else {
+        return;
+    };
+    for (PinLabelText(i), mut text) in texts.iter_mut() {
+        let Some(pin) = era_map.pins.get(*i) else {
+            continue;
+        };
+        text.sections[0].value = if pin_input.0 == Some(*i) {
+            format!("{}_", pin.label)
+        } else {
+            pin.label.clone()
+        };
+    }
+}
@@ -0,0 +1,171 @@
+use bevy::{prelude::*, utils::HashMap};
+use noise::{NoiseFn, Perlin};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    audio::{BGMPicker, UpdateBGMTrackEvent},
+    enemy::Mob,
+    item::WorldObject,
+    night::NightTracker,
+    player::Player,
+    GameState,
+};
+
+use super::{world_helpers::camera_pos_to_chunk_pos, WorldGeneration, CHUNK_SIZE};
+
+pub const DEFAULT_DAY_TRACK: &str = "sounds/bgm_day.ogg";
+
+pub struct BiomePlugin;
+impl Plugin for BiomePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CurrentBiome>()
+            .add_system(update_current_biome.in_set(OnUpdate(GameState::Main)));
+    }
+}
+
+#[derive(
+    Debug, Hash, Eq, PartialEq, Clone, Copy, Default, Reflect, FromReflect, Serialize, Deserialize,
+)]
+pub enum Biome {
+    #[default]
+    Forest,
+    Swamp,
+    RockyHighlands,
+    MushroomGrove,
+}
+
+/// Everything that changes between biomes. A chunk belongs to the biome whose
+/// `moisture` and `temperature` are closest to the noise sampled at its center.
+#[derive(Reflect, FromReflect, Default, Debug, Clone)]
+pub struct BiomeParams {
+    pub moisture: f64,
+    pub temperature: f64,
+    /// Replaces the era's tree weights, if not empty
+    pub tree_weights: HashMap<WorldObject, f32>,
+    /// Overrides the era's object frequencies, and can add objects only found in this biome
+    pub object_generation_frequencies: HashMap<WorldObject, f64>,
+    /// Multiplies the weight of each mob's spawner, mobs not listed keep their weight
+    pub mob_weights: HashMap<Mob, f32>,
+    /// Day music to play while in this biome
+    pub music: Option<String>,
+}
+
+/// Moisture and temperature noise for picking biomes. Built once per seed, since
+/// it is sampled for every chunk that generates or gets walked into.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct BiomeNoise {
+    moisture: Perlin,
+    temperature: Perlin,
+}
+
+impl BiomeNoise {
+    pub fn new(seed: u64) -> Self {
+        let seed = seed as u32;
+        Self {
+            moisture: Perlin::new(4 + seed),
+            temperature: Perlin::new(5 + seed),
+        }
+    }
+    /// Moisture and temperature, both in 0..1. Lower frequency than the elevation noise,
+    /// so biomes span several chunks, but a single island still crosses a few of them.
+    pub fn get_biome_noise_for_tile(&self, x: f64, y: f64) -> (f64, f64) {
+        let base_oct = 1. / 100.;
+        // perlin rarely strays far from 0, stretch it so the outer biomes get picked too
+        let contrast = 1.6;
+
+        let moisture = (self.moisture.get([x * base_oct, y * base_oct]) * contrast + 1.) / 2.;
+        let temperature = (self.temperature.get([x * base_oct, y * base_oct]) * contrast + 1.) / 2.;
+        (moisture.clamp(0., 1.), temperature.clamp(0., 1.))
+    }
+}
+
+impl WorldGeneration {
+    pub fn get_biome_for_chunk(
+        &self,
+        chunk_pos: IVec2,
+        biome_noise: &BiomeNoise,
+    ) -> Option<(Biome, &BiomeParams)> {
+        let x = (chunk_pos.x * CHUNK_SIZE as i32 + CHUNK_SIZE as i32 / 2) as f64;
+        let y = (chunk_pos.y * CHUNK_SIZE as i32 + CHUNK_SIZE as i32 / 2) as f64;
+        let (moisture, temperature) = biome_noise.get_biome_noise_for_tile(x, y);
+        self.biomes
+            .iter()
+            .min_by(|(_, a), (_, b)| {
+                let a_dist =
+                    (a.moisture - moisture).powi(2) + (a.temperature - temperature).powi(2);
+                let b_dist =
+                    (b.moisture - moisture).powi(2) + (b.temperature - temperature).powi(2);
+                a_dist.total_cmp(&b_dist)
+            })
+            .map(|(biome, params)| (*biome, params))
+    }
+    pub fn get_tree_weights_for_chunk(
+        &self,
+        chunk_pos: IVec2,
+        biome_noise: &BiomeNoise,
+    ) -> HashMap<WorldObject, f32> {
+        match self.get_biome_for_chunk(chunk_pos, biome_noise) {
+            Some((_, params)) if !params.tree_weights.is_empty() => params.tree_weights.clone(),
+            _ => self.forest_params.tree_weights.clone(),
+        }
+    }
+    pub fn get_object_frequencies_for_chunk(
+        &self,
+        chunk_pos: IVec2,
+        biome_noise: &BiomeNoise,
+    ) -> HashMap<WorldObject, f64> {
+        let mut frequencies = self.object_generation_frequencies.clone();
+        if let Some((_, params)) = self.get_biome_for_chunk(chunk_pos, biome_noise) {
+            frequencies.extend(params.object_generation_frequencies.clone());
+        }
+        frequencies
+    }
+}
+
+/// The biome the player is standing in, used to pick the day music
+#[derive(Resource, Debug, Default)]
+pub struct CurrentBiome {
+    pub chunk_pos: Option<IVec2>,
+    pub biome: Option<Biome>,
+    pub music: Option<String>,
+}
+
+impl CurrentBiome {
+    pub fn get_day_track(&self) -> String {
+        self.music
+            .clone()
+            .unwrap_or_else(|| DEFAULT_DAY_TRACK.to_owned())
+    }
+}
+
+fn update_current_biome(
+    player: Query<&GlobalTransform, With<Player>>,
+    world_generation_params: Res<WorldGeneration>,
+    biome_noise: Option<Res<BiomeNoise>>,
+    mut current_biome: ResMut<CurrentBiome>,
+    night_tracker: Res<NightTracker>,
+    bgm_tracker: Res<BGMPicker>,
+    mut bgm_track_event: EventWriter<UpdateBGMTrackEvent>,
+) {
+    let (Ok(txfm), Some(biome_noise)) = (player.get_single(), biome_noise) else {
+        return;
+    };
+    let chunk_pos = camera_pos_to_chunk_pos(&txfm.translation().truncate());
+    if current_biome.chunk_pos == Some(chunk_pos) && !world_generation_params.is_changed() {
+        return;
+    }
+    current_biome.chunk_pos = Some(chunk_pos);
+    let biome = world_generation_params.get_biome_for_chunk(chunk_pos, &biome_noise);
+    if biome.map(|(b, _)| b) == current_biome.biome {
+        return;
+    }
+    current_biome.biome = biome.map(|(b, _)| b);
+    current_biome.music = biome.and_then(|(_, params)| params.music.clone());
+
+    let day_track = current_biome.get_day_track();
+    if !night_tracker.is_night() && bgm_tracker.current_track != day_track {
+        bgm_track_event.send(UpdateBGMTrackEvent {
+            asset_path: day_track,
+        });
+    }
+}
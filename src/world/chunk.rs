@@ -2,19 +2,29 @@ use std::hash::Hash;
 
 use bevy::math::Vec3Swizzles;
 use bevy::prelude::*;
+use bevy::tasks::{AsyncComputeTaskPool, Task};
 use bevy::utils::HashMap;
 use bevy_ecs_tilemap::{prelude::*, tiles::TilePos};
 use bevy_rapier2d::prelude::Collider;
+use futures_lite::future;
 
-use super::dimension::{dim_spawned, GenerationSeed};
+use super::biome::BiomeNoise;
+use super::dimension::{dim_spawned, ActiveDimension, GenerationSeed};
 
 use super::dungeon::Dungeon;
-use super::generation::WorldObjectCache;
+use super::generation::GenerationPlugin;
+use super::simulation::{simulate_unloaded_chunk, SimulatedObject, SimulatedSpawners};
 use super::world_helpers::get_neighbour_tile;
 use super::y_sort::YSort;
 
+use crate::assets::SpriteAnchor;
 use crate::container::ContainerRegistry;
+use crate::enemy::spawner::ChunkSpawners;
+use crate::item::Recipes;
+use crate::night::NightTracker;
 use crate::player::{handle_move_player, Player};
+use crate::proto::proto_param::ProtoParam;
+use crate::sappling::Sappling;
 use crate::ui::{ChestContainer, FurnaceContainer};
 use crate::world::wall_auto_tile::ChunkWallCache;
 use crate::world::world_helpers::world_pos_to_tile_pos;
@@ -24,8 +34,8 @@ use serde::{Deserialize, Serialize};
 
 use super::tile::TilePlugin;
 use super::{
-    world_helpers, TileMapPosition, CHUNK_SIZE, ISLAND_SIZE, MAX_VISIBILITY,
-    NUM_CHUNKS_AROUND_CAMERA, TILE_SIZE,
+    world_helpers, ChunkViewRadius, TileMapPosition, WorldGeneration, WorldSize, CHUNK_SIZE,
+    MAX_VISIBILITY, TILE_SIZE,
 };
 
 pub struct ChunkPlugin;
@@ -35,14 +45,18 @@ impl Plugin for ChunkPlugin {
             .add_event::<DespawnChunkEvent>()
             .add_event::<CreateChunkEvent>()
             .add_event::<GenerateObjectsEvent>()
+            .init_resource::<ChunkGenerationTasks>()
             .add_systems(
                 (
                     Self::spawn_chunks_around_camera
                         .after(handle_move_player)
                         .run_if(dim_spawned),
-                    Self::handle_new_chunk_event.after(Self::spawn_chunks_around_camera),
+                    Self::apply_finished_chunk_tasks.after(Self::spawn_chunks_around_camera),
+                    Self::handle_new_chunk_event.after(Self::apply_finished_chunk_tasks),
                     Self::handle_update_tiles_for_new_chunks.after(CustomFlush),
                     Self::toggle_on_screen_mesh_visibility.before(CustomFlush),
+                    Self::clear_chunk_tasks_on_new_dimension
+                        .before(Self::spawn_chunks_around_camera),
                 )
                     .in_set(OnUpdate(GameState::Main)),
             )
@@ -51,15 +65,27 @@ impl Plugin for ChunkPlugin {
                     .in_base_set(CoreSet::PostUpdate)
                     .run_if(in_state(GameState::Main)),
             )
-            .add_system(
-                generate_and_cache_island_chunks.run_if(resource_added::<WorldObjectCache>()),
-            )
             .add_system(apply_system_buffers.in_set(CustomFlush));
     }
 }
 
+/// How many chunks can be handed over from the background tasks each frame
+const MAX_CHUNKS_APPLIED_PER_FRAME: usize = 2;
+
 #[derive(Component)]
 pub struct SpawnedChunk;
+/// Chunks whose tiles and objects are being generated on the [AsyncComputeTaskPool]
+#[derive(Resource, Default)]
+pub struct ChunkGenerationTasks {
+    pub tasks: HashMap<IVec2, Task<GeneratedChunk>>,
+    /// finished object lists, waiting for their chunk to spawn
+    pub generated_objs: HashMap<IVec2, Vec<(TileMapPosition, WorldObject)>>,
+}
+pub struct GeneratedChunk {
+    pub tiles: Vec<(TileMapPosition, TileSpriteData)>,
+    /// only generated for chunks that have never been visited
+    pub objs: Option<Vec<(TileMapPosition, WorldObject)>>,
+}
 #[derive(Eq, Hash, Reflect, Component, PartialEq, Default, Debug, Clone)]
 #[reflect(Component)]
 pub struct TileSpriteData {
@@ -131,55 +157,43 @@ pub struct Chunk {
     pub chunk_pos: IVec2,
 }
 
-pub fn generate_and_cache_island_chunks(
-    mut game: GameParam,
-    seed: Res<GenerationSeed>,
-    dungeon_check: Query<&Dungeon>,
-) {
-    if dungeon_check.iter().next().is_some() {
-        return;
-    }
-    let gen_radius = ((ISLAND_SIZE / CHUNK_SIZE as f32) + 1.) as i32;
-    let era = game.era.current_era.clone();
-    debug!(
-        "Caching ALL chunks {:?}",
-        game.world_generation_params.water_frequency,
-    );
-    for y in -gen_radius..=gen_radius {
-        for x in -gen_radius..=gen_radius {
-            let chunk_pos = IVec2::new(x, y);
-            for y in 0..CHUNK_SIZE {
-                for x in 0..CHUNK_SIZE {
-                    let tile_pos = TilePos { x, y };
-
-                    let (bits, mut index_shift, blocks) = TilePlugin::get_tile_from_perlin_noise(
-                        &game.world_generation_params,
-                        chunk_pos,
-                        tile_pos,
-                        seed.seed,
-                    );
+/// Builds the tile data for every tile of a chunk from noise. Doesn't touch the ECS,
+/// so it can run on a background thread.
+pub fn generate_tile_data_for_chunk(
+    world_generation_params: &WorldGeneration,
+    chunk_pos: IVec2,
+    seed: u64,
+    world_size: WorldSize,
+    default_texture_offset: u8,
+) -> Vec<(TileMapPosition, TileSpriteData)> {
+    let mut tiles = vec![];
+    for y in 0..CHUNK_SIZE {
+        for x in 0..CHUNK_SIZE {
+            let tile_pos = TilePos { x, y };
+            let (bits, mut index_shift, blocks) = TilePlugin::get_tile_from_perlin_noise(
+                world_generation_params,
+                chunk_pos,
+                tile_pos,
+                seed,
+                world_size,
+            );
 
-                    let block_bits = bits[0] + bits[1] * 2 + bits[2] * 4 + bits[3] * 8;
-                    if index_shift == 0 {
-                        index_shift = era.get_texture_index() as u8;
-                    }
-                    let data = TileSpriteData {
-                        tile_bit_index: block_bits,
-                        block_type: blocks,
-                        raw_block_type: blocks,
-                        texture_offset: index_shift,
-                    };
-                    game.world_obj_cache.tile_data_cache.insert(
-                        TileMapPosition {
-                            chunk_pos,
-                            tile_pos,
-                        },
-                        data,
-                    );
-                }
+            let block_bits = bits[0] + bits[1] * 2 + bits[2] * 4 + bits[3] * 8;
+            if index_shift == 0 {
+                index_shift = default_texture_offset;
             }
+            tiles.push((
+                TileMapPosition::new(chunk_pos, tile_pos),
+                TileSpriteData {
+                    tile_bit_index: block_bits,
+                    block_type: blocks,
+                    raw_block_type: blocks,
+                    texture_offset: index_shift,
+                },
+            ));
         }
     }
+    tiles
 }
 
 impl ChunkPlugin {
@@ -187,8 +201,15 @@ impl ChunkPlugin {
         mut cache_events: EventReader<CreateChunkEvent>,
         mut commands: Commands,
         sprite_sheet: Res<ImageAssets>,
-        game: GameParam,
+        mut game: GameParam,
         seed: Res<GenerationSeed>,
+        biome_noise: Res<BiomeNoise>,
+        world_size: Res<WorldSize>,
+        night_tracker: Res<NightTracker>,
+        mut container_reg: ResMut<ContainerRegistry>,
+        recipes: Res<Recipes>,
+        proto_param: ProtoParam,
+        dungeon_check: Query<&Dungeon, With<ActiveDimension>>,
     ) {
         for e in cache_events.iter() {
             let chunk_pos = e.chunk_pos;
@@ -197,6 +218,17 @@ impl ChunkPlugin {
             if game.get_chunk_entity(chunk_pos).is_some() {
                 continue;
             }
+            // catch up on everything that happened while the chunk was unloaded
+            if dungeon_check.get_single().is_err() {
+                simulate_unloaded_chunk(
+                    chunk_pos,
+                    night_tracker.get_game_time_secs(),
+                    &mut game.world_obj_cache,
+                    &mut container_reg,
+                    &recipes,
+                    &proto_param,
+                );
+            }
 
             let chunk_pos = e.chunk_pos;
             let tilemap_size = TilemapSize {
@@ -232,11 +264,12 @@ impl ChunkPlugin {
                                 chunk_pos,
                                 tile_pos,
                                 seed.seed,
+                                *world_size,
                             );
 
                         let block_bits = bits[0] + bits[1] * 2 + bits[2] * 4 + bits[3] * 8;
                         if index_shift == 0 {
-                            index_shift = era.get_texture_index() as u8;
+                            index_shift = game.eras.get_texture_index(&era) as u8;
                         }
                         let data = TileSpriteData {
                             tile_bit_index: block_bits,
@@ -397,50 +430,145 @@ impl ChunkPlugin {
     pub fn spawn_chunks_around_camera(
         game: GameParam,
         mut camera_query: Query<&Transform, With<Player>>,
-        mut create_chunk_event: EventWriter<CreateChunkEvent>,
-        _load_chunk_event: EventWriter<SpawnChunkEvent>,
+        mut chunk_tasks: ResMut<ChunkGenerationTasks>,
+        view_radius: Res<ChunkViewRadius>,
+        seed: Res<GenerationSeed>,
+        world_size: Res<WorldSize>,
+        proto_param: ProtoParam,
+        dungeon_check: Query<&Dungeon, With<ActiveDimension>>,
     ) {
         let transform = camera_query.single_mut();
         let camera_chunk_pos = world_helpers::camera_pos_to_chunk_pos(&transform.translation.xy());
-        for y in (camera_chunk_pos.y - NUM_CHUNKS_AROUND_CAMERA)
-            ..=(camera_chunk_pos.y + NUM_CHUNKS_AROUND_CAMERA)
-        {
-            for x in (camera_chunk_pos.x - NUM_CHUNKS_AROUND_CAMERA)
-                ..=(camera_chunk_pos.x + NUM_CHUNKS_AROUND_CAMERA)
-            {
+        let thread_pool = AsyncComputeTaskPool::get();
+        for y in (camera_chunk_pos.y - view_radius.0)..=(camera_chunk_pos.y + view_radius.0) {
+            for x in (camera_chunk_pos.x - view_radius.0)..=(camera_chunk_pos.x + view_radius.0) {
                 let chunk_pos = IVec2::new(x, y);
-                if game.get_chunk_entity(chunk_pos).is_none() {
-                    // println!("send chunk spawn event {chunk_pos}");
-                    create_chunk_event.send(CreateChunkEvent { chunk_pos });
+                if game.get_chunk_entity(chunk_pos).is_some()
+                    || chunk_tasks.tasks.contains_key(&chunk_pos)
+                {
+                    continue;
                 }
+                let needs_tiles = !game
+                    .world_obj_cache
+                    .tile_data_cache
+                    .contains_key(&TileMapPosition::new(chunk_pos, TilePos { x: 0, y: 0 }));
+                let is_chunk_generated = if dungeon_check.get_single().is_ok() {
+                    game.is_dungeon_chunk_generated(chunk_pos)
+                } else {
+                    game.is_chunk_generated(chunk_pos)
+                };
+                let needs_objs =
+                    !is_chunk_generated && !chunk_tasks.generated_objs.contains_key(&chunk_pos);
+
+                // everything the task needs is copied in, so it never touches the world
+                let params = game.world_generation_params.clone();
+                let medium_objs = GenerationPlugin::get_medium_objs(&params, &proto_param);
+                let seed = seed.seed;
+                let biome_noise = *biome_noise;
+                let world_size = *world_size;
+                let default_texture_offset =
+                    game.eras.get_texture_index(&game.era.current_era) as u8;
+                let task = thread_pool.spawn(async move {
+                    GeneratedChunk {
+                        tiles: if needs_tiles {
+                            generate_tile_data_for_chunk(
+                                &params,
+                                chunk_pos,
+                                seed,
+                                world_size,
+                                default_texture_offset,
+                            )
+                        } else {
+                            vec![]
+                        },
+                        objs: needs_objs.then(|| {
+                            GenerationPlugin::generate_objects_for_chunk(
+                                &params,
+                                chunk_pos,
+                                seed,
+                                &biome_noise,
+                                &medium_objs,
+                            )
+                        }),
+                    }
+                });
+                chunk_tasks.tasks.insert(chunk_pos, task);
             }
         }
     }
+    /// Hands finished background generation over to the chunk spawning systems,
+    /// a few chunks per frame so crossing a chunk border doesn't hitch
+    pub fn apply_finished_chunk_tasks(
+        mut game: GameParam,
+        mut chunk_tasks: ResMut<ChunkGenerationTasks>,
+        mut create_chunk_event: EventWriter<CreateChunkEvent>,
+    ) {
+        let finished: Vec<IVec2> = chunk_tasks
+            .tasks
+            .iter()
+            .filter(|(_, task)| task.is_finished())
+            .map(|(chunk_pos, _)| *chunk_pos)
+            .take(MAX_CHUNKS_APPLIED_PER_FRAME)
+            .collect();
+        for chunk_pos in finished {
+            let Some(task) = chunk_tasks.tasks.remove(&chunk_pos) else {
+                continue;
+            };
+            let generated = future::block_on(task);
+            for (pos, data) in generated.tiles {
+                game.world_obj_cache
+                    .tile_data_cache
+                    .entry(pos)
+                    .or_insert(data);
+            }
+            if let Some(objs) = generated.objs {
+                chunk_tasks.generated_objs.insert(chunk_pos, objs);
+            }
+            create_chunk_event.send(CreateChunkEvent { chunk_pos });
+        }
+    }
+    /// Anything still generating belongs to the old world, so throw it away
+    pub fn clear_chunk_tasks_on_new_dimension(
+        new_dim: Query<(), Added<ActiveDimension>>,
+        mut chunk_tasks: ResMut<ChunkGenerationTasks>,
+    ) {
+        if !new_dim.is_empty() {
+            chunk_tasks.tasks.clear();
+            chunk_tasks.generated_objs.clear();
+        }
+    }
     //TODO: change despawning systems to use playe rpos instead??
     fn despawn_outofrange_chunks(
-        game: GameParam,
+        mut game: GameParam,
         player_query: Query<&Transform, (With<Player>, With<YSort>)>,
         mut commands: Commands,
-        chunk_query: Query<(&Transform, &Children), With<Chunk>>,
+        chunk_query: Query<(&Transform, &Children, Option<&ChunkSpawners>), With<Chunk>>,
         containers: Query<(
             &GlobalTransform,
             Option<&FurnaceContainer>,
             Option<&ChestContainer>,
         )>,
+        sapplings: Query<(&GlobalTransform, &WorldObject, &Sappling)>,
         mut container_reg: ResMut<ContainerRegistry>,
+        night_tracker: Res<NightTracker>,
+        proto_param: ProtoParam,
+        dungeon_check: Query<&Dungeon, With<ActiveDimension>>,
+        view_radius: Res<ChunkViewRadius>,
     ) {
+        let now = night_tracker.get_game_time_secs();
+        let is_dungeon = dungeon_check.get_single().is_ok();
         for player_transform in player_query.iter() {
             let max_distance = f32::hypot(
                 CHUNK_SIZE as f32 * TILE_SIZE.x,
                 CHUNK_SIZE as f32 * TILE_SIZE.y,
             );
-            for (chunk_transform, children) in chunk_query.iter() {
+            for (chunk_transform, children, spawners) in chunk_query.iter() {
                 let chunk_pos = chunk_transform.translation.xy();
                 let distance = player_transform.translation.xy().distance(chunk_pos);
                 //TODO: calculate maximum possible distance for 2x2 chunksa
                 let x = (chunk_pos.x / (CHUNK_SIZE as f32 * TILE_SIZE.x)).floor() as i32;
                 let y = (chunk_pos.y / (CHUNK_SIZE as f32 * TILE_SIZE.y)).floor() as i32;
-                if distance > max_distance * 2. * NUM_CHUNKS_AROUND_CAMERA as f32
+                if distance > max_distance * (view_radius.0 + 1) as f32
                     && game.get_chunk_entity(IVec2::new(x, y)).is_some()
                 {
                     debug!("            despawning chunk {x:?},{y:?}");
@@ -457,6 +585,16 @@ impl ChunkPlugin {
                                     world_pos_to_tile_pos(t.translation().xy()),
                                     furnace.items.clone(),
                                 );
+                                if !is_dungeon {
+                                    game.world_obj_cache.simulated_objs.insert(
+                                        world_pos_to_tile_pos(t.translation().xy()),
+                                        SimulatedObject {
+                                            obj: WorldObject::Furnace,
+                                            last_simulated: now,
+                                            progress: 0.,
+                                        },
+                                    );
+                                }
                             }
                             if let Some(chest) = chest_option {
                                 debug!(
@@ -470,6 +608,33 @@ impl ChunkPlugin {
                                 );
                             }
                         }
+                        // keep track of how far along sapplings are so they keep growing
+                        if let Ok((t, obj, sappling)) = sapplings.get(*child) {
+                            if !is_dungeon {
+                                let anchor = proto_param
+                                    .get_component::<SpriteAnchor, _>(*obj)
+                                    .unwrap_or(&SpriteAnchor(Vec2::ZERO));
+                                game.world_obj_cache.simulated_objs.insert(
+                                    world_pos_to_tile_pos(t.translation().xy() - anchor.0),
+                                    SimulatedObject {
+                                        obj: *obj,
+                                        last_simulated: now,
+                                        progress: sappling.0.elapsed_secs(),
+                                    },
+                                );
+                            }
+                        }
+                    }
+                    if let Some(spawners) = spawners {
+                        if !is_dungeon {
+                            game.world_obj_cache.unloaded_spawners.insert(
+                                IVec2::new(x, y),
+                                SimulatedSpawners {
+                                    last_simulated: now,
+                                    spawners: spawners.spawners.clone(),
+                                },
+                            );
+                        }
                     }
                     commands
                         .entity(game.get_chunk_entity(IVec2::new(x, y)).unwrap())
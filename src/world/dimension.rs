@@ -1,13 +1,13 @@
-use bevy::{prelude::*, utils::HashMap};
+use bevy::{prelude::*, reflect::TypeUuid, utils::HashMap};
 use bevy_proto::prelude::ProtoCommands;
 use bevy_save::{CloneReflect, Snapshot};
 use serde::{Deserialize, Serialize};
 
 use crate::{
     enemy::Mob,
-    item::Equipment,
+    item::{Equipment, WorldObject},
     player::{MovePlayerEvent, Player},
-    CustomFlush, GameParam, GameState,
+    CustomFlush, GameParam, GameState, ImageAssets,
 };
 
 use super::{
@@ -54,32 +54,117 @@ impl Clone for ChunkCache {
     }
 }
 
+/// An era, by its position in the [EraList]
 #[derive(Component, Default, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
-pub enum Era {
-    #[default]
+#[serde(from = "EraSaveData")]
+pub struct Era(pub usize);
+
+impl Era {
+    pub fn index(&self) -> usize {
+        self.0
+    }
+    pub fn from_index(index: usize) -> Self {
+        Era(index)
+    }
+}
+
+/// Saves from when there were only two eras stored them by name
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum EraSaveData {
+    Index(usize),
+    Legacy(LegacyEra),
+}
+#[derive(Deserialize)]
+enum LegacyEra {
     Main,
     Second,
 }
-
-impl Era {
-    pub fn get_texture_index(&self) -> usize {
-        match self {
-            Era::Main => 0,
-            Era::Second => 2 * 16,
+impl From<EraSaveData> for Era {
+    fn from(data: EraSaveData) -> Self {
+        match data {
+            EraSaveData::Index(i) => Era(i),
+            EraSaveData::Legacy(LegacyEra::Main) => Era(0),
+            EraSaveData::Legacy(LegacyEra::Second) => Era(1),
         }
     }
-    pub fn index(&self) -> usize {
+}
+
+/// What has to be found in the previous era before an era can be travelled to
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum EraUnlockRequirement {
+    #[default]
+    None,
+    /// the boss of the previous era has been killed
+    BossKilled,
+}
+
+impl EraUnlockRequirement {
+    pub fn is_met(&self, prev_era: &Era, era_manager: &EraManager) -> bool {
         match self {
-            Era::Main => 0,
-            Era::Second => 1,
+            EraUnlockRequirement::None => true,
+            EraUnlockRequirement::BossKilled => era_manager.bosses_killed.contains(prev_era),
         }
     }
-    pub fn from_index(index: usize) -> Self {
-        match index {
-            0 => Era::Main,
-            1 => Era::Second,
-            _ => panic!("Invalid Era index"),
-        }
+}
+
+/// Loaded from eras.era.ron, describes one era
+#[derive(Clone, Debug, Deserialize, TypeUuid)]
+#[uuid = "6c2b7e4a-0f3d-4f59-9a4e-2a1d8f3c5b71"]
+pub struct EraDesc {
+    pub name: String,
+    /// where the era's tiles start in the tile sheet
+    pub texture_offset: usize,
+    /// name of the proto that sets up [WorldGeneration](super::WorldGeneration) for this era
+    pub world_generation_params: String,
+    #[serde(default)]
+    pub unlock_requirement: EraUnlockRequirement,
+    /// objects that are swapped for this era's version of them when generated
+    #[serde(default)]
+    pub object_remaps: Vec<(WorldObject, WorldObject)>,
+    /// Time Fragments it costs to travel to this era through a Time Gate
+    #[serde(default)]
+    pub travel_cost: i32,
+}
+
+pub type EraListProto = Vec<EraDesc>;
+
+/// Every era in the game, in the order they are unlocked
+#[derive(Resource, Default, Debug, Clone)]
+pub struct EraList {
+    pub eras: Vec<EraDesc>,
+}
+
+impl EraList {
+    pub fn get(&self, era: &Era) -> Option<&EraDesc> {
+        self.eras.get(era.index())
+    }
+    pub fn len(&self) -> usize {
+        self.eras.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.eras.is_empty()
+    }
+    pub fn get_texture_index(&self, era: &Era) -> usize {
+        self.get(era).map(|e| e.texture_offset).unwrap_or(0)
+    }
+    pub fn get_world_generation_params(&self, era: &Era) -> String {
+        self.get(era)
+            .map(|e| e.world_generation_params.clone())
+            .unwrap_or_else(|| format!("Era{}WorldGenerationParams", era.index() + 1))
+    }
+    /// The era after this one, if there is one and its unlock requirement has been met
+    pub fn get_next_era(&self, era: &Era, era_manager: &EraManager) -> Option<Era> {
+        let next = Era::from_index(era.index() + 1);
+        self.get(&next)
+            .filter(|desc| desc.unlock_requirement.is_met(era, era_manager))
+            .map(|_| next)
+    }
+    pub fn remap_obj(&self, era: &Era, obj: WorldObject) -> WorldObject {
+        self.get(era)
+            .and_then(|e| e.object_remaps.iter().find(|(from, _)| *from == obj))
+            .map(|(_, to)| *to)
+            .unwrap_or(obj)
     }
 }
 
@@ -87,6 +172,8 @@ impl Era {
 pub struct EraManager {
     pub current_era: Era,
     pub visited_eras: Vec<Era>,
+    /// eras whose boss the player has killed
+    pub bosses_killed: Vec<Era>,
     pub era_generation_cache: HashMap<Era, WorldObjectCache>,
 }
 pub struct DimensionPlugin;
@@ -94,6 +181,8 @@ pub struct DimensionPlugin;
 impl Plugin for DimensionPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<DimensionSpawnEvent>()
+            .init_resource::<EraList>()
+            .add_system(load_era_list.in_schedule(OnExit(GameState::Loading)))
             .add_system(Self::clear_entities_for_dim_swap.before(CustomFlush))
             .add_system(
                 Self::new_dim_with_params
@@ -132,6 +221,9 @@ impl DimensionPlugin {
                     .era_generation_cache
                     .insert(curr_era, game.world_obj_cache.clone());
                 game.era.current_era = new_era.clone();
+                if !game.era.visited_eras.contains(new_era) {
+                    game.era.visited_eras.push(new_era.clone());
+                }
 
                 commands.remove_resource::<WorldObjectCache>();
                 let new_world_cache = game
@@ -142,13 +234,14 @@ impl DimensionPlugin {
                     .unwrap_or(WorldObjectCache::default());
                 commands.insert_resource(new_world_cache);
 
-                proto_commands.apply(format!("Era{}WorldGenerationParams", new_era.index() + 1));
+                proto_commands.apply(game.eras.get_world_generation_params(new_era));
             } else {
                 debug!("USE CURR ERA: {:?}", game.era.current_era.index());
-                proto_commands.apply(format!(
-                    "Era{}WorldGenerationParams",
-                    game.era.current_era.index() + 1
-                ));
+                let curr_era = game.era.current_era.clone();
+                if !game.era.visited_eras.contains(&curr_era) {
+                    game.era.visited_eras.push(curr_era);
+                }
+                proto_commands.apply(game.eras.get_world_generation_params(&game.era.current_era));
                 if let Some(era_cache) = game.era.era_generation_cache.get(&game.era.current_era) {
                     debug!("APPLYING ERA CACHE");
                     commands.insert_resource(era_cache.clone());
@@ -187,6 +280,17 @@ impl DimensionPlugin {
     }
 }
 
+fn load_era_list(
+    mut commands: Commands,
+    assets: Res<ImageAssets>,
+    era_list_desc: Res<Assets<EraListProto>>,
+) {
+    let era_list = era_list_desc.get(&assets.eras).unwrap();
+    commands.insert_resource(EraList {
+        eras: era_list.clone(),
+    });
+}
+
 pub fn dim_spawned(dim_spawn: Query<Entity, With<ActiveDimension>>) -> bool {
     dim_spawn.iter().count() > 0
 }
@@ -3,6 +3,7 @@ use bevy_proto::prelude::ProtoCommands;
 
 use crate::{
     player::MovePlayerEvent,
+    ui::accessibility::LargeText,
     world::dimension::{Dimension, SpawnDimension},
     GameParam, GameState, GAME_HEIGHT,
 };
@@ -159,6 +160,7 @@ pub fn spawn_dungeon_text(
             },
             Name::new("FPS TEXT"),
             DungeonText,
+            LargeText,
             RenderLayers::from_layers(&[3]),
         ));
     }
@@ -1,12 +1,14 @@
-use super::chunk::{GenerateObjectsEvent, TileSpriteData};
+use super::biome::BiomeNoise;
+use super::chunk::{ChunkGenerationTasks, GenerateObjectsEvent, TileSpriteData};
 use super::dimension::{ActiveDimension, GenerationSeed};
 use super::dungeon::Dungeon;
 use super::noise_helpers::{_poisson_disk_sampling, get_object_points_for_chunk};
 use super::portal::{Portal, TimePortal};
+use super::simulation::{SimulatedObject, SimulatedSpawners};
 use super::wall_auto_tile::{handle_wall_break, handle_wall_placed, update_wall, ChunkWallCache};
 use super::world_helpers::tile_pos_to_world_pos;
 use super::y_sort::YSort;
-use super::{WorldGeneration, ISLAND_SIZE};
+use super::{WorldGeneration, WorldSize};
 use crate::assets::SpriteAnchor;
 use crate::container::ContainerRegistry;
 use crate::enemy::spawn_helpers::is_tile_water;
@@ -30,7 +32,7 @@ use crate::{DEBUG, NO_GEN};
 
 use bevy::prelude::*;
 use bevy::sprite::MaterialMesh2dBundle;
-use bevy::utils::HashMap;
+use bevy::utils::{HashMap, HashSet};
 use bevy_ecs_tilemap::prelude::*;
 use bevy_proto::prelude::{ProtoCommands, Prototypes};
 use bevy_rapier2d::prelude::Collider;
@@ -48,10 +50,11 @@ pub struct DoneGeneratingEvent {
     pub chunk_pos: IVec2,
 }
 
-const UNIQUE_OBJECTS_DATA: [(WorldObject, Vec2, i32); 2] = [
+const UNIQUE_OBJECTS_DATA: [(WorldObject, Vec2, i32); 3] = [
     (WorldObject::BossShrine, Vec2::new(8., 8.), 10),
     (WorldObject::DungeonEntrance, Vec2::new(2., 2.), 7),
-    // (WorldObject::TimeGate, Vec2::new(2., 2.), 3),
+    // generated in every era once there is more than one, so players can travel back
+    (WorldObject::TimeGate, Vec2::new(2., 2.), 3),
 ];
 const STARTING_ZONE_OBJS: [(WorldObject, i32); 3] = [
     (WorldObject::Pebble, 1),
@@ -67,6 +70,15 @@ pub struct WorldObjectCache {
     pub generated_chunks: Vec<IVec2>,
     pub generated_dungeon_chunks: Vec<IVec2>,
     pub tile_data_cache: HashMap<TileMapPosition, TileSpriteData>,
+    /// objects in unloaded chunks that keep progressing, caught up when the chunk respawns
+    pub simulated_objs: HashMap<TileMapPosition, SimulatedObject>,
+    /// broken objects waiting to grow back
+    pub regrowing_objs: HashMap<TileMapPosition, SimulatedObject>,
+    pub unloaded_spawners: HashMap<IVec2, SimulatedSpawners>,
+    /// quarter turns of objects that were placed rotated in build mode
+    pub rotated_objs: HashMap<TileMapPosition, u8>,
+    /// objects the player built, the only ones raiders will break
+    pub player_placed_objs: HashSet<TileMapPosition>,
 }
 pub struct GenerationPlugin;
 
@@ -143,12 +155,12 @@ impl GenerationPlugin {
     fn generate_forest_for_chunk(
         world_generation_params: &WorldGeneration,
         chunk_pos: IVec2,
-        seed: u64,
+        biome_noise: &BiomeNoise,
     ) -> Vec<(TileMapPosition, WorldObject)> {
         let mut rng = rand::thread_rng();
 
         //TODO: make these come from proto, use frequencies?
-        let TREES = world_generation_params.forest_params.tree_weights.clone();
+        let TREES = world_generation_params.get_tree_weights_for_chunk(chunk_pos, biome_noise);
         let spawn_ring_offset = if chunk_pos == IVec2::new(0, 0)
             || chunk_pos == IVec2::new(0, -1)
             || chunk_pos == IVec2::new(-1, 0)
@@ -273,12 +285,19 @@ impl GenerationPlugin {
         mut commands: Commands,
         asset_server: Res<AssetServer>,
         dungeon_check: Query<&Dungeon>,
+        world_size: Res<WorldSize>,
     ) {
         if new_dim.is_empty() {
             return;
         }
-        let max_obj_spawn_radius = ((ISLAND_SIZE / CHUNK_SIZE as f32) - 2.) as i32;
+        // unique objects always spawn on the starting island
+        let max_obj_spawn_radius = ((world_size.get_island_size() / CHUNK_SIZE as f32) - 2.) as i32;
+        // every era gets a gate once there is somewhere else to travel to, so players can return
+        let needs_time_gate = game.eras.len() > 1;
         for (obj_to_clear, _size, _) in UNIQUE_OBJECTS_DATA {
+            if obj_to_clear == WorldObject::TimeGate && !needs_time_gate {
+                continue;
+            }
             if !game.world_obj_cache.unique_objs.contains_key(&obj_to_clear) {
                 debug!("NEW UNIQUE OBJ: {obj_to_clear:?}");
 
@@ -338,12 +357,158 @@ impl GenerationPlugin {
                 .insert(Name::new("Time Portal"));
         }
     }
+    /// Picks every object a new chunk should get, before they are checked against the
+    /// chunk's tiles and the object cache. Doesn't touch the ECS, so it can run on a
+    /// background thread.
+    pub fn generate_objects_for_chunk(
+        world_generation_params: &WorldGeneration,
+        chunk_pos: IVec2,
+        seed: u64,
+        biome_noise: &BiomeNoise,
+        medium_objs: &HashSet<WorldObject>,
+    ) -> Vec<(TileMapPosition, WorldObject)> {
+        // generate stone walls for dungeons
+        let stone = Self::generate_stone_for_chunk(world_generation_params, chunk_pos, seed);
+        // generate forest walls trees for chunk
+        let mut trees =
+            Self::generate_forest_for_chunk(world_generation_params, chunk_pos, biome_noise);
+
+        // random size forest clearings
+        if chunk_pos.x.abs() > 1 || chunk_pos.y.abs() > 1 {
+            let mut rng = rand::thread_rng();
+            let rng_x = rng.gen_range(0..CHUNK_SIZE);
+            let rng_y = rng.gen_range(0..CHUNK_SIZE);
+            let clear_tiles = get_radial_tile_positions(
+                TileMapPosition::new(chunk_pos, TilePos::new(rng_x, rng_y)),
+                rng.gen_range(4..8),
+            );
+            trees = trees
+                .into_iter()
+                .filter(|tp| !clear_tiles.contains(&tp.0))
+                .collect_vec();
+        }
+
+        // generate all objs
+        let mut objs_to_spawn: Box<dyn Iterator<Item = (TileMapPosition, WorldObject)>> =
+            Box::new(stone.clone().into_iter().chain(trees.clone().into_iter()));
+        let mut occupied_tiles: HashMap<TileMapPosition, WorldObject> =
+            stone.into_iter().chain(trees.into_iter()).collect();
+
+        for (obj_to_clear, frequency) in world_generation_params
+            .get_object_frequencies_for_chunk(chunk_pos, biome_noise)
+            .iter()
+        {
+            let mut validated_objs: Vec<(TileMapPosition, WorldObject)> = vec![];
+            let raw_points = get_object_points_for_chunk(seed, *frequency);
+            let points = raw_points
+                .iter()
+                .map(|tp| {
+                    let tp_vec = Vec2::new(
+                        tp.0 + (chunk_pos.x as f32 * CHUNK_SIZE as f32 * TILE_SIZE.x),
+                        tp.1 + (chunk_pos.y as f32 * CHUNK_SIZE as f32 * TILE_SIZE.x),
+                    );
+
+                    let relative_tp = world_helpers::world_pos_to_tile_pos(tp_vec);
+                    (relative_tp, *obj_to_clear)
+                })
+                .collect::<Vec<(TileMapPosition, WorldObject)>>();
+            for (pos, obj_to_clear) in points.iter() {
+                // check if tile(s) already occupied by another object waiting to spawn
+                let is_medium = medium_objs.contains(obj_to_clear);
+                let tiles_obj_wants_to_take_up = if is_medium {
+                    pos.get_neighbour_tiles_for_medium_objects()
+                        .into_iter()
+                        .chain(vec![*pos])
+                        .collect_vec()
+                } else {
+                    vec![*pos]
+                };
+                if tiles_obj_wants_to_take_up
+                    .iter()
+                    .any(|p| occupied_tiles.contains_key(p))
+                {
+                    // override chests and dungeon exits, skip anything else
+                    if obj_to_clear == &WorldObject::DungeonExit
+                        || obj_to_clear == &WorldObject::Chest
+                        || obj_to_clear == &WorldObject::DungeonEntrance
+                    {
+                        occupied_tiles.remove(pos);
+                        occupied_tiles.insert(*pos, *obj_to_clear);
+                    } else {
+                        continue;
+                    }
+                }
+
+                // mark tiles as occupied for future objects
+                tiles_obj_wants_to_take_up.iter().for_each(|p| {
+                    occupied_tiles.insert(*p, *obj_to_clear);
+                });
+                validated_objs.push((*pos, *obj_to_clear));
+            }
+
+            objs_to_spawn = Box::new(objs_to_spawn.chain(validated_objs.into_iter()));
+        }
+
+        // generate starting area objs to ensure player has enough pebbles/sticks
+        if chunk_pos == IVec2::ZERO
+            || chunk_pos == IVec2::new(-1, 0)
+            || chunk_pos == IVec2::new(0, -1)
+            || chunk_pos == IVec2::new(-1, -1)
+        {
+            let mut starting_objs = vec![];
+            for (obj_to_clear, num) in STARTING_ZONE_OBJS.iter() {
+                let x_range = if chunk_pos.x == 0 {
+                    0..CHUNK_SIZE / 2
+                } else {
+                    CHUNK_SIZE / 2..CHUNK_SIZE
+                };
+                let y_range = if chunk_pos.y == 0 {
+                    0..CHUNK_SIZE / 2
+                } else {
+                    10..CHUNK_SIZE
+                };
+                for _ in 0..*num {
+                    starting_objs.push((
+                        TileMapPosition::new(
+                            chunk_pos,
+                            TilePos::new(
+                                rand::thread_rng().gen_range(x_range.clone()),
+                                rand::thread_rng().gen_range(y_range.clone()),
+                            ),
+                        ),
+                        *obj_to_clear,
+                    ));
+                }
+            }
+            objs_to_spawn = Box::new(objs_to_spawn.chain(starting_objs.into_iter()));
+        }
+        objs_to_spawn.collect::<Vec<(TileMapPosition, WorldObject)>>()
+    }
+    /// The generated objects that take up 2x2 tiles
+    pub fn get_medium_objs(
+        world_generation_params: &WorldGeneration,
+        proto_param: &ProtoParam,
+    ) -> HashSet<WorldObject> {
+        world_generation_params
+            .object_generation_frequencies
+            .keys()
+            .chain(
+                world_generation_params
+                    .biomes
+                    .values()
+                    .flat_map(|b| b.object_generation_frequencies.keys()),
+            )
+            .filter(|obj| obj.is_medium_size(proto_param))
+            .copied()
+            .collect()
+    }
     pub fn generate_and_cache_objects(
         mut commands: Commands,
         mut game: GameParam,
         mut chunk_spawn_event: EventReader<GenerateObjectsEvent>,
         dungeon_check: Query<&Dungeon, With<ActiveDimension>>,
         seed: Res<GenerationSeed>,
+        biome_noise: Res<BiomeNoise>,
         mut minimap_update: EventWriter<UpdateMiniMapEvent>,
         mut chunk_wall_cache: Query<&mut ChunkWallCache>,
         mut proto_commands: ProtoCommands,
@@ -356,6 +521,7 @@ impl GenerationPlugin {
         >,
         mut done_event: EventWriter<DoneGeneratingEvent>,
         mut schematic_spawn_event: EventWriter<SchematicSpawnEvent>,
+        mut chunk_tasks: ResMut<ChunkGenerationTasks>,
     ) {
         if *NO_GEN {
             return;
@@ -371,135 +537,24 @@ impl GenerationPlugin {
             };
             if !is_chunk_generated {
                 debug!("Generating new objects for {chunk_pos:?}");
-                // generate stone walls for dungeons
-                let stone = Self::generate_stone_for_chunk(
-                    &game.world_generation_params,
-                    chunk_pos,
-                    seed.seed,
-                );
-                // generate forest walls trees for chunk
-                let mut trees = Self::generate_forest_for_chunk(
-                    &game.world_generation_params,
-                    chunk_pos,
-                    seed.seed,
-                );
-
-                // random size forest clearings
-                if chunk_pos.x.abs() > 1 || chunk_pos.y.abs() > 1 {
-                    let mut rng = rand::thread_rng();
-                    let rng_x = rng.gen_range(0..CHUNK_SIZE);
-                    let rng_y = rng.gen_range(0..CHUNK_SIZE);
-                    let clear_tiles = get_radial_tile_positions(
-                        TileMapPosition::new(chunk_pos, TilePos::new(rng_x, rng_y)),
-                        rng.gen_range(4..8),
-                    );
-                    trees = trees
-                        .into_iter()
-                        .filter(|tp| !clear_tiles.contains(&tp.0))
-                        .collect_vec();
-                }
-
-                // generate all objs
-                let mut objs_to_spawn: Box<dyn Iterator<Item = (TileMapPosition, WorldObject)>> =
-                    Box::new(stone.clone().into_iter().chain(trees.clone().into_iter()));
-                let mut occupied_tiles: HashMap<TileMapPosition, WorldObject> =
-                    stone.into_iter().chain(trees.into_iter()).collect();
-
-                for (obj_to_clear, frequency) in game
-                    .world_generation_params
-                    .object_generation_frequencies
-                    .iter()
-                {
-                    let mut validated_objs: Vec<(TileMapPosition, WorldObject)> = vec![];
-                    let raw_points = get_object_points_for_chunk(seed.seed, *frequency);
-                    let points = raw_points
-                        .iter()
-                        .map(|tp| {
-                            let tp_vec = Vec2::new(
-                                tp.0 + (chunk_pos.x as f32 * CHUNK_SIZE as f32 * TILE_SIZE.x),
-                                tp.1 + (chunk_pos.y as f32 * CHUNK_SIZE as f32 * TILE_SIZE.x),
-                            );
-
-                            let relative_tp = world_helpers::world_pos_to_tile_pos(tp_vec);
-                            (relative_tp, *obj_to_clear)
-                        })
-                        .collect::<Vec<(TileMapPosition, WorldObject)>>();
-                    for (pos, obj_to_clear) in points.iter() {
-                        // check if tile(s) already occupied by another object waiting to spawn
-                        let is_medium = obj_to_clear.is_medium_size(&proto_param);
-                        let tiles_obj_wants_to_take_up = if is_medium {
-                            pos.get_neighbour_tiles_for_medium_objects()
-                                .into_iter()
-                                .chain(vec![*pos])
-                                .collect_vec()
-                        } else {
-                            vec![*pos]
-                        };
-                        if tiles_obj_wants_to_take_up
-                            .iter()
-                            .any(|p| occupied_tiles.contains_key(p))
-                        {
-                            // override chests and dungeon exits, skip anything else
-                            if obj_to_clear == &WorldObject::DungeonExit
-                                || obj_to_clear == &WorldObject::Chest
-                                || obj_to_clear == &WorldObject::DungeonEntrance
-                            {
-                                occupied_tiles.remove(pos);
-                                occupied_tiles.insert(*pos, *obj_to_clear);
-                            } else {
-                                continue;
-                            }
-                        }
-
-                        // mark tiles as occupied for future objects
-                        tiles_obj_wants_to_take_up.iter().for_each(|p| {
-                            occupied_tiles.insert(*p, *obj_to_clear);
-                        });
-                        validated_objs.push((*pos, *obj_to_clear));
-                    }
-
-                    objs_to_spawn = Box::new(objs_to_spawn.chain(validated_objs.into_iter()));
-                }
-
-                // generate starting area objs to ensure player has enough pebbles/sticks
-                if chunk_pos == IVec2::ZERO
-                    || chunk_pos == IVec2::new(-1, 0)
-                    || chunk_pos == IVec2::new(0, -1)
-                    || chunk_pos == IVec2::new(-1, -1)
-                {
-                    let mut starting_objs = vec![];
-                    for (obj_to_clear, num) in STARTING_ZONE_OBJS.iter() {
-                        let x_range = if chunk_pos.x == 0 {
-                            0..CHUNK_SIZE / 2
-                        } else {
-                            CHUNK_SIZE / 2..CHUNK_SIZE
-                        };
-                        let y_range = if chunk_pos.y == 0 {
-                            0..CHUNK_SIZE / 2
-                        } else {
-                            10..CHUNK_SIZE
-                        };
-                        for _ in 0..*num {
-                            starting_objs.push((
-                                TileMapPosition::new(
-                                    chunk_pos,
-                                    TilePos::new(
-                                        rand::thread_rng().gen_range(x_range.clone()),
-                                        rand::thread_rng().gen_range(y_range.clone()),
-                                    ),
-                                ),
-                                *obj_to_clear,
-                            ));
-                        }
-                    }
-                    objs_to_spawn = Box::new(objs_to_spawn.chain(starting_objs.into_iter()));
-                }
-                let mut objs_to_spawn =
-                    objs_to_spawn.collect::<Vec<(TileMapPosition, WorldObject)>>();
+                let mut objs_to_spawn = chunk_tasks
+                    .generated_objs
+                    .remove(&chunk_pos)
+                    .unwrap_or_else(|| {
+                        Self::generate_objects_for_chunk(
+                            &game.world_generation_params,
+                            chunk_pos,
+                            seed.seed,
+                            &biome_noise,
+                            &Self::get_medium_objs(&game.world_generation_params, &proto_param),
+                        )
+                    });
                 if dungeon_check.is_err() {
                     let cached_objs = game.get_objects_from_chunk_cache(chunk_pos);
+                    let era = game.era.current_era.clone();
                     objs_to_spawn = objs_to_spawn
                         .into_iter()
+                        .map(|(pos, obj)| (pos, game.eras.remap_obj(&era, obj)))
                         .chain(cached_objs.to_owned().into_iter())
                         .collect::<Vec<(TileMapPosition, WorldObject)>>();
                 } else {
@@ -1,3 +1,4 @@
+pub mod biome;
 pub mod chunk;
 pub mod dimension;
 pub mod dungeon;
@@ -5,10 +6,12 @@ mod dungeon_generation;
 pub mod generation;
 mod noise_helpers;
 pub mod portal;
+pub mod simulation;
 pub mod tile;
 pub mod wall_auto_tile;
 pub mod world_helpers;
 pub mod y_sort;
+use std::env;
 use std::fmt::Display;
 use std::fmt::Formatter;
 
@@ -23,11 +26,13 @@ use world_helpers::tile_pos_to_world_pos;
 use crate::{item::WorldObject, schematic::SchematicType};
 
 use self::{
+    biome::{Biome, BiomeParams, BiomePlugin},
     chunk::ChunkPlugin,
     dimension::DimensionPlugin,
     dungeon::DungeonPlugin,
     dungeon_generation::{Bias, GridSize, NumSteps},
     generation::GenerationPlugin,
+    simulation::SimulationPlugin,
     tile::TilePlugin,
     world_helpers::get_neighbour_tile,
     y_sort::YSortPlugin,
@@ -35,9 +40,94 @@ use self::{
 
 pub const TILE_SIZE: TilemapTileSize = TilemapTileSize { x: 16., y: 16. };
 pub const CHUNK_SIZE: u32 = 16;
-pub const ISLAND_SIZE: f32 = CHUNK_SIZE as f32 * 6.;
 pub const MAX_VISIBILITY: u32 = (2.3 * (CHUNK_SIZE as f32 / 2.) * TILE_SIZE.x) as u32;
-pub const NUM_CHUNKS_AROUND_CAMERA: i32 = 1;
+pub const DEFAULT_VIEW_RADIUS: i32 = 2;
+pub const MAX_VIEW_RADIUS: i32 = 4;
+
+/// How many chunks are kept loaded around the player in each direction.
+/// Can be overridden with the `VIEW_RADIUS` env var.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct ChunkViewRadius(pub i32);
+
+impl Default for ChunkViewRadius {
+    fn default() -> Self {
+        let radius = env::var("VIEW_RADIUS")
+            .ok()
+            .and_then(|r| r.parse::<i32>().ok())
+            .unwrap_or(DEFAULT_VIEW_RADIUS);
+        Self(radius.clamp(1, MAX_VIEW_RADIUS))
+    }
+}
+
+/// How much land a world has. Everything but [WorldSize::Small] and [WorldSize::Medium]
+/// spreads islands out on a grid, with open ocean between them that has to be bridged.
+/// Picked from the World Size setting when a world is created, and stored in the save after that.
+/// The `WORLD_SIZE` env var overrides the setting, for debugging.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WorldSize {
+    Small,
+    #[default]
+    Medium,
+    Large,
+    Infinite,
+}
+
+impl WorldSize {
+    pub fn from_env() -> Option<Self> {
+        match env::var("WORLD_SIZE").map(|s| s.to_lowercase()).as_deref() {
+            Ok("small") => Some(WorldSize::Small),
+            Ok("medium") => Some(WorldSize::Medium),
+            Ok("large") => Some(WorldSize::Large),
+            Ok("infinite") => Some(WorldSize::Infinite),
+            _ => None,
+        }
+    }
+    pub fn all() -> [WorldSize; 4] {
+        [
+            WorldSize::Small,
+            WorldSize::Medium,
+            WorldSize::Large,
+            WorldSize::Infinite,
+        ]
+    }
+    pub fn get_name(&self) -> String {
+        match self {
+            WorldSize::Small => "Small",
+            WorldSize::Medium => "Medium",
+            WorldSize::Large => "Large",
+            WorldSize::Infinite => "Infinite",
+        }
+        .to_string()
+    }
+    /// Radius of a single island, in tiles
+    pub fn get_island_size(&self) -> f32 {
+        match self {
+            WorldSize::Small => CHUNK_SIZE as f32 * 4.,
+            WorldSize::Medium | WorldSize::Large | WorldSize::Infinite => CHUNK_SIZE as f32 * 6.,
+        }
+    }
+    /// How many rings of islands surround the starting island, `None` if they never stop
+    pub fn get_island_rings(&self) -> Option<i32> {
+        match self {
+            WorldSize::Small | WorldSize::Medium => Some(0),
+            WorldSize::Large => Some(1),
+            WorldSize::Infinite => None,
+        }
+    }
+    /// Distance between the centers of neighbouring islands, in tiles
+    pub fn get_island_spacing(&self) -> f32 {
+        self.get_island_size() * 3.
+    }
+    /// The center of the island closest to a tile, in tiles
+    pub fn get_nearest_island_center(&self, pos: Vec2) -> Vec2 {
+        let spacing = self.get_island_spacing();
+        let mut cell = (pos / spacing).round();
+        if let Some(rings) = self.get_island_rings() {
+            cell = cell.clamp(Vec2::splat(-rings as f32), Vec2::splat(rings as f32));
+        }
+        cell * spacing
+    }
+}
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ChunkObjectData(pub Vec<(f32, f32, WorldObject)>);
@@ -126,6 +216,7 @@ pub struct WorldGeneration {
     pub schematic_frequencies: HashMap<SchematicType, f64>,
     pub object_generation_frequencies: HashMap<WorldObject, f64>,
     pub obj_allowed_tiles_map: HashMap<WorldObject, Vec<WorldObject>>,
+    pub biomes: HashMap<Biome, BiomeParams>,
 }
 
 #[derive(Component, Schematic, Reflect, FromReflect, Default, Debug, Clone)]
@@ -140,15 +231,18 @@ pub struct ForestGenerationParams {
 pub struct WorldPlugin;
 impl Plugin for WorldPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugin(GenerationPlugin)
+        app.add_plugin(BiomePlugin)
+            .add_plugin(GenerationPlugin)
             .add_plugin(ChunkPlugin)
             .add_plugin(DimensionPlugin)
             .add_plugin(DungeonPlugin)
+            .add_plugin(SimulationPlugin)
             .add_plugin(TilePlugin)
             .add_plugin(YSortPlugin)
             // .add_plugin(ResourceInspectorPlugin::<NumSteps>::default())
             // .add_plugin(ResourceInspectorPlugin::<GridSize>::default())
             // .add_plugin(ResourceInspectorPlugin::<Bias>::default())
+            .init_resource::<ChunkViewRadius>()
             .init_resource::<NumSteps>()
             .init_resource::<GridSize>()
             .init_resource::<Bias>()
@@ -0,0 +1,203 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    assets::SpriteAnchor,
+    combat::ObjBreakEvent,
+    container::ContainerRegistry,
+    enemy::spawner::Spawner,
+    item::{smelt_furnace_offscreen, PlaceItemEvent, Recipes, WorldObject},
+    night::NightTracker,
+    proto::proto_param::ProtoParam,
+    sappling::{grow_sappling_offscreen, Sappling},
+    CustomFlush, GameParam, GameState,
+};
+
+use super::{
+    dimension::ActiveDimension,
+    dungeon::Dungeon,
+    generation::WorldObjectCache,
+    world_helpers::{tile_pos_to_world_pos, world_pos_to_tile_pos},
+    TileMapPosition,
+};
+
+/// Objects that grow back where they were broken
+const REGROWABLE_OBJS: [WorldObject; 2] = [WorldObject::BerryBush, WorldObject::Era2BerryBush];
+/// One in-game day
+const REGROW_SECS: f32 = 408.;
+
+/// An object whose progress is tracked while its chunk is unloaded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulatedObject {
+    pub obj: WorldObject,
+    /// game time, in seconds, the object was last simulated at
+    pub last_simulated: f32,
+    /// seconds into the current stage, for sapplings
+    pub progress: f32,
+}
+
+/// The spawners of an unloaded chunk, so their timers carry over when it respawns
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulatedSpawners {
+    pub last_simulated: f32,
+    pub spawners: Vec<Spawner>,
+}
+
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            (
+                record_broken_regrowable_objs,
+                regrow_objs_in_loaded_chunks,
+                restore_sappling_progress.before(CustomFlush),
+            )
+                .in_set(OnUpdate(GameState::Main)),
+        );
+    }
+}
+
+/// Advances everything cached for a chunk by the game time that passed since it was unloaded.
+/// Called right before the chunk's objects are respawned.
+pub fn simulate_unloaded_chunk(
+    chunk_pos: IVec2,
+    now: f32,
+    cache: &mut WorldObjectCache,
+    container_reg: &mut ContainerRegistry,
+    recipes: &Recipes,
+    proto_param: &ProtoParam,
+) {
+    let simulated: Vec<_> = cache
+        .simulated_objs
+        .iter()
+        .filter(|(pos, _)| pos.chunk_pos == chunk_pos)
+        .map(|(pos, sim)| (*pos, sim.clone()))
+        .collect();
+    for (pos, mut sim) in simulated {
+        let elapsed = now - sim.last_simulated;
+        if sim.obj == WorldObject::Furnace {
+            if let Some(furnace) = container_reg.containers.get_mut(&pos) {
+                smelt_furnace_offscreen(furnace, elapsed, recipes, proto_param);
+            }
+            cache.simulated_objs.remove(&pos);
+            continue;
+        }
+        let (grown_obj, progress) =
+            grow_sappling_offscreen(sim.obj, sim.progress, elapsed, proto_param);
+        if grown_obj != sim.obj {
+            cache.objects.insert(pos, grown_obj);
+        }
+        if proto_param
+            .get_component::<Sappling, _>(grown_obj)
+            .is_some()
+        {
+            sim.obj = grown_obj;
+            sim.progress = progress;
+            sim.last_simulated = now;
+            cache.simulated_objs.insert(pos, sim);
+        } else {
+            cache.simulated_objs.remove(&pos);
+        }
+    }
+
+    let regrown: Vec<_> = cache
+        .regrowing_objs
+        .iter()
+        .filter(|(pos, sim)| pos.chunk_pos == chunk_pos && now - sim.last_simulated >= REGROW_SECS)
+        .map(|(pos, sim)| (*pos, sim.obj))
+        .collect();
+    for (pos, obj) in regrown {
+        cache.regrowing_objs.remove(&pos);
+        cache.objects.entry(pos).or_insert(obj);
+    }
+
+    if let Some(unloaded) = cache.unloaded_spawners.get_mut(&chunk_pos) {
+        let elapsed = Duration::from_secs_f32((now - unloaded.last_simulated).max(0.));
+        for spawner in unloaded.spawners.iter_mut() {
+            if spawner.spawn_timer.percent() > 0. {
+                spawner.spawn_timer.tick(elapsed);
+                if spawner.spawn_timer.finished() {
+                    spawner.spawn_timer.reset();
+                }
+            }
+        }
+        unloaded.last_simulated = now;
+    }
+}
+
+fn record_broken_regrowable_objs(
+    mut obj_break_events: EventReader<ObjBreakEvent>,
+    mut game: GameParam,
+    night_tracker: Res<NightTracker>,
+    dungeon_check: Query<&Dungeon, With<ActiveDimension>>,
+) {
+    if dungeon_check.get_single().is_ok() {
+        return;
+    }
+    for broken in obj_break_events.iter() {
+        if !broken.give_drops_and_xp || !REGROWABLE_OBJS.contains(&broken.obj) {
+            continue;
+        }
+        game.world_obj_cache.regrowing_objs.insert(
+            broken.pos,
+            SimulatedObject {
+                obj: broken.obj,
+                last_simulated: night_tracker.get_game_time_secs(),
+                progress: 0.,
+            },
+        );
+    }
+}
+
+fn regrow_objs_in_loaded_chunks(
+    mut game: GameParam,
+    night_tracker: Res<NightTracker>,
+    mut place_item_event: EventWriter<PlaceItemEvent>,
+    dungeon_check: Query<&Dungeon, With<ActiveDimension>>,
+) {
+    if dungeon_check.get_single().is_ok() {
+        return;
+    }
+    let now = night_tracker.get_game_time_secs();
+    let regrown: Vec<_> = game
+        .world_obj_cache
+        .regrowing_objs
+        .iter()
+        .filter(|(pos, sim)| {
+            now - sim.last_simulated >= REGROW_SECS
+                && game.get_chunk_entity(pos.chunk_pos).is_some()
+        })
+        .map(|(pos, sim)| (*pos, sim.obj))
+        .collect();
+    for (pos, obj) in regrown {
+        game.world_obj_cache.regrowing_objs.remove(&pos);
+        place_item_event.send(PlaceItemEvent {
+            obj,
+            pos: tile_pos_to_world_pos(pos, false),
+            placed_by_player: false,
+            override_existing_obj: false,
+        });
+    }
+}
+
+/// Sapplings that grew while their chunk was unloaded pick up where the catch-up left off
+fn restore_sappling_progress(
+    mut sapplings: Query<(&WorldObject, &GlobalTransform, &mut Sappling), Added<Sappling>>,
+    mut game: GameParam,
+    proto_param: ProtoParam,
+) {
+    for (obj, txfm, mut sappling) in sapplings.iter_mut() {
+        let anchor = proto_param
+            .get_component::<SpriteAnchor, _>(*obj)
+            .unwrap_or(&SpriteAnchor(Vec2::ZERO));
+        let pos: TileMapPosition = world_pos_to_tile_pos(txfm.translation().truncate() - anchor.0);
+        if let Some(sim) = game.world_obj_cache.simulated_objs.remove(&pos) {
+            sappling
+                .0
+                .set_elapsed(Duration::from_secs_f32(sim.progress));
+        }
+    }
+}
@@ -2,11 +2,11 @@ use bevy::prelude::*;
 use bevy_ecs_tilemap::{prelude::*, tiles::TilePos};
 use interpolation::lerp;
 
-use crate::{item::WorldObject, world::ISLAND_SIZE, GameParam};
+use crate::{item::WorldObject, GameParam};
 
 use super::{
     chunk::TileSpriteData, noise_helpers, world_helpers::get_neighbour_tile, TileMapPosition,
-    WorldGeneration, CHUNK_SIZE,
+    WorldGeneration, WorldSize, CHUNK_SIZE,
 };
 
 pub struct TilePlugin;
@@ -32,6 +32,7 @@ impl TilePlugin {
         chunk_pos: IVec2,
         tile_pos: TilePos,
         seed: u64,
+        world_size: WorldSize,
     ) -> ([u8; 4], u8, [WorldObject; 4]) {
         let x = tile_pos.x as f64;
         let y = tile_pos.y as f64;
@@ -59,7 +60,7 @@ impl TilePlugin {
             // let m = f64::powf(m / (1. + 0.5 + 0.25), 1.);
             // print!("{:?}", e);
             // let m = f64::powf(m, 1.);
-            let e = Self::apply_distance_function_to_tile(x as f32, y as f32, e);
+            let e = Self::apply_distance_function_to_tile(x as f32, y as f32, e, world_size);
             let block = if e <= world_generation_params.water_frequency {
                 WorldObject::WaterTile
             } else {
@@ -93,8 +94,11 @@ impl TilePlugin {
         }
         (bits, index_shift, blocks)
     }
-    pub fn apply_distance_function_to_tile(x: f32, y: f32, e: f64) -> f64 {
-        let d = (Vec2::new(x, y).distance(Vec2::ZERO)) / ISLAND_SIZE;
+    /// Pushes tiles far from every island center towards water, so each island ends in ocean
+    pub fn apply_distance_function_to_tile(x: f32, y: f32, e: f64, world_size: WorldSize) -> f64 {
+        let pos = Vec2::new(x, y);
+        let d =
+            pos.distance(world_size.get_nearest_island_center(pos)) / world_size.get_island_size();
         let mix = 0.5;
         let ne = lerp(&e, &(1. - d as f64), &mix);
         if ne >= 0.25 {
@@ -67,6 +67,15 @@ pub fn tile_pos_to_world_pos(pos: TileMapPosition, _center: bool) -> Vec2 {
             + pos.chunk_pos.y as f32 * CHUNK_SIZE as f32 * TILE_SIZE.x,
     )
 }
+/// Tile position counted from the world origin instead of from its chunk
+pub fn tile_pos_to_global_tile(pos: TileMapPosition) -> IVec2 {
+    (tile_pos_to_world_pos(pos, false) / TILE_SIZE.x)
+        .round()
+        .as_ivec2()
+}
+pub fn global_tile_to_tile_pos(tile: IVec2) -> TileMapPosition {
+    world_pos_to_tile_pos(tile.as_vec2() * TILE_SIZE.x)
+}
 pub fn world_pos_to_ui_screen_pos(pos: Vec2, camera_pos: Vec2) -> Vec2 {
     
     pos - camera_pos